@@ -0,0 +1,534 @@
+//! Spectrum analysis for feeding spectrum analyzer and spectrogram displays.
+//!
+//! A [`SpectrumAnalyzer`] windows a block of audio, transforms it and converts
+//! the bins to decibels, optionally smoothing them over time and across
+//! neighbouring bins. The results can be mapped to the `0..=1` magnitudes
+//! drawn by widgets, either per bin or resampled onto a logarithmic frequency
+//! axis with [`SpectrumAnalyzer::log_bins`]. The spectrum can be frozen into
+//! named [`SpectrumSnapshot`]s, kept as reference traces to compare the live
+//! spectrum against.
+//!
+//! Audio streamed from the audio thread is analyzed with a
+//! [`StreamingAnalyzer`], which cuts it into overlapping windows and spreads
+//! their transforms across frames. Each frame shares a [`FrameBudget`] between
+//! every analyzer, so large FFT sizes and several analyzers don't stall the
+//! GUI thread:
+//!
+//! ```ignore
+//! let budget = FrameBudget::new(Duration::from_millis(2));
+//! for analyzer in &mut self.analyzers {
+//!     analyzer.process(&budget);
+//! }
+//! ```
+
+use std::collections::VecDeque;
+use std::ops::RangeInclusive;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use rustfft::num_complex::Complex;
+use rustfft::{Fft, FftPlanner};
+
+use crate::units::{gain_to_db, MINUS_INFINITY_DB};
+use crate::{LogMapping, ValueMapping};
+
+/// A window function applied to each block before transforming it
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Window {
+    Rectangular,
+    Hann,
+    Hamming,
+    Blackman,
+}
+
+impl Window {
+    /// Gets the window's coefficients for a block of `size` samples
+    pub fn coefficients(&self, size: usize) -> Vec<f32> {
+        let n = (size.max(2) - 1) as f32;
+        (0..size)
+            .map(|i| {
+                let phase = std::f32::consts::TAU * i as f32 / n;
+                match self {
+                    Window::Rectangular => 1f32,
+                    Window::Hann => 0.5f32 - 0.5f32 * phase.cos(),
+                    Window::Hamming => 0.54f32 - 0.46f32 * phase.cos(),
+                    Window::Blackman => {
+                        0.42f32 - 0.5f32 * phase.cos() + 0.08f32 * (2f32 * phase).cos()
+                    }
+                }
+            })
+            .collect()
+    }
+}
+
+/// A spectrum frozen by [`SpectrumAnalyzer::freeze`], kept as a reference
+/// trace to draw behind the live one
+#[derive(Clone, Debug, PartialEq)]
+pub struct SpectrumSnapshot {
+    name: String,
+    /// The smoothed magnitude of each bin when the snapshot was taken, in
+    /// decibels
+    magnitudes: Vec<f32>,
+}
+
+impl SpectrumSnapshot {
+    /// The name the snapshot was frozen under
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// The magnitude of each bin when the snapshot was taken, in decibels
+    pub fn magnitudes(&self) -> &[f32] {
+        &self.magnitudes
+    }
+}
+
+/// Turns blocks of audio into smoothed magnitude spectra in decibels
+pub struct SpectrumAnalyzer {
+    fft: Arc<dyn Fft<f32>>,
+    window: Vec<f32>,
+    /// Scales bin magnitudes so a full scale sine reads `0 dB`
+    gain: f32,
+    buffer: Vec<Complex<f32>>,
+    scratch: Vec<Complex<f32>>,
+    /// The smoothed magnitude of each bin from DC up to Nyquist, in decibels
+    magnitudes: Vec<f32>,
+    /// How much of the previous spectrum is kept each block, from `0..1`
+    time_smoothing: f32,
+    /// The width in octaves that bins are averaged over, or `0.0` for none
+    octave_smoothing: f32,
+    /// The decibels mapped to `0..=1` by [`SpectrumAnalyzer::normalized`]
+    db_range: RangeInclusive<f32>,
+    /// The spectra frozen so far, in the order they were first taken
+    snapshots: Vec<SpectrumSnapshot>,
+}
+
+impl SpectrumAnalyzer {
+    /// Creates an analyzer for blocks of `size` samples, which is best kept to
+    /// a power of two
+    pub fn new(size: usize, window: Window) -> Self {
+        let fft = FftPlanner::new().plan_fft_forward(size);
+        let window = window.coefficients(size);
+        let gain = 2f32 / window.iter().sum::<f32>().max(f32::EPSILON);
+        Self {
+            scratch: vec![Complex::default(); fft.get_inplace_scratch_len()],
+            fft,
+            window,
+            gain,
+            buffer: vec![Complex::default(); size],
+            magnitudes: vec![MINUS_INFINITY_DB; size / 2 + 1],
+            time_smoothing: 0f32,
+            octave_smoothing: 0f32,
+            db_range: -96f32..=0f32,
+            snapshots: Vec::new(),
+        }
+    }
+
+    /// Keeps `amount` of the previous spectrum each block, from `0..1`, so
+    /// the display falls back smoothly instead of flickering
+    pub fn with_time_smoothing(mut self, amount: f32) -> Self {
+        self.time_smoothing = amount.clamp(0f32, 0.999f32);
+        self
+    }
+
+    /// Averages each bin with its neighbours within a band `octaves` wide,
+    /// such as `1.0 / 3.0` for third octave smoothing
+    pub fn with_octave_smoothing(mut self, octaves: f32) -> Self {
+        self.octave_smoothing = octaves.max(0f32);
+        self
+    }
+
+    /// Sets the decibels mapped to `0..=1` by
+    /// [`normalized`](SpectrumAnalyzer::normalized)
+    pub fn with_db_range(mut self, db_range: RangeInclusive<f32>) -> Self {
+        self.db_range = db_range;
+        self
+    }
+
+    /// The number of samples in each block
+    pub fn size(&self) -> usize {
+        self.buffer.len()
+    }
+
+    /// The frequency at the center of a bin
+    pub fn bin_frequency(&self, bin: usize, sample_rate: f32) -> f32 {
+        bin as f32 * sample_rate / self.size() as f32
+    }
+
+    /// Analyzes a block of samples and returns the magnitude of each bin from
+    /// DC up to Nyquist in decibels. Shorter blocks are padded with silence.
+    pub fn process(&mut self, block: &[f32]) -> &[f32] {
+        for (i, (bin, window)) in self.buffer.iter_mut().zip(self.window.iter()).enumerate() {
+            *bin = Complex::new(block.get(i).copied().unwrap_or(0f32) * window, 0f32);
+        }
+        self.fft
+            .process_with_scratch(&mut self.buffer, &mut self.scratch);
+
+        let bins: Vec<f32> = self.buffer[..self.magnitudes.len()]
+            .iter()
+            .map(|bin| bin.norm() * self.gain)
+            .collect();
+        let bins = smooth_octaves(&bins, self.octave_smoothing);
+        for (magnitude, bin) in self.magnitudes.iter_mut().zip(bins) {
+            let db = gain_to_db(bin);
+            // Rise instantly and only smooth the fall
+            *magnitude = if db >= *magnitude {
+                db
+            } else {
+                db + (*magnitude - db) * self.time_smoothing
+            };
+        }
+        &self.magnitudes
+    }
+
+    /// The magnitude of each bin from the last block, in decibels
+    pub fn magnitudes(&self) -> &[f32] {
+        &self.magnitudes
+    }
+
+    /// The magnitude of each bin from the last block, mapped to `0..=1` over
+    /// the analyzer's decibel range
+    pub fn normalized(&self) -> Vec<f32> {
+        self.magnitudes
+            .iter()
+            .map(|db| self.db_range.normalize(*db))
+            .collect()
+    }
+
+    /// Resamples the last block onto `count` points spaced logarithmically
+    /// over `frequencies`, mapped to `0..=1` over the analyzer's decibel
+    /// range. Each point takes the loudest bin it covers, so narrow peaks
+    /// aren't lost where bins are dense.
+    pub fn log_bins(
+        &self,
+        sample_rate: f32,
+        frequencies: RangeInclusive<f32>,
+        count: usize,
+    ) -> Vec<f32> {
+        self.resample_log(&self.magnitudes, sample_rate, frequencies, count)
+    }
+
+    /// Freezes the current smoothed spectrum as a snapshot called `name`,
+    /// replacing any earlier snapshot of the same name. Snapshots are kept
+    /// until removed, whatever the analyzer processes afterwards.
+    pub fn freeze(&mut self, name: impl Into<String>) -> &SpectrumSnapshot {
+        let snapshot = SpectrumSnapshot {
+            name: name.into(),
+            magnitudes: self.magnitudes.clone(),
+        };
+        let index = match self.snapshots.iter().position(|s| s.name == snapshot.name) {
+            Some(index) => {
+                self.snapshots[index] = snapshot;
+                index
+            }
+            None => {
+                self.snapshots.push(snapshot);
+                self.snapshots.len() - 1
+            }
+        };
+        &self.snapshots[index]
+    }
+
+    /// The frozen snapshots, in the order they were first taken
+    pub fn snapshots(&self) -> &[SpectrumSnapshot] {
+        &self.snapshots
+    }
+
+    /// The snapshot frozen as `name`, if any
+    pub fn snapshot(&self, name: &str) -> Option<&SpectrumSnapshot> {
+        self.snapshots.iter().find(|s| s.name == name)
+    }
+
+    /// Removes the snapshot frozen as `name`, returning it if there was one
+    pub fn remove_snapshot(&mut self, name: &str) -> Option<SpectrumSnapshot> {
+        let index = self.snapshots.iter().position(|s| s.name == name)?;
+        Some(self.snapshots.remove(index))
+    }
+
+    /// Removes every snapshot
+    pub fn clear_snapshots(&mut self) {
+        self.snapshots.clear();
+    }
+
+    /// Resamples the snapshot frozen as `name` like
+    /// [`log_bins`](SpectrumAnalyzer::log_bins), so it can be drawn on the
+    /// same axes as the live spectrum
+    pub fn snapshot_log_bins(
+        &self,
+        name: &str,
+        sample_rate: f32,
+        frequencies: RangeInclusive<f32>,
+        count: usize,
+    ) -> Option<Vec<f32>> {
+        let snapshot = self.snapshot(name)?;
+        Some(self.resample_log(&snapshot.magnitudes, sample_rate, frequencies, count))
+    }
+
+    /// Resamples `magnitudes`, one per bin in decibels, onto `count` points
+    /// spaced logarithmically over `frequencies`
+    fn resample_log(
+        &self,
+        magnitudes: &[f32],
+        sample_rate: f32,
+        frequencies: RangeInclusive<f32>,
+        count: usize,
+    ) -> Vec<f32> {
+        let mapping = LogMapping::new(frequencies);
+        let bin_of = |frequency: f32| frequency * self.size() as f32 / sample_rate;
+        let last = magnitudes.len() - 1;
+        (0..count)
+            .map(|i| {
+                let position = i as f32 / (count.max(2) - 1) as f32;
+                let step = 0.5f32 / count.max(1) as f32;
+                let start = bin_of(mapping.denormalize(position - step));
+                let end = bin_of(mapping.denormalize(position + step));
+                let db = if end - start < 1f32 {
+                    // Interpolate between bins where they're sparse
+                    let bin = bin_of(mapping.denormalize(position)).min(last as f32);
+                    let (low, t) = (bin.floor() as usize, bin.fract());
+                    let high = (low + 1).min(last);
+                    magnitudes[low] + (magnitudes[high] - magnitudes[low]) * t
+                } else {
+                    let (start, end) = (start.round() as usize, (end.round() as usize).min(last));
+                    magnitudes[start.min(end)..=end]
+                        .iter()
+                        .copied()
+                        .fold(MINUS_INFINITY_DB, f32::max)
+                };
+                self.db_range.normalize(db)
+            })
+            .collect()
+    }
+}
+
+/// How many windows a [`StreamingAnalyzer`] keeps waiting before dropping the
+/// oldest, so a display that fell behind catches up with the latest audio
+const MAX_PENDING_WINDOWS: usize = 8;
+
+/// The time the GUI thread may spend on analysis in one frame, shared by
+/// every analyzer processed in that frame
+#[derive(Clone, Copy, Debug)]
+pub struct FrameBudget {
+    deadline: Instant,
+}
+
+impl FrameBudget {
+    /// Starts a budget of `duration` from now
+    pub fn new(duration: Duration) -> Self {
+        Self {
+            deadline: Instant::now() + duration,
+        }
+    }
+
+    /// Whether the frame's time for analysis is up
+    pub fn is_spent(&self) -> bool {
+        Instant::now() >= self.deadline
+    }
+}
+
+/// Analyzes a stream of audio in overlapping windows, spreading the work
+/// across frames. Samples are pushed as they arrive from the audio thread and
+/// the windows they complete are transformed by [`StreamingAnalyzer::process`]
+/// within each frame's [`FrameBudget`].
+pub struct StreamingAnalyzer {
+    analyzer: SpectrumAnalyzer,
+    /// The number of samples between the starts of neighbouring windows
+    hop: usize,
+    /// The samples of the windows that haven't been processed yet
+    history: VecDeque<f32>,
+    /// The samples of the window being processed
+    block: Vec<f32>,
+}
+
+impl StreamingAnalyzer {
+    /// Creates a streaming analyzer starting a window every `1 / overlap` of
+    /// the analyzer's block size, such as `4` for windows overlapping by 75%
+    pub fn new(analyzer: SpectrumAnalyzer, overlap: usize) -> Self {
+        let size = analyzer.size();
+        Self {
+            hop: (size / overlap.max(1)).max(1),
+            history: VecDeque::with_capacity(size * 2),
+            block: Vec::with_capacity(size),
+            analyzer,
+        }
+    }
+
+    /// Adds samples to the stream. If more than a few windows are waiting,
+    /// the oldest are dropped.
+    pub fn push(&mut self, samples: &[f32]) {
+        self.history.extend(samples);
+        let kept = self.analyzer.size() + self.hop * (MAX_PENDING_WINDOWS - 1);
+        if self.history.len() > kept {
+            // Drop whole hops so windows keep starting on the same samples
+            let excess = (self.history.len() - kept).div_ceil(self.hop) * self.hop;
+            self.history.drain(..excess.min(self.history.len()));
+        }
+    }
+
+    /// The number of complete windows waiting to be processed
+    pub fn pending(&self) -> usize {
+        match self.history.len().checked_sub(self.analyzer.size()) {
+            Some(extra) => extra / self.hop + 1,
+            None => 0,
+        }
+    }
+
+    /// Processes waiting windows, oldest first, until the budget is spent.
+    /// At least one window is processed if any is waiting, so every analyzer
+    /// keeps up even when the budget runs out early. Returns the number of
+    /// windows processed.
+    pub fn process(&mut self, budget: &FrameBudget) -> usize {
+        let mut processed = 0;
+        while self.pending() > 0 && (processed == 0 || !budget.is_spent()) {
+            self.block.clear();
+            self.block
+                .extend(self.history.iter().take(self.analyzer.size()));
+            self.analyzer.process(&self.block);
+            self.history.drain(..self.hop);
+            processed += 1;
+        }
+        processed
+    }
+
+    /// The analyzer, holding the spectrum of the last processed window
+    pub fn analyzer(&self) -> &SpectrumAnalyzer {
+        &self.analyzer
+    }
+}
+
+/// Averages each linear magnitude with the bins around it within a band
+/// `octaves` wide. Returns the magnitudes unchanged when `octaves` is `0.0`.
+pub fn smooth_octaves(magnitudes: &[f32], octaves: f32) -> Vec<f32> {
+    if octaves <= 0f32 {
+        return magnitudes.to_vec();
+    }
+    let ratio = 2f32.powf(octaves / 2f32);
+    let last = magnitudes.len().saturating_sub(1);
+    (0..magnitudes.len())
+        .map(|bin| {
+            let start = ((bin as f32 / ratio).floor() as usize).min(bin);
+            let end = ((bin as f32 * ratio).ceil() as usize).clamp(bin, last);
+            let band = &magnitudes[start..=end];
+            band.iter().sum::<f32>() / band.len() as f32
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use assert_approx_eq::assert_approx_eq;
+
+    fn sine(frequency: f32, sample_rate: f32, size: usize) -> Vec<f32> {
+        (0..size)
+            .map(|i| (std::f32::consts::TAU * frequency * i as f32 / sample_rate).sin())
+            .collect()
+    }
+
+    #[test]
+    fn builds_windows() {
+        let hann = Window::Hann.coefficients(5);
+        assert_approx_eq!(hann[0], 0f32);
+        assert_approx_eq!(hann[2], 1f32);
+        assert_approx_eq!(hann[4], 0f32);
+        assert!(Window::Rectangular
+            .coefficients(4)
+            .iter()
+            .all(|c| *c == 1f32));
+    }
+
+    #[test]
+    fn finds_sine() {
+        let mut analyzer = SpectrumAnalyzer::new(1024, Window::Hann);
+        // Exactly on bin 64
+        let frequency = analyzer.bin_frequency(64, 48000f32);
+        let magnitudes = analyzer.process(&sine(frequency, 48000f32, 1024));
+        assert_eq!(magnitudes.len(), 513);
+        assert_approx_eq!(magnitudes[64], 0f32, 0.1f32);
+        assert!(magnitudes[200] < -60f32);
+        let normalized = analyzer.normalized();
+        assert_approx_eq!(normalized[64], 1f32, 0.01f32);
+    }
+
+    #[test]
+    fn smooths_over_time() {
+        let mut analyzer = SpectrumAnalyzer::new(256, Window::Hann).with_time_smoothing(0.5f32);
+        let frequency = analyzer.bin_frequency(16, 48000f32);
+        let peak = analyzer.process(&sine(frequency, 48000f32, 256))[16];
+        let fallen = analyzer.process(&[])[16];
+        assert_approx_eq!(fallen, (peak + MINUS_INFINITY_DB) / 2f32, 0.1f32);
+    }
+
+    #[test]
+    fn smooths_across_bins() {
+        let mut magnitudes = vec![0f32; 64];
+        magnitudes[32] = 1f32;
+        let smoothed = smooth_octaves(&magnitudes, 1f32);
+        assert!(smoothed[32] < 1f32 && smoothed[32] > 0f32);
+        assert!(smoothed[30] > 0f32);
+        assert_eq!(smoothed[8], 0f32);
+        assert_eq!(smooth_octaves(&magnitudes, 0f32), magnitudes);
+    }
+
+    #[test]
+    fn streams_within_budget() {
+        let analyzer = SpectrumAnalyzer::new(256, Window::Hann);
+        let frequency = analyzer.bin_frequency(16, 48000f32);
+        let mut stream = StreamingAnalyzer::new(analyzer, 2);
+        stream.push(&sine(frequency, 48000f32, 512));
+        assert_eq!(stream.pending(), 3);
+
+        // A spent budget still processes one window
+        assert_eq!(stream.process(&FrameBudget::new(Duration::ZERO)), 1);
+        assert_eq!(stream.pending(), 2);
+        assert_eq!(stream.process(&FrameBudget::new(Duration::from_secs(1))), 2);
+        assert_eq!(stream.pending(), 0);
+        assert_approx_eq!(stream.analyzer().magnitudes()[16], 0f32, 0.1f32);
+
+        // Falling far behind drops the oldest windows
+        stream.push(&vec![0f32; 256 * 100]);
+        assert_eq!(stream.pending(), MAX_PENDING_WINDOWS);
+    }
+
+    #[test]
+    fn resamples_to_log_bins() {
+        let mut analyzer = SpectrumAnalyzer::new(1024, Window::Hann);
+        let frequency = analyzer.bin_frequency(100, 48000f32);
+        analyzer.process(&sine(frequency, 48000f32, 1024));
+        let bins = analyzer.log_bins(48000f32, 20f32..=20000f32, 64);
+        assert_eq!(bins.len(), 64);
+        // The sine's column is the loudest
+        let loudest = LogMapping::new(20f32..=20000f32).normalize(frequency) * 63f32;
+        let max = bins.iter().copied().fold(0f32, f32::max);
+        assert_approx_eq!(bins[loudest.round() as usize], max, 0.05f32);
+    }
+
+    #[test]
+    fn freezes_snapshots() {
+        let mut analyzer = SpectrumAnalyzer::new(256, Window::Hann);
+        let frequency = analyzer.bin_frequency(16, 48000f32);
+        analyzer.process(&sine(frequency, 48000f32, 256));
+        analyzer.freeze("A");
+        analyzer.freeze("B");
+        // The snapshots keep the sine after the live spectrum falls silent
+        analyzer.process(&[]);
+        assert!(analyzer.magnitudes()[16] < -96f32);
+        let a = analyzer.snapshot("A").unwrap();
+        assert_approx_eq!(a.magnitudes()[16], 0f32, 0.1f32);
+        let bins = analyzer
+            .snapshot_log_bins("A", 48000f32, 20f32..=20000f32, 64)
+            .unwrap();
+        assert!(bins.iter().any(|bin| *bin > 0.99f32));
+
+        // Freezing under a taken name replaces that snapshot in place
+        analyzer.freeze("A");
+        let names: Vec<&str> = analyzer.snapshots().iter().map(|s| s.name()).collect();
+        assert_eq!(names, ["A", "B"]);
+        assert!(analyzer.snapshot("A").unwrap().magnitudes()[16] < -96f32);
+        assert!(analyzer.remove_snapshot("B").is_some());
+        assert_eq!(
+            analyzer.snapshot_log_bins("B", 48000f32, 20f32..=20000f32, 64),
+            None
+        );
+    }
+}
@@ -0,0 +1,191 @@
+//! Attack and release smoothing with peak hold, for the motion of meters
+
+/// Smooths a level with separate attack and release times and tracks a held
+/// peak that decays after a hold time.
+///
+/// Times are in seconds and are independent of how often the level is
+/// updated, so the same settings move the same way whether they run per
+/// sample on the audio thread or once per frame on the GUI.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Ballistics {
+    /// The time constant in seconds used while the level rises
+    attack: f32,
+    /// The time constant in seconds used while the level falls
+    release: f32,
+    /// How long in seconds the peak holds before it decays
+    hold: f32,
+    /// How fast the peak decays in units per second, in the same units as the
+    /// level
+    peak_decay: f32,
+    /// The smoothing coefficients for updates at `rate`
+    attack_coeff: f32,
+    release_coeff: f32,
+    /// The number of updates per second that [`Ballistics::process`] assumes
+    rate: f32,
+    value: f32,
+    peak: f32,
+    /// The time in seconds left before the peak starts to decay
+    hold_remaining: f32,
+}
+
+impl Ballistics {
+    /// Creates ballistics with attack and release time constants in seconds,
+    /// updated 60 times per second
+    pub fn new(attack: f32, release: f32) -> Self {
+        let mut ballistics = Self {
+            attack: attack.max(0f32),
+            release: release.max(0f32),
+            hold: 0f32,
+            peak_decay: f32::INFINITY,
+            attack_coeff: 0f32,
+            release_coeff: 0f32,
+            rate: 60f32,
+            value: 0f32,
+            peak: 0f32,
+            hold_remaining: 0f32,
+        };
+        ballistics.set_rate(60f32);
+        ballistics
+    }
+
+    /// Holds peaks for `hold` seconds, then lets them fall by `decay` units
+    /// per second
+    pub fn with_peak_hold(mut self, hold: f32, decay: f32) -> Self {
+        self.hold = hold.max(0f32);
+        self.peak_decay = decay.max(0f32);
+        self
+    }
+
+    /// Sets the number of updates per second, such as the sample rate or the
+    /// frame rate
+    pub fn with_rate(mut self, rate: f32) -> Self {
+        self.set_rate(rate);
+        self
+    }
+
+    /// Sets the number of updates per second assumed by
+    /// [`process`](Ballistics::process)
+    pub fn set_rate(&mut self, rate: f32) {
+        self.rate = rate.max(f32::EPSILON);
+        self.attack_coeff = coefficient(self.attack, self.rate.recip());
+        self.release_coeff = coefficient(self.release, self.rate.recip());
+    }
+
+    /// Moves towards `input` by one update at the configured rate and returns
+    /// the smoothed level
+    pub fn process(&mut self, input: f32) -> f32 {
+        let coeff = if input > self.value {
+            self.attack_coeff
+        } else {
+            self.release_coeff
+        };
+        self.step(input, coeff, self.rate.recip())
+    }
+
+    /// Moves towards `input` by `elapsed` seconds and returns the smoothed
+    /// level, for updates that don't arrive at a steady rate
+    pub fn advance(&mut self, input: f32, elapsed: f32) -> f32 {
+        let time = if input > self.value {
+            self.attack
+        } else {
+            self.release
+        };
+        self.step(input, coefficient(time, elapsed), elapsed)
+    }
+
+    fn step(&mut self, input: f32, coeff: f32, elapsed: f32) -> f32 {
+        self.value = input + (self.value - input) * coeff;
+        if self.value >= self.peak {
+            self.peak = self.value;
+            self.hold_remaining = self.hold;
+        } else if self.hold_remaining > 0f32 {
+            self.hold_remaining -= elapsed;
+        } else {
+            self.peak = (self.peak - self.peak_decay * elapsed).max(self.value);
+        }
+        self.value
+    }
+
+    /// The smoothed level
+    pub fn value(&self) -> f32 {
+        self.value
+    }
+
+    /// The held peak level
+    pub fn peak(&self) -> f32 {
+        self.peak
+    }
+
+    /// Drops the held peak to the smoothed level, as when the user clears a
+    /// meter's peak hold, leaving the level where it is
+    pub fn reset_peak(&mut self) {
+        self.peak = self.value;
+        self.hold_remaining = 0f32;
+    }
+
+    /// Jumps straight to `value`, clearing the held peak
+    pub fn reset(&mut self, value: f32) {
+        self.value = value;
+        self.peak = value;
+        self.hold_remaining = 0f32;
+    }
+}
+
+/// Gets the one pole coefficient for a time constant over `elapsed` seconds,
+/// where a time of zero follows the input immediately
+fn coefficient(time: f32, elapsed: f32) -> f32 {
+    if time <= 0f32 {
+        0f32
+    } else {
+        (-elapsed / time).exp()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use assert_approx_eq::assert_approx_eq;
+
+    #[test]
+    fn smooths_attack_and_release() {
+        let mut ballistics = Ballistics::new(0f32, 0.1f32).with_rate(1000f32);
+        assert_eq!(ballistics.process(1f32), 1f32);
+        // One time constant falls to 1/e
+        for _ in 0..100 {
+            ballistics.process(0f32);
+        }
+        assert_approx_eq!(ballistics.value(), (-1f32).exp(), 1e-3f32);
+    }
+
+    #[test]
+    fn independent_of_rate() {
+        let mut fast = Ballistics::new(0.05f32, 0.2f32).with_rate(48000f32);
+        let mut slow = Ballistics::new(0.05f32, 0.2f32).with_rate(60f32);
+        for _ in 0..48000 / 10 {
+            fast.process(1f32);
+        }
+        for _ in 0..6 {
+            slow.process(1f32);
+        }
+        assert_approx_eq!(fast.value(), slow.value(), 1e-3f32);
+        let mut varying = Ballistics::new(0.05f32, 0.2f32);
+        varying.advance(1f32, 0.03f32);
+        varying.advance(1f32, 0.07f32);
+        assert_approx_eq!(varying.value(), slow.value(), 1e-3f32);
+    }
+
+    #[test]
+    fn holds_and_decays_peaks() {
+        let mut ballistics = Ballistics::new(0f32, 0f32).with_peak_hold(0.5f32, 2f32);
+        ballistics.advance(1f32, 0.1f32);
+        ballistics.advance(0f32, 0.25f32);
+        ballistics.advance(0f32, 0.25f32);
+        assert_eq!(ballistics.value(), 0f32);
+        assert_eq!(ballistics.peak(), 1f32);
+        // Hold is over, so the peak falls by 2 per second
+        ballistics.advance(0f32, 0.25f32);
+        assert_approx_eq!(ballistics.peak(), 0.5f32);
+        ballistics.advance(0f32, 1f32);
+        assert_eq!(ballistics.peak(), 0f32);
+    }
+}
@@ -0,0 +1,110 @@
+//! A timebase of beats divided into steps, shared by editors that line up
+//! with the host's tempo
+
+use crate::units::{beats_to_seconds, seconds_to_beats};
+use crate::{CurvePoint, CurvePoints};
+
+/// A tempo and a division of its beats into steps, so that a step sequence
+/// and an envelope can be edited against the same grid. A sequence of step
+/// values becomes an envelope with [`BeatGrid::stepped_envelope`], to draw
+/// steps and refine them as a curve.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct BeatGrid {
+    /// The tempo in beats per minute
+    pub bpm: f32,
+    /// How many steps each beat is divided into, such as `4` for sixteenth
+    /// notes in 4/4
+    pub steps_per_beat: u32,
+}
+
+impl Default for BeatGrid {
+    fn default() -> Self {
+        Self {
+            bpm: 120f32,
+            steps_per_beat: 4,
+        }
+    }
+}
+
+impl BeatGrid {
+    pub fn new(bpm: f32, steps_per_beat: u32) -> Self {
+        Self {
+            bpm,
+            steps_per_beat,
+        }
+    }
+
+    /// The length of a step in seconds
+    pub fn step_length(&self) -> f32 {
+        beats_to_seconds(1f32 / self.steps_per_beat.max(1) as f32, self.bpm)
+    }
+
+    /// The time in seconds that the step at `index` starts at
+    pub fn step_time(&self, index: usize) -> f32 {
+        index as f32 * self.step_length()
+    }
+
+    /// The index of the step playing at `seconds`
+    pub fn step_at(&self, seconds: f32) -> usize {
+        let steps = seconds_to_beats(seconds, self.bpm) * self.steps_per_beat.max(1) as f32;
+        steps.max(0f32).floor() as usize
+    }
+
+    /// Builds an envelope that holds the value of each step for the length
+    /// of the step, then jumps to the next one `gap` seconds before it
+    /// starts, since the times of envelope points must increase. The
+    /// envelope starts at `0` and ends where the last step does.
+    pub fn stepped_envelope<T: Default>(&self, steps: &[f32], gap: f32) -> CurvePoints<T> {
+        let length = self.step_length();
+        let gap = gap.min(length / 2f32);
+        let mut points = Vec::with_capacity(steps.len() * 2);
+        for (index, value) in steps.iter().enumerate() {
+            let start = self.step_time(index);
+            let end = if index + 1 == steps.len() {
+                start + length
+            } else {
+                start + length - gap
+            };
+            points.push(CurvePoint::new(start, *value, 0f32, T::default()));
+            points.push(CurvePoint::new(end, *value, 0f32, T::default()));
+        }
+        CurvePoints::new(points)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use assert_approx_eq::assert_approx_eq;
+
+    #[test]
+    fn divides_beats_into_steps() {
+        // Sixteenth notes at 120 BPM
+        let grid = BeatGrid::default();
+        assert_approx_eq!(grid.step_length(), 0.125f32);
+        assert_approx_eq!(grid.step_time(3), 0.375f32);
+        assert_eq!(grid.step_at(0.374f32), 2);
+        assert_eq!(grid.step_at(0.376f32), 3);
+        assert_eq!(grid.step_at(-1f32), 0);
+    }
+
+    #[test]
+    fn builds_stepped_envelopes() {
+        let grid = BeatGrid::new(60f32, 1);
+        let points: CurvePoints = grid.stepped_envelope(&[0.5f32, 1f32, 0f32], 0.01f32);
+        let expected = [
+            (0f32, 0.5f32),
+            (0.99f32, 0.5f32),
+            (1f32, 1f32),
+            (1.99f32, 1f32),
+            (2f32, 0f32),
+            (3f32, 0f32),
+        ];
+        assert_eq!(points.len(), expected.len());
+        for (point, (x, y)) in points.iter().zip(expected) {
+            assert_approx_eq!(point.x, x);
+            assert_eq!(point.y, y);
+        }
+        assert!(points.validate(0f32..=3f32, 0f32..=1f32).is_ok());
+    }
+}
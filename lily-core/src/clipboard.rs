@@ -0,0 +1,99 @@
+//! The JSON text curves are copied to the clipboard as, so shapes can be
+//! moved between plugin instances or shared as text.
+
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+
+use crate::CurvePoints;
+
+/// What a copied curve is, so an envelope isn't pasted into an EQ
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CurveKind {
+    /// The points of an MSEG, as `(seconds, value)`
+    Envelope,
+    /// The breakpoints of a band curve, as `(hz, value)`
+    Bands,
+}
+
+/// The JSON text of a copied curve
+#[derive(Serialize, Deserialize)]
+struct CurveClip<P> {
+    kind: CurveKind,
+    points: P,
+}
+
+impl<T: Serialize> CurvePoints<T> {
+    /// Writes the points as the JSON text copied to the clipboard
+    pub fn to_clipboard(&self, kind: CurveKind) -> String {
+        let clip = CurveClip { kind, points: self };
+        serde_json::to_string_pretty(&clip).unwrap_or_default()
+    }
+}
+
+impl<T: DeserializeOwned + Default> CurvePoints<T> {
+    /// Reads points copied with [`CurvePoints::to_clipboard`]. Returns `None`
+    /// if the text isn't a curve of this `kind` or has points at the same
+    /// `x`. Points are sorted by `x` and values are clamped to `0..=1`.
+    pub fn from_clipboard(text: &str, kind: CurveKind) -> Option<Self> {
+        let clip: CurveClip<Self> = serde_json::from_str(text).ok()?;
+        let mut points = clip.points;
+        let finite = points
+            .iter()
+            .all(|p| p.x.is_finite() && p.y.is_finite() && p.curve.is_finite());
+        if clip.kind != kind || points.is_empty() || !finite {
+            return None;
+        }
+        points.sort();
+        if points.windows(2).any(|w| w[0].x == w[1].x) {
+            return None;
+        }
+        for point in points.iter_mut() {
+            point.y = point.y.clamp(0f32, 1f32);
+        }
+        Some(points)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn round_trips_through_text() {
+        let points: CurvePoints = CurvePoints::new(vec![
+            (0f32, 0f32).into(),
+            (0.5f32, 1f32, 0.25f32).into(),
+            (1f32, 0f32).into(),
+        ]);
+        let text = points.to_clipboard(CurveKind::Envelope);
+        let pasted = CurvePoints::<()>::from_clipboard(&text, CurveKind::Envelope).unwrap();
+        let values: Vec<(f32, f32, f32)> = pasted.iter().map(|p| (p.x, p.y, p.curve)).collect();
+        assert_eq!(
+            values,
+            vec![
+                (0f32, 0f32, 0f32),
+                (0.5f32, 1f32, 0.25f32),
+                (1f32, 0f32, 0f32)
+            ]
+        );
+        assert!(CurvePoints::<()>::from_clipboard(&text, CurveKind::Bands).is_none());
+    }
+
+    #[test]
+    fn cleans_up_pasted_text() {
+        let text = r#"{"kind": "bands", "points": [
+            {"x": 1000.0, "y": 2.0, "curve": 0.0},
+            {"x": 100.0, "y": 0.5, "curve": 0.0}
+        ]}"#;
+        let pasted = CurvePoints::<()>::from_clipboard(text, CurveKind::Bands).unwrap();
+        assert_eq!(pasted[0].x, 100f32);
+        assert_eq!(pasted[1].y, 1f32);
+        let doubled = r#"{"kind": "bands", "points": [
+            {"x": 100.0, "y": 0.0, "curve": 0.0},
+            {"x": 100.0, "y": 1.0, "curve": 0.0}
+        ]}"#;
+        assert!(CurvePoints::<()>::from_clipboard(doubled, CurveKind::Bands).is_none());
+        assert!(CurvePoints::<()>::from_clipboard("hello", CurveKind::Bands).is_none());
+    }
+}
@@ -0,0 +1,931 @@
+use glam::Vec2;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::ops::{Deref, DerefMut, RangeInclusive};
+
+/// A list of [`CurvePoint`]s sorted by `x`, each carrying a user payload of
+/// type `T`
+#[derive(Serialize, Deserialize, Clone)]
+#[serde(bound(deserialize = "T: Deserialize<'de> + Default"))]
+pub struct CurvePoints<T = ()>(pub Vec<CurvePoint<T>>);
+
+impl<T> CurvePoints<T> {
+    pub fn new(points: Vec<CurvePoint<T>>) -> Self {
+        Self(points)
+    }
+
+    /// Iterates over each pair of neighbouring points as `(start, end,
+    /// curve)`, where `curve` is the curve between them
+    pub fn segments(&self) -> impl Iterator<Item = (&CurvePoint<T>, &CurvePoint<T>, f32)> {
+        self.0.windows(2).map(|w| (&w[0], &w[1], w[1].curve))
+    }
+
+    /// Gets the points either side of the point at `index`, if any
+    pub fn neighbours(&self, index: usize) -> (Option<&CurvePoint<T>>, Option<&CurvePoint<T>>) {
+        let previous = index.checked_sub(1).and_then(|i| self.0.get(i));
+        (previous, self.0.get(index + 1))
+    }
+
+    /// Inserts a point after the points with a smaller `x`, keeping the
+    /// points sorted, and returns its index. Returns `None` without inserting
+    /// the point if another point has the same `x`, or its `x` is NaN, since
+    /// times must be strictly increasing.
+    pub fn insert_sorted(&mut self, point: CurvePoint<T>) -> Option<usize> {
+        if point.x.is_nan() {
+            return None;
+        }
+        let index = self.0.partition_point(|p| p.x < point.x);
+        if self.0.get(index).map(|p| p.x) == Some(point.x) {
+            return None;
+        }
+        self.0.insert(index, point);
+        self.debug_assert_sorted();
+        Some(index)
+    }
+
+    /// Clamps a new position for the point at `index` so that it stays at
+    /// least `min_distance` away from its neighbours on the x axis, within
+    /// `x_range`, and with a `y` in `0..=1`. Returns `None` if there is no
+    /// point at `index`.
+    pub fn clamp_point(
+        &self,
+        index: usize,
+        to: Vec2,
+        min_distance: f32,
+        x_range: RangeInclusive<f32>,
+    ) -> Option<Vec2> {
+        self.0.get(index)?;
+        let (previous, next) = self.neighbours(index);
+        let left_bound = previous
+            .map(|p| p.x + min_distance)
+            .unwrap_or(*x_range.start());
+        let right_bound = next
+            .map(|p| p.x - min_distance)
+            .unwrap_or(*x_range.end())
+            .max(left_bound);
+        Some(Vec2::new(
+            to.x.clamp(left_bound, right_bound),
+            to.y.clamp(0f32, 1f32),
+        ))
+    }
+
+    /// Finds where a point inserted at `at` goes: the index it takes, keeping
+    /// the points sorted, and its position clamped to stay at least
+    /// `min_distance` away from its neighbours on the x axis, within
+    /// `x_range`, and with a `y` in `0..=1`. Returns `None` if the gap
+    /// between the neighbours is too narrow for another point.
+    pub fn insertion_point(
+        &self,
+        at: Vec2,
+        min_distance: f32,
+        x_range: RangeInclusive<f32>,
+    ) -> Option<(usize, Vec2)> {
+        let x = at.x.clamp(*x_range.start(), *x_range.end());
+        let index = self.0.partition_point(|p| p.x <= x);
+        let left_bound = match index.checked_sub(1) {
+            Some(previous) => self.0[previous].x + min_distance,
+            None => *x_range.start(),
+        };
+        let right_bound = self
+            .0
+            .get(index)
+            .map_or(*x_range.end(), |next| next.x - min_distance);
+        if left_bound > right_bound {
+            return None;
+        }
+        Some((
+            index,
+            Vec2::new(x.clamp(left_bound, right_bound), at.y.clamp(0f32, 1f32)),
+        ))
+    }
+
+    /// Moves the point at `index` as close to `to` as [`clamp_point`] allows
+    /// and returns where it ended up
+    ///
+    /// [`clamp_point`]: CurvePoints::clamp_point
+    pub fn try_move_point(
+        &mut self,
+        index: usize,
+        to: Vec2,
+        min_distance: f32,
+        x_range: RangeInclusive<f32>,
+    ) -> Option<Vec2> {
+        let clamped = self.clamp_point(index, to, min_distance, x_range)?;
+        let point = &mut self.0[index];
+        point.x = clamped.x;
+        point.y = clamped.y;
+        self.debug_assert_sorted();
+        Some(clamped)
+    }
+
+    /// Stretches or squeezes the points within `region` in time so that the
+    /// region ends at `end`, moving each point in proportion to how far into
+    /// the region it is. The points before and after the region stay where
+    /// they are, so `end` is clamped to keep every point at least
+    /// `min_distance` away from its neighbours and within `x_range`. Returns
+    /// where the region ended up ending.
+    pub fn stretch_region(
+        &mut self,
+        region: RangeInclusive<f32>,
+        end: f32,
+        min_distance: f32,
+        x_range: RangeInclusive<f32>,
+    ) -> f32 {
+        let start = *region.start();
+        let width = region.end() - start;
+        if width <= 0f32 {
+            return *region.end();
+        }
+
+        // The scales of the region that keep the points apart
+        let mut min_scale = min_distance / width;
+        let mut max_scale = f32::INFINITY;
+        for pair in self.0.windows(2) {
+            let (a, b) = (pair[0].x, pair[1].x);
+            match (region.contains(&a), region.contains(&b)) {
+                (true, true) => min_scale = min_scale.max(min_distance / (b - a)),
+                (false, true) if b > start => {
+                    min_scale = min_scale.max((a + min_distance - start) / (b - start));
+                }
+                (true, false) if a > start => {
+                    max_scale = max_scale.min((b - min_distance - start) / (a - start));
+                }
+                _ => (),
+            }
+        }
+        if let Some(last) = self.0.iter().rev().find(|p| region.contains(&p.x)) {
+            if last.x > start {
+                max_scale = max_scale.min((x_range.end() - start) / (last.x - start));
+            }
+        }
+        if min_scale > max_scale {
+            return *region.end();
+        }
+
+        let scale = ((end - start) / width).clamp(min_scale, max_scale);
+        for point in self.0.iter_mut().filter(|p| region.contains(&p.x)) {
+            point.x = start + (point.x - start) * scale;
+        }
+        self.debug_assert_sorted();
+        start + width * scale
+    }
+
+    /// Moves the points at `indices` together by `offset`, as when several
+    /// selected points are dragged at once. The points that don't move stay
+    /// where they are, so the offset in time is clamped to keep every moved
+    /// point at least `min_distance` away from them and within `x_range`,
+    /// keeping the moved points the same distance apart. Each `y` is clamped
+    /// to `0..=1` on its own, so points reaching the top or bottom stop there
+    /// while the rest keep moving. Returns the offset in time the points
+    /// moved by.
+    pub fn move_group(
+        &mut self,
+        indices: &[usize],
+        offset: Vec2,
+        min_distance: f32,
+        x_range: RangeInclusive<f32>,
+    ) -> f32 {
+        let len = self.0.len();
+        let moved: Vec<bool> = (0..len).map(|i| indices.contains(&i)).collect();
+        let (mut min_x, mut max_x) = (f32::NEG_INFINITY, f32::INFINITY);
+        for index in 0..len {
+            if !moved[index] {
+                continue;
+            }
+            // Only the ends of each run of moved points can meet a point
+            // that stays put
+            if index == 0 || !moved[index - 1] {
+                let bound = match index.checked_sub(1) {
+                    Some(previous) => self.0[previous].x + min_distance,
+                    None => *x_range.start(),
+                };
+                min_x = min_x.max(bound - self.0[index].x);
+            }
+            if index + 1 == len || !moved[index + 1] {
+                let bound = match self.0.get(index + 1) {
+                    Some(next) => next.x - min_distance,
+                    None => *x_range.end(),
+                };
+                max_x = max_x.min(bound - self.0[index].x);
+            }
+        }
+        if min_x > max_x || !moved.contains(&true) {
+            return 0f32;
+        }
+
+        let dx = offset.x.clamp(min_x, max_x);
+        for (point, _) in self.0.iter_mut().zip(&moved).filter(|(_, moved)| **moved) {
+            point.x += dx;
+            point.y = (point.y + offset.y).clamp(0f32, 1f32);
+        }
+        self.debug_assert_sorted();
+        dx
+    }
+
+    /// Gets the point linked to the point at `index` of another envelope with
+    /// `len` points, which is at time `x` there, as when the envelopes of
+    /// several parameters are edited in step: the point at the same index
+    /// when both have as many points, or else the point nearest in time.
+    /// Returns `None` if there are no points.
+    pub fn linked_point(&self, index: usize, len: usize, x: f32) -> Option<usize> {
+        if len == self.0.len() && index < len {
+            return Some(index);
+        }
+        self.0
+            .iter()
+            .enumerate()
+            .min_by(|(_, a), (_, b)| (a.x - x).abs().total_cmp(&(b.x - x).abs()))
+            .map(|(index, _)| index)
+    }
+
+    /// Mirrors a time edit of another envelope with `len` points, whose point
+    /// at `index` moved from time `from` to `to`, by moving the point linked
+    /// to it by [`linked_point`] as far in time, as far as [`clamp_point`]
+    /// allows. Its value stays the same. Returns the index of the point and
+    /// where it ended up.
+    ///
+    /// [`linked_point`]: CurvePoints::linked_point
+    /// [`clamp_point`]: CurvePoints::clamp_point
+    pub fn follow_time(
+        &mut self,
+        index: usize,
+        len: usize,
+        from: f32,
+        to: f32,
+        min_distance: f32,
+        x_range: RangeInclusive<f32>,
+    ) -> Option<(usize, Vec2)> {
+        let linked = self.linked_point(index, len, from)?;
+        let point = &self.0[linked];
+        let moved = Vec2::new(point.x + to - from, point.y);
+        let clamped = self.try_move_point(linked, moved, min_distance, x_range)?;
+        Some((linked, clamped))
+    }
+
+    /// Sorts the points by `x`, keeping points with equal `x` in their current
+    /// order
+    pub fn sort(&mut self) {
+        self.0.sort_by(|a, b| a.x.total_cmp(&b.x));
+    }
+
+    /// Gets the index of the first point whose payload matches `predicate`.
+    /// Use this to find a point again after points have been inserted or
+    /// removed before it.
+    pub fn index_of(&self, predicate: impl Fn(&T) -> bool) -> Option<usize> {
+        self.0.iter().position(|p| predicate(&p.data))
+    }
+
+    fn debug_assert_sorted(&self) {
+        debug_assert!(
+            self.0.windows(2).all(|w| w[0].x < w[1].x),
+            "curve point times must be strictly increasing"
+        );
+    }
+
+    /// Iterates over the indices and tags of the tagged points
+    pub fn tagged(&self) -> impl Iterator<Item = (usize, PointTag)> + '_ {
+        self.0
+            .iter()
+            .enumerate()
+            .filter_map(|(index, point)| point.tag.map(|tag| (index, tag)))
+    }
+
+    /// Gets the value of the curve at `x`, interpolating between the points
+    /// either side of it along the curve of their segment as the MSEG graph
+    /// draws them, and holding the first and last values beyond the ends.
+    /// Returns `None` if there are no points.
+    pub fn value_at(&self, x: f32) -> Option<f32> {
+        let (first, last) = (self.0.first()?, self.0.last()?);
+        if x <= first.x {
+            return Some(first.y);
+        }
+        if x >= last.x {
+            return Some(last.y);
+        }
+        let (start, end, curve) = self.segments().find(|(_, end, _)| x <= end.x)?;
+        let t = (x - start.x) / (end.x - start.x);
+        Some(lerp(start.y, end.y, curve_shape(t, curve)))
+    }
+
+    /// Gets the point halfway through the time of the segment ending at the
+    /// point at `index`, on its curve, where editors put the handle that
+    /// bends it. Returns `None` for the first point, which ends no segment.
+    pub fn segment_midpoint(&self, index: usize) -> Option<Vec2> {
+        let start = self.0.get(index.checked_sub(1)?)?;
+        let end = self.0.get(index)?;
+        Some(Vec2::new(
+            lerp(start.x, end.x, 0.5f32),
+            lerp(start.y, end.y, curve_shape(0.5f32, end.curve)),
+        ))
+    }
+
+    /// Gets the curve that bends the segment ending at the point at `index`
+    /// through the value `y` halfway through its time, within
+    /// [`MAX_CURVE`] either way, for dragging the handle at its midpoint.
+    /// Returns `None` for the first point and for flat segments, which no
+    /// curve bends.
+    pub fn curve_through(&self, index: usize, y: f32) -> Option<f32> {
+        let start = self.0.get(index.checked_sub(1)?)?;
+        let end = self.0.get(index)?;
+        if start.y == end.y {
+            return None;
+        }
+        // Halfway through, a segment is `1 / (e^(curve / 2) + 1)` of the way
+        // from its start value to its end value
+        let fraction = ((y - start.y) / (end.y - start.y)).clamp(f32::EPSILON, 1f32 - f32::EPSILON);
+        Some((2f32 * (1f32 / fraction - 1f32).ln()).clamp(-MAX_CURVE, MAX_CURVE))
+    }
+
+    /// Gets the index of the segment containing `x`, counting the segment
+    /// between points `i` and `i + 1` as segment `i`. Returns `None` if `x`
+    /// is outside of the points.
+    pub fn segment_index_at(&self, x: f32) -> Option<usize> {
+        self.segments()
+            .position(|(start, end, _)| (start.x..=end.x).contains(&x))
+    }
+
+    /// Checks that every point is finite, within `x_range` and `y_range`,
+    /// and after the point before it, returning the first problem found
+    pub fn validate(
+        &self,
+        x_range: RangeInclusive<f32>,
+        y_range: RangeInclusive<f32>,
+    ) -> Result<(), CurveError> {
+        for (index, point) in self.0.iter().enumerate() {
+            if !(point.x.is_finite() && point.y.is_finite() && point.curve.is_finite()) {
+                return Err(CurveError::NotFinite { index });
+            }
+            if !x_range.contains(&point.x) || !y_range.contains(&point.y) {
+                return Err(CurveError::OutOfRange { index });
+            }
+            if index > 0 && self.0[index - 1].x >= point.x {
+                return Err(CurveError::Unsorted { index });
+            }
+        }
+        Ok(())
+    }
+}
+
+impl<T: Clone> CurvePoints<T> {
+    /// Morphs from the envelope `a` to the envelope `b`, `t` of the way, with
+    /// `t` clamped to `0..=1`.
+    ///
+    /// Envelopes with as many points are morphed point by point, moving each
+    /// point and its curve towards the one at the same index. Otherwise both
+    /// are resampled at every `x` either has a point at and the values are
+    /// morphed, which keeps the shapes but not the curves of `a` and `b`.
+    /// Points take their user data and tags from the nearer envelope, `a`
+    /// before halfway and `b` after.
+    pub fn morph(a: &Self, b: &Self, t: f32) -> Self {
+        let t = t.clamp(0f32, 1f32);
+        let nearer = if t < 0.5f32 { a } else { b };
+        if a.len() == b.len() {
+            let points = a.iter().zip(b.iter()).zip(nearer.iter());
+            return Self(
+                points
+                    .map(|((from, to), source)| CurvePoint {
+                        x: lerp(from.x, to.x, t),
+                        y: lerp(from.y, to.y, t),
+                        curve: lerp(from.curve, to.curve, t),
+                        data: source.data.clone(),
+                        tag: source.tag,
+                    })
+                    .collect(),
+            );
+        }
+        if a.is_empty() || b.is_empty() {
+            return nearer.clone();
+        }
+
+        let mut xs: Vec<f32> = a.iter().chain(b.iter()).map(|p| p.x).collect();
+        xs.sort_by(f32::total_cmp);
+        xs.dedup();
+        Self(
+            xs.into_iter()
+                .map(|x| {
+                    // The point of the nearer envelope at or before `x`
+                    let index = nearer.iter().rposition(|p| p.x <= x).unwrap_or(0);
+                    let source = &nearer[index];
+                    CurvePoint {
+                        x,
+                        y: lerp(
+                            a.value_at(x).unwrap_or_default(),
+                            b.value_at(x).unwrap_or_default(),
+                            t,
+                        ),
+                        curve: 0f32,
+                        data: source.data.clone(),
+                        tag: source.tag.filter(|_| source.x == x),
+                    }
+                })
+                .collect(),
+        )
+    }
+}
+
+/// The strongest curve [`CurvePoints::curve_through`] bends a segment with,
+/// either way
+pub const MAX_CURVE: f32 = 20f32;
+
+/// Shapes `t`, how far through the time of a segment, into how far from its
+/// start value to its end value the segment is there, for the `curve` of the
+/// segment. A curve of `0` is a straight line, positive curves rise slowly
+/// and then quickly like an exponential, and negative curves rise quickly and
+/// then slowly like a logarithm.
+pub fn curve_shape(t: f32, curve: f32) -> f32 {
+    if curve.abs() < 1e-6f32 {
+        return t;
+    }
+    (curve * t).exp_m1() / curve.exp_m1()
+}
+
+/// Interpolates from `a` to `b`, reaching each exactly at the ends
+fn lerp(a: f32, b: f32, t: f32) -> f32 {
+    a * (1f32 - t) + b * t
+}
+
+/// What is wrong with a list of curve points that a widget can't show, found
+/// by [`CurvePoints::validate`]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CurveError {
+    /// The point at `index` has a NaN or infinite position or curve
+    NotFinite { index: usize },
+    /// The point at `index` is outside of the ranges the widget shows
+    OutOfRange { index: usize },
+    /// The point at `index` isn't after the point before it
+    Unsorted { index: usize },
+}
+
+impl fmt::Display for CurveError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CurveError::NotFinite { index } => write!(f, "point {index} is not a number"),
+            CurveError::OutOfRange { index } => write!(f, "point {index} is out of range"),
+            CurveError::Unsorted { index } => {
+                write!(f, "point {index} is not after the point before it")
+            }
+        }
+    }
+}
+
+impl std::error::Error for CurveError {}
+
+impl<T> Deref for CurvePoints<T> {
+    type Target = Vec<CurvePoint<T>>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl<T> DerefMut for CurvePoints<T> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.0
+    }
+}
+
+/// A point with an adjustable single-control exponential curve
+#[derive(Copy, Clone, serde::Serialize, serde::Deserialize)]
+#[serde(bound(deserialize = "T: Deserialize<'de> + Default"))]
+pub struct CurvePoint<T = ()> {
+    pub x: f32,
+    pub y: f32,
+    /// Defines the exponential curve between the current and last point
+    pub curve: f32,
+    /// Arbitrary user data that moves with the point, such as an ID linking
+    /// it to a host parameter
+    #[serde(default)]
+    pub data: T,
+    /// How editors mark the point, if it matters to the structure of the
+    /// curve
+    #[serde(default)]
+    pub tag: Option<PointTag>,
+}
+
+impl<T> CurvePoint<T> {
+    pub fn new(x: f32, y: f32, curve: f32, data: T) -> Self {
+        Self {
+            x,
+            y,
+            curve,
+            data,
+            tag: None,
+        }
+    }
+
+    /// Marks this point with `tag`
+    pub fn with_tag(self, tag: PointTag) -> Self {
+        Self {
+            tag: Some(tag),
+            ..self
+        }
+    }
+
+    /// Replaces the user data of this point
+    pub fn with_data<U>(self, data: U) -> CurvePoint<U> {
+        CurvePoint {
+            x: self.x,
+            y: self.y,
+            curve: self.curve,
+            data,
+            tag: self.tag,
+        }
+    }
+}
+
+/// A mark on a curve point that editors draw, so that breakpoints that matter
+/// to the structure of a curve stand out from the rest
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum PointTag {
+    /// Where a loop starts
+    LoopStart,
+    /// Where a loop ends
+    LoopEnd,
+    /// Where an envelope holds while a note is held
+    Sustain,
+    /// The boundary between two stages of an envelope
+    Stage,
+    /// A color of the user's choosing, as `0xRRGGBBAA`
+    Color(u32),
+}
+
+impl<T: Default> From<Vec2> for CurvePoint<T> {
+    fn from(v: Vec2) -> Self {
+        Self {
+            x: v.x,
+            y: v.y,
+            curve: 0f32,
+            data: T::default(),
+            tag: None,
+        }
+    }
+}
+
+impl<T: Default> From<(f32, f32)> for CurvePoint<T> {
+    fn from((x, y): (f32, f32)) -> Self {
+        Self {
+            x,
+            y,
+            curve: 0f32,
+            data: T::default(),
+            tag: None,
+        }
+    }
+}
+
+impl<T: Default> From<(f32, f32, f32)> for CurvePoint<T> {
+    fn from((x, y, curve): (f32, f32, f32)) -> Self {
+        Self {
+            x,
+            y,
+            curve,
+            data: T::default(),
+            tag: None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use assert_approx_eq::assert_approx_eq;
+
+    fn points() -> CurvePoints {
+        CurvePoints::new(vec![
+            (0f32, 0f32).into(),
+            (1f32, 1f32, 0.5f32).into(),
+            (2f32, 0f32, -0.5f32).into(),
+        ])
+    }
+
+    #[test]
+    fn iterates_segments() {
+        let points = points();
+        let segments: Vec<_> = points.segments().map(|(a, b, c)| (a.x, b.x, c)).collect();
+        assert_eq!(segments, vec![(0f32, 1f32, 0.5f32), (1f32, 2f32, -0.5f32)]);
+        assert_eq!(
+            CurvePoints::<()>::new(vec![(0f32, 0f32).into()])
+                .segments()
+                .count(),
+            0
+        );
+    }
+
+    #[test]
+    fn gets_neighbours() {
+        let points = points();
+        let (previous, next) = points.neighbours(0);
+        assert!(previous.is_none());
+        assert_eq!(next.map(|p| p.x), Some(1f32));
+        let (previous, next) = points.neighbours(2);
+        assert_eq!(previous.map(|p| p.x), Some(1f32));
+        assert!(next.is_none());
+    }
+
+    #[test]
+    fn inserts_sorted() {
+        let mut points = points();
+        assert_eq!(points.insert_sorted((1.5f32, 0.5f32).into()), Some(2));
+        assert_eq!(points.insert_sorted((3f32, 0f32).into()), Some(4));
+        let xs: Vec<f32> = points.iter().map(|p| p.x).collect();
+        assert_eq!(xs, vec![0f32, 1f32, 1.5f32, 2f32, 3f32]);
+    }
+
+    #[test]
+    fn rejects_inserts_at_the_time_of_another_point() {
+        let mut points = points();
+        assert_eq!(points.insert_sorted((1f32, 0.25f32).into()), None);
+        assert_eq!(points.insert_sorted((0f32, 1f32).into()), None);
+        assert_eq!(points.insert_sorted((f32::NAN, 1f32).into()), None);
+        assert_eq!(points.len(), 3);
+        assert_eq!(points[1].y, 1f32);
+    }
+
+    #[test]
+    fn moves_point_within_neighbours() {
+        let mut points = points();
+        let moved = points.try_move_point(1, Vec2::new(5f32, 2f32), 0.1f32, 0f32..=4f32);
+        assert_eq!(moved, Some(Vec2::new(1.9f32, 1f32)));
+        assert_eq!(points[1].x, 1.9f32);
+        let moved = points.try_move_point(0, Vec2::new(-1f32, 0.5f32), 0.1f32, 0f32..=4f32);
+        assert_eq!(moved, Some(Vec2::new(0f32, 0.5f32)));
+        assert_eq!(
+            points.try_move_point(3, Vec2::ZERO, 0.1f32, 0f32..=4f32),
+            None
+        );
+    }
+
+    #[test]
+    fn stretches_regions() {
+        let xs = |points: &CurvePoints| -> Vec<f32> { points.iter().map(|p| p.x).collect() };
+        let evenly = || CurvePoints::new((0..5).map(|x| (x as f32, 0f32).into()).collect());
+        let mut points = evenly();
+        let end = points.stretch_region(1f32..=3f32, 3.5f32, 0.1f32, 0f32..=8f32);
+        assert_eq!(end, 3.5f32);
+        assert_eq!(xs(&points), vec![0f32, 1f32, 2.25f32, 3.5f32, 4f32]);
+
+        // The region can't reach the point after it
+        let mut points = evenly();
+        let end = points.stretch_region(1f32..=3f32, 5f32, 0.1f32, 0f32..=8f32);
+        assert_approx_eq!(end, 3.9f32);
+        assert_approx_eq!(points[2].x, 2.45f32);
+
+        // or squeeze its points together
+        let mut points = evenly();
+        let end = points.stretch_region(1f32..=3f32, 1f32, 0.1f32, 0f32..=8f32);
+        assert_approx_eq!(end, 1.2f32);
+        assert_approx_eq!(points[2].x, 1.1f32);
+        assert_eq!(points[4].x, 4f32);
+
+        // Without a point after it, the region stays within the range
+        let mut points = evenly();
+        let end = points.stretch_region(2f32..=4f32, 10f32, 0.1f32, 0f32..=6f32);
+        assert_eq!(end, 6f32);
+        assert_eq!(xs(&points), vec![0f32, 1f32, 2f32, 4f32, 6f32]);
+    }
+
+    #[test]
+    fn moves_groups_of_points() {
+        let evenly = || {
+            CurvePoints::new(
+                (0..5)
+                    .map(|x| (x as f32, 0.5f32).into())
+                    .collect::<Vec<CurvePoint>>(),
+            )
+        };
+        let positions = |points: &CurvePoints| -> Vec<(f32, f32)> {
+            points.iter().map(|p| (p.x, p.y)).collect()
+        };
+        let mut points = evenly();
+        let dx = points.move_group(&[1, 2], Vec2::new(0.5f32, 0.25f32), 0.1f32, 0f32..=8f32);
+        assert_eq!(dx, 0.5f32);
+        assert_eq!(
+            positions(&points),
+            vec![
+                (0f32, 0.5f32),
+                (1.5f32, 0.75f32),
+                (2.5f32, 0.75f32),
+                (3f32, 0.5f32),
+                (4f32, 0.5f32)
+            ]
+        );
+
+        // Each run of points stops short of the points that stay put, and
+        // the values stop at the top
+        let mut points = evenly();
+        let dx = points.move_group(&[1, 3], Vec2::new(2f32, 1f32), 0.1f32, 0f32..=8f32);
+        assert_approx_eq!(dx, 0.9f32);
+        assert_approx_eq!(points[3].x, 3.9f32);
+        assert_eq!(points[1].y, 1f32);
+
+        // The last point stays within the range
+        let mut points = evenly();
+        let dx = points.move_group(&[3, 4], Vec2::new(-5f32, 0f32), 0.1f32, 0f32..=4.5f32);
+        assert_approx_eq!(dx, -0.9f32);
+        let dx = points.move_group(&[4], Vec2::new(5f32, 0f32), 0.1f32, 0f32..=4.5f32);
+        assert_approx_eq!(dx, 1.4f32);
+        assert_eq!(points.move_group(&[], Vec2::ONE, 0.1f32, 0f32..=8f32), 0f32);
+    }
+
+    #[test]
+    fn follows_linked_time_edits() {
+        let mut points: CurvePoints = CurvePoints::new(vec![
+            (0f32, 0f32).into(),
+            (1f32, 0.5f32).into(),
+            (2f32, 1f32).into(),
+        ]);
+        // Envelopes with as many points link the points at the same index
+        assert_eq!(points.linked_point(1, 3, 1.8f32), Some(1));
+        assert_eq!(
+            points.follow_time(1, 3, 1f32, 1.25f32, 0.1f32, 0f32..=4f32),
+            Some((1, Vec2::new(1.25f32, 0.5f32)))
+        );
+        // and otherwise the points nearest in time
+        assert_eq!(points.linked_point(3, 5, 1.8f32), Some(2));
+        assert_eq!(
+            points.follow_time(2, 5, 1.75f32, 1f32, 0.1f32, 0f32..=4f32),
+            Some((2, Vec2::new(1.35f32, 1f32)))
+        );
+        assert_eq!(
+            CurvePoints::<()>::new(Vec::new()).linked_point(0, 1, 0f32),
+            None
+        );
+    }
+
+    #[test]
+    fn keeps_data_with_points() {
+        let mut points = CurvePoints::new(vec![
+            CurvePoint::new(0f32, 0f32, 0f32, "a"),
+            CurvePoint::new(2f32, 0f32, 0f32, "b"),
+        ]);
+        points.insert_sorted(CurvePoint::new(1f32, 1f32, 0f32, "c"));
+        assert_eq!(points.index_of(|d| *d == "b"), Some(2));
+        points.remove(0);
+        assert_eq!(points.index_of(|d| *d == "b"), Some(1));
+        points[0].x = 3f32;
+        points.sort();
+        assert_eq!(points.index_of(|d| *d == "c"), Some(1));
+        assert_eq!(points.index_of(|d| *d == "a"), None);
+    }
+
+    #[test]
+    fn keeps_tags_with_points() {
+        let mut points = points();
+        points[2] = points[2].with_tag(PointTag::LoopEnd);
+        points
+            .insert_sorted(CurvePoint::from((0.5f32, 1f32)).with_tag(PointTag::Color(0xff0000ff)));
+        let tagged: Vec<_> = points.tagged().collect();
+        assert_eq!(
+            tagged,
+            vec![(1, PointTag::Color(0xff0000ff)), (3, PointTag::LoopEnd)]
+        );
+        assert_eq!(points[3].with_data(1u8).tag, Some(PointTag::LoopEnd));
+        // Points saved before tags existed are untagged
+        let point: CurvePoint = serde_json::from_str(r#"{"x":0.0,"y":1.0,"curve":0.0}"#).unwrap();
+        assert_eq!(point.tag, None);
+    }
+
+    #[test]
+    fn validates_points() {
+        let points = points();
+        assert_eq!(points.validate(0f32..=2f32, 0f32..=1f32), Ok(()));
+        assert_eq!(
+            points.validate(0f32..=1f32, 0f32..=1f32),
+            Err(CurveError::OutOfRange { index: 2 })
+        );
+        let mut unsorted = points.clone();
+        unsorted[2].x = 1f32;
+        assert_eq!(
+            unsorted.validate(0f32..=2f32, 0f32..=1f32),
+            Err(CurveError::Unsorted { index: 2 })
+        );
+        let mut nan = points;
+        nan[1].curve = f32::NAN;
+        assert_eq!(
+            nan.validate(0f32..=f32::INFINITY, 0f32..=1f32),
+            Err(CurveError::NotFinite { index: 1 })
+        );
+    }
+
+    #[test]
+    fn gets_values_between_points() {
+        let points = points();
+        assert_eq!(points.value_at(-1f32), Some(0f32));
+        // Along the curves of the segments, rising slowly and then falling
+        // quickly
+        assert_approx_eq!(points.value_at(0.25f32).unwrap(), 0.205248f32);
+        assert_approx_eq!(points.value_at(1.5f32).unwrap(), 0.437823f32);
+        assert_eq!(points.value_at(1f32), Some(1f32));
+        assert_eq!(points.value_at(3f32), Some(0f32));
+        assert_eq!(CurvePoints::<()>::new(Vec::new()).value_at(0f32), None);
+    }
+
+    #[test]
+    fn morphs_point_by_point() {
+        let a = points();
+        let b = CurvePoints::new(vec![
+            (0f32, 1f32).into(),
+            (2f32, 0f32, -0.5f32).into(),
+            (4f32, 1f32).into(),
+        ]);
+        let values = |points: &CurvePoints| -> Vec<(f32, f32, f32)> {
+            points.iter().map(|p| (p.x, p.y, p.curve)).collect()
+        };
+        assert_eq!(values(&CurvePoints::morph(&a, &b, 0f32)), values(&a));
+        assert_eq!(values(&CurvePoints::morph(&a, &b, 1f32)), values(&b));
+        assert_eq!(
+            values(&CurvePoints::morph(&a, &b, 0.5f32)),
+            vec![
+                (0f32, 0.5f32, 0f32),
+                (1.5f32, 0.5f32, 0f32),
+                (3f32, 0.5f32, -0.25f32)
+            ]
+        );
+    }
+
+    #[test]
+    fn morphs_by_resampling() {
+        let a = CurvePoints::new(vec![
+            CurvePoint::new(0f32, 0f32, 0f32, "a").with_tag(PointTag::Sustain),
+            CurvePoint::new(2f32, 1f32, 0f32, "a"),
+        ]);
+        let b = CurvePoints::new(vec![
+            CurvePoint::new(0f32, 1f32, 0f32, "b"),
+            CurvePoint::new(1f32, 0f32, 0f32, "b"),
+            CurvePoint::new(2f32, 1f32, 0f32, "b"),
+        ]);
+        let morphed = CurvePoints::morph(&a, &b, 0.25f32);
+        let values: Vec<(f32, f32)> = morphed.iter().map(|p| (p.x, p.y)).collect();
+        assert_eq!(
+            values,
+            vec![(0f32, 0.25f32), (1f32, 0.375f32), (2f32, 1f32)]
+        );
+        assert!(morphed.iter().all(|p| p.data == "a"));
+        assert_eq!(morphed[0].tag, Some(PointTag::Sustain));
+        assert_eq!(morphed[1].tag, None);
+        let morphed = CurvePoints::morph(&a, &b, 2f32);
+        assert!(morphed.iter().all(|p| p.data == "b" && p.tag.is_none()));
+    }
+
+    #[test]
+    fn finds_insertion_points() {
+        let points = points();
+        let insertion = |x, y| points.insertion_point(Vec2::new(x, y), 0.1f32, 0f32..=4f32);
+        assert_eq!(insertion(1.5f32, 2f32), Some((2, Vec2::new(1.5f32, 1f32))));
+        // Too close to a neighbour, and moved away from it
+        assert_eq!(
+            insertion(1.05f32, 0.5f32),
+            Some((2, Vec2::new(1.1f32, 0.5f32)))
+        );
+        assert_eq!(
+            insertion(0.95f32, 0.5f32),
+            Some((1, Vec2::new(0.9f32, 0.5f32)))
+        );
+        // After the last point, within the range
+        assert_eq!(insertion(9f32, 0f32), Some((3, Vec2::new(4f32, 0f32))));
+        // No room between points closer than twice the distance
+        let dense = CurvePoints::<()>::new(vec![(0f32, 0f32).into(), (0.15f32, 1f32).into()]);
+        assert_eq!(
+            dense.insertion_point(Vec2::new(0.1f32, 0.5f32), 0.1f32, 0f32..=1f32),
+            None
+        );
+    }
+
+    #[test]
+    fn gets_segment_at() {
+        let points = points();
+        assert_eq!(points.segment_index_at(0.5f32), Some(0));
+        assert_eq!(points.segment_index_at(1.5f32), Some(1));
+        assert_eq!(points.segment_index_at(3f32), None);
+    }
+
+    #[test]
+    fn shapes_curves() {
+        assert_eq!(curve_shape(0.25f32, 0f32), 0.25f32);
+        for curve in [-8f32, -0.5f32, 1e-7f32, 3f32] {
+            assert_approx_eq!(curve_shape(0f32, curve), 0f32);
+            assert_approx_eq!(curve_shape(1f32, curve), 1f32);
+        }
+        assert!(curve_shape(0.5f32, 3f32) < 0.5f32);
+        assert!(curve_shape(0.5f32, -3f32) > 0.5f32);
+    }
+
+    #[test]
+    fn bends_segments_through_midpoints() {
+        let mut points = points();
+        assert!(points.segment_midpoint(0).is_none());
+        let midpoint = points.segment_midpoint(1).unwrap();
+        assert_eq!(midpoint.x, 0.5f32);
+        assert_approx_eq!(midpoint.y, points.value_at(0.5f32).unwrap());
+        // Bending the rising segment below its straight line
+        let curve = points.curve_through(1, 0.2f32).unwrap();
+        assert!(curve > 0f32);
+        points[1].curve = curve;
+        assert_approx_eq!(points.segment_midpoint(1).unwrap().y, 0.2f32);
+        assert_eq!(points.curve_through(1, -1f32), Some(MAX_CURVE));
+        // Flat segments can't be bent
+        points[2].y = 1f32;
+        assert!(points.curve_through(2, 0.5f32).is_none());
+        assert!(points.curve_through(0, 0.5f32).is_none());
+    }
+}
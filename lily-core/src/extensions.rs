@@ -1,4 +1,4 @@
-//! Extensions for things not in vizia
+//! Extensions for ranges
 
 use std::ops::RangeInclusive;
 
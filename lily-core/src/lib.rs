@@ -0,0 +1,25 @@
+//! The data types behind lily's widgets, without the GUI.
+//!
+//! Curves, value mappings, unit conversions, meter ballistics, waveform peaks
+//! and spectrum analysis don't need vizia, so they live here where audio
+//! thread code can use them too, such as to evaluate the envelope an MSEG
+//! edits or to run meters with the same ballistics as the GUI. `lily`
+//! re-exports everything under the same paths as before, so GUI code keeps
+//! using `lily::util` and `lily::units`.
+
+#[cfg(feature = "fft")]
+pub mod analysis;
+mod ballistics;
+mod beat_grid;
+mod clipboard;
+mod curve_point;
+mod extensions;
+mod mapping;
+mod meter_channels;
+mod overs;
+mod peak_pyramid;
+pub mod units;
+pub use {
+    ballistics::*, beat_grid::*, clipboard::*, curve_point::*, extensions::*, mapping::*,
+    meter_channels::*, overs::*, peak_pyramid::*,
+};
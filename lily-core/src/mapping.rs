@@ -0,0 +1,240 @@
+//! Tapers between parameter values and the normalized `0..=1` positions
+//! widgets work in
+
+use std::ops::RangeInclusive;
+
+use crate::RangeExt;
+
+/// Maps a parameter value to a normalized position in `0..=1` and back
+pub trait ValueMapping {
+    /// Gets the normalized position of a value
+    fn normalize(&self, value: f32) -> f32;
+    /// Gets the value at a normalized position
+    fn denormalize(&self, normalized: f32) -> f32;
+    /// The number of evenly spaced positions the mapping snaps to, for
+    /// widgets that draw a tick at each, or `None` if it's continuous
+    fn steps(&self) -> Option<usize> {
+        None
+    }
+}
+
+/// A linear mapping. Reversed ranges such as `1f32..=-1f32` are allowed and
+/// flip the direction of the widget.
+impl ValueMapping for RangeInclusive<f32> {
+    fn normalize(&self, value: f32) -> f32 {
+        self.map(value).clamp(0f32, 1f32)
+    }
+
+    fn denormalize(&self, normalized: f32) -> f32 {
+        self.start() + normalized.clamp(0f32, 1f32) * self.width()
+    }
+}
+
+/// A logarithmic mapping, such as for frequencies. Both ends of the range must
+/// be positive.
+#[derive(Clone, Debug, PartialEq)]
+pub struct LogMapping {
+    pub range: RangeInclusive<f32>,
+}
+
+impl LogMapping {
+    pub fn new(range: RangeInclusive<f32>) -> Self {
+        debug_assert!(
+            *range.start() > 0f32 && *range.end() > 0f32,
+            "logarithmic ranges must be positive"
+        );
+        Self { range }
+    }
+}
+
+impl ValueMapping for LogMapping {
+    fn normalize(&self, value: f32) -> f32 {
+        let (start, end) = (*self.range.start(), *self.range.end());
+        ((value / start).ln() / (end / start).ln()).clamp(0f32, 1f32)
+    }
+
+    fn denormalize(&self, normalized: f32) -> f32 {
+        let (start, end) = (*self.range.start(), *self.range.end());
+        start * (end / start).powf(normalized.clamp(0f32, 1f32))
+    }
+}
+
+/// A power curve mapping. An exponent above `1.0` gives more of the widget to
+/// the start of the range, such as for times and gains.
+#[derive(Clone, Debug, PartialEq)]
+pub struct PowerMapping {
+    pub range: RangeInclusive<f32>,
+    pub exponent: f32,
+}
+
+impl PowerMapping {
+    pub fn new(range: RangeInclusive<f32>, exponent: f32) -> Self {
+        Self { range, exponent }
+    }
+}
+
+impl ValueMapping for PowerMapping {
+    fn normalize(&self, value: f32) -> f32 {
+        self.range.normalize(value).powf(self.exponent.recip())
+    }
+
+    fn denormalize(&self, normalized: f32) -> f32 {
+        self.range
+            .denormalize(normalized.clamp(0f32, 1f32).powf(self.exponent))
+    }
+}
+
+/// Snaps another mapping to a number of evenly spaced steps, such as for
+/// choices or semitones
+#[derive(Clone, Debug, PartialEq)]
+pub struct SteppedMapping<M> {
+    pub mapping: M,
+    /// The number of values that can be chosen, at least `2`
+    pub steps: usize,
+}
+
+impl<M: ValueMapping> SteppedMapping<M> {
+    pub fn new(mapping: M, steps: usize) -> Self {
+        Self {
+            mapping,
+            steps: steps.max(2),
+        }
+    }
+
+    fn snap(&self, normalized: f32) -> f32 {
+        let intervals = (self.steps - 1) as f32;
+        (normalized.clamp(0f32, 1f32) * intervals).round() / intervals
+    }
+}
+
+impl<M: ValueMapping> ValueMapping for SteppedMapping<M> {
+    fn normalize(&self, value: f32) -> f32 {
+        self.snap(self.mapping.normalize(value))
+    }
+
+    fn denormalize(&self, normalized: f32) -> f32 {
+        self.mapping.denormalize(self.snap(normalized))
+    }
+
+    fn steps(&self) -> Option<usize> {
+        Some(self.steps)
+    }
+}
+
+/// Snaps another mapping to a detent value, such as the center of a pan or a
+/// bipolar modulation amount, when a position is within `threshold` of the
+/// detent's position
+#[derive(Clone, Debug, PartialEq)]
+pub struct DetentMapping<M> {
+    pub mapping: M,
+    /// The value positions snap to
+    pub detent: f32,
+    /// How far from the detent, in normalized positions, positions snap to it
+    pub threshold: f32,
+}
+
+impl<M: ValueMapping> DetentMapping<M> {
+    pub fn new(mapping: M, detent: f32, threshold: f32) -> Self {
+        Self {
+            mapping,
+            detent,
+            threshold,
+        }
+    }
+
+    fn snap(&self, normalized: f32) -> f32 {
+        let detent = self.mapping.normalize(self.detent);
+        if (normalized - detent).abs() <= self.threshold {
+            detent
+        } else {
+            normalized
+        }
+    }
+}
+
+impl<M: ValueMapping> ValueMapping for DetentMapping<M> {
+    fn normalize(&self, value: f32) -> f32 {
+        self.snap(self.mapping.normalize(value))
+    }
+
+    fn denormalize(&self, normalized: f32) -> f32 {
+        let detent = self.mapping.normalize(self.detent);
+        if (normalized - detent).abs() <= self.threshold {
+            self.detent
+        } else {
+            self.mapping.denormalize(normalized)
+        }
+    }
+
+    fn steps(&self) -> Option<usize> {
+        self.mapping.steps()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use assert_approx_eq::assert_approx_eq;
+
+    #[test]
+    fn maps_linear() {
+        let range = -1f32..=1f32;
+        assert_approx_eq!(range.normalize(0f32), 0.5f32);
+        assert_approx_eq!(range.denormalize(0.75f32), 0.5f32);
+        assert_approx_eq!(range.normalize(2f32), 1f32);
+        let reversed = 1f32..=-1f32;
+        assert_approx_eq!(reversed.normalize(1f32), 0f32);
+        assert_approx_eq!(reversed.denormalize(1f32), -1f32);
+    }
+
+    #[test]
+    fn maps_log() {
+        let mapping = LogMapping::new(20f32..=20000f32);
+        assert_approx_eq!(mapping.normalize(632.4555f32), 0.5f32, 1e-4f32);
+        assert_approx_eq!(mapping.denormalize(0f32), 20f32);
+        assert_approx_eq!(mapping.denormalize(1f32), 20000f32, 1e-2f32);
+    }
+
+    #[test]
+    fn maps_power() {
+        let mapping = PowerMapping::new(0f32..=10f32, 2f32);
+        assert_approx_eq!(mapping.denormalize(0.5f32), 2.5f32);
+        assert_approx_eq!(mapping.normalize(2.5f32), 0.5f32);
+    }
+
+    #[test]
+    fn maps_steps() {
+        let mapping = SteppedMapping::new(0f32..=12f32, 13);
+        assert_approx_eq!(mapping.denormalize(0.49f32), 6f32);
+        assert_approx_eq!(mapping.normalize(6.4f32), 0.5f32);
+        for n in [0f32, 0.3f32, 0.7f32, 1f32] {
+            let value = mapping.denormalize(n);
+            assert_approx_eq!(value, value.round());
+        }
+    }
+
+    #[test]
+    fn snaps_to_detents() {
+        let mapping = DetentMapping::new(-1f32..=1f32, 0f32, 0.05f32);
+        assert_eq!(mapping.denormalize(0.53f32), 0f32);
+        assert_eq!(mapping.normalize(0.09f32), 0.5f32);
+        assert_approx_eq!(mapping.denormalize(0.6f32), 0.2f32);
+        assert_eq!(mapping.steps(), None);
+        let stepped = DetentMapping::new(SteppedMapping::new(0f32..=4f32, 5), 2f32, 0.05f32);
+        assert_eq!(stepped.steps(), Some(5));
+    }
+
+    #[test]
+    fn round_trips() {
+        let mappings: [Box<dyn ValueMapping>; 3] = [
+            Box::new(-5f32..=5f32),
+            Box::new(LogMapping::new(0.1f32..=10f32)),
+            Box::new(PowerMapping::new(0f32..=1f32, 3f32)),
+        ];
+        for mapping in mappings.iter() {
+            for n in [0f32, 0.25f32, 0.5f32, 1f32] {
+                assert_approx_eq!(mapping.normalize(mapping.denormalize(n)), n, 1e-5f32);
+            }
+        }
+    }
+}
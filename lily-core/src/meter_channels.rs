@@ -0,0 +1,124 @@
+//! The state behind a meter with several channels, reset together when the
+//! meter is clicked
+
+use crate::units::MINUS_INFINITY_DB;
+use crate::{Ballistics, OverDetector};
+
+/// The motion of a meter with several channels, such as the left and right of
+/// a stereo meter: each channel's smoothed level, its held peak, and whether
+/// it went over, all in decibels.
+///
+/// Clicking a meter usually clears its held peaks and clip lights together,
+/// which [`MeterChannels::reset_holds`] does for every channel at once.
+#[derive(Clone, Debug, PartialEq)]
+pub struct MeterChannels {
+    ballistics: Vec<Ballistics>,
+    overs: Vec<OverDetector>,
+}
+
+impl MeterChannels {
+    /// Creates `count` channels that move with `ballistics`, whose hold time
+    /// and decay set how long peaks stay up and how fast they fall in
+    /// decibels per second, and that go over the threshold of `overs`. The
+    /// channels start out silent.
+    pub fn new(count: usize, mut ballistics: Ballistics, overs: OverDetector) -> Self {
+        ballistics.reset(MINUS_INFINITY_DB);
+        Self {
+            ballistics: vec![ballistics; count],
+            overs: vec![overs; count],
+        }
+    }
+
+    /// The number of channels
+    pub fn len(&self) -> usize {
+        self.ballistics.len()
+    }
+
+    /// Whether the meter has no channels
+    pub fn is_empty(&self) -> bool {
+        self.ballistics.is_empty()
+    }
+
+    /// Moves each channel towards its level in decibels by `elapsed`
+    /// seconds. Levels past the last channel are ignored. Returns whether any
+    /// channel started an over or went higher.
+    pub fn advance(&mut self, levels: &[f32], elapsed: f32) -> bool {
+        let mut over = false;
+        self.advance_with(levels, elapsed, |_, _| over = true);
+        over
+    }
+
+    /// Moves each channel like [`MeterChannels::advance`], calling `over`
+    /// with the channel and how many decibels it went over by whenever one
+    /// starts an over or goes higher
+    pub fn advance_with(&mut self, levels: &[f32], elapsed: f32, mut over: impl FnMut(usize, f32)) {
+        for (channel, ((ballistics, overs), level)) in self
+            .ballistics
+            .iter_mut()
+            .zip(&mut self.overs)
+            .zip(levels)
+            .enumerate()
+        {
+            ballistics.advance(*level, elapsed);
+            if let Some(overshoot) = overs.process_db(*level) {
+                over(channel, overshoot);
+            }
+        }
+    }
+
+    /// The smoothed level of a channel in decibels
+    pub fn level(&self, channel: usize) -> Option<f32> {
+        self.ballistics.get(channel).map(Ballistics::value)
+    }
+
+    /// The held peak of a channel in decibels, where its peak-hold marker is
+    /// drawn
+    pub fn peak(&self, channel: usize) -> Option<f32> {
+        self.ballistics.get(channel).map(Ballistics::peak)
+    }
+
+    /// Whether a channel went over since the holds were last reset, which
+    /// lights its clip indicator
+    pub fn clipped(&self, channel: usize) -> bool {
+        self.overs
+            .get(channel)
+            .is_some_and(|overs| overs.count() > 0)
+    }
+
+    /// Drops every held peak to its channel's level and clears the clip
+    /// indicators, leaving the levels where they are
+    pub fn reset_holds(&mut self) {
+        for ballistics in &mut self.ballistics {
+            ballistics.reset_peak();
+        }
+        for overs in &mut self.overs {
+            overs.reset();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn holds_peaks_per_channel_until_reset() {
+        let ballistics = Ballistics::new(0f32, 0f32).with_peak_hold(1f32, 20f32);
+        let mut meter = MeterChannels::new(2, ballistics, OverDetector::default());
+        assert!(meter.advance(&[-6f32, 1f32], 0.1f32));
+        assert!(!meter.advance(&[-20f32, -20f32], 0.1f32));
+        assert_eq!(meter.level(0), Some(-20f32));
+        assert_eq!(meter.peak(0), Some(-6f32));
+        assert_eq!(meter.peak(1), Some(1f32));
+        assert!(!meter.clipped(0));
+        assert!(meter.clipped(1));
+
+        // Clicking the meter clears the holds and the clip light, but not the
+        // levels
+        meter.reset_holds();
+        assert_eq!(meter.peak(1), Some(-20f32));
+        assert_eq!(meter.level(1), Some(-20f32));
+        assert!(!meter.clipped(1));
+        assert_eq!(meter.peak(2), None);
+    }
+}
@@ -0,0 +1,116 @@
+//! Detecting overs, levels above a threshold such as clipping, for meters to
+//! report
+
+use crate::units::gain_to_db;
+
+/// Watches a level for overs, the levels above a threshold such as 0 dBFS
+/// for clipping or -1 dBTP for true peaks, so meters can report them beyond
+/// their own clip lights.
+///
+/// A level that stays above the threshold is one over, which ends once the
+/// level falls back to the threshold. An over is reported when it starts and
+/// again each time it goes higher, with how many decibels it went over by.
+#[derive(Clone, Debug, PartialEq)]
+pub struct OverDetector {
+    /// The threshold in decibels
+    threshold: f32,
+    /// The highest level in decibels of the over in progress
+    over: Option<f32>,
+    /// How many overs started since the last reset
+    count: usize,
+}
+
+impl Default for OverDetector {
+    fn default() -> Self {
+        Self::new(0f32)
+    }
+}
+
+impl OverDetector {
+    /// Creates a detector of the levels above `threshold` decibels
+    pub fn new(threshold: f32) -> Self {
+        Self {
+            threshold,
+            over: None,
+            count: 0,
+        }
+    }
+
+    /// The threshold in decibels
+    pub fn threshold(&self) -> f32 {
+        self.threshold
+    }
+
+    /// Checks a linear peak level, of either sign. Returns how many decibels
+    /// the level is over the threshold by if an over starts or goes higher.
+    pub fn process(&mut self, gain: f32) -> Option<f32> {
+        self.process_db(gain_to_db(gain.abs()))
+    }
+
+    /// Checks a level in decibels. Returns how many decibels the level is
+    /// over the threshold by if an over starts or goes higher.
+    pub fn process_db(&mut self, db: f32) -> Option<f32> {
+        if db <= self.threshold {
+            self.over = None;
+            return None;
+        }
+        match self.over {
+            Some(highest) if db <= highest => None,
+            over => {
+                if over.is_none() {
+                    self.count += 1;
+                }
+                self.over = Some(db);
+                Some(db - self.threshold)
+            }
+        }
+    }
+
+    /// Whether the level is over the threshold as of the last check
+    pub fn is_over(&self) -> bool {
+        self.over.is_some()
+    }
+
+    /// How many overs started since the detector was created or reset
+    pub fn count(&self) -> usize {
+        self.count
+    }
+
+    /// Forgets the overs so far, as when the user clears a clip light
+    pub fn reset(&mut self) {
+        self.over = None;
+        self.count = 0;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use assert_approx_eq::assert_approx_eq;
+
+    #[test]
+    fn reports_overs_once_and_when_higher() {
+        let mut overs = OverDetector::new(-1f32);
+        assert_eq!(overs.process_db(-3f32), None);
+        assert_eq!(overs.process_db(-0.5f32), Some(0.5f32));
+        // Still the same over, which only reports going higher
+        assert_eq!(overs.process_db(-0.8f32), None);
+        assert_eq!(overs.process_db(0f32), Some(1f32));
+        assert!(overs.is_over());
+        assert_eq!(overs.count(), 1);
+        // Back down to the threshold ends the over
+        assert_eq!(overs.process_db(-1f32), None);
+        assert!(!overs.is_over());
+        assert_eq!(overs.process_db(-0.9f32).map(|db| db > 0f32), Some(true));
+        assert_eq!(overs.count(), 2);
+        overs.reset();
+        assert_eq!(overs.count(), 0);
+    }
+
+    #[test]
+    fn detects_clipping_samples() {
+        let mut overs = OverDetector::default();
+        assert_eq!(overs.process(0.99f32), None);
+        assert_approx_eq!(overs.process(-2f32).unwrap(), 6.0206f32, 1e-3f32);
+    }
+}
@@ -0,0 +1,282 @@
+//! Hierarchical min/max peaks for drawing waveforms at any zoom level
+
+use std::collections::HashMap;
+use std::ops::Range;
+
+/// The number of blocks of one level combined into a block of the next
+const LEVEL_FACTOR: usize = 2;
+/// The number of pixel columns in each tile of [`PeakTiles`]
+const TILE_COLUMNS: usize = 64;
+
+/// The lowest and highest sample of a block of samples
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Peak {
+    pub min: f32,
+    pub max: f32,
+}
+
+impl Peak {
+    fn of_sample(sample: f32) -> Self {
+        Self {
+            min: sample,
+            max: sample,
+        }
+    }
+
+    fn merge(self, other: Peak) -> Self {
+        Self {
+            min: self.min.min(other.min),
+            max: self.max.max(other.max),
+        }
+    }
+}
+
+/// Min/max peaks of a sample buffer at progressively coarser levels, where
+/// each block of level `n` covers `2^n` samples. Drawing a waveform then only
+/// touches about one block per pixel, however far it is zoomed out.
+#[derive(Clone, Debug, PartialEq)]
+pub struct PeakPyramid {
+    /// The peaks of every level, starting with one per sample
+    levels: Vec<Vec<Peak>>,
+}
+
+impl PeakPyramid {
+    pub fn new(samples: &[f32]) -> Self {
+        let mut levels = vec![samples
+            .iter()
+            .copied()
+            .map(Peak::of_sample)
+            .collect::<Vec<_>>()];
+        while let Some(level) = levels.last().filter(|level| level.len() > 1) {
+            let next = level
+                .chunks(LEVEL_FACTOR)
+                .map(|blocks| blocks.iter().copied().reduce(Peak::merge).unwrap())
+                .collect();
+            levels.push(next);
+        }
+        Self { levels }
+    }
+
+    /// The number of samples the pyramid was built from
+    pub fn len(&self) -> usize {
+        self.levels[0].len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// The number of levels, including the samples themselves
+    pub fn level_count(&self) -> usize {
+        self.levels.len()
+    }
+
+    /// Gets the coarsest level whose blocks are no bigger than
+    /// `samples_per_pixel`, with the number of samples in each of its blocks
+    pub fn level_for(&self, samples_per_pixel: f32) -> (usize, &[Peak]) {
+        let level = if samples_per_pixel <= 1f32 {
+            0
+        } else {
+            (samples_per_pixel.log2().floor() as usize).min(self.levels.len() - 1)
+        };
+        (LEVEL_FACTOR.pow(level as u32), &self.levels[level])
+    }
+
+    /// Gets one peak per pixel for `width` pixels showing the samples in
+    /// `range`. Pixels past the end of the samples are left out.
+    pub fn peaks(&self, range: Range<usize>, width: usize) -> Vec<Peak> {
+        let range = range.start.min(self.len())..range.end.min(self.len());
+        if range.is_empty() || width == 0 {
+            return Vec::new();
+        }
+        let samples_per_pixel = range.len() as f32 / width as f32;
+        let (block_size, blocks) = self.level_for(samples_per_pixel);
+        (0..width)
+            .filter_map(|pixel| {
+                let start = range.start + (pixel as f32 * samples_per_pixel) as usize;
+                let end = range.start + ((pixel + 1) as f32 * samples_per_pixel).ceil() as usize;
+                block_peak(blocks, block_size, start, end.min(range.end))
+            })
+            .collect()
+    }
+
+    /// Gets the peaks of the pixel `columns` of a waveform drawn from its
+    /// first sample at `samples_per_pixel`, where column `n` starts at sample
+    /// `n * samples_per_pixel`. Columns past the end of the samples are left
+    /// out.
+    pub fn columns(&self, columns: Range<usize>, samples_per_pixel: f32) -> Vec<Peak> {
+        let (block_size, blocks) = self.level_for(samples_per_pixel);
+        columns
+            .map_while(|column| {
+                let start = (column as f32 * samples_per_pixel) as usize;
+                let end = ((column + 1) as f32 * samples_per_pixel).ceil() as usize;
+                block_peak(blocks, block_size, start, end.min(self.len()))
+            })
+            .collect()
+    }
+}
+
+/// Gets the peak of the samples from `start` to `end` from the blocks of a
+/// level, or `None` if `start` is past the last block
+fn block_peak(blocks: &[Peak], block_size: usize, start: usize, end: usize) -> Option<Peak> {
+    let (start, end) = (start / block_size, end.div_ceil(block_size));
+    blocks
+        .get(start..end.max(start + 1).min(blocks.len()))?
+        .iter()
+        .copied()
+        .reduce(Peak::merge)
+}
+
+/// The peaks of a scrolling waveform, computed in tiles of pixel columns and
+/// kept while they stay in view. Scrolling then only computes the columns
+/// that came into view, and zooming starts over. Tiles are dropped once they
+/// are more than a tile out of view.
+#[derive(Clone, Debug, Default)]
+pub struct PeakTiles {
+    /// The zoom the tiles were computed at
+    samples_per_pixel: f32,
+    /// The peaks of each tile, by the index of the tile
+    tiles: HashMap<usize, Vec<Peak>>,
+}
+
+impl PeakTiles {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Gets one peak per pixel for `width` pixels, starting at the sample
+    /// `offset` of a waveform zoomed to `samples_per_pixel`. The offset is
+    /// rounded down to a whole column so tiles line up while scrolling.
+    /// Pixels past the end of the samples are left out.
+    pub fn peaks(
+        &mut self,
+        pyramid: &PeakPyramid,
+        offset: usize,
+        samples_per_pixel: f32,
+        width: usize,
+    ) -> Vec<Peak> {
+        if samples_per_pixel != self.samples_per_pixel {
+            self.tiles.clear();
+            self.samples_per_pixel = samples_per_pixel;
+        }
+        let first = (offset as f32 / samples_per_pixel) as usize;
+        let tiles = first / TILE_COLUMNS..(first + width).div_ceil(TILE_COLUMNS);
+        let kept = tiles.start.saturating_sub(1)..tiles.end + 1;
+        self.tiles.retain(|tile, _| kept.contains(tile));
+
+        let mut peaks = Vec::with_capacity(width);
+        for tile in tiles {
+            let tile_start = tile * TILE_COLUMNS;
+            let columns = self.tiles.entry(tile).or_insert_with(|| {
+                pyramid.columns(tile_start..tile_start + TILE_COLUMNS, samples_per_pixel)
+            });
+            let start = first.saturating_sub(tile_start).min(columns.len());
+            let end = (first + width - tile_start).min(columns.len());
+            peaks.extend_from_slice(&columns[start..end.max(start)]);
+        }
+        peaks
+    }
+
+    /// The number of tiles kept
+    pub fn tile_count(&self) -> usize {
+        self.tiles.len()
+    }
+
+    /// Drops every tile, for when the samples change
+    pub fn clear(&mut self) {
+        self.tiles.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn peak(min: f32, max: f32) -> Peak {
+        Peak { min, max }
+    }
+
+    fn samples() -> Vec<f32> {
+        (0..16)
+            .map(|i| if i % 2 == 0 { i as f32 } else { -(i as f32) })
+            .collect()
+    }
+
+    #[test]
+    fn builds_levels() {
+        let pyramid = PeakPyramid::new(&samples());
+        assert_eq!(pyramid.len(), 16);
+        // 16, 8, 4, 2 and 1 blocks
+        assert_eq!(pyramid.level_count(), 5);
+        let (block_size, top) = pyramid.level_for(1000f32);
+        assert_eq!(block_size, 16);
+        assert_eq!(top, &[peak(-15f32, 14f32)]);
+    }
+
+    #[test]
+    fn picks_level_for_zoom() {
+        let pyramid = PeakPyramid::new(&samples());
+        assert_eq!(pyramid.level_for(0.5f32).0, 1);
+        assert_eq!(pyramid.level_for(2f32).0, 2);
+        assert_eq!(pyramid.level_for(3.9f32).0, 2);
+        assert_eq!(pyramid.level_for(4f32).0, 4);
+    }
+
+    #[test]
+    fn gets_peaks_per_pixel() {
+        let pyramid = PeakPyramid::new(&samples());
+        let peaks = pyramid.peaks(0..16, 4);
+        assert_eq!(
+            peaks,
+            vec![
+                peak(-3f32, 2f32),
+                peak(-7f32, 6f32),
+                peak(-11f32, 10f32),
+                peak(-15f32, 14f32),
+            ]
+        );
+        // Zoomed in past one sample per pixel
+        let peaks = pyramid.peaks(4..6, 4);
+        assert_eq!(peaks.len(), 4);
+        assert_eq!(peaks[0], peak(4f32, 4f32));
+        assert_eq!(peaks[3], peak(-5f32, -5f32));
+        // Odd ratios still cover every sample
+        let peaks = pyramid.peaks(0..16, 3);
+        assert_eq!(peaks.iter().map(|p| p.max).fold(f32::MIN, f32::max), 14f32);
+        assert_eq!(peaks.iter().map(|p| p.min).fold(f32::MAX, f32::min), -15f32);
+        assert!(pyramid.peaks(20..30, 4).is_empty());
+    }
+
+    #[test]
+    fn gets_column_peaks() {
+        let pyramid = PeakPyramid::new(&samples());
+        assert_eq!(
+            pyramid.columns(1..3, 4f32),
+            vec![peak(-7f32, 6f32), peak(-11f32, 10f32)]
+        );
+        // Stops at the end of the samples
+        assert_eq!(pyramid.columns(2..10, 4f32).len(), 2);
+    }
+
+    #[test]
+    fn tiles_scrolling_peaks() {
+        let samples: Vec<f32> = (0..10_000).map(|i| (i as f32 * 0.01f32).sin()).collect();
+        let pyramid = PeakPyramid::new(&samples);
+        let mut tiles = PeakTiles::new();
+
+        let peaks = tiles.peaks(&pyramid, 0, 4f32, 100);
+        assert_eq!(peaks, pyramid.columns(0..100, 4f32));
+        assert_eq!(tiles.tile_count(), 2);
+        // Scrolling by 30 columns reuses both tiles and adds a third
+        let peaks = tiles.peaks(&pyramid, 120, 4f32, 100);
+        assert_eq!(peaks, pyramid.columns(30..130, 4f32));
+        assert_eq!(tiles.tile_count(), 3);
+        // Tiles far out of view are dropped, keeping the three in view
+        tiles.peaks(&pyramid, 4000, 4f32, 100);
+        assert_eq!(tiles.tile_count(), 3);
+        // Zooming starts over, and the end of the samples is left out
+        let peaks = tiles.peaks(&pyramid, 9000, 16f32, 100);
+        assert_eq!(peaks, pyramid.columns(562..662, 16f32));
+        assert_eq!(peaks.len(), 63);
+    }
+}
@@ -0,0 +1,160 @@
+//! Conversions between the units audio parameters are usually expressed in,
+//! and formatting for showing them in widget readouts.
+
+/// The gain in decibels treated as silence when converting to and from linear
+/// gain
+pub const MINUS_INFINITY_DB: f32 = -100f32;
+
+const NOTE_NAMES: [&str; 12] = [
+    "C", "C#", "D", "D#", "E", "F", "F#", "G", "G#", "A", "A#", "B",
+];
+
+/// Converts decibels to linear gain. Anything at or below
+/// [`MINUS_INFINITY_DB`] is silent.
+pub fn db_to_gain(db: f32) -> f32 {
+    if db <= MINUS_INFINITY_DB {
+        0f32
+    } else {
+        10f32.powf(db / 20f32)
+    }
+}
+
+/// Converts linear gain to decibels, clamped to [`MINUS_INFINITY_DB`]
+pub fn gain_to_db(gain: f32) -> f32 {
+    if gain <= 0f32 {
+        MINUS_INFINITY_DB
+    } else {
+        (20f32 * gain.log10()).max(MINUS_INFINITY_DB)
+    }
+}
+
+/// Converts a frequency in Hz to a fractional MIDI note, where A4 (note 69)
+/// is 440 Hz
+pub fn hz_to_midi(hz: f32) -> f32 {
+    69f32 + 12f32 * (hz / 440f32).log2()
+}
+
+/// Converts a fractional MIDI note to a frequency in Hz, where A4 (note 69)
+/// is 440 Hz
+pub fn midi_to_hz(note: f32) -> f32 {
+    440f32 * 2f32.powf((note - 69f32) / 12f32)
+}
+
+/// Gets the name of a MIDI note with its octave, where middle C (note 60) is
+/// `C4`
+pub fn note_name(note: u8) -> String {
+    let octave = (note / 12) as i32 - 1;
+    format!("{}{}", NOTE_NAMES[(note % 12) as usize], octave)
+}
+
+/// Converts a duration in milliseconds to a number of samples
+pub fn ms_to_samples(ms: f32, sample_rate: f32) -> f32 {
+    ms * sample_rate / 1000f32
+}
+
+/// Converts a number of samples to a duration in milliseconds
+pub fn samples_to_ms(samples: f32, sample_rate: f32) -> f32 {
+    samples * 1000f32 / sample_rate
+}
+
+/// Converts a number of beats to seconds at a tempo in beats per minute
+pub fn beats_to_seconds(beats: f32, bpm: f32) -> f32 {
+    beats * 60f32 / bpm
+}
+
+/// Converts seconds to a number of beats at a tempo in beats per minute
+pub fn seconds_to_beats(seconds: f32, bpm: f32) -> f32 {
+    seconds * bpm / 60f32
+}
+
+/// Formats linear gain as decibels, e.g. `-6.0 dB` or `-inf dB`
+pub fn format_db(gain: f32) -> String {
+    let db = gain_to_db(gain);
+    if db <= MINUS_INFINITY_DB {
+        String::from("-inf dB")
+    } else {
+        format!("{db:.1} dB")
+    }
+}
+
+/// Formats a frequency, switching to kHz from 1000 Hz, e.g. `440 Hz` or
+/// `2.50 kHz`
+pub fn format_hz(hz: f32) -> String {
+    if hz >= 1000f32 {
+        format!("{:.2} kHz", hz / 1000f32)
+    } else {
+        format!("{hz:.0} Hz")
+    }
+}
+
+/// Formats a duration in seconds, switching to milliseconds below one second,
+/// e.g. `250 ms` or `1.50 s`
+pub fn format_seconds(seconds: f32) -> String {
+    if seconds.abs() < 1f32 {
+        format!("{:.0} ms", seconds * 1000f32)
+    } else {
+        format!("{seconds:.2} s")
+    }
+}
+
+/// Formats a frequency as the nearest note name and its offset in cents,
+/// e.g. `A4 +12`
+pub fn format_note(hz: f32) -> String {
+    let note = hz_to_midi(hz);
+    let nearest = note.round().clamp(0f32, 127f32);
+    let cents = ((note - nearest) * 100f32).round();
+    if cents == 0f32 {
+        note_name(nearest as u8)
+    } else {
+        format!("{} {:+}", note_name(nearest as u8), cents)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use assert_approx_eq::assert_approx_eq;
+
+    #[test]
+    fn converts_db() {
+        assert_approx_eq!(db_to_gain(0f32), 1f32);
+        assert_approx_eq!(db_to_gain(-20f32), 0.1f32);
+        assert_approx_eq!(gain_to_db(0.5f32), -6.0206f32, 1e-3f32);
+        assert_eq!(db_to_gain(MINUS_INFINITY_DB), 0f32);
+        assert_eq!(gain_to_db(0f32), MINUS_INFINITY_DB);
+    }
+
+    #[test]
+    fn converts_midi() {
+        assert_approx_eq!(midi_to_hz(69f32), 440f32);
+        assert_approx_eq!(midi_to_hz(81f32), 880f32, 1e-3f32);
+        assert_approx_eq!(hz_to_midi(261.6256f32), 60f32, 1e-3f32);
+    }
+
+    #[test]
+    fn names_notes() {
+        assert_eq!(note_name(60), "C4");
+        assert_eq!(note_name(69), "A4");
+        assert_eq!(note_name(0), "C-1");
+        assert_eq!(format_note(440f32), "A4");
+        assert_eq!(format_note(midi_to_hz(69.25f32)), "A4 +25");
+    }
+
+    #[test]
+    fn converts_time() {
+        assert_approx_eq!(ms_to_samples(10f32, 48000f32), 480f32);
+        assert_approx_eq!(samples_to_ms(480f32, 48000f32), 10f32);
+        assert_approx_eq!(beats_to_seconds(4f32, 120f32), 2f32);
+        assert_approx_eq!(seconds_to_beats(2f32, 120f32), 4f32);
+    }
+
+    #[test]
+    fn formats_values() {
+        assert_eq!(format_db(1f32), "0.0 dB");
+        assert_eq!(format_db(0f32), "-inf dB");
+        assert_eq!(format_hz(440f32), "440 Hz");
+        assert_eq!(format_hz(2500f32), "2.50 kHz");
+        assert_eq!(format_seconds(0.25f32), "250 ms");
+        assert_eq!(format_seconds(1.5f32), "1.50 s");
+    }
+}
@@ -1,14 +1,75 @@
 use proc_macro::TokenStream;
-use quote::{format_ident, quote};
+use proc_macro2::TokenTree;
+use quote::{format_ident, quote, ToTokens};
 use syn::{
-    parse_macro_input, parse_quote, punctuated::Punctuated, DeriveInput, GenericParam, Ident, Meta,
-    MetaList,
+    parse::ParseStream, parse_macro_input, parse_quote, punctuated::Punctuated, Attribute,
+    DeriveInput, GenericParam, Ident, LitStr, ReturnType, Token, Type,
 };
 
-#[proc_macro_derive(Handle, attributes(callback))]
+/// The argument types listed in a `#[callback(...)]` attribute
+type CallbackArgs = Punctuated<Type, Token![,]>;
+
+/// A field marked `#[callback(...)]`
+struct Callback {
+    ident: Ident,
+    /// The argument types after the `&mut EventContext`
+    args: CallbackArgs,
+    /// The return type, for callbacks whose result the widget acts on
+    output: ReturnType,
+    /// Whether the field boxes an `FnMut`
+    is_mut: bool,
+    /// The field's doc comment, shown on its setter
+    docs: Vec<Attribute>,
+}
+
+/// Generates a `{View}Handle` trait with a setter on `Handle` for each field
+/// marked `#[callback(...)]`, listing the argument types after the
+/// `&mut EventContext`, which every callback takes first so they can be called
+/// from `View::event`. `#[callback]` declares a callback without arguments.
+/// Fields boxing an `FnMut` take closures that can mutate their captured state.
+/// A return type can follow the arguments, as in `#[callback(usize -> bool)]`,
+/// for callbacks that let the host veto an action.
+///
+/// Fields marked `#[property]` get a setter of the same name taking the
+/// field's type. Setters are documented with their field's doc comment. With
+/// `#[property(res)]` the setter takes an `impl Res<T>` instead, so the field
+/// can also be bound to a [`Lens`] and follow its changes.
+///
+/// Fields marked `#[event]` get a variant in a private `{View}InternalEvent`
+/// enum, handled by a generated `handle_internal_event` method to be called
+/// from `View::event`. The variant of a callback such as `on_changing_offset`
+/// is `ChangingOffset` with the callback's arguments and calls the callback.
+/// The variant of any other field such as `state` is `SetState` with the
+/// field's type and sets the field. The enum isn't generic, so these fields
+/// and callback arguments can't use the struct's type parameters, and a
+/// callback marked `#[event]` can't return a value since nothing receives it.
+///
+/// `#[style_class("point", "white-key")]` on the struct generates a private
+/// `{View}Styles` struct with a hidden element for each class, created with
+/// `{View}Styles::new(cx)`. Stylesheets color the elements through their
+/// class, and `draw` reads the colors back with accessors such as
+/// `point_background_color(cx)` and `white_key_border_color(cx)`. Sizes set
+/// in pixels are read back with `point_width(cx)` and
+/// `point_border_width(cx)`, which are `None` when the stylesheet leaves them
+/// unset so `draw` can fall back to its own defaults. Pseudo-classes are set
+/// on an element with `set_point_pseudo_class(cx, PseudoClass::HOVER, true)`,
+/// so stylesheets can restyle hovered or dragged points with rules such as
+/// `.point:hover`.
+///
+/// Fields marked `#[state]` are copied into a public `{View}State` struct
+/// with a [`Lens`] for each field, so UI outside the widget can show them.
+/// Such widgets need an `on_state_change` callback taking the state, called by
+/// the generated `notify_state_change` method when the state differs from a
+/// snapshot taken with `state()` at the start of `View::event`.
+#[proc_macro_derive(Handle, attributes(callback, property, event, style_class, state))]
 pub fn create_handle_callbacks(input: TokenStream) -> TokenStream {
     // Parse the input tokens into a syntax tree
     let input = parse_macro_input!(input as DeriveInput);
+    expand_handle(input).into()
+}
+
+/// Generates the code of `#[derive(Handle)]` for a parsed struct
+fn expand_handle(input: DeriveInput) -> proc_macro2::TokenStream {
     let ident = input.ident;
 
     let id = format_ident!("{ident}Handle");
@@ -20,51 +81,377 @@ pub fn create_handle_callbacks(input: TokenStream) -> TokenStream {
     let bounds = input.generics.where_clause.clone();
     let vis = input.vis;
 
-    // A hashmap of callback field names as well as the metalist. Only fields with the `callback` attribute are included.
+    // The style classes given with the `style_class` attribute on the struct
+    let mut style_classes: Vec<LitStr> = Vec::new();
+    for attr in input
+        .attrs
+        .iter()
+        .filter(|a| a.path.is_ident("style_class"))
+    {
+        match attr.parse_args_with(Punctuated::<LitStr, Token![,]>::parse_terminated) {
+            Ok(classes) => style_classes.extend(classes),
+            Err(err) => return err.to_compile_error(),
+        }
+    }
+    let styles = if style_classes.is_empty() {
+        quote!()
+    } else {
+        let styles_ident = format_ident!("{ident}Styles");
+        let slots: Vec<Ident> = style_classes
+            .iter()
+            .map(|class| format_ident!("{}", class.value().replace('-', "_")))
+            .collect();
+        let backgrounds: Vec<Ident> = slots
+            .iter()
+            .map(|slot| format_ident!("{slot}_background_color"))
+            .collect();
+        let borders: Vec<Ident> = slots
+            .iter()
+            .map(|slot| format_ident!("{slot}_border_color"))
+            .collect();
+        let widths: Vec<Ident> = slots
+            .iter()
+            .map(|slot| format_ident!("{slot}_width"))
+            .collect();
+        let border_widths: Vec<Ident> = slots
+            .iter()
+            .map(|slot| format_ident!("{slot}_border_width"))
+            .collect();
+        let pseudo_class_setters: Vec<Ident> = slots
+            .iter()
+            .map(|slot| format_ident!("set_{slot}_pseudo_class"))
+            .collect();
+        quote! {
+            /// Hidden elements styled through their class, whose colors and sizes
+            /// are read back when drawing
+            #[derive(Clone, Copy)]
+            struct #styles_ident {
+                #(#slots: Entity,)*
+            }
+
+            #[allow(dead_code)]
+            impl #styles_ident {
+                fn new(cx: &mut Context) -> Self {
+                    Self {
+                        #(#slots: Element::new(cx).class(#style_classes).display(Display::None).entity,)*
+                    }
+                }
+
+                #(
+                    fn #backgrounds(&self, cx: &DrawContext) -> Color {
+                        cx.style.background_color.get(self.#slots).copied().unwrap_or_default()
+                    }
+
+                    fn #borders(&self, cx: &DrawContext) -> Color {
+                        cx.style.border_color.get(self.#slots).copied().unwrap_or_default()
+                    }
+
+                    fn #widths(&self, cx: &DrawContext) -> Option<f32> {
+                        match cx.style.width.get(self.#slots) {
+                            Some(Units::Pixels(px)) => Some(*px),
+                            _ => None,
+                        }
+                    }
+
+                    fn #border_widths(&self, cx: &DrawContext) -> Option<f32> {
+                        match cx.style.border_width.get(self.#slots) {
+                            Some(Units::Pixels(px)) => Some(*px),
+                            _ => None,
+                        }
+                    }
+
+                    fn #pseudo_class_setters(&self, cx: &mut EventContext, class: PseudoClass, enabled: bool) {
+                        if let Some(pseudo_classes) = cx.style.pseudo_classes.get_mut(self.#slots) {
+                            if pseudo_classes.contains(class) != enabled {
+                                pseudo_classes.set(class, enabled);
+                                cx.style.needs_restyle = true;
+                            }
+                        }
+                    }
+                )*
+            }
+        }
+    };
+
+    // Only fields with the `callback` attribute are included as callbacks
     let output = if let syn::Data::Struct(data) = input.data {
-        let callbacks: Vec<(Ident, MetaList)> = data
+        let callbacks: syn::Result<Vec<Callback>> = data
             .fields
             .iter()
             // Only get fields with callback attributes
             .filter_map(|field| {
-                let metas: Vec<Meta> = field
-                    .attrs
-                    .iter()
-                    .filter_map(|a| a.parse_meta().ok())
-                    .collect();
-                // Find (if any) the attribute with the "callback" ident
-                metas.iter().find_map(|meta| match meta {
-                    Meta::List(meta_list) => {
-                        match meta_list.path == (format_ident!("callback")).into() {
-                            true => Some((field.ident.clone().unwrap(), meta_list.clone())),
-                            false => None,
-                        }
+                let attr = field.attrs.iter().find(|a| a.path.is_ident("callback"))?;
+                // Both `#[callback]` and `#[callback()]` declare a callback without arguments
+                let signature = if attr.tokens.is_empty() {
+                    Ok((Punctuated::new(), ReturnType::Default))
+                } else {
+                    attr.parse_args_with(parse_callback_args)
+                };
+                if let Some(context) = context_arg(&field.ty) {
+                    if context != "EventContext" {
+                        return Some(Err(syn::Error::new_spanned(
+                            &field.ty,
+                            "callbacks take `&mut EventContext` as their first argument",
+                        )));
                     }
-                    _ => None,
-                })
+                }
+                let is_mut = is_fn_mut(&field.ty);
+                Some(signature.map(|(args, output)| Callback {
+                    ident: field.ident.clone().unwrap(),
+                    args,
+                    output,
+                    is_mut,
+                    docs: doc_attrs(&field.attrs),
+                }))
             })
             .collect();
+        let callbacks = match callbacks {
+            Ok(callbacks) => callbacks,
+            Err(err) => return err.to_compile_error(),
+        };
+
+        let callback_idents: Vec<&Ident> = callbacks.iter().map(|c| &c.ident).collect();
+        let callback_types: Vec<&CallbackArgs> = callbacks.iter().map(|c| &c.args).collect();
+        let callback_outputs: Vec<&ReturnType> = callbacks.iter().map(|c| &c.output).collect();
+        let callback_docs: Vec<&Vec<Attribute>> = callbacks.iter().map(|c| &c.docs).collect();
+        let callback_traits: Vec<_> = callbacks
+            .iter()
+            .map(|c| if c.is_mut { quote!(FnMut) } else { quote!(Fn) })
+            .collect();
 
-        let callback_idents: Vec<Ident> =
-            callbacks.iter().map(|(ident, _)| ident.clone()).collect();
-        let callback_types: Vec<Punctuated<_, _>> =
-            callbacks.iter().map(|(_, ty)| ty.nested.clone()).collect();
+        // Plain fields with the `property` attribute, set by value or bound to a `Res`
+        let mut property_decls = Vec::new();
+        let mut property_impls = Vec::new();
+        for field in data.fields.iter() {
+            let attr = match field.attrs.iter().find(|a| a.path.is_ident("property")) {
+                Some(attr) => attr,
+                None => continue,
+            };
+            let is_res = if attr.tokens.is_empty() {
+                false
+            } else {
+                match attr.parse_args::<Ident>() {
+                    Ok(arg) if arg == "res" => true,
+                    Ok(arg) => {
+                        return syn::Error::new(arg.span(), "expected `res`").to_compile_error()
+                    }
+                    Err(err) => return err.to_compile_error(),
+                }
+            };
+            let docs = doc_attrs(&field.attrs);
+            let name = field.ident.clone().unwrap();
+            let ty = &field.ty;
+
+            if is_res {
+                property_decls.push(quote! {
+                    #(#docs)*
+                    fn #name(self, value: impl Res<#ty>) -> Self;
+                });
+                property_impls.push(quote! {
+                    fn #name(self, value: impl Res<#ty>) -> Self {
+                        value.set_or_bind(self.cx, self.entity, |cx, entity, value| {
+                            if let Some(view) = cx.views.get_mut(&entity) {
+                                if let Some(down) = view.downcast_mut::<#ident #generics>() {
+                                    down.#name = value;
+                                }
+                            }
+                        });
+                        self
+                    }
+                });
+            } else {
+                property_decls.push(quote! {
+                    #(#docs)*
+                    fn #name(self, value: #ty) -> Self;
+                });
+                property_impls.push(quote! {
+                    fn #name(self, value: #ty) -> Self {
+                        if let Some(view) = self.cx.views.get_mut(&self.entity) {
+                            if let Some(down) = view.downcast_mut::<#ident #generics>() {
+                                down.#name = value;
+                            }
+                        }
+                        self
+                    }
+                });
+            }
+        }
+
+        // Fields with the `event` attribute, updated through internal events
+        let event_ident = format_ident!("{ident}InternalEvent");
+        let type_params: Vec<&Ident> = generics.type_params().map(|p| &p.ident).collect();
+        let mut event_variants = Vec::new();
+        let mut event_arms = Vec::new();
+        for field in data.fields.iter() {
+            if !field.attrs.iter().any(|a| a.path.is_ident("event")) {
+                continue;
+            }
+            let name = field.ident.clone().unwrap();
+            let callback = callbacks.iter().find(|c| c.ident == name);
+            if let Some(Callback {
+                output: ReturnType::Type(_, output),
+                ..
+            }) = callback
+            {
+                return syn::Error::new_spanned(
+                    output,
+                    "callbacks marked `#[event]` can't return a value",
+                )
+                .to_compile_error();
+            }
+            let event_types = match callback {
+                Some(callback) => callback.args.to_token_stream(),
+                None => field.ty.to_token_stream(),
+            };
+            if mentions_type_param(event_types, &type_params) {
+                return syn::Error::new_spanned(
+                    &name,
+                    "fields marked `#[event]` can't use the struct's type parameters",
+                )
+                .to_compile_error();
+            }
+            if let Some(Callback { args: types, .. }) = callback {
+                let variant =
+                    format_ident!("{}", camel_case(name.to_string().trim_start_matches("on_")));
+                let args: Vec<Ident> = (0..types.len()).map(|i| format_ident!("arg{i}")).collect();
+                if types.is_empty() {
+                    event_variants.push(quote!(#variant));
+                    event_arms.push(quote! {
+                        #event_ident::#variant => {
+                            if let Some(callback) = &mut self.#name {
+                                (callback)(cx);
+                            }
+                        }
+                    });
+                } else {
+                    let types = types.iter();
+                    event_variants.push(quote!(#variant(#(#types),*)));
+                    event_arms.push(quote! {
+                        #event_ident::#variant(#(#args),*) => {
+                            if let Some(callback) = &mut self.#name {
+                                (callback)(cx, #(#args),*);
+                            }
+                        }
+                    });
+                }
+            } else {
+                let variant = format_ident!("Set{}", camel_case(&name.to_string()));
+                let ty = &field.ty;
+                event_variants.push(quote!(#variant(#ty)));
+                event_arms.push(quote! {
+                    #event_ident::#variant(value) => self.#name = value,
+                });
+            }
+        }
+        let internal_events = if event_variants.is_empty() {
+            quote!()
+        } else {
+            quote! {
+                #[derive(Clone)]
+                enum #event_ident {
+                    #(#event_variants),*
+                }
+
+                impl #generics #ident #generics #bounds {
+                    /// Calls the callback or sets the field of an internal event
+                    fn handle_internal_event(&mut self, cx: &mut EventContext, event: &mut Event) {
+                        event.map(|ev: &#event_ident, _| match ev.clone() {
+                            #(#event_arms)*
+                        });
+                    }
+                }
+            }
+        };
+
+        // Fields with the `state` attribute, reported through `on_state_change`
+        let state_fields: Vec<&syn::Field> = data
+            .fields
+            .iter()
+            .filter(|field| field.attrs.iter().any(|a| a.path.is_ident("state")))
+            .collect();
+        let state = if state_fields.is_empty() {
+            quote!()
+        } else {
+            if !callbacks.iter().any(|c| c.ident == "on_state_change") {
+                return syn::Error::new(
+                    ident.span(),
+                    "widgets with `#[state]` fields need an `on_state_change` callback",
+                )
+                .to_compile_error();
+            }
+            let state_ident = format_ident!("{ident}State");
+            let state_doc = format!(
+                "The internal state of a [`{ident}`], reported through its `on_state_change` callback"
+            );
+            let names: Vec<&Ident> = state_fields
+                .iter()
+                .map(|field| field.ident.as_ref().unwrap())
+                .collect();
+            let types: Vec<&Type> = state_fields.iter().map(|field| &field.ty).collect();
+            let docs: Vec<Vec<Attribute>> = state_fields
+                .iter()
+                .map(|field| doc_attrs(&field.attrs))
+                .collect();
+            quote! {
+                #[doc = #state_doc]
+                #[derive(Clone, Debug, PartialEq, Lens)]
+                #vis struct #state_ident {
+                    #(
+                        #(#docs)*
+                        pub #names: #types,
+                    )*
+                }
+
+                impl Data for #state_ident {
+                    fn same(&self, other: &Self) -> bool {
+                        self == other
+                    }
+                }
+
+                impl #generics #ident #generics #bounds {
+                    /// Gets a snapshot of the fields reported through `on_state_change`
+                    fn state(&self) -> #state_ident {
+                        #state_ident {
+                            #(#names: self.#names.clone(),)*
+                        }
+                    }
+
+                    /// Calls `on_state_change` if the state differs from `before`
+                    fn notify_state_change(&mut self, cx: &mut EventContext, before: &#state_ident) {
+                        let state = self.state();
+                        if state != *before {
+                            if let Some(callback) = &mut self.on_state_change {
+                                (callback)(cx, state);
+                            }
+                        }
+                    }
+                }
+            }
+        };
 
         quote! {
+            #styles
+
+            #state
+
+            #internal_events
+
             #vis trait #id #generics #bounds
             {
                 #(
+                    #(#callback_docs)*
                     fn #callback_idents<F> (self, callback: F) -> Self
                     where
-                        F: 'static + Fn(&mut EventContext, #callback_types);
+                        F: 'static + #callback_traits(&mut EventContext, #callback_types) #callback_outputs;
                 )*
+                #(#property_decls)*
             }
 
             impl #generics_with_lifetime #id #generics for Handle<#lifetime, #ident #generics> #bounds {
                 #(
                     fn #callback_idents<F>(self, callback: F) -> Self
                     where
-                        F: 'static + Fn(&mut EventContext, #callback_types) {
+                        F: 'static + #callback_traits(&mut EventContext, #callback_types) #callback_outputs {
                             if let Some(view) = self.cx.views.get_mut(&self.entity) {
                                 if let Some(down) = view.downcast_mut::<#ident #generics>() {
                                     down.#callback_idents = Some(Box::new(callback));
@@ -73,6 +460,7 @@ pub fn create_handle_callbacks(input: TokenStream) -> TokenStream {
                             self
                         }
                 )*
+                #(#property_impls)*
             }
         }
     } else {
@@ -81,6 +469,162 @@ pub fn create_handle_callbacks(input: TokenStream) -> TokenStream {
         }
     };
 
-    // Hand the output tokens back to the compiler
-    TokenStream::from(output)
+    output
+}
+
+/// Whether `tokens` name any of the type parameters `params`, including
+/// inside brackets such as `Vec<T>`
+fn mentions_type_param(tokens: proc_macro2::TokenStream, params: &[&Ident]) -> bool {
+    tokens.into_iter().any(|token| match token {
+        TokenTree::Ident(ident) => params.contains(&&ident),
+        TokenTree::Group(group) => mentions_type_param(group.stream(), params),
+        _ => false,
+    })
+}
+
+/// Whether a callback field's type boxes an `FnMut` rather than an `Fn`
+fn is_fn_mut(ty: &Type) -> bool {
+    ty.to_token_stream()
+        .to_string()
+        .split(|c: char| !c.is_alphanumeric() && c != '_')
+        .any(|word| word == "FnMut")
+}
+
+/// The type of the first argument of a callback field's closure, such as
+/// `EventContext`
+fn context_arg(ty: &Type) -> Option<String> {
+    let tokens = ty.to_token_stream().to_string();
+    let mut words = tokens
+        .split(|c: char| !c.is_alphanumeric() && c != '_')
+        .filter(|word| !word.is_empty());
+    words.find(|word| *word == "Fn" || *word == "FnMut")?;
+    words.find(|word| *word != "mut").map(str::to_string)
+}
+
+/// Converts a `snake_case` field name to `CamelCase`
+fn camel_case(name: &str) -> String {
+    name.split('_')
+        .map(|word| {
+            let mut chars = word.chars();
+            match chars.next() {
+                Some(first) => first.to_uppercase().chain(chars).collect(),
+                None => String::new(),
+            }
+        })
+        .collect()
+}
+
+/// Parses the arguments of a `#[callback(...)]` attribute, such as
+/// `usize, Vec2` or `usize -> bool`
+fn parse_callback_args(input: ParseStream) -> syn::Result<(CallbackArgs, ReturnType)> {
+    let mut args = CallbackArgs::new();
+    while !input.is_empty() && !input.peek(Token![->]) {
+        args.push_value(input.parse()?);
+        if !input.peek(Token![,]) {
+            break;
+        }
+        args.push_punct(input.parse()?);
+    }
+    Ok((args, input.parse()?))
+}
+
+/// Gets the doc comment attributes of a field
+fn doc_attrs(attrs: &[Attribute]) -> Vec<Attribute> {
+    attrs
+        .iter()
+        .filter(|a| a.path.is_ident("doc"))
+        .cloned()
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The variants of the internal event enum generated for `input`
+    fn internal_event_variants(input: DeriveInput) -> Vec<String> {
+        let output: syn::File =
+            syn::parse2(expand_handle(input)).expect("the generated code doesn't parse");
+        output
+            .items
+            .iter()
+            .find_map(|item| match item {
+                syn::Item::Enum(item) if item.ident == "TableInternalEvent" => Some(
+                    item.variants
+                        .iter()
+                        .map(|variant| variant.to_token_stream().to_string())
+                        .collect(),
+                ),
+                _ => None,
+            })
+            .expect("no internal event enum")
+    }
+
+    #[test]
+    fn generates_internal_events() {
+        let input = parse_quote! {
+            struct Table<L>
+            where
+                L: Lens<Target = Vec<f32>>,
+            {
+                offsets: PhantomData<L>,
+                #[event]
+                #[callback(usize, f32)]
+                on_changing_offset: Option<Box<dyn Fn(&mut EventContext, usize, f32)>>,
+                #[event]
+                #[callback]
+                on_reset: Option<Box<dyn FnMut(&mut EventContext)>>,
+                #[event]
+                selected: Option<usize>,
+            }
+        };
+        let expected = [
+            quote!(ChangingOffset(usize, f32)),
+            quote!(Reset),
+            quote!(SetSelected(Option<usize>)),
+        ];
+        assert_eq!(
+            internal_event_variants(input),
+            expected.map(|variant| variant.to_string())
+        );
+    }
+
+    #[test]
+    fn rejects_event_callbacks_returning_values() {
+        let input = parse_quote! {
+            struct Table {
+                #[event]
+                #[callback(usize -> bool)]
+                can_remove_row: Option<Box<dyn Fn(&mut EventContext, usize) -> bool>>,
+            }
+        };
+        let output = expand_handle(input).to_string();
+        assert!(output.contains("compile_error"));
+        assert!(output.contains("can't return a value"));
+    }
+
+    #[test]
+    fn rejects_generic_event_fields() {
+        let input = parse_quote! {
+            struct Table<T>
+            where
+                T: 'static + Clone,
+            {
+                #[event]
+                #[callback(Vec<T>)]
+                on_changing_rows: Option<Box<dyn Fn(&mut EventContext, Vec<T>)>>,
+            }
+        };
+        let output = expand_handle(input).to_string();
+        assert!(output.contains("compile_error"));
+        assert!(output.contains("type parameters"));
+
+        let input = parse_quote! {
+            struct Table<T> {
+                #[event]
+                selected: Option<T>,
+            }
+        };
+        assert!(expand_handle(input).to_string().contains("type parameters"));
+    }
 }
@@ -0,0 +1,110 @@
+//! How the MSEG graph's per-event and per-draw work scales with automation
+//! density curves of thousands of points.
+//!
+//! Run with `cargo bench --bench mseg`.
+
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion};
+use glam::Vec2;
+use lily::util::CurvePoints;
+use lily::widgets::{closest_point, simplify_for_display, ProjectedPoints, ViewTransform};
+use vizia::cache::BoundingBox;
+
+const SIZES: [usize; 3] = [100, 1_000, 10_000];
+
+fn bounds() -> BoundingBox {
+    BoundingBox {
+        x: 0f32,
+        y: 0f32,
+        w: 800f32,
+        h: 300f32,
+    }
+}
+
+/// A wobbly curve of `len` points spread over 8 seconds
+fn curve(len: usize) -> CurvePoints {
+    CurvePoints::new(
+        (0..len)
+            .map(|i| {
+                let x = i as f32 / len as f32 * 8f32;
+                (x, 0.5f32 + 0.5f32 * (x * 13f32).sin()).into()
+            })
+            .collect(),
+    )
+}
+
+fn project(c: &mut Criterion) {
+    let mut group = c.benchmark_group("project");
+    for len in SIZES {
+        let points = curve(len);
+        let whole = ViewTransform::new(bounds(), 0f32..=1f32, 8f32);
+        let zoomed = ViewTransform::new(bounds(), 0.4f32..=0.45f32, 8f32);
+        group.bench_with_input(BenchmarkId::new("whole", len), &points, |b, points| {
+            b.iter(|| {
+                let mut projected = ProjectedPoints::default();
+                projected.update(&whole, points);
+                black_box(projected.ui_points().len())
+            })
+        });
+        group.bench_with_input(BenchmarkId::new("zoomed", len), &points, |b, points| {
+            b.iter(|| {
+                let mut projected = ProjectedPoints::default();
+                projected.update(&zoomed, points);
+                black_box(projected.ui_points().len())
+            })
+        });
+        // What every hover pays when nothing changed
+        let mut projected = ProjectedPoints::default();
+        projected.update(&whole, &points);
+        group.bench_with_input(BenchmarkId::new("unchanged", len), &points, |b, points| {
+            b.iter(|| black_box(projected.update(&whole, points)))
+        });
+    }
+    group.finish();
+}
+
+fn hover(c: &mut Criterion) {
+    let mut group = c.benchmark_group("hover");
+    for len in SIZES {
+        let mut projected = ProjectedPoints::default();
+        projected.update(
+            &ViewTransform::new(bounds(), 0f32..=1f32, 8f32),
+            &curve(len),
+        );
+        let ui_points = projected.ui_points().to_vec();
+        group.bench_with_input(
+            BenchmarkId::from_parameter(len),
+            &ui_points,
+            |b, ui_points| {
+                b.iter(|| closest_point(ui_points, black_box(Vec2::new(400f32, 150f32)), 16f32))
+            },
+        );
+    }
+    group.finish();
+}
+
+fn simplify(c: &mut Criterion) {
+    let mut group = c.benchmark_group("simplify");
+    for len in SIZES {
+        let mut projected = ProjectedPoints::default();
+        projected.update(
+            &ViewTransform::new(bounds(), 0f32..=1f32, 8f32),
+            &curve(len),
+        );
+        let ui_points = projected.ui_points().to_vec();
+        let mut simplified = Vec::new();
+        group.bench_with_input(
+            BenchmarkId::from_parameter(len),
+            &ui_points,
+            |b, ui_points| {
+                b.iter(|| {
+                    simplify_for_display(ui_points, &mut simplified);
+                    black_box(simplified.len())
+                })
+            },
+        );
+    }
+    group.finish();
+}
+
+criterion_group!(benches, project, hover, simplify);
+criterion_main!(benches);
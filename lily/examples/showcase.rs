@@ -1,9 +1,4 @@
-use lily::{
-    math::Vec2,
-    util::{CurvePoint, CurvePoints},
-    widgets::*,
-    DEFAULT_STYLE,
-};
+use lily::prelude::*;
 use std::ops::RangeInclusive;
 use vizia::prelude::*;
 
@@ -12,6 +7,7 @@ pub struct AppData {
     xy_data: Vec2,
     mseg_data: CurvePoints,
     mseg_zoom_data: RangeInclusive<f32>,
+    midi_mappings: Vec<MidiMapping>,
 }
 
 impl Default for AppData {
@@ -19,6 +15,7 @@ impl Default for AppData {
         Self {
             xy_data: Vec2::ZERO,
             mseg_zoom_data: 0.0f32..=1.0f32,
+            midi_mappings: Vec::new(),
             mseg_data: CurvePoints(
                 vec![
                     (0f32, 0f32),
@@ -44,10 +41,11 @@ pub enum AppEvent {
     MsegPoint { index: usize, pos: Vec2 },
     MsegInsertPoint { index: usize, pos: Vec2 },
     MsegRemovePoint { index: usize },
+    MsegTension { index: usize, curve: f32 },
 }
 
 impl Model for AppData {
-    fn event(&mut self, _cx: &mut EventContext, event: &mut Event) {
+    fn event(&mut self, cx: &mut EventContext, event: &mut Event) {
         event.map(|ev: &AppEvent, _| match *ev {
             AppEvent::XyControl { point } => {
                 self.xy_data = point;
@@ -59,17 +57,28 @@ impl Model for AppData {
                 self.mseg_zoom_data = *self.mseg_zoom_data.start()..=value
             }
             AppEvent::MsegPoint { index, pos } => {
-                if let Some(p) = self.mseg_data.get_mut(index) {
-                    p.x = pos.x;
-                    p.y = pos.y
-                }
+                self.mseg_data
+                    .try_move_point(index, pos, 0.01f32, 0f32..=8f32);
             }
-            AppEvent::MsegInsertPoint { index, pos } => {
-                self.mseg_data.insert(index, CurvePoint::from(pos));
+            AppEvent::MsegInsertPoint { pos, .. } => {
+                // A point at the same time as another one isn't inserted
+                self.mseg_data.insert_sorted(CurvePoint::from(pos));
             }
             AppEvent::MsegRemovePoint { index } => {
                 self.mseg_data.remove(index);
             }
+            AppEvent::MsegTension { index, curve } => {
+                self.mseg_data[index].curve = curve;
+            }
+        });
+        // There is no MIDI input here, so learning maps the next controller
+        event.map(|ev: &MidiLearnEvent, _| {
+            if let MidiLearnEvent::Learn(id) = ev {
+                self.midi_mappings.retain(|mapping| &mapping.id != id);
+                let cc = self.midi_mappings.len() as u8 + 1;
+                self.midi_mappings.push(MidiMapping::new(id.clone(), cc));
+                cx.emit(MidiLearnEvent::Disarm);
+            }
         });
     }
 }
@@ -78,8 +87,35 @@ fn main() {
     Application::new(|cx| {
         cx.add_theme(DEFAULT_STYLE);
         AppData::default().build(cx);
+        TooltipProvider::default().build(cx);
+        ContextMenuProvider::default().build(cx);
+        ThemeProvider::install(cx, Theme::Dark);
+        FocusProvider::install(cx);
+        MidiLearnProvider::default().build(cx);
 
         VStack::new(cx, |cx| {
+            Button::new(
+                cx,
+                |cx| {
+                    let theme = match ThemeProvider::theme.get(cx) {
+                        Theme::Dark => Theme::Light,
+                        _ => Theme::Dark,
+                    };
+                    set_theme(cx, theme);
+                },
+                |cx| Label::new(cx, "Switch theme"),
+            );
+            Button::new(
+                cx,
+                |cx| {
+                    if MidiLearnProvider::armed.get(cx) {
+                        cx.emit(MidiLearnEvent::Disarm);
+                    } else {
+                        cx.emit(MidiLearnEvent::Arm);
+                    }
+                },
+                |cx| Label::new(cx, "MIDI learn"),
+            );
             HStack::new(cx, |cx| {
                 // XY Pad
                 VStack::new(cx, |cx| {
@@ -92,7 +128,8 @@ fn main() {
                             });
                         })
                         .height(Pixels(24f32))
-                        .width(Pixels(200f32));
+                        .width(Pixels(200f32))
+                        .midi_learn("x", AppData::midi_mappings);
                 })
                 .width(Pixels(200f32));
                 DragSlider::new(cx, AppData::xy_data.map(|pos| pos.y), 1f32..=-1f32)
@@ -103,7 +140,8 @@ fn main() {
                     })
                     .height(Pixels(200f32))
                     .width(Pixels(24f32))
-                    .top(Percentage(0f32));
+                    .top(Percentage(0f32))
+                    .midi_learn("y", AppData::midi_mappings);
             });
             // Multi stage envelope generator
             Mseg::new(cx, AppData::mseg_data, AppData::mseg_zoom_data, 8f32)
@@ -112,6 +150,9 @@ fn main() {
                 .on_changing_point(|cx, index, pos| {
                     cx.emit(AppEvent::MsegPoint { index, pos });
                 })
+                .on_changing_tension(|cx, index, curve| {
+                    cx.emit(AppEvent::MsegTension { index, curve });
+                })
                 .on_insert_point(|cx, index, pos| cx.emit(AppEvent::MsegInsertPoint { index, pos }))
                 .on_remove_point(|cx, index| cx.emit(AppEvent::MsegRemovePoint { index }));
         })
@@ -120,6 +161,10 @@ fn main() {
         .height(Stretch(1f32))
         .child_space(Pixels(24f32))
         .row_between(Pixels(24f32));
+
+        // Built last so it draws over the widgets
+        Tooltip::new(cx);
+        ContextMenu::new(cx);
     })
     // .with_scale_policy(WindowScalePolicy::ScaleFactor(1.25))
     .run();
@@ -0,0 +1,327 @@
+//! Easing curves and a small animator for moving widget state over time,
+//! such as springing an XY pad back to center, animating a zoom or fading a
+//! hover ring.
+//!
+//! An [`Animator`] is advanced by hand with the time since its last update,
+//! so a view can drive it from whatever timer or frame event it already has.
+//! [`Inertia`] is driven the same way to keep something moving after a drag
+//! lets go of it.
+
+use std::ops::RangeInclusive;
+
+use glam::Vec2;
+
+/// Maps linear progress in `0..=1` onto a curve
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+pub enum Easing {
+    #[default]
+    Linear,
+    QuadIn,
+    QuadOut,
+    QuadInOut,
+    CubicIn,
+    CubicOut,
+    CubicInOut,
+    ExpoOut,
+    /// Overshoots the target a little before settling, like a spring
+    BackOut,
+}
+
+impl Easing {
+    /// Gets the eased progress for a linear progress `t`, clamped to `0..=1`
+    pub fn apply(&self, t: f32) -> f32 {
+        let t = t.clamp(0f32, 1f32);
+        match self {
+            Easing::Linear => t,
+            Easing::QuadIn => t * t,
+            Easing::QuadOut => 1f32 - (1f32 - t).powi(2),
+            Easing::QuadInOut => {
+                if t < 0.5f32 {
+                    2f32 * t * t
+                } else {
+                    1f32 - (-2f32 * t + 2f32).powi(2) / 2f32
+                }
+            }
+            Easing::CubicIn => t.powi(3),
+            Easing::CubicOut => 1f32 - (1f32 - t).powi(3),
+            Easing::CubicInOut => {
+                if t < 0.5f32 {
+                    4f32 * t.powi(3)
+                } else {
+                    1f32 - (-2f32 * t + 2f32).powi(3) / 2f32
+                }
+            }
+            Easing::ExpoOut => {
+                if t >= 1f32 {
+                    1f32
+                } else {
+                    1f32 - 2f32.powf(-10f32 * t)
+                }
+            }
+            Easing::BackOut => {
+                const C1: f32 = 1.70158f32;
+                const C3: f32 = C1 + 1f32;
+                1f32 + C3 * (t - 1f32).powi(3) + C1 * (t - 1f32).powi(2)
+            }
+        }
+    }
+}
+
+/// A value that can be interpolated by an [`Animator`]
+pub trait Interpolate: Clone {
+    /// Gets the value `t` of the way from `self` to `other`
+    fn interpolate(&self, other: &Self, t: f32) -> Self;
+}
+
+impl Interpolate for f32 {
+    fn interpolate(&self, other: &Self, t: f32) -> Self {
+        self + (other - self) * t
+    }
+}
+
+impl Interpolate for Vec2 {
+    fn interpolate(&self, other: &Self, t: f32) -> Self {
+        self.lerp(*other, t)
+    }
+}
+
+impl Interpolate for RangeInclusive<f32> {
+    fn interpolate(&self, other: &Self, t: f32) -> Self {
+        self.start().interpolate(other.start(), t)..=self.end().interpolate(other.end(), t)
+    }
+}
+
+/// Moves a value towards a target over a duration along an [`Easing`] curve
+#[derive(Clone, Debug, PartialEq)]
+pub struct Animator<T: Interpolate> {
+    from: T,
+    to: T,
+    /// The length of the current animation in seconds
+    duration: f32,
+    /// The time in seconds since the current animation started
+    elapsed: f32,
+    easing: Easing,
+}
+
+impl<T: Interpolate> Animator<T> {
+    /// Creates an animator resting at `value`
+    pub fn new(value: T) -> Self {
+        Self {
+            from: value.clone(),
+            to: value,
+            duration: 0f32,
+            elapsed: 0f32,
+            easing: Easing::Linear,
+        }
+    }
+
+    /// Starts animating from the current value to `target` over `duration`
+    /// seconds. Retargeting mid animation starts from wherever the value is,
+    /// so it never jumps.
+    pub fn animate_to(&mut self, target: T, duration: f32, easing: Easing) {
+        self.from = self.value();
+        self.to = target;
+        self.duration = duration.max(0f32);
+        self.elapsed = 0f32;
+        self.easing = easing;
+    }
+
+    /// Jumps straight to `value`, stopping any animation
+    pub fn set(&mut self, value: T) {
+        *self = Self::new(value);
+    }
+
+    /// Advances the animation by `elapsed` seconds and returns the new value
+    pub fn advance(&mut self, elapsed: f32) -> T {
+        self.elapsed = (self.elapsed + elapsed.max(0f32)).min(self.duration);
+        self.value()
+    }
+
+    /// The current value
+    pub fn value(&self) -> T {
+        if self.duration <= 0f32 {
+            return self.to.clone();
+        }
+        let t = self.easing.apply(self.elapsed / self.duration);
+        self.from.interpolate(&self.to, t)
+    }
+
+    /// The value being animated towards
+    pub fn target(&self) -> &T {
+        &self.to
+    }
+
+    /// Whether the value hasn't reached its target yet, so the view should
+    /// keep redrawing
+    pub fn is_animating(&self) -> bool {
+        self.elapsed < self.duration
+    }
+}
+
+/// How long in seconds coasting takes to slow to about a third of its speed
+const INERTIA_TIME_CONSTANT: f32 = 0.325f32;
+/// How much of the last measured speed a drag's velocity takes on, smoothing
+/// out uneven mouse reports
+const VELOCITY_SMOOTHING: f32 = 0.6f32;
+/// How long in seconds a drag may rest before it's let go without coasting
+const RELEASE_WINDOW: f32 = 0.05f32;
+
+/// Keeps a dragged value moving after it's let go, slowing down until it
+/// stops, like flicking through a long list.
+///
+/// The drag's velocity is measured from its movements, in units of the value
+/// per second. Once the drag is let go quickly enough, the value coasts on
+/// from that velocity, decaying exponentially, until it drops below the stop
+/// velocity.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Inertia {
+    /// The velocity below which coasting stops, in units per second
+    stop_velocity: f32,
+    /// The velocity in units per second
+    velocity: f32,
+    coasting: bool,
+}
+
+impl Inertia {
+    /// Creates an inertia at rest that stops coasting below `stop_velocity`
+    /// units per second
+    pub fn new(stop_velocity: f32) -> Self {
+        Self {
+            stop_velocity: stop_velocity.abs(),
+            velocity: 0f32,
+            coasting: false,
+        }
+    }
+
+    /// Measures a drag that moved the value by `delta` over `elapsed` seconds
+    /// since its last movement, stopping any coasting
+    pub fn drag(&mut self, delta: f32, elapsed: f32) {
+        self.coasting = false;
+        if elapsed > 0f32 {
+            let velocity = delta / elapsed;
+            self.velocity += (velocity - self.velocity) * VELOCITY_SMOOTHING;
+        }
+    }
+
+    /// Lets go of the drag `idle` seconds after it last moved, starting to
+    /// coast if it was still moving fast enough. Returns whether it coasts.
+    pub fn release(&mut self, idle: f32) -> bool {
+        self.coasting = idle <= RELEASE_WINDOW && self.velocity.abs() >= self.stop_velocity;
+        if !self.coasting {
+            self.velocity = 0f32;
+        }
+        self.coasting
+    }
+
+    /// Advances the coasting by `elapsed` seconds and returns how far the
+    /// value moved
+    pub fn advance(&mut self, elapsed: f32) -> f32 {
+        if !self.coasting {
+            return 0f32;
+        }
+        let decay = (-elapsed.max(0f32) / INERTIA_TIME_CONSTANT).exp();
+        let distance = self.velocity * INERTIA_TIME_CONSTANT * (1f32 - decay);
+        self.velocity *= decay;
+        if self.velocity.abs() < self.stop_velocity {
+            self.stop();
+        }
+        distance
+    }
+
+    /// Stops coasting and forgets the drag's velocity, as when the value is
+    /// pressed again or reaches the end of its range
+    pub fn stop(&mut self) {
+        self.velocity = 0f32;
+        self.coasting = false;
+    }
+
+    /// Whether the value is still coasting, so the view should keep
+    /// advancing it
+    pub fn is_coasting(&self) -> bool {
+        self.coasting
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use assert_approx_eq::assert_approx_eq;
+
+    #[test]
+    fn eases_between_ends() {
+        let easings = [
+            Easing::Linear,
+            Easing::QuadIn,
+            Easing::QuadOut,
+            Easing::QuadInOut,
+            Easing::CubicIn,
+            Easing::CubicOut,
+            Easing::CubicInOut,
+            Easing::ExpoOut,
+            Easing::BackOut,
+        ];
+        for easing in easings {
+            assert_approx_eq!(easing.apply(0f32), 0f32);
+            assert_approx_eq!(easing.apply(1f32), 1f32);
+        }
+        assert_approx_eq!(Easing::QuadIn.apply(0.5f32), 0.25f32);
+        assert_approx_eq!(Easing::CubicInOut.apply(0.5f32), 0.5f32);
+        assert!(Easing::BackOut.apply(0.8f32) > 1f32);
+    }
+
+    #[test]
+    fn animates_to_target() {
+        let mut animator = Animator::new(0f32);
+        assert!(!animator.is_animating());
+        animator.animate_to(10f32, 1f32, Easing::Linear);
+        assert!(animator.is_animating());
+        assert_approx_eq!(animator.advance(0.25f32), 2.5f32);
+        assert_approx_eq!(animator.advance(1f32), 10f32);
+        assert!(!animator.is_animating());
+        animator.set(3f32);
+        assert_approx_eq!(animator.value(), 3f32);
+    }
+
+    #[test]
+    fn retargets_without_jumping() {
+        let mut animator = Animator::new(Vec2::ZERO);
+        animator.animate_to(Vec2::ONE, 1f32, Easing::Linear);
+        animator.advance(0.5f32);
+        animator.animate_to(Vec2::ZERO, 1f32, Easing::Linear);
+        assert_eq!(animator.value(), Vec2::splat(0.5f32));
+        let mut zoom = Animator::new(0f32..=1f32);
+        zoom.animate_to(0.25f32..=0.5f32, 0.2f32, Easing::CubicOut);
+        assert_eq!(zoom.advance(0.2f32), 0.25f32..=0.5f32);
+    }
+
+    #[test]
+    fn coasts_after_a_flick() {
+        let mut inertia = Inertia::new(10f32);
+        for _ in 0..10 {
+            inertia.drag(10f32, 0.01f32);
+        }
+        assert!(inertia.release(0.01f32));
+        // Slower and slower, and no further than the velocity over the time
+        // constant in total
+        let first = inertia.advance(0.1f32);
+        let second = inertia.advance(0.1f32);
+        assert!(first > second && second > 0f32);
+        let mut total = first + second;
+        while inertia.is_coasting() {
+            total += inertia.advance(0.1f32);
+        }
+        assert!(total < 1000f32 * INERTIA_TIME_CONSTANT);
+        assert_approx_eq!(inertia.advance(0.1f32), 0f32);
+    }
+
+    #[test]
+    fn rests_after_a_pause() {
+        let mut inertia = Inertia::new(10f32);
+        inertia.drag(10f32, 0.01f32);
+        assert!(!inertia.release(0.5f32));
+        assert!(!inertia.is_coasting());
+        // Too slow to coast
+        inertia.drag(0.01f32, 0.01f32);
+        assert!(!inertia.release(0f32));
+    }
+}
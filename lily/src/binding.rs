@@ -0,0 +1,90 @@
+//! Binding widgets to parameters owned by a host or an audio engine.
+//!
+//! A [`ParamBinding`] is the smallest interface lily needs to drive a
+//! parameter: its normalized value, a way to set it, its readout and its
+//! default. Implementing it once for a plugin API's parameter handle, such as
+//! a VST3 or CLAP parameter, lets widgets read and write the parameter without
+//! a lens to a plain value and an `on_changing` callback for each of them.
+//!
+//! ```ignore
+//! binding::slider(cx, Data::gain);
+//! ```
+//!
+//! Hosts that record automation gestures also need to hear when drags begin
+//! and end, which they get by implementing
+//! [`GestureSink`](crate::gesture::GestureSink).
+
+use vizia::prelude::*;
+
+use crate::format::ValueFormatter;
+use crate::param::NormalizedParam;
+use crate::util::ValueMapping;
+use crate::widgets::{DragSlider, DragSliderHandle};
+
+/// A parameter that widgets read and write directly. Bindings are handles to
+/// the parameter, so setting a value through one clone changes it for all of
+/// them.
+pub trait ParamBinding {
+    /// The current value, normalized to `0..=1`
+    fn normalized(&self) -> f32;
+
+    /// Sets the value from a normalized `0..=1` value
+    fn set_normalized(&self, normalized: f32);
+
+    /// The readout of a normalized value, e.g. `-6.0 dB`
+    fn display(&self, normalized: f32) -> String;
+
+    /// The normalized value that resetting goes back to
+    fn default_normalized(&self) -> f32;
+}
+
+/// A [`ParamBinding`] as a [`NormalizedParam`], for widgets taking one. The
+/// plain values lily sees are the binding's normalized values.
+#[derive(Clone)]
+pub struct BoundParam<B>(pub B);
+
+impl<B: ParamBinding> ValueMapping for BoundParam<B> {
+    fn normalize(&self, value: f32) -> f32 {
+        value.clamp(0f32, 1f32)
+    }
+
+    fn denormalize(&self, normalized: f32) -> f32 {
+        normalized.clamp(0f32, 1f32)
+    }
+}
+
+impl<B: ParamBinding> ValueFormatter for BoundParam<B> {
+    fn format(&self, value: f32) -> String {
+        self.0.display(value)
+    }
+}
+
+impl<B: ParamBinding> NormalizedParam for BoundParam<B> {
+    fn normalized(&self) -> f32 {
+        self.0.normalized()
+    }
+
+    fn default_plain(&self) -> f32 {
+        self.0.default_normalized()
+    }
+}
+
+/// Creates a [`DragSlider`] that reads and sets a bound parameter, and resets
+/// it to its default from the context menu
+///
+/// # Parameters
+///
+/// * `cx` - the current [`Context`]
+/// * `binding` - a [`Lens`] to the [`ParamBinding`], so the slider is redrawn
+///   when the model holding it changes
+pub fn slider<L, B>(cx: &mut Context, binding: L) -> Handle<DragSlider<impl Lens<Target = f32>>>
+where
+    L: Lens<Target = B>,
+    B: 'static + ParamBinding + Clone,
+{
+    let param = BoundParam(binding.get(cx));
+    let value = binding.map(|binding| binding.normalized());
+    let setter = param.0.clone();
+    DragSlider::with_param(cx, value, param)
+        .on_changing(move |_, normalized| setter.set_normalized(normalized))
+}
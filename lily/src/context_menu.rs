@@ -0,0 +1,267 @@
+//! A shared right-click context menu for lily widgets.
+//!
+//! Build a [`ContextMenuProvider`] near the root of the UI and a
+//! [`ContextMenu`] after the rest of the content so it draws on top. Widgets
+//! open the menu with [`ContextMenuExt::open_context_menu`] and receive the
+//! chosen [`ContextMenuAction`] as an event, which they pass on through their
+//! `on_context_menu` callback. Applications can add their own entries to every
+//! menu with [`ContextMenuProvider::with_entries`], using
+//! [`ContextMenuAction::Custom`] to tell them apart.
+
+use vizia::prelude::*;
+
+/// What a context menu entry does when chosen
+#[derive(Clone, Debug, PartialEq, Data)]
+pub enum ContextMenuAction {
+    /// Reset the value to its default
+    Reset,
+    /// Type in a value
+    EnterValue,
+    /// Start learning a MIDI controller for the value
+    MidiLearn,
+    /// Lock the value so it can't be changed by presets or automation
+    Lock,
+    /// Copy the value to the clipboard
+    Copy,
+    /// Paste a value from the clipboard
+    Paste,
+    /// An application specific entry, identified by name
+    Custom(String),
+}
+
+/// A single entry of a context menu
+#[derive(Clone, Debug, PartialEq, Data)]
+pub struct ContextMenuItem {
+    pub label: String,
+    pub action: ContextMenuAction,
+}
+
+impl ContextMenuItem {
+    pub fn new(label: impl Into<String>, action: ContextMenuAction) -> Self {
+        Self {
+            label: label.into(),
+            action,
+        }
+    }
+
+    pub fn reset() -> Self {
+        Self::new("Reset", ContextMenuAction::Reset)
+    }
+
+    pub fn enter_value() -> Self {
+        Self::new("Enter value...", ContextMenuAction::EnterValue)
+    }
+
+    pub fn midi_learn() -> Self {
+        Self::new("MIDI learn", ContextMenuAction::MidiLearn)
+    }
+
+    pub fn lock() -> Self {
+        Self::new("Lock", ContextMenuAction::Lock)
+    }
+
+    pub fn copy() -> Self {
+        Self::new("Copy", ContextMenuAction::Copy)
+    }
+
+    pub fn paste() -> Self {
+        Self::new("Paste", ContextMenuAction::Paste)
+    }
+
+    /// An application specific entry
+    pub fn custom(label: impl Into<String>, id: impl Into<String>) -> Self {
+        Self::new(label, ContextMenuAction::Custom(id.into()))
+    }
+
+    /// The entries shown for widgets that edit a single parameter value
+    pub fn parameter_items() -> Vec<Self> {
+        vec![
+            Self::reset(),
+            Self::enter_value(),
+            Self::midi_learn(),
+            Self::lock(),
+        ]
+    }
+}
+
+pub enum ContextMenuEvent {
+    Open {
+        /// The widget that chosen actions are sent to
+        origin: Entity,
+        items: Vec<ContextMenuItem>,
+        x: f32,
+        y: f32,
+    },
+    Select(usize),
+    Close,
+}
+
+/// Holds the state of the shared context menu
+#[allow(clippy::type_complexity)]
+#[derive(Lens)]
+pub struct ContextMenuProvider {
+    pub items: Vec<ContextMenuItem>,
+    pub open: bool,
+    pub x: f32,
+    pub y: f32,
+    origin: Option<Entity>,
+    /// Adds application specific entries to a menu before it is opened
+    entries: Option<Box<dyn Fn(Entity, &mut Vec<ContextMenuItem>)>>,
+}
+
+impl ContextMenuProvider {
+    pub fn new() -> Self {
+        Self {
+            items: Vec::new(),
+            open: false,
+            x: 0f32,
+            y: 0f32,
+            origin: None,
+            entries: None,
+        }
+    }
+
+    /// Add application specific entries to every menu. The callback is given
+    /// the widget opening the menu and the entries it asked for.
+    pub fn with_entries<F>(mut self, entries: F) -> Self
+    where
+        F: 'static + Fn(Entity, &mut Vec<ContextMenuItem>),
+    {
+        self.entries = Some(Box::new(entries));
+        self
+    }
+}
+
+impl Default for ContextMenuProvider {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Model for ContextMenuProvider {
+    fn event(&mut self, cx: &mut EventContext, event: &mut Event) {
+        event.map(|ev: &ContextMenuEvent, meta| {
+            match ev {
+                ContextMenuEvent::Open {
+                    origin,
+                    items,
+                    x,
+                    y,
+                } => {
+                    let mut items = items.clone();
+                    if let Some(entries) = &self.entries {
+                        (entries)(*origin, &mut items);
+                    }
+                    self.open = !items.is_empty();
+                    self.items = items;
+                    self.origin = Some(*origin);
+                    self.x = *x;
+                    self.y = *y;
+                }
+                ContextMenuEvent::Select(index) => {
+                    if let (Some(origin), Some(item)) = (self.origin, self.items.get(*index)) {
+                        cx.emit_to(origin, item.action.clone());
+                    }
+                    self.open = false;
+                    self.origin = None;
+                }
+                ContextMenuEvent::Close => {
+                    self.open = false;
+                    self.origin = None;
+                }
+            }
+            meta.consume();
+        });
+
+        // Any click that wasn't on an entry closes the menu. Entries consume
+        // their clicks, and the click opening a menu reaches us before its
+        // `Open` event does.
+        event.map(|ev: &WindowEvent, _| {
+            if let WindowEvent::MouseDown(_) = ev {
+                if self.open {
+                    self.open = false;
+                    self.origin = None;
+                }
+            }
+        });
+    }
+}
+
+/// The view that displays the shared context menu. Build this once, after the
+/// rest of the UI, below a [`ContextMenuProvider`].
+pub struct ContextMenu;
+
+impl ContextMenu {
+    pub fn new(cx: &mut Context) -> Handle<Self> {
+        Self.build(cx, |cx| {
+            Binding::new(cx, ContextMenuProvider::items, |cx, items| {
+                for (index, item) in items.get(cx).iter().enumerate() {
+                    ContextMenuEntry { index }
+                        .build(cx, |cx| {
+                            Label::new(cx, &item.label).hoverable(false);
+                        })
+                        .class("item");
+                }
+            });
+        })
+        .position_type(PositionType::SelfDirected)
+        .bind(ContextMenuProvider::x, |handle, x| {
+            let x = x.get(handle.cx);
+            handle.left(Pixels(x));
+        })
+        .bind(ContextMenuProvider::y, |handle, y| {
+            let y = y.get(handle.cx);
+            handle.top(Pixels(y));
+        })
+        .bind(ContextMenuProvider::open, |handle, open| {
+            let open = open.get(handle.cx);
+            handle.display(if open { Display::Flex } else { Display::None });
+        })
+    }
+}
+
+impl View for ContextMenu {
+    fn element(&self) -> Option<&'static str> {
+        Some("context-menu")
+    }
+}
+
+struct ContextMenuEntry {
+    index: usize,
+}
+
+impl View for ContextMenuEntry {
+    fn event(&mut self, cx: &mut EventContext, event: &mut Event) {
+        event.map(|ev: &WindowEvent, meta| {
+            if let WindowEvent::MouseDown(MouseButton::Left) = ev {
+                cx.emit(ContextMenuEvent::Select(self.index));
+                meta.consume();
+            }
+        });
+    }
+}
+
+/// Opens the shared context menu from a widget's event handler
+pub trait ContextMenuExt {
+    /// Open a context menu at the cursor. Chosen entries are sent back to the
+    /// current widget as a [`ContextMenuAction`] event.
+    fn open_context_menu(&mut self, items: Vec<ContextMenuItem>);
+    fn close_context_menu(&mut self);
+}
+
+impl ContextMenuExt for EventContext<'_> {
+    fn open_context_menu(&mut self, items: Vec<ContextMenuItem>) {
+        let origin = self.current();
+        let (x, y) = (self.mouse.cursorx, self.mouse.cursory);
+        self.emit(ContextMenuEvent::Open {
+            origin,
+            items,
+            x,
+            y,
+        });
+    }
+
+    fn close_context_menu(&mut self) {
+        self.emit(ContextMenuEvent::Close);
+    }
+}
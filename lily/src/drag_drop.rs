@@ -0,0 +1,241 @@
+//! Dragging things from one lily widget onto another.
+//!
+//! Build a [`DragDropProvider`] near the root of the UI, make widgets that can
+//! be picked up into sources with [`DragDropExt::drag_source`] and widgets
+//! that accept drops into targets with [`DragDropExt::drop_target`]. A source
+//! starts a drag with its [`DragPayload`] once the mouse moves a few pixels
+//! while pressed, and releasing it over a target calls the target's callback
+//! with the payload. For example, an LFO shape can be dropped onto an
+//! [`Mseg`](crate::widgets::Mseg) to replace its points, or a modulation
+//! source onto a slider to assign it:
+//!
+//! ```ignore
+//! Label::new(cx, "LFO 1").drag_source(|_| DragPayload::ModulationSource("lfo1".into()));
+//! DragSlider::new(cx, Data::cutoff).drop_target(|cx, payload| {
+//!     if let DragPayload::ModulationSource(source) = payload {
+//!         cx.emit(AppEvent::Assign(source.clone(), "cutoff".into()));
+//!     }
+//! });
+//! ```
+
+use glam::Vec2;
+use vizia::prelude::*;
+
+use crate::util::{CurvePoints, PseudoClassExt};
+
+/// How far in logical pixels the mouse moves while pressed on a source
+/// before a drag starts
+const DRAG_THRESHOLD: f32 = 4f32;
+
+/// What is being dragged
+#[derive(Clone)]
+pub enum DragPayload {
+    /// The points of a shape, such as an LFO shape or an envelope preset
+    Curve(CurvePoints),
+    /// The id of a modulation source, such as an LFO or an envelope
+    ModulationSource(String),
+    /// An application specific payload, identified by name
+    Custom(String),
+}
+
+pub enum DragDropEvent {
+    /// A source started dragging its payload
+    Start(DragPayload),
+    /// The payload was dropped, sent to the target under the mouse
+    Drop(DragPayload),
+    /// The drag ended, whether or not the payload was dropped on a target
+    End,
+}
+
+/// Holds whether something is being dragged, so drop targets only take the
+/// mouse during a drag
+#[derive(Default, Lens)]
+pub struct DragDropProvider {
+    pub dragging: bool,
+    /// The payload of the current drag
+    pub payload: Option<DragPayload>,
+}
+
+impl Model for DragDropProvider {
+    fn event(&mut self, _cx: &mut EventContext, event: &mut Event) {
+        event.map(|ev: &DragDropEvent, _| match ev {
+            DragDropEvent::Start(payload) => {
+                self.dragging = true;
+                self.payload = Some(payload.clone());
+            }
+            DragDropEvent::End => {
+                self.dragging = false;
+                self.payload = None;
+            }
+            DragDropEvent::Drop(_) => (),
+        });
+    }
+}
+
+/// The overlay of a widget that can be picked up. It is marked `:active`
+/// while pressed and while its payload is being dragged.
+#[allow(clippy::type_complexity)]
+pub struct DragSource {
+    payload: Box<dyn Fn(&mut EventContext) -> DragPayload>,
+    /// Where the mouse was pressed, until the drag starts or the mouse is
+    /// released
+    pressed_at: Option<Vec2>,
+    /// The payload being dragged, once the drag has started
+    dragged: Option<DragPayload>,
+}
+
+impl DragSource {
+    /// Create a new `DragSource` covering the current view
+    ///
+    /// # Parameters
+    ///
+    /// * `cx` - the current [`Context`]
+    /// * `payload` - builds the payload when a drag starts
+    pub fn new<F>(cx: &mut Context, payload: F) -> Handle<Self>
+    where
+        F: 'static + Fn(&mut EventContext) -> DragPayload,
+    {
+        Self {
+            payload: Box::new(payload),
+            pressed_at: None,
+            dragged: None,
+        }
+        .build(cx, |_| {})
+        .position_type(PositionType::SelfDirected)
+        .width(Stretch(1f32))
+        .height(Stretch(1f32))
+    }
+}
+
+impl View for DragSource {
+    fn element(&self) -> Option<&'static str> {
+        Some("drag-source")
+    }
+
+    fn event(&mut self, cx: &mut EventContext, event: &mut Event) {
+        if cx.is_disabled() {
+            return;
+        }
+        event.map(|ev: &WindowEvent, meta| match *ev {
+            WindowEvent::MouseDown(MouseButton::Left) => {
+                cx.capture_active();
+                self.pressed_at = Some(Vec2::new(cx.mouse.cursorx, cx.mouse.cursory));
+                meta.consume();
+            }
+            WindowEvent::MouseMove(x, y) => {
+                let threshold = cx.to_physical(DRAG_THRESHOLD);
+                let moved = self
+                    .pressed_at
+                    .is_some_and(|origin| origin.distance(Vec2::new(x, y)) >= threshold);
+                if moved && self.dragged.is_none() {
+                    let payload = (self.payload)(cx);
+                    self.dragged = Some(payload.clone());
+                    cx.emit(DragDropEvent::Start(payload));
+                }
+            }
+            WindowEvent::MouseUp(MouseButton::Left) => {
+                self.pressed_at = None;
+                cx.release_active();
+                if let Some(payload) = self.dragged.take() {
+                    let target = cx.hovered();
+                    if target != cx.current() {
+                        cx.emit_to(target, DragDropEvent::Drop(payload));
+                    }
+                    cx.emit(DragDropEvent::End);
+                }
+                meta.consume();
+            }
+            _ => (),
+        });
+    }
+}
+
+/// The overlay of a widget that accepts drops. It only takes the mouse while
+/// something is being dragged, and gets the `dragging` class then so it can
+/// be highlighted.
+#[allow(clippy::type_complexity)]
+pub struct DropTarget {
+    on_drop: Box<dyn Fn(&mut EventContext, &DragPayload)>,
+}
+
+impl DropTarget {
+    /// Create a new `DropTarget` covering the current view
+    ///
+    /// # Parameters
+    ///
+    /// * `cx` - the current [`Context`]
+    /// * `on_drop` - called with the payload dropped on the target
+    pub fn new<F>(cx: &mut Context, on_drop: F) -> Handle<Self>
+    where
+        F: 'static + Fn(&mut EventContext, &DragPayload),
+    {
+        Self {
+            on_drop: Box::new(on_drop),
+        }
+        .build(cx, |_| {})
+        .position_type(PositionType::SelfDirected)
+        .width(Stretch(1f32))
+        .height(Stretch(1f32))
+        .toggle_class("dragging", DragDropProvider::dragging)
+        .hoverable(DragDropProvider::dragging)
+    }
+}
+
+impl View for DropTarget {
+    fn element(&self) -> Option<&'static str> {
+        Some("drop-target")
+    }
+
+    fn event(&mut self, cx: &mut EventContext, event: &mut Event) {
+        event.map(|ev: &DragDropEvent, meta| {
+            if let DragDropEvent::Drop(payload) = ev {
+                if !cx.is_disabled() {
+                    (self.on_drop)(cx, payload);
+                }
+                meta.consume();
+            }
+        });
+    }
+}
+
+/// Lets widgets be dragged onto each other
+pub trait DragDropExt {
+    /// Covers the widget with a [`DragSource`] that drags the payload built
+    /// by `payload`. The overlay takes the widget's mouse presses, so this is
+    /// meant for widgets that are only picked up, like shape thumbnails and
+    /// modulation source labels.
+    fn drag_source<F>(self, payload: F) -> Self
+    where
+        F: 'static + Fn(&mut EventContext) -> DragPayload;
+
+    /// Covers the widget with a [`DropTarget`] that calls `callback` with
+    /// each payload dropped on it. The widget keeps the mouse when nothing is
+    /// being dragged.
+    fn drop_target<F>(self, callback: F) -> Self
+    where
+        F: 'static + Fn(&mut EventContext, &DragPayload);
+}
+
+impl<V: View> DragDropExt for Handle<'_, V> {
+    fn drag_source<F>(self, payload: F) -> Self
+    where
+        F: 'static + Fn(&mut EventContext) -> DragPayload,
+    {
+        let entity = self.entity;
+        self.cx.with_current(entity, |cx| {
+            DragSource::new(cx, payload);
+        });
+        self
+    }
+
+    fn drop_target<F>(self, callback: F) -> Self
+    where
+        F: 'static + Fn(&mut EventContext, &DragPayload),
+    {
+        let entity = self.entity;
+        self.cx.with_current(entity, |cx| {
+            DropTarget::new(cx, callback);
+        });
+        self
+    }
+}
@@ -0,0 +1,124 @@
+//! Meter and scope widgets fed straight from the audio thread.
+//!
+//! Each function here builds a widget that owns the data it shows and polls a
+//! [`MeterSource`] or [`ScopeSource`] for it about once per frame, so the
+//! application doesn't need a model field and an event for every meter. Any
+//! source works, such as lily's ring buffer and triple buffer or an
+//! application's own channels.
+//!
+//! ```ignore
+//! let (producer, consumer) = ring_buffer(1024);
+//! feed::threshold_scope(cx, consumer, 256, Data::threshold, None);
+//! ```
+
+use vizia::prelude::*;
+
+use crate::util::{spawn_emit, MeterSource, ScopeSource, FEED_INTERVAL};
+use crate::widgets::{ThresholdScope, VoiceDisplay, VoiceState};
+
+pub enum FeedEvent {
+    Levels(Vec<f32>),
+    Voices(Vec<VoiceState>),
+}
+
+/// The level history of a scope fed by a [`ScopeSource`], keeping the newest
+/// `max_len` levels
+#[derive(Lens)]
+pub struct LevelHistory {
+    pub levels: Vec<f32>,
+    max_len: usize,
+}
+
+impl Model for LevelHistory {
+    fn event(&mut self, _cx: &mut EventContext, event: &mut Event) {
+        event.map(|ev: &FeedEvent, meta| {
+            if let FeedEvent::Levels(levels) = ev {
+                self.levels.extend_from_slice(levels);
+                if self.levels.len() > self.max_len {
+                    self.levels.drain(..self.levels.len() - self.max_len);
+                }
+                meta.consume();
+            }
+        });
+    }
+}
+
+/// The voices of a display fed by a [`MeterSource`]
+#[derive(Lens)]
+pub struct VoiceFeed {
+    pub voices: Vec<VoiceState>,
+}
+
+impl Model for VoiceFeed {
+    fn event(&mut self, _cx: &mut EventContext, event: &mut Event) {
+        event.map(|ev: &FeedEvent, meta| {
+            if let FeedEvent::Voices(voices) = ev {
+                self.voices = voices.clone();
+                meta.consume();
+            }
+        });
+    }
+}
+
+/// Creates a [`ThresholdScope`] showing the levels drained from a source
+///
+/// # Parameters
+///
+/// * `cx` - the current [`Context`]
+/// * `source` - the [`ScopeSource`] of levels normalized to `0..=1`
+/// * `max_len` - the number of levels kept in the scope's history
+/// * `threshold` - a [`Lens`] of the normalized threshold
+/// * `hysteresis` - the normalized height of the hysteresis band below the
+///   threshold, or `None` to hide it
+pub fn threshold_scope<S, T>(
+    cx: &mut Context,
+    mut source: S,
+    max_len: usize,
+    threshold: T,
+    hysteresis: Option<f32>,
+) -> Handle<ThresholdScope<impl Lens<Target = Vec<f32>>, T>>
+where
+    S: 'static + ScopeSource<Sample = f32>,
+    T: Lens<Target = f32>,
+{
+    ThresholdScope::build_scope(cx, LevelHistory::levels, threshold, hysteresis, |cx| {
+        LevelHistory {
+            levels: Vec::with_capacity(max_len),
+            max_len,
+        }
+        .build(cx);
+        let scope = cx.current();
+        spawn_emit(cx, Some(scope), FEED_INTERVAL, move || {
+            let mut levels = Vec::new();
+            source.drain_samples(&mut levels);
+            (!levels.is_empty()).then_some(FeedEvent::Levels(levels))
+        });
+    })
+}
+
+/// Creates a [`VoiceDisplay`] showing the latest voices read from a source
+///
+/// # Parameters
+///
+/// * `cx` - the current [`Context`]
+/// * `source` - the [`MeterSource`] of the state of every voice
+/// * `voices` - the number of voices shown until the source is first read
+pub fn voice_display<S>(
+    cx: &mut Context,
+    mut source: S,
+    voices: usize,
+) -> Handle<VoiceDisplay<impl Lens<Target = Vec<VoiceState>>>>
+where
+    S: 'static + MeterSource<Value = Vec<VoiceState>>,
+{
+    VoiceDisplay::build_display(cx, VoiceFeed::voices, |cx| {
+        VoiceFeed {
+            voices: vec![VoiceState::default(); voices],
+        }
+        .build(cx);
+        let display = cx.current();
+        spawn_emit(cx, Some(display), FEED_INTERVAL, move || {
+            source.latest().map(FeedEvent::Voices)
+        });
+    })
+}
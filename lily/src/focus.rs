@@ -0,0 +1,152 @@
+//! Driving lily widgets from the keyboard.
+//!
+//! Interactive widgets are tab stops: with a [`FocusProvider`] installed near
+//! the root of the UI, Tab moves the keyboard focus to the next tab stop and
+//! Shift+Tab to the previous one, in the order the views are built and
+//! wrapping around at either end. Disabled and hidden tab stops are skipped.
+//! Other views become tab stops with [`FocusExt::tab_stop`].
+//!
+//! A focused widget draws a focus ring with the `outline-color` and
+//! `outline-width` that the stylesheet sets on `.tab-stop:focus`. The arrow
+//! and page keys then edit its value, and Enter and Space activate it as
+//! described by [`Activation`].
+//!
+//! Keyboard control is all lily offers for accessibility. The vizia it builds
+//! on has no accessibility tree, so widgets don't report roles, labels or
+//! values to screen readers. They'll need that once vizia gains one.
+
+use vizia::prelude::*;
+
+/// The class of views that Tab moves the focus to
+pub const TAB_STOP_CLASS: &str = "tab-stop";
+
+/// What a key press asks of the focused widget, so widgets answer to Enter and
+/// Space the same way
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Activation {
+    /// Enter, which opens what the widget holds for editing, such as typing a
+    /// value into a value box, or else presses it
+    Open,
+    /// Space, which presses the widget, such as toggling a button
+    Press,
+}
+
+impl Activation {
+    /// The activation of a key, or `None` if the key doesn't activate widgets
+    pub fn from_key(code: Code) -> Option<Self> {
+        match code {
+            Code::Enter | Code::NumpadEnter => Some(Activation::Open),
+            Code::Space => Some(Activation::Press),
+            _ => None,
+        }
+    }
+}
+
+/// Moves the keyboard focus between the tab stops below it
+#[derive(Default)]
+pub struct FocusProvider;
+
+impl FocusProvider {
+    pub fn install(cx: &mut Context) {
+        Self.build(cx);
+    }
+}
+
+impl Model for FocusProvider {
+    fn event(&mut self, cx: &mut EventContext, event: &mut Event) {
+        // Tab reaches the provider from the focused view, unless a view on
+        // the way takes it
+        event.map(|ev: &WindowEvent, meta| {
+            if let WindowEvent::KeyDown(Code::Tab, _) = ev {
+                let backwards = cx.modifiers.contains(Modifiers::SHIFT);
+                let stops: Vec<Entity> = cx
+                    .tree
+                    .into_iter()
+                    .filter(|entity| is_tab_stop(cx, *entity))
+                    .collect();
+                if let Some(next) = next_stop(&stops, meta.origin, backwards) {
+                    cx.with_current(next, |cx| cx.focus());
+                    cx.style.needs_restyle = true;
+                }
+                meta.consume();
+            }
+        });
+    }
+}
+
+/// Whether `entity` is a tab stop that is neither disabled nor hidden, by
+/// itself or by an ancestor
+fn is_tab_stop(cx: &EventContext, entity: Entity) -> bool {
+    let marked = cx
+        .style
+        .classes
+        .get(entity)
+        .is_some_and(|classes| classes.contains(TAB_STOP_CLASS));
+    let mut current = Some(entity);
+    while let Some(view) = current {
+        let disabled = cx
+            .style
+            .pseudo_classes
+            .get(view)
+            .is_some_and(|classes| classes.contains(PseudoClass::DISABLED));
+        let hidden = cx.style.display.get(view) == Some(&Display::None);
+        if disabled || hidden {
+            return false;
+        }
+        current = cx.tree.get_parent(view);
+    }
+    marked
+}
+
+/// The stop after `focused`, or before it going backwards, wrapping around at
+/// either end. The first or last stop follows a view that isn't a stop.
+fn next_stop<T: Copy + PartialEq>(stops: &[T], focused: T, backwards: bool) -> Option<T> {
+    let len = stops.len();
+    if len == 0 {
+        return None;
+    }
+    let next = match (stops.iter().position(|stop| *stop == focused), backwards) {
+        (Some(index), false) => (index + 1) % len,
+        (Some(index), true) => (index + len - 1) % len,
+        (None, false) => 0,
+        (None, true) => len - 1,
+    };
+    Some(stops[next])
+}
+
+/// Makes views tab stops
+pub trait FocusExt {
+    /// Lets Tab move the focus to the view, which gets a focus ring from the
+    /// `.tab-stop:focus` rule of the stylesheet
+    fn tab_stop(self) -> Self;
+}
+
+impl<V: View> FocusExt for Handle<'_, V> {
+    fn tab_stop(self) -> Self {
+        self.class(TAB_STOP_CLASS)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn cycles_through_stops() {
+        let stops = [1, 2, 3];
+        assert_eq!(next_stop(&stops, 1, false), Some(2));
+        assert_eq!(next_stop(&stops, 3, false), Some(1));
+        assert_eq!(next_stop(&stops, 1, true), Some(3));
+        // Focus outside of the stops, such as on the window itself
+        assert_eq!(next_stop(&stops, 0, false), Some(1));
+        assert_eq!(next_stop(&stops, 0, true), Some(3));
+        assert_eq!(next_stop(&[], 0, false), None::<i32>);
+    }
+
+    #[test]
+    fn activates_with_enter_and_space() {
+        assert_eq!(Activation::from_key(Code::Enter), Some(Activation::Open));
+        assert_eq!(Activation::from_key(Code::Space), Some(Activation::Press));
+        assert_eq!(Activation::from_key(Code::KeyA), None);
+    }
+}
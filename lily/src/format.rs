@@ -0,0 +1,257 @@
+//! Formatting values for readouts and parsing them back from text entry.
+//!
+//! The built-in formatters each implement both [`ValueFormatter`] and
+//! [`ValueParser`], so anything they show can be typed back in, and take
+//! values in the same units as the functions in [`crate::units`].
+
+use crate::units::{
+    db_to_gain, format_db, format_hz, format_note, format_seconds, midi_to_hz, MINUS_INFINITY_DB,
+};
+
+/// Turns a value into the text shown in a readout, such as a tooltip
+pub trait ValueFormatter {
+    fn format(&self, value: f32) -> String;
+}
+
+/// Turns text typed by the user back into a value, if it can be read
+pub trait ValueParser {
+    fn parse(&self, text: &str) -> Option<f32>;
+}
+
+impl<F: Fn(f32) -> String> ValueFormatter for F {
+    fn format(&self, value: f32) -> String {
+        (self)(value)
+    }
+}
+
+/// A plain number with a fixed number of decimals
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Decimal(pub usize);
+
+impl Default for Decimal {
+    fn default() -> Self {
+        Self(2)
+    }
+}
+
+impl ValueFormatter for Decimal {
+    fn format(&self, value: f32) -> String {
+        format!("{:.*}", self.0, value)
+    }
+}
+
+impl ValueParser for Decimal {
+    fn parse(&self, text: &str) -> Option<f32> {
+        text.trim().parse().ok()
+    }
+}
+
+/// Linear gain shown in decibels, e.g. `-6.0 dB`. Parses `-6`, `-6 dB` and
+/// `-inf`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Decibels;
+
+impl ValueFormatter for Decibels {
+    fn format(&self, value: f32) -> String {
+        format_db(value)
+    }
+}
+
+impl ValueParser for Decibels {
+    fn parse(&self, text: &str) -> Option<f32> {
+        let text = text.trim().to_lowercase();
+        let text = text.strip_suffix("db").unwrap_or(&text).trim();
+        if text == "-inf" {
+            return Some(db_to_gain(MINUS_INFINITY_DB));
+        }
+        text.parse().ok().map(db_to_gain)
+    }
+}
+
+/// A value in `0..=1` shown as a percentage, e.g. `50%`
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Percent;
+
+impl ValueFormatter for Percent {
+    fn format(&self, value: f32) -> String {
+        format!("{:.0}%", value * 100f32)
+    }
+}
+
+impl ValueParser for Percent {
+    fn parse(&self, text: &str) -> Option<f32> {
+        let text = text.trim();
+        let text = text.strip_suffix('%').unwrap_or(text).trim();
+        text.parse::<f32>().ok().map(|percent| percent / 100f32)
+    }
+}
+
+/// A frequency, e.g. `440 Hz` or `2.50 kHz`. Parses `2.5k`, `2.5 kHz` and
+/// `440hz`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Hertz;
+
+impl ValueFormatter for Hertz {
+    fn format(&self, value: f32) -> String {
+        format_hz(value)
+    }
+}
+
+impl ValueParser for Hertz {
+    fn parse(&self, text: &str) -> Option<f32> {
+        let (value, unit) = split_unit(text)?;
+        match unit.as_str() {
+            "" | "hz" => Some(value),
+            "k" | "khz" => Some(value * 1000f32),
+            _ => None,
+        }
+    }
+}
+
+/// A duration in seconds, e.g. `250 ms` or `1.50 s`. Numbers without a unit
+/// are read as seconds.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Seconds;
+
+impl ValueFormatter for Seconds {
+    fn format(&self, value: f32) -> String {
+        format_seconds(value)
+    }
+}
+
+impl ValueParser for Seconds {
+    fn parse(&self, text: &str) -> Option<f32> {
+        let (value, unit) = split_unit(text)?;
+        match unit.as_str() {
+            "" | "s" | "sec" => Some(value),
+            "ms" => Some(value / 1000f32),
+            _ => None,
+        }
+    }
+}
+
+/// An interval in semitones, e.g. `+7 st`
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Semitones;
+
+impl ValueFormatter for Semitones {
+    fn format(&self, value: f32) -> String {
+        if value.fract() == 0f32 {
+            format!("{value:+.0} st")
+        } else {
+            format!("{value:+.2} st")
+        }
+    }
+}
+
+impl ValueParser for Semitones {
+    fn parse(&self, text: &str) -> Option<f32> {
+        let (value, unit) = split_unit(text)?;
+        matches!(unit.as_str(), "" | "st" | "semi").then_some(value)
+    }
+}
+
+/// A frequency shown as the nearest note and its offset in cents, e.g.
+/// `A4 +12`. Parses note names such as `C#3` or `Db2 -10`, and falls back to
+/// frequencies like [`Hertz`].
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct NoteName;
+
+impl ValueFormatter for NoteName {
+    fn format(&self, value: f32) -> String {
+        format_note(value)
+    }
+}
+
+impl ValueParser for NoteName {
+    fn parse(&self, text: &str) -> Option<f32> {
+        parse_note(text).or_else(|| Hertz.parse(text))
+    }
+}
+
+/// Parses a note name with an optional offset in cents into a frequency
+fn parse_note(text: &str) -> Option<f32> {
+    let mut chars = text.trim().chars().peekable();
+    let degree = match chars.next()?.to_ascii_uppercase() {
+        'C' => 0,
+        'D' => 2,
+        'E' => 4,
+        'F' => 5,
+        'G' => 7,
+        'A' => 9,
+        'B' => 11,
+        _ => return None,
+    };
+    let accidental = match chars.peek() {
+        Some('#') => 1,
+        Some('b') => -1,
+        _ => 0,
+    };
+    if accidental != 0 {
+        chars.next();
+    }
+    let rest: String = chars.collect();
+    let (octave, cents) = match rest.trim_start().find(|c: char| c.is_whitespace()) {
+        Some(split) => {
+            let (octave, cents) = rest.trim_start().split_at(split);
+            (octave.to_string(), cents.trim().parse::<f32>().ok()?)
+        }
+        None => (rest.trim().to_string(), 0f32),
+    };
+    let octave: i32 = octave.parse().ok()?;
+    let note = (octave + 1) * 12 + degree + accidental;
+    Some(midi_to_hz(note as f32 + cents / 100f32))
+}
+
+/// Splits text into its leading number and the lowercase unit after it
+fn split_unit(text: &str) -> Option<(f32, String)> {
+    let text = text.trim();
+    let split = text.find(|c: char| c.is_alphabetic()).unwrap_or(text.len());
+    let (number, unit) = text.split_at(split);
+    Some((number.trim().parse().ok()?, unit.trim().to_lowercase()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use assert_approx_eq::assert_approx_eq;
+
+    #[test]
+    fn formats_values() {
+        assert_eq!(Decimal(1).format(0.25f32), "0.2");
+        assert_eq!(Percent.format(0.5f32), "50%");
+        assert_eq!(Semitones.format(7f32), "+7 st");
+        assert_eq!(Semitones.format(-0.5f32), "-0.50 st");
+        assert_eq!((|v: f32| format!("{v} x")).format(2f32), "2 x");
+    }
+
+    #[test]
+    fn parses_values() {
+        assert_approx_eq!(Decibels.parse("-6 dB").unwrap(), 0.501187f32, 1e-5f32);
+        assert_eq!(Decibels.parse("-inf"), Some(0f32));
+        assert_approx_eq!(Percent.parse("25 %").unwrap(), 0.25f32);
+        assert_eq!(Hertz.parse("2.5k"), Some(2500f32));
+        assert_eq!(Hertz.parse("440 Hz"), Some(440f32));
+        assert_eq!(Hertz.parse("440 s"), None);
+        assert_eq!(Seconds.parse("250ms"), Some(0.25f32));
+        assert_eq!(Seconds.parse("2"), Some(2f32));
+        assert_eq!(Semitones.parse("-12 st"), Some(-12f32));
+        assert_approx_eq!(NoteName.parse("A4").unwrap(), 440f32);
+        assert_approx_eq!(NoteName.parse("C#4").unwrap(), 277.1826f32, 1e-3f32);
+        assert_approx_eq!(NoteName.parse("Bb3").unwrap(), 233.0819f32, 1e-3f32);
+        assert_approx_eq!(NoteName.parse("1k").unwrap(), 1000f32);
+        assert_eq!(Decimal::default().parse("nope"), None);
+    }
+
+    #[test]
+    fn round_trips() {
+        // Only as precise as the readout
+        let gain = Decibels.parse(&Decibels.format(0.25f32)).unwrap();
+        assert_approx_eq!(gain, 0.25f32, 1e-2f32);
+        assert_approx_eq!(Hertz.parse(&Hertz.format(12500f32)).unwrap(), 12500f32);
+        assert_approx_eq!(Seconds.parse(&Seconds.format(0.25f32)).unwrap(), 0.25f32);
+        assert_approx_eq!(Semitones.parse(&Semitones.format(3f32)).unwrap(), 3f32);
+        let hz = midi_to_hz(61.2f32);
+        assert_approx_eq!(NoteName.parse(&NoteName.format(hz)).unwrap(), hz, 1e-2f32);
+    }
+}
@@ -0,0 +1,117 @@
+//! Automation gestures around the edits made with lily widgets.
+//!
+//! Hosts record automation in gestures: an edit begins, its values change any
+//! number of times and it ends. Interactive widgets report their drags through
+//! the [`GestureExt`] methods on their [`EventContext`], so a host integration
+//! implements [`GestureSink`] once instead of hooking into every widget.
+//!
+//! Build a [`GestureProvider`] with the sink near the root of the UI. A model
+//! can also act as the sink itself by implementing [`GestureSink`] and calling
+//! [`GestureSink::handle_gesture_event`] from its `event`. Without either,
+//! gestures are ignored. Widgets that only change what is shown, such as the
+//! zoomer, don't report gestures.
+
+use vizia::prelude::*;
+
+pub enum GestureEvent {
+    Begin(Entity),
+    Perform {
+        widget: Entity,
+        index: usize,
+        values: Vec<f32>,
+    },
+    End(Entity),
+}
+
+/// Receives the edits made with widgets, bracketed by the start and end of the
+/// drag making them
+///
+/// `widget` is the view being dragged, which for compound widgets such as the
+/// MSEG is a part of the widget, so sinks looking up widgets may need to check
+/// its ancestors.
+pub trait GestureSink {
+    /// A drag of `widget` started
+    fn begin_edit(&mut self, cx: &mut EventContext, widget: Entity);
+
+    /// The drag changed the `values` of the edited item, such as the value of
+    /// a slider, the x and y of a point, the curve of an envelope segment or
+    /// the time, level and pan of a delay tap. `index` tells apart the items of widgets editing several, such as
+    /// the points of an envelope, and is `0` for the others.
+    fn perform_edit(&mut self, cx: &mut EventContext, widget: Entity, index: usize, values: &[f32]);
+
+    /// The drag of `widget` ended
+    fn end_edit(&mut self, cx: &mut EventContext, widget: Entity);
+
+    /// Passes a [`GestureEvent`] on to the methods above
+    fn handle_gesture_event(&mut self, cx: &mut EventContext, event: &mut Event) {
+        event.map(|ev: &GestureEvent, meta| {
+            match ev {
+                GestureEvent::Begin(widget) => self.begin_edit(cx, *widget),
+                GestureEvent::Perform {
+                    widget,
+                    index,
+                    values,
+                } => self.perform_edit(cx, *widget, *index, values),
+                GestureEvent::End(widget) => self.end_edit(cx, *widget),
+            }
+            meta.consume();
+        });
+    }
+}
+
+/// Hands the gestures of the widgets below it to a [`GestureSink`]
+pub struct GestureProvider {
+    sink: Box<dyn GestureSink>,
+}
+
+impl GestureProvider {
+    pub fn new(sink: impl GestureSink + 'static) -> Self {
+        Self {
+            sink: Box::new(sink),
+        }
+    }
+}
+
+impl Model for GestureProvider {
+    fn event(&mut self, cx: &mut EventContext, event: &mut Event) {
+        self.sink.handle_gesture_event(cx, event);
+    }
+}
+
+/// Reports the gestures of the current widget from its event handler
+pub trait GestureExt {
+    /// Starts an edit, when a drag that changes values begins
+    fn begin_edit(&mut self);
+    /// Reports the values changed by the current edit
+    fn perform_edit(&mut self, index: usize, values: &[f32]);
+    /// Ends an edit, when the drag is released or cancelled
+    fn end_edit(&mut self);
+    /// Reports a change made outside of a drag, such as a reset, as an edit
+    /// of its own
+    fn single_edit(&mut self, index: usize, values: &[f32]) {
+        self.begin_edit();
+        self.perform_edit(index, values);
+        self.end_edit();
+    }
+}
+
+impl GestureExt for EventContext<'_> {
+    fn begin_edit(&mut self) {
+        let widget = self.current();
+        self.emit(GestureEvent::Begin(widget));
+    }
+
+    fn perform_edit(&mut self, index: usize, values: &[f32]) {
+        let widget = self.current();
+        self.emit(GestureEvent::Perform {
+            widget,
+            index,
+            values: values.to_vec(),
+        });
+    }
+
+    fn end_edit(&mut self) {
+        let widget = self.current();
+        self.emit(GestureEvent::End(widget));
+    }
+}
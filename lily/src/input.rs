@@ -0,0 +1,387 @@
+//! Which mouse buttons and modifier keys perform the actions of lily's
+//! editors.
+//!
+//! Editors differ in how points are deleted, menus opened, values reset and
+//! views panned, and users bring the habits of the editor they know. An
+//! [`InputMap`] binds each [`InputAction`] to the mouse presses that perform
+//! it. Install one with [`InputMapProvider::install`] near the root of the UI
+//! to change the gestures of every widget, or around a single widget to change
+//! just its gestures. Without a provider, widgets use [`InputMap::lily`].
+//!
+//! ```ignore
+//! InputMapProvider::install(
+//!     cx,
+//!     InputMap::bitwig().bind(
+//!         InputAction::OpenMenu,
+//!         MouseBinding::click(MouseButton::Left).with(Modifiers::CTRL),
+//!     ),
+//! );
+//! ```
+//!
+//! Presses are matched with the exact modifiers held, so a click and a
+//! Ctrl-click can perform different actions. Pressing and dragging points,
+//! the end of a selected region or a marquee with the left button,
+//! Shift-clicking points to select several, fine dragging with Shift,
+//! holding XY pad drags to a radius or an angle with Ctrl, and freeing MSEG
+//! points from the snap grid with Ctrl, aren't remapped.
+//!
+//! Widgets tell double clicks apart themselves with a [`PressReader`], rather
+//! than from the window's `MouseDoubleClick` events, so that how quick and how
+//! close together the presses must be can be set on the input map with
+//! [`InputMap::with_double_click`].
+
+use std::rc::Rc;
+use std::time::{Duration, Instant};
+
+use glam::Vec2;
+use vizia::prelude::*;
+
+use crate::util::ScaleExt;
+
+/// The longest time between the presses of a double click by default
+pub const DOUBLE_CLICK_INTERVAL: Duration = Duration::from_millis(400);
+/// The farthest distance in logical pixels between the presses of a double
+/// click by default
+pub const DOUBLE_CLICK_DISTANCE: f32 = 4f32;
+
+/// An action of lily's editors that can be bound to mouse presses
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum InputAction {
+    /// Removes the point or tap under the cursor
+    DeletePoint,
+    /// Adds a point or tap where the cursor is, away from existing ones
+    InsertPoint,
+    /// Opens the context menu of a control or editor
+    OpenMenu,
+    /// Sets a control back to its default value
+    Reset,
+    /// Scrolls a zoomed editor while the button is held and dragged
+    Pan,
+    /// Selects a time region of an envelope while the button is held and
+    /// dragged
+    SelectRegion,
+}
+
+/// A press of a mouse button with modifier keys held
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct MouseBinding {
+    pub button: MouseButton,
+    pub modifiers: Modifiers,
+    /// Whether the button is pressed twice in quick succession
+    pub double_click: bool,
+}
+
+impl MouseBinding {
+    /// A single press of `button` without modifiers
+    pub fn click(button: MouseButton) -> Self {
+        Self {
+            button,
+            modifiers: Modifiers::empty(),
+            double_click: false,
+        }
+    }
+
+    /// A double click of `button` without modifiers
+    pub fn double_click(button: MouseButton) -> Self {
+        Self {
+            double_click: true,
+            ..Self::click(button)
+        }
+    }
+
+    /// The same press with `modifiers` held
+    pub fn with(self, modifiers: Modifiers) -> Self {
+        Self { modifiers, ..self }
+    }
+}
+
+/// Reads the presses of a view's `MouseDown` events, telling double clicks
+/// apart by the timing set on the installed [`InputMap`].
+///
+/// The second press of a double click is read as a double click only, and
+/// doesn't start another one, so a triple click is a double click followed by
+/// a click.
+#[derive(Clone, Debug, Default)]
+pub struct PressReader {
+    /// The button, time and cursor position of the last press that could
+    /// start a double click
+    last: Option<(MouseButton, Instant, Vec2)>,
+}
+
+impl PressReader {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The press a `MouseDown` event is, with the modifiers currently held
+    pub fn read(&mut self, cx: &EventContext, event: &WindowEvent) -> Option<MouseBinding> {
+        if let WindowEvent::MouseDown(button) = *event {
+            let input = cx.input_map();
+            let cursor = Vec2::new(cx.mouse.cursorx, cx.mouse.cursory);
+            let double_click = self.completes_double_click(
+                button,
+                Instant::now(),
+                cursor,
+                input.double_click_interval(),
+                cx.to_physical(input.double_click_distance()),
+            );
+            let press = MouseBinding {
+                double_click,
+                ..MouseBinding::click(button)
+            };
+            Some(press.with(cx.modifiers))
+        } else {
+            None
+        }
+    }
+
+    /// Whether a press of `button` at `time` and `position` is the second
+    /// press of a double click, remembering it as the first press otherwise
+    fn completes_double_click(
+        &mut self,
+        button: MouseButton,
+        time: Instant,
+        position: Vec2,
+        interval: Duration,
+        distance: f32,
+    ) -> bool {
+        let double_click = match self.last.take() {
+            Some((last_button, last_time, last_position)) => {
+                last_button == button
+                    && time.saturating_duration_since(last_time) <= interval
+                    && last_position.distance(position) <= distance
+            }
+            None => false,
+        };
+        if !double_click {
+            self.last = Some((button, time, position));
+        }
+        double_click
+    }
+}
+
+/// The mouse presses bound to each [`InputAction`], and how quick and close
+/// together the presses of a double click must be
+#[derive(Clone, Debug, PartialEq)]
+pub struct InputMap {
+    bindings: Vec<(InputAction, MouseBinding)>,
+    double_click_interval: Duration,
+    /// In logical pixels
+    double_click_distance: f32,
+}
+
+impl Default for InputMap {
+    fn default() -> Self {
+        Self::lily()
+    }
+}
+
+impl InputMap {
+    /// An input map without any bindings
+    pub fn empty() -> Self {
+        Self {
+            bindings: Vec::new(),
+            double_click_interval: DOUBLE_CLICK_INTERVAL,
+            double_click_distance: DOUBLE_CLICK_DISTANCE,
+        }
+    }
+
+    /// lily's own gestures: right-click deletes the point under the cursor
+    /// and opens the menu elsewhere, double-click inserts points and resets
+    /// controls, the middle button pans and Alt-dragging selects a region
+    pub fn lily() -> Self {
+        use MouseButton::*;
+        Self::empty()
+            .bind(InputAction::DeletePoint, MouseBinding::click(Right))
+            .bind(InputAction::InsertPoint, MouseBinding::double_click(Left))
+            .bind(InputAction::OpenMenu, MouseBinding::click(Right))
+            .bind(InputAction::Reset, MouseBinding::double_click(Left))
+            .bind(InputAction::Pan, MouseBinding::click(Middle))
+            .bind(
+                InputAction::SelectRegion,
+                MouseBinding::click(Left).with(Modifiers::ALT),
+            )
+    }
+
+    /// Close to Ableton Live's envelope editing: clicking empty space inserts
+    /// a point and double-clicking a point deletes it
+    pub fn ableton() -> Self {
+        use MouseButton::*;
+        Self::empty()
+            .bind(InputAction::DeletePoint, MouseBinding::double_click(Left))
+            .bind(InputAction::InsertPoint, MouseBinding::click(Left))
+            .bind(InputAction::OpenMenu, MouseBinding::click(Right))
+            .bind(InputAction::Reset, MouseBinding::double_click(Left))
+            .bind(InputAction::Pan, MouseBinding::click(Middle))
+            .bind(
+                InputAction::SelectRegion,
+                MouseBinding::click(Left).with(Modifiers::ALT),
+            )
+    }
+
+    /// Close to FL Studio's envelope editing: right-click inserts and deletes
+    /// points, so the menu opens with Ctrl and right-click
+    pub fn fl_studio() -> Self {
+        use MouseButton::*;
+        Self::empty()
+            .bind(InputAction::DeletePoint, MouseBinding::click(Right))
+            .bind(InputAction::InsertPoint, MouseBinding::click(Right))
+            .bind(
+                InputAction::OpenMenu,
+                MouseBinding::click(Right).with(Modifiers::CTRL),
+            )
+            .bind(InputAction::Reset, MouseBinding::double_click(Left))
+            .bind(InputAction::Pan, MouseBinding::click(Middle))
+            .bind(
+                InputAction::SelectRegion,
+                MouseBinding::click(Left).with(Modifiers::ALT),
+            )
+    }
+
+    /// Close to Bitwig Studio's envelope editing: double-click inserts a point
+    /// in empty space and deletes the point under the cursor
+    pub fn bitwig() -> Self {
+        use MouseButton::*;
+        Self::empty()
+            .bind(InputAction::DeletePoint, MouseBinding::double_click(Left))
+            .bind(InputAction::InsertPoint, MouseBinding::double_click(Left))
+            .bind(InputAction::OpenMenu, MouseBinding::click(Right))
+            .bind(InputAction::Reset, MouseBinding::double_click(Left))
+            .bind(InputAction::Pan, MouseBinding::click(Middle))
+            .bind(
+                InputAction::SelectRegion,
+                MouseBinding::click(Left).with(Modifiers::ALT),
+            )
+    }
+
+    /// Adds a press that performs `action`, keeping its other bindings
+    pub fn bind(mut self, action: InputAction, binding: MouseBinding) -> Self {
+        self.bindings.push((action, binding));
+        self
+    }
+
+    /// Removes every binding of `action`, so it can only be performed in
+    /// other ways, such as from a context menu
+    pub fn unbind(mut self, action: InputAction) -> Self {
+        self.bindings.retain(|(bound, _)| *bound != action);
+        self
+    }
+
+    /// The presses bound to `action`
+    pub fn bindings(&self, action: InputAction) -> impl Iterator<Item = &MouseBinding> {
+        self.bindings
+            .iter()
+            .filter(move |(bound, _)| *bound == action)
+            .map(|(_, binding)| binding)
+    }
+
+    /// Whether `press` performs `action`
+    pub fn triggers(&self, action: InputAction, press: MouseBinding) -> bool {
+        self.bindings(action).any(|binding| *binding == press)
+    }
+
+    /// Sets the longest time between the presses of a double click, and the
+    /// farthest the cursor may move between them in logical pixels
+    pub fn with_double_click(mut self, interval: Duration, distance: f32) -> Self {
+        self.double_click_interval = interval;
+        self.double_click_distance = distance;
+        self
+    }
+
+    /// The longest time between the presses of a double click
+    pub fn double_click_interval(&self) -> Duration {
+        self.double_click_interval
+    }
+
+    /// The farthest distance in logical pixels between the presses of a
+    /// double click
+    pub fn double_click_distance(&self) -> f32 {
+        self.double_click_distance
+    }
+}
+
+/// Holds the [`InputMap`] of the widgets below it
+pub struct InputMapProvider {
+    input_map: Rc<InputMap>,
+}
+
+impl InputMapProvider {
+    /// Sets the gestures of the widgets built after it below the current view
+    pub fn install(cx: &mut Context, input_map: InputMap) {
+        Self {
+            input_map: Rc::new(input_map),
+        }
+        .build(cx);
+    }
+}
+
+impl Model for InputMapProvider {}
+
+/// Gets the [`InputMap`] installed above the current view
+pub trait InputMapExt {
+    /// The installed input map, or [`InputMap::lily`] if there is none
+    fn input_map(&self) -> Rc<InputMap>;
+}
+
+impl<C: DataContext> InputMapExt for C {
+    fn input_map(&self) -> Rc<InputMap> {
+        match self.data::<InputMapProvider>() {
+            Some(provider) => provider.input_map.clone(),
+            None => Rc::new(InputMap::lily()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn matches_presses_exactly() {
+        let map = InputMap::fl_studio();
+        let right = MouseBinding::click(MouseButton::Right);
+        assert!(map.triggers(InputAction::DeletePoint, right));
+        assert!(!map.triggers(InputAction::OpenMenu, right));
+        assert!(map.triggers(InputAction::OpenMenu, right.with(Modifiers::CTRL)));
+        let double = MouseBinding::double_click(MouseButton::Left);
+        assert!(!map.triggers(InputAction::DeletePoint, double));
+    }
+
+    #[test]
+    fn rebinds_actions() {
+        let map = InputMap::lily().unbind(InputAction::OpenMenu).bind(
+            InputAction::OpenMenu,
+            MouseBinding::click(MouseButton::Left).with(Modifiers::CTRL),
+        );
+        assert_eq!(map.bindings(InputAction::OpenMenu).count(), 1);
+        assert!(!map.triggers(
+            InputAction::OpenMenu,
+            MouseBinding::click(MouseButton::Right)
+        ));
+        // Other actions keep their bindings
+        assert!(map.triggers(
+            InputAction::DeletePoint,
+            MouseBinding::click(MouseButton::Right)
+        ));
+    }
+
+    #[test]
+    fn detects_double_clicks() {
+        let mut presses = PressReader::new();
+        let interval = Duration::from_millis(400);
+        let start = Instant::now();
+        let mut press = |button, millis, x| {
+            let time = start + Duration::from_millis(millis);
+            presses.completes_double_click(button, time, Vec2::new(x, 0f32), interval, 4f32)
+        };
+        assert!(!press(MouseButton::Left, 0, 10f32));
+        assert!(press(MouseButton::Left, 300, 12f32));
+        // A third press starts over
+        assert!(!press(MouseButton::Left, 400, 12f32));
+        // Too slow, too far apart, or another button
+        assert!(!press(MouseButton::Left, 900, 12f32));
+        assert!(!press(MouseButton::Left, 1000, 20f32));
+        assert!(!press(MouseButton::Right, 1100, 20f32));
+        assert!(press(MouseButton::Right, 1200, 20f32));
+    }
+}
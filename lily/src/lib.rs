@@ -1,5 +1,27 @@
+pub mod anim;
+pub mod binding;
+pub mod context_menu;
+pub mod drag_drop;
+pub mod feed;
+pub mod focus;
+pub mod format;
+pub mod gesture;
+pub mod input;
+pub mod locale;
+pub mod midi_learn;
+#[cfg(feature = "nih")]
+pub mod nih;
+pub mod param;
+pub mod prelude;
+#[cfg(any(test, feature = "testing"))]
+pub mod testing;
+pub mod theme;
+pub mod tooltip;
 pub mod util;
 pub mod widgets;
 pub use glam as math;
+#[cfg(feature = "fft")]
+pub use lily_core::analysis;
+pub use lily_core::units;
 
 pub const DEFAULT_STYLE: &str = include_str!("../assets/default.css");
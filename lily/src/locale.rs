@@ -0,0 +1,165 @@
+//! Localizing the values shown by lily widgets.
+//!
+//! Readouts are formatted in English with decimal points, as in
+//! [`crate::format`] and [`crate::units`], and then passed through the
+//! [`Locale`] installed with [`LocaleProvider::install`] before they are
+//! shown. This covers tooltips, axis labels and the text of readouts. Without
+//! a provider, text is shown as formatted.
+//!
+//! ```ignore
+//! struct German;
+//!
+//! impl Locale for German {
+//!     fn decimal_separator(&self) -> char {
+//!         ','
+//!     }
+//!
+//!     fn translate<'a>(&self, word: &'a str) -> Cow<'a, str> {
+//!         match word {
+//!             "pan" => Cow::Borrowed("Panorama"),
+//!             _ => Cow::Borrowed(word),
+//!         }
+//!     }
+//! }
+//!
+//! LocaleProvider::install(cx, German);
+//! ```
+
+use std::borrow::Cow;
+use std::rc::Rc;
+
+use vizia::prelude::*;
+
+/// How formatted values are shown to the user
+pub trait Locale {
+    /// The character between the whole and the fractional digits of numbers
+    fn decimal_separator(&self) -> char {
+        '.'
+    }
+
+    /// Translates a word of a readout, such as the unit labels `dB`, `Hz` and
+    /// `ms` or the `inf` of `-inf dB`. Words are runs of letters, so note
+    /// names like `C#4` are translated as `C`. Words without a translation
+    /// are returned unchanged.
+    fn translate<'a>(&self, word: &'a str) -> Cow<'a, str> {
+        Cow::Borrowed(word)
+    }
+
+    /// Localizes text formatted by lily, swapping the decimal points between
+    /// digits for [`Locale::decimal_separator`] and translating its words
+    fn localize(&self, text: &str) -> String {
+        let separator = self.decimal_separator();
+        let mut localized = String::with_capacity(text.len());
+        let mut chars = text.char_indices().peekable();
+        let mut after_digit = false;
+        while let Some((start, c)) = chars.next() {
+            if c.is_alphabetic() {
+                let mut end = start + c.len_utf8();
+                while let Some((i, next)) = chars.next_if(|(_, next)| next.is_alphabetic()) {
+                    end = i + next.len_utf8();
+                }
+                localized.push_str(&self.translate(&text[start..end]));
+                after_digit = false;
+            } else {
+                let before_digit = chars.peek().is_some_and(|(_, next)| next.is_ascii_digit());
+                if c == '.' && after_digit && before_digit {
+                    localized.push(separator);
+                } else {
+                    localized.push(c);
+                }
+                after_digit = c.is_ascii_digit();
+            }
+        }
+        localized
+    }
+
+    /// Reads a number typed by the user with this locale's decimal separator
+    fn parse_number(&self, text: &str) -> Option<f32> {
+        text.trim()
+            .replace(self.decimal_separator(), ".")
+            .parse()
+            .ok()
+    }
+}
+
+/// Shows values as lily formats them, with decimal points and English unit
+/// labels
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct English;
+
+impl Locale for English {}
+
+/// Holds the [`Locale`] of the widgets below it
+pub struct LocaleProvider {
+    locale: Rc<dyn Locale>,
+}
+
+impl LocaleProvider {
+    /// Sets the locale of the UI. Install it near the root, before building
+    /// any widgets, as axis labels are localized when they are built.
+    pub fn install(cx: &mut Context, locale: impl Locale + 'static) {
+        Self {
+            locale: Rc::new(locale),
+        }
+        .build(cx);
+    }
+}
+
+impl Model for LocaleProvider {}
+
+/// Gets the [`Locale`] installed above the current view
+pub trait LocaleExt {
+    /// The installed locale, or [`English`] if there is none
+    fn locale(&self) -> Rc<dyn Locale>;
+
+    /// Localizes a formatted readout with [`Locale::localize`]
+    fn localize(&self, text: &str) -> String {
+        self.locale().localize(text)
+    }
+}
+
+impl<C: DataContext> LocaleExt for C {
+    fn locale(&self) -> Rc<dyn Locale> {
+        match self.data::<LocaleProvider>() {
+            Some(provider) => provider.locale.clone(),
+            None => Rc::new(English),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct Test;
+
+    impl Locale for Test {
+        fn decimal_separator(&self) -> char {
+            ','
+        }
+
+        fn translate<'a>(&self, word: &'a str) -> Cow<'a, str> {
+            match word {
+                "s" => Cow::Borrowed("sek"),
+                "inf" => Cow::Borrowed("unendlich"),
+                _ => Cow::Borrowed(word),
+            }
+        }
+    }
+
+    #[test]
+    fn localizes_readouts() {
+        assert_eq!(English.localize("1.50 s, -6.0 dB"), "1.50 s, -6.0 dB");
+        assert_eq!(Test.localize("1.50 s, -6.0 dB"), "1,50 sek, -6,0 dB");
+        assert_eq!(Test.localize("-inf dB"), "-unendlich dB");
+        assert_eq!(Test.localize("C#4 +12"), "C#4 +12");
+        assert_eq!(Test.localize("Loading..."), "Loading...");
+    }
+
+    #[test]
+    fn parses_numbers() {
+        assert_eq!(Test.parse_number(" 12,5 "), Some(12.5f32));
+        assert_eq!(English.parse_number("12.5"), Some(12.5f32));
+        assert_eq!(Test.parse_number("twelve"), None);
+    }
+}
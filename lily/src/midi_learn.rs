@@ -0,0 +1,161 @@
+//! A MIDI learn mode for mapping controllers to lily widgets.
+//!
+//! Build a [`MidiLearnProvider`] near the root of the UI and give the widgets
+//! that can be mapped an id with [`MidiLearnExt::midi_learn`]. Emitting
+//! [`MidiLearnEvent::Arm`] puts a learn overlay over each of them, and clicking
+//! one emits [`MidiLearnEvent::Learn`] with its id. The application handles
+//! that event to start listening for a controller, adds the mapping it hears
+//! and emits [`MidiLearnEvent::Disarm`] when done. Mapped widgets show a badge
+//! with their controller, read from the application's list of
+//! [`MidiMapping`]s.
+
+use vizia::prelude::*;
+
+use crate::locale::LocaleExt;
+
+/// A MIDI controller mapped to a widget
+#[derive(Clone, Debug, PartialEq, Eq, Data)]
+pub struct MidiMapping {
+    /// The id the widget was given with [`MidiLearnExt::midi_learn`]
+    pub id: String,
+    /// The controller number, `0..=127`
+    pub cc: u8,
+}
+
+impl MidiMapping {
+    pub fn new(id: impl Into<String>, cc: u8) -> Self {
+        Self { id: id.into(), cc }
+    }
+
+    /// The text of the widget's badge, e.g. `CC 74`
+    pub fn badge(&self) -> String {
+        format!("CC {}", self.cc)
+    }
+}
+
+pub enum MidiLearnEvent {
+    /// Shows the learn overlays so a widget can be picked
+    Arm,
+    /// Hides the learn overlays again
+    Disarm,
+    /// A widget was picked while armed. The provider doesn't consume this
+    /// event, so the application can handle it as well.
+    Learn(String),
+}
+
+/// Holds whether MIDI learn is armed and which widget is waiting for a
+/// controller
+#[derive(Default, Lens)]
+pub struct MidiLearnProvider {
+    pub armed: bool,
+    /// The id of the widget picked last while armed
+    pub learning: Option<String>,
+}
+
+impl Model for MidiLearnProvider {
+    fn event(&mut self, _cx: &mut EventContext, event: &mut Event) {
+        event.map(|ev: &MidiLearnEvent, _| match ev {
+            MidiLearnEvent::Arm => {
+                self.armed = true;
+                self.learning = None;
+            }
+            MidiLearnEvent::Disarm => {
+                self.armed = false;
+                self.learning = None;
+            }
+            MidiLearnEvent::Learn(id) => self.learning = Some(id.clone()),
+        });
+    }
+}
+
+/// The overlay covering a widget that can be mapped. It only takes the mouse
+/// while MIDI learn is armed, and gets the `armed` class then and the
+/// `learning` class while its widget waits for a controller.
+pub struct MidiLearnOverlay {
+    id: String,
+}
+
+impl MidiLearnOverlay {
+    /// Create a new `MidiLearnOverlay` covering the current view
+    ///
+    /// # Parameters
+    ///
+    /// * `cx` - the current [`Context`]
+    /// * `id` - the id emitted when the overlay is clicked
+    /// * `mappings` - a [`Lens`] of the application's [`MidiMapping`]s, used
+    ///   to show the badge of the widget's controller
+    pub fn new<L>(cx: &mut Context, id: String, mappings: L) -> Handle<Self>
+    where
+        L: Lens<Target = Vec<MidiMapping>>,
+    {
+        let (badge_id, mapped_id, learning_id) = (id.clone(), id.clone(), id.clone());
+        let locale = cx.locale();
+        Self { id }
+            .build(cx, |cx| {
+                Label::new(
+                    cx,
+                    mappings.clone().map(move |mappings| {
+                        mappings
+                            .iter()
+                            .find(|mapping| mapping.id == badge_id)
+                            .map(|mapping| locale.localize(&mapping.badge()))
+                            .unwrap_or_default()
+                    }),
+                )
+                .class("badge")
+                .toggle_class(
+                    "mapped",
+                    mappings.map(move |mappings| mappings.iter().any(|m| m.id == mapped_id)),
+                );
+            })
+            .position_type(PositionType::SelfDirected)
+            .width(Stretch(1f32))
+            .height(Stretch(1f32))
+            .toggle_class("armed", MidiLearnProvider::armed)
+            .toggle_class(
+                "learning",
+                MidiLearnProvider::learning
+                    .map(move |learning| learning.as_ref() == Some(&learning_id)),
+            )
+            .hoverable(MidiLearnProvider::armed)
+    }
+}
+
+impl View for MidiLearnOverlay {
+    fn element(&self) -> Option<&'static str> {
+        Some("midi-learn")
+    }
+
+    fn event(&mut self, cx: &mut EventContext, event: &mut Event) {
+        event.map(|ev: &WindowEvent, meta| {
+            if let WindowEvent::MouseDown(MouseButton::Left) = ev {
+                cx.emit(MidiLearnEvent::Learn(self.id.clone()));
+                meta.consume();
+            }
+        });
+    }
+}
+
+/// Lets widgets be mapped to MIDI controllers
+pub trait MidiLearnExt {
+    /// Covers the widget with a [`MidiLearnOverlay`] that picks it as `id`
+    /// while MIDI learn is armed and shows the badge of its controller from
+    /// `mappings`
+    fn midi_learn<L>(self, id: impl Into<String>, mappings: L) -> Self
+    where
+        L: Lens<Target = Vec<MidiMapping>>;
+}
+
+impl<V: View> MidiLearnExt for Handle<'_, V> {
+    fn midi_learn<L>(self, id: impl Into<String>, mappings: L) -> Self
+    where
+        L: Lens<Target = Vec<MidiMapping>>,
+    {
+        let id = id.into();
+        let entity = self.entity;
+        self.cx.with_current(entity, |cx| {
+            MidiLearnOverlay::new(cx, id, mappings);
+        });
+        self
+    }
+}
@@ -0,0 +1,293 @@
+//! Binding widgets to [`nih_plug`] parameters.
+//!
+//! [`NihContext::install`] hands the edits of bound widgets to the plugin's
+//! [`GuiContext`] as a [`GestureSink`], so hosts see the begin and end
+//! set-parameter calls they need to record automation. [`NihParam`] wraps a
+//! parameter as a [`NormalizedParam`], so widgets taking one get the
+//! parameter's readout, text entry and default. Widgets work on nih-plug's
+//! normalized values, and the parameter maps them to its plain values.
+//!
+//! ```ignore
+//! NihContext::install(cx, gui_context);
+//! nih::slider(cx, Data::params, |params| &params.gain);
+//! ```
+//!
+//! An [`XyPad`] sets a parameter per axis with [`xy_pad`]. The points of an
+//! MSEG aren't parameters of their own, so envelopes are left to the plugin's
+//! persisted state rather than bound here.
+
+use std::sync::Arc;
+
+use glam::Vec2;
+use nih_plug::prelude::{GuiContext, Param, ParamPtr};
+use vizia::prelude::*;
+
+use crate::format::{ValueFormatter, ValueParser};
+use crate::gesture::GestureSink;
+use crate::param::NormalizedParam;
+use crate::util::ValueMapping;
+use crate::widgets::{DragSlider, XyPad};
+
+pub enum NihParamEvent {
+    /// Binds the edits of a widget to a parameter
+    Bind(Entity, ParamPtr),
+    /// Binds the edits of an [`XyPad`] to a parameter per axis
+    BindPoint(Entity, ParamPtr, ParamPtr),
+}
+
+/// The parameters set by the edits of a widget
+#[derive(Clone, Copy)]
+enum Binding {
+    /// Set to the first value of an edit, which is already normalized
+    Value(ParamPtr),
+    /// Set to the point of an [`XyPad`], across and up
+    Point(ParamPtr, ParamPtr),
+}
+
+impl Binding {
+    fn params(self) -> Vec<ParamPtr> {
+        match self {
+            Binding::Value(param) => vec![param],
+            Binding::Point(x, y) => vec![x, y],
+        }
+    }
+
+    /// The normalized value each parameter takes from the values of an edit
+    fn normalized(self, values: &[f32]) -> Vec<(ParamPtr, f32)> {
+        match (self, values) {
+            (Binding::Value(param), [value, ..]) => vec![(param, *value)],
+            (Binding::Point(x, y), [point_x, point_y, ..]) => {
+                let normalized = point_to_normalized(Vec2::new(*point_x, *point_y));
+                vec![(x, normalized.x), (y, normalized.y)]
+            }
+            _ => Vec::new(),
+        }
+    }
+}
+
+/// Sets the parameters bound to widgets through the plugin's [`GuiContext`]
+pub struct NihContext {
+    gui_context: Arc<dyn GuiContext>,
+    bindings: Vec<(Entity, Binding)>,
+}
+
+impl NihContext {
+    /// Adds the context to the tree. Widgets bound to parameters need to be
+    /// built inside the view it is installed on.
+    pub fn install(cx: &mut Context, gui_context: Arc<dyn GuiContext>) {
+        Self {
+            gui_context,
+            bindings: Vec::new(),
+        }
+        .build(cx);
+    }
+
+    /// The parameter bound to `widget` or, for a part of a widget, to the
+    /// closest of its ancestors
+    fn binding(&self, cx: &EventContext, widget: Entity) -> Option<Binding> {
+        let mut entity = Some(widget);
+        while let Some(current) = entity {
+            if let Some((_, binding)) = self.bindings.iter().find(|(bound, _)| *bound == current) {
+                return Some(*binding);
+            }
+            entity = cx.tree.get_parent(current);
+        }
+        None
+    }
+}
+
+impl Model for NihContext {
+    fn event(&mut self, cx: &mut EventContext, event: &mut Event) {
+        event.map(|ev: &NihParamEvent, meta| {
+            let (widget, binding) = match *ev {
+                NihParamEvent::Bind(widget, param) => (widget, Binding::Value(param)),
+                NihParamEvent::BindPoint(widget, x, y) => (widget, Binding::Point(x, y)),
+            };
+            self.bindings.retain(|(bound, _)| *bound != widget);
+            self.bindings.push((widget, binding));
+            meta.consume();
+        });
+        self.handle_gesture_event(cx, event);
+    }
+}
+
+// SAFETY: the parameter pointers come from parameters owned by the plugin,
+// which outlives its editor
+impl GestureSink for NihContext {
+    fn begin_edit(&mut self, cx: &mut EventContext, widget: Entity) {
+        let params = self
+            .binding(cx, widget)
+            .map_or_else(Vec::new, Binding::params);
+        for param in params {
+            unsafe { self.gui_context.raw_begin_set_parameter(param) };
+        }
+    }
+
+    fn perform_edit(&mut self, cx: &mut EventContext, widget: Entity, _: usize, values: &[f32]) {
+        let normalized = self
+            .binding(cx, widget)
+            .map_or_else(Vec::new, |binding| binding.normalized(values));
+        for (param, normalized) in normalized {
+            unsafe {
+                self.gui_context
+                    .raw_set_parameter_normalized(param, normalized)
+            };
+        }
+    }
+
+    fn end_edit(&mut self, cx: &mut EventContext, widget: Entity) {
+        let params = self
+            .binding(cx, widget)
+            .map_or_else(Vec::new, Binding::params);
+        for param in params {
+            unsafe { self.gui_context.raw_end_set_parameter(param) };
+        }
+    }
+}
+
+/// A parameter of the plugin's parameters `Ps`, chosen by `map`, as a
+/// [`NormalizedParam`]. The plain values lily sees are the parameter's
+/// normalized values.
+pub struct NihParam<Ps, P> {
+    params: Arc<Ps>,
+    map: fn(&Ps) -> &P,
+}
+
+impl<Ps, P: Param> NihParam<Ps, P> {
+    pub fn new(params: Arc<Ps>, map: fn(&Ps) -> &P) -> Self {
+        Self { params, map }
+    }
+
+    pub fn param(&self) -> &P {
+        (self.map)(&self.params)
+    }
+
+    /// The pointer identifying the parameter in [`NihParamEvent`]s
+    pub fn as_ptr(&self) -> ParamPtr {
+        self.param().as_ptr()
+    }
+}
+
+impl<Ps, P> Clone for NihParam<Ps, P> {
+    fn clone(&self) -> Self {
+        Self {
+            params: self.params.clone(),
+            map: self.map,
+        }
+    }
+}
+
+impl<Ps, P: Param> ValueMapping for NihParam<Ps, P> {
+    fn normalize(&self, value: f32) -> f32 {
+        value.clamp(0f32, 1f32)
+    }
+
+    fn denormalize(&self, normalized: f32) -> f32 {
+        normalized.clamp(0f32, 1f32)
+    }
+}
+
+impl<Ps, P: Param> ValueFormatter for NihParam<Ps, P> {
+    fn format(&self, value: f32) -> String {
+        self.param().normalized_value_to_string(value, true)
+    }
+}
+
+impl<Ps, P: Param> ValueParser for NihParam<Ps, P> {
+    fn parse(&self, text: &str) -> Option<f32> {
+        self.param().string_to_normalized_value(text)
+    }
+}
+
+impl<Ps, P: Param> NormalizedParam for NihParam<Ps, P> {
+    fn normalized(&self) -> f32 {
+        self.param().normalized_value()
+    }
+
+    fn default_plain(&self) -> f32 {
+        let param = self.param();
+        param.preview_normalized(param.default_plain_value())
+    }
+}
+
+/// Binds the edits of a widget to a parameter. The widget's edits set the
+/// parameter to the first value they report, which needs to be normalized,
+/// such as the value of a slider created with a [`NihParam`].
+pub fn bind<V: View, Ps, P: Param>(handle: Handle<V>, param: &NihParam<Ps, P>) -> Handle<V> {
+    let entity = handle.entity;
+    handle.cx.emit(NihParamEvent::Bind(entity, param.as_ptr()));
+    handle
+}
+
+/// Creates a [`DragSlider`] bound to a parameter, which sets the parameter as
+/// it is dragged or reset
+///
+/// # Parameters
+///
+/// * `cx` - the current [`Context`]
+/// * `params` - a [`Lens`] to the plugin's parameters
+/// * `map` - picks the parameter out of the plugin's parameters, e.g.
+///   `|params| &params.gain`
+pub fn slider<L, Ps, P>(
+    cx: &mut Context,
+    params: L,
+    map: fn(&Ps) -> &P,
+) -> Handle<DragSlider<impl Lens<Target = f32>>>
+where
+    L: Lens<Target = Arc<Ps>>,
+    Ps: 'static,
+    P: 'static + Param,
+{
+    let param = NihParam::new(params.get(cx), map);
+    let value = params.map(move |params| map(params).normalized_value());
+    let handle = DragSlider::with_param(cx, value, param.clone());
+    bind(handle, &param)
+}
+
+/// Binds the point of an [`XyPad`] to a parameter across and one up. The pad
+/// runs from `-1` to `1` on both axes, with `y` growing downwards, which maps
+/// to the normalized values of the parameters from left to right and from
+/// bottom to top. The pad's point follows the parameters through
+/// [`normalized_to_point`].
+///
+/// ```ignore
+/// let (x, y) = (
+///     NihParam::new(params.clone(), |params| &params.morph_x),
+///     NihParam::new(params.clone(), |params| &params.morph_y),
+/// );
+/// let (x_param, y_param) = (x.clone(), y.clone());
+/// let point = Data::params.map(move |_| {
+///     nih::normalized_to_point(Vec2::new(x_param.normalized(), y_param.normalized()))
+/// });
+/// nih::xy_pad(XyPad::new(cx, point), &x, &y);
+/// ```
+pub fn xy_pad<'a, L, Ps, X: Param, Y: Param>(
+    handle: Handle<'a, XyPad<L>>,
+    x_param: &NihParam<Ps, X>,
+    y_param: &NihParam<Ps, Y>,
+) -> Handle<'a, XyPad<L>>
+where
+    L: Lens<Target = Vec2>,
+{
+    let entity = handle.entity;
+    handle.cx.emit(NihParamEvent::BindPoint(
+        entity,
+        x_param.as_ptr(),
+        y_param.as_ptr(),
+    ));
+    handle
+}
+
+/// The normalized values of the parameters across and up for a point of an
+/// [`XyPad`]
+pub fn point_to_normalized(point: Vec2) -> Vec2 {
+    let point = point.clamp(Vec2::splat(-1f32), Vec2::splat(1f32));
+    Vec2::new((point.x + 1f32) / 2f32, (1f32 - point.y) / 2f32)
+}
+
+/// The point of an [`XyPad`] for the normalized values of the parameters
+/// across and up
+pub fn normalized_to_point(normalized: Vec2) -> Vec2 {
+    let normalized = normalized.clamp(Vec2::ZERO, Vec2::ONE);
+    Vec2::new(normalized.x * 2f32 - 1f32, 1f32 - normalized.y * 2f32)
+}
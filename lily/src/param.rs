@@ -0,0 +1,169 @@
+//! Parameters that carry their own taper, readout and default.
+//!
+//! A [`NormalizedParam`] bundles everything a widget needs to know about a
+//! value besides the value itself, so widgets can take one object instead of
+//! a range, a formatter and a default. [`Param`] is the plain implementation;
+//! plugin frameworks can implement the trait for their own parameter types.
+
+use std::fmt;
+use std::rc::Rc;
+
+use vizia::prelude::*;
+
+use crate::format::{Decimal, ValueFormatter};
+use crate::util::ValueMapping;
+
+/// A value with a normalized `0..=1` position, a [`ValueMapping`] between that
+/// position and the plain value, a [`ValueFormatter`] for its readout and a
+/// default
+pub trait NormalizedParam: ValueMapping + ValueFormatter {
+    /// The current value as a normalized position
+    fn normalized(&self) -> f32;
+
+    /// The plain value that resetting goes back to
+    fn default_plain(&self) -> f32;
+
+    /// The current plain value
+    fn plain(&self) -> f32 {
+        self.denormalize(self.normalized())
+    }
+
+    /// The default value as a normalized position
+    fn default_normalized(&self) -> f32 {
+        self.normalize(self.default_plain())
+    }
+
+    /// The current value formatted for a readout
+    fn display(&self) -> String {
+        self.format(self.plain())
+    }
+}
+
+/// A [`NormalizedParam`] holding its normalized value alongside its mapping,
+/// formatter and default
+#[derive(Clone)]
+pub struct Param {
+    normalized: f32,
+    default: f32,
+    mapping: Rc<dyn ValueMapping>,
+    formatter: Rc<dyn ValueFormatter>,
+}
+
+impl Param {
+    /// Creates a parameter set to and defaulting to the plain `value`, shown
+    /// with two decimals
+    pub fn new(value: f32, mapping: impl ValueMapping + 'static) -> Self {
+        Self {
+            normalized: mapping.normalize(value),
+            default: value,
+            mapping: Rc::new(mapping),
+            formatter: Rc::new(Decimal::default()),
+        }
+    }
+
+    /// Sets the plain value that resetting goes back to
+    pub fn with_default(mut self, default: f32) -> Self {
+        self.default = default;
+        self
+    }
+
+    /// Sets how the value is shown in readouts
+    pub fn with_formatter(mut self, formatter: impl ValueFormatter + 'static) -> Self {
+        self.formatter = Rc::new(formatter);
+        self
+    }
+
+    pub fn set_normalized(&mut self, normalized: f32) {
+        self.normalized = normalized.clamp(0f32, 1f32);
+    }
+
+    pub fn set_plain(&mut self, plain: f32) {
+        self.normalized = self.mapping.normalize(plain);
+    }
+
+    /// Sets the value back to its default
+    pub fn reset(&mut self) {
+        self.set_plain(self.default);
+    }
+}
+
+impl ValueMapping for Param {
+    fn normalize(&self, value: f32) -> f32 {
+        self.mapping.normalize(value)
+    }
+
+    fn denormalize(&self, normalized: f32) -> f32 {
+        self.mapping.denormalize(normalized)
+    }
+
+    fn steps(&self) -> Option<usize> {
+        self.mapping.steps()
+    }
+}
+
+impl ValueFormatter for Param {
+    fn format(&self, value: f32) -> String {
+        self.formatter.format(value)
+    }
+}
+
+impl NormalizedParam for Param {
+    fn normalized(&self) -> f32 {
+        self.normalized
+    }
+
+    fn default_plain(&self) -> f32 {
+        self.default
+    }
+}
+
+impl Data for Param {
+    fn same(&self, other: &Self) -> bool {
+        self.normalized == other.normalized
+            && self.default == other.default
+            && Rc::ptr_eq(&self.mapping, &other.mapping)
+            && Rc::ptr_eq(&self.formatter, &other.formatter)
+    }
+}
+
+impl fmt::Debug for Param {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Param")
+            .field("normalized", &self.normalized)
+            .field("plain", &self.plain())
+            .field("default", &self.default)
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::format::Hertz;
+    use crate::util::LogMapping;
+    use assert_approx_eq::assert_approx_eq;
+
+    #[test]
+    fn maps_and_formats() {
+        let mut param = Param::new(1000f32, LogMapping::new(20f32..=20000f32))
+            .with_formatter(Hertz)
+            .with_default(440f32);
+        assert_approx_eq!(param.plain(), 1000f32, 1e-2f32);
+        assert_eq!(param.display(), "1.00 kHz");
+        param.set_normalized(0f32);
+        assert_approx_eq!(param.plain(), 20f32);
+        param.reset();
+        assert_approx_eq!(param.plain(), 440f32, 1e-2f32);
+        assert_approx_eq!(param.normalized(), param.default_normalized());
+    }
+
+    #[test]
+    fn compares_data() {
+        let param = Param::new(0.5f32, 0f32..=1f32);
+        let mut other = param.clone();
+        assert!(param.same(&other));
+        other.set_plain(0.25f32);
+        assert!(!param.same(&other));
+        assert!(!param.same(&Param::new(0.5f32, 0f32..=1f32)));
+    }
+}
@@ -0,0 +1,36 @@
+//! Everything a plugin UI usually needs from lily in one import: the widgets
+//! and their handle traits, the providers and the extension traits that use
+//! them, curves, parameters, formatters and themes.
+//!
+//! ```ignore
+//! use lily::prelude::*;
+//! use vizia::prelude::*;
+//! ```
+//!
+//! Lower level building blocks, such as the audio thread buffers and the
+//! analysis and drawing helpers, are left in their modules.
+
+pub use crate::binding::{BoundParam, ParamBinding};
+pub use crate::context_menu::{
+    ContextMenu, ContextMenuAction, ContextMenuExt, ContextMenuItem, ContextMenuProvider,
+};
+pub use crate::drag_drop::{DragDropExt, DragDropProvider, DragPayload};
+pub use crate::focus::{Activation, FocusExt, FocusProvider};
+pub use crate::format::{
+    Decibels, Decimal, Hertz, NoteName, Percent, Seconds, Semitones, ValueFormatter, ValueParser,
+};
+pub use crate::gesture::{GestureExt, GestureProvider, GestureSink};
+pub use crate::input::{InputAction, InputMap, InputMapExt, InputMapProvider, MouseBinding};
+pub use crate::locale::{English, Locale, LocaleExt, LocaleProvider};
+pub use crate::math::Vec2;
+pub use crate::midi_learn::{MidiLearnEvent, MidiLearnExt, MidiLearnProvider, MidiMapping};
+pub use crate::param::{NormalizedParam, Param};
+pub use crate::theme::{set_theme, Palette, Theme, ThemeEvent, ThemeProvider};
+pub use crate::tooltip::{Tooltip, TooltipExt, TooltipPlacement, TooltipProvider};
+pub use crate::util::{
+    BeatGrid, CurveClipboardExt, CurveError, CurveKind, CurvePoint, CurvePoints, DetentMapping,
+    DisabledExt, LogMapping, Modulation, ModulationExt, PointTag, PowerMapping, RotaryDrag,
+    SteppedMapping, ValueMapping,
+};
+pub use crate::widgets::*;
+pub use crate::DEFAULT_STYLE;
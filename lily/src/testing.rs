@@ -0,0 +1,217 @@
+//! Driving lily widgets without a window, for interaction tests.
+//!
+//! A [`TestContext`] holds a vizia [`Context`] with a fixed window size and no
+//! renderer. Views are built into it, synthetic mouse and keyboard input is
+//! dispatched the way a window backend would dispatch it, and every call
+//! handles the resulting events and updates layout like a frame of the event
+//! loop. Tests then check the values recorded by the widget's callbacks, or
+//! the state of the models built into the context.
+//!
+//! ```ignore
+//! let mut test = TestContext::new(400f32, 100f32);
+//! let changes = Recorder::new();
+//! let record = changes.clone();
+//! test.build(|cx| {
+//!     Data::default().build(cx);
+//!     DragSlider::new(cx, Data::value, 0f32..=1f32)
+//!         .on_changing(move |_, value| record.push(value));
+//! });
+//! test.drag((10f32, 50f32), (200f32, 50f32));
+//! assert_eq!(changes.last(), Some(0.5f32));
+//! ```
+//!
+//! Enabled for lily's own tests and, for applications testing their editors,
+//! by the `testing` feature. The `golden` feature adds rendering to compare
+//! widgets against reference images.
+
+use std::cell::RefCell;
+use std::rc::Rc;
+use std::time::{Duration, Instant};
+
+use vizia::cache::BoundingBox;
+use vizia::prelude::*;
+
+#[cfg(feature = "golden")]
+mod golden;
+#[cfg(feature = "golden")]
+pub use golden::*;
+
+/// How many moves [`TestContext::drag`] takes between the press and the
+/// release
+const DRAG_STEPS: usize = 8;
+
+/// A headless vizia context for driving widgets in tests
+pub struct TestContext {
+    cx: Context,
+    /// How far [`TestContext::wait`] has moved the animation clock ahead of
+    /// the real one
+    waited: Duration,
+}
+
+impl TestContext {
+    /// Creates a context with a window `width` by `height` logical pixels,
+    /// at a scale factor of 1
+    pub fn new(width: f32, height: f32) -> Self {
+        let mut cx = Context::new();
+        let root = Entity::root();
+        cx.style.width.insert(root, Units::Pixels(width));
+        cx.style.height.insert(root, Units::Pixels(height));
+        cx.cache.set_width(root, width);
+        cx.cache.set_height(root, height);
+        let mut test = Self {
+            cx,
+            waited: Duration::ZERO,
+        };
+        test.update();
+        test
+    }
+
+    /// Builds views and models into the window, returning what `content`
+    /// returns, such as the entity of a widget to look up its bounds
+    pub fn build<R>(&mut self, content: impl FnOnce(&mut Context) -> R) -> R {
+        let result = content(&mut self.cx);
+        self.update();
+        result
+    }
+
+    /// Handles every queued event and brings the models, styles, running
+    /// transitions and layout up to date, as the event loop does each frame
+    pub fn update(&mut self) {
+        self.cx.process_events();
+        self.cx.process_data_updates();
+        self.cx.process_style_updates();
+        self.cx.style.apply_animations(Instant::now() + self.waited);
+        self.cx.process_visual_updates();
+    }
+
+    /// Moves the animation clock `duration` ahead and updates, so the style
+    /// transitions started before, such as a hover fading in, have moved on
+    /// that far without the test sleeping
+    pub fn wait(&mut self, duration: Duration) {
+        self.waited += duration;
+        self.update();
+    }
+
+    /// Dispatches a window event as the window backend would, to the hovered,
+    /// captured or focused view, and handles what follows
+    pub fn dispatch(&mut self, event: WindowEvent) {
+        self.cx.dispatch_system_event(event);
+        self.update();
+    }
+
+    /// Moves the mouse to `(x, y)`
+    pub fn move_mouse(&mut self, x: f32, y: f32) {
+        self.dispatch(WindowEvent::MouseMove(x, y));
+    }
+
+    /// Presses a mouse button where the mouse is
+    pub fn press(&mut self, button: MouseButton) {
+        self.dispatch(WindowEvent::MouseDown(button));
+    }
+
+    /// Releases a mouse button where the mouse is
+    pub fn release(&mut self, button: MouseButton) {
+        self.dispatch(WindowEvent::MouseUp(button));
+    }
+
+    /// Moves the mouse to `(x, y)` and clicks the left button
+    pub fn click(&mut self, x: f32, y: f32) {
+        self.move_mouse(x, y);
+        self.press(MouseButton::Left);
+        self.release(MouseButton::Left);
+    }
+
+    /// Moves the mouse to `(x, y)` and clicks the left button twice in quick
+    /// succession
+    pub fn double_click(&mut self, x: f32, y: f32) {
+        self.click(x, y);
+        self.press(MouseButton::Left);
+        self.release(MouseButton::Left);
+    }
+
+    /// Presses the left button at `from`, moves the mouse to `to` in a few
+    /// steps and releases it there
+    pub fn drag(&mut self, from: (f32, f32), to: (f32, f32)) {
+        self.move_mouse(from.0, from.1);
+        self.press(MouseButton::Left);
+        for step in 1..=DRAG_STEPS {
+            let t = step as f32 / DRAG_STEPS as f32;
+            self.move_mouse(from.0 + (to.0 - from.0) * t, from.1 + (to.1 - from.1) * t);
+        }
+        self.release(MouseButton::Left);
+    }
+
+    /// Holds down modifier keys for the events that follow, until they are
+    /// set again
+    pub fn set_modifiers(&mut self, modifiers: Modifiers) {
+        self.cx.modifiers = modifiers;
+    }
+
+    /// Presses and releases a key, which goes to the focused view
+    pub fn press_key(&mut self, code: Code) {
+        self.dispatch(WindowEvent::KeyDown(code, None));
+        self.dispatch(WindowEvent::KeyUp(code, None));
+    }
+
+    /// Gives `entity` the keyboard focus, as clicking it would
+    pub fn focus(&mut self, entity: Entity) {
+        self.cx.with_current(entity, |cx| cx.focus());
+        self.update();
+    }
+
+    /// The bounds of a view in the window, as of the last update
+    pub fn bounds(&self, entity: Entity) -> BoundingBox {
+        self.cx.cache.get_bounds(entity)
+    }
+
+    /// The context itself, for anything the helpers don't cover
+    pub fn cx(&mut self) -> &mut Context {
+        &mut self.cx
+    }
+}
+
+/// Collects the values passed to a widget's callbacks. Clones share the same
+/// values, so one clone can be moved into the callback and another kept to
+/// check what was recorded.
+#[derive(Debug)]
+pub struct Recorder<T>(Rc<RefCell<Vec<T>>>);
+
+impl<T> Clone for Recorder<T> {
+    fn clone(&self) -> Self {
+        Self(self.0.clone())
+    }
+}
+
+impl<T> Default for Recorder<T> {
+    fn default() -> Self {
+        Self(Rc::default())
+    }
+}
+
+impl<T: Clone> Recorder<T> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn push(&self, value: T) {
+        self.0.borrow_mut().push(value);
+    }
+
+    /// Every value recorded so far, oldest first
+    pub fn values(&self) -> Vec<T> {
+        self.0.borrow().clone()
+    }
+
+    /// The value recorded last
+    pub fn last(&self) -> Option<T> {
+        self.0.borrow().last().cloned()
+    }
+
+    pub fn len(&self) -> usize {
+        self.0.borrow().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.borrow().is_empty()
+    }
+}
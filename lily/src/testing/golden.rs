@@ -0,0 +1,180 @@
+//! Rendering widgets offscreen and comparing them to reference images.
+//!
+//! [`TestContext::render`] draws the window into a headless OpenGL context,
+//! and [`assert_golden`] compares the result to a PNG stored under
+//! `tests/golden`, allowing for the small antialiasing differences between
+//! GPUs and drivers. A missing reference fails the test like a mismatch does.
+//! Setting `LILY_BLESS=1` writes the references from the renders instead, to
+//! record the images of new tests or after an intended change to a `draw`
+//! method, and the written images are committed with the change.
+//!
+//! ```ignore
+//! let mut test = TestContext::new(200f32, 100f32);
+//! test.build(|cx| { /* build the widget with fixed data */ });
+//! assert_golden("drawbars", &test.render());
+//! ```
+//!
+//! Fonts aren't loaded into the offscreen canvas, so reference images are
+//! best kept to widgets that draw without text.
+
+use std::path::PathBuf;
+
+use glutin::dpi::PhysicalSize;
+use glutin::event_loop::EventLoop;
+use glutin::ContextBuilder;
+use image::RgbaImage;
+use vizia::prelude::*;
+use vizia::vg::{self, renderer::OpenGl};
+
+use super::TestContext;
+
+/// How far a channel of a pixel may be from the reference, out of 255,
+/// before the pixel counts as changed
+const CHANNEL_TOLERANCE: u8 = 8;
+/// The share of pixels that may change before the images no longer match
+const PIXEL_TOLERANCE: f32 = 0.002;
+
+impl TestContext {
+    /// Draws the window offscreen and reads back the image
+    pub fn render(&mut self) -> RgbaImage {
+        let bounds = self.bounds(Entity::root());
+        let (width, height) = (bounds.w.round() as u32, bounds.h.round() as u32);
+
+        let event_loop = event_loop();
+        let context = ContextBuilder::new()
+            .build_headless(&event_loop, PhysicalSize::new(width, height))
+            .expect("no headless OpenGL context");
+        // SAFETY: the context lives until the image is read back, and no
+        // other context is made current on this thread meanwhile
+        let context = unsafe { context.make_current() }.expect("no current OpenGL context");
+        let renderer = OpenGl::new_from_function(|name| context.get_proc_address(name) as *const _)
+            .expect("no OpenGL renderer");
+        let mut canvas = vg::Canvas::new(renderer).expect("no canvas");
+        canvas.set_size(width, height, 1f32);
+        canvas.clear_rect(
+            0,
+            0,
+            width,
+            height,
+            vg::Color::rgbaf(0f32, 0f32, 0f32, 0f32),
+        );
+
+        self.cx.canvases.insert(Entity::root(), canvas);
+        self.cx.style.needs_redraw = true;
+        self.cx.draw();
+        let mut canvas = self.cx.canvases.remove(&Entity::root()).unwrap();
+        canvas.flush();
+        let screenshot = canvas.screenshot().expect("can't read back the render");
+
+        let (pixels, width, height) = screenshot.into_contiguous_buf();
+        let bytes = pixels
+            .iter()
+            .flat_map(|pixel| [pixel.r, pixel.g, pixel.b, pixel.a])
+            .collect();
+        RgbaImage::from_raw(width as u32, height as u32, bytes).unwrap()
+    }
+}
+
+/// Tests create their contexts on worker threads, where winit only makes an
+/// event loop when asked to
+#[cfg(any(target_os = "linux", target_os = "freebsd"))]
+fn event_loop() -> EventLoop<()> {
+    use glutin::platform::unix::EventLoopExtUnix;
+    EventLoop::new_any_thread()
+}
+
+#[cfg(target_os = "windows")]
+fn event_loop() -> EventLoop<()> {
+    use glutin::platform::windows::EventLoopExtWindows;
+    EventLoop::new_any_thread()
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "freebsd", target_os = "windows")))]
+fn event_loop() -> EventLoop<()> {
+    EventLoop::new()
+}
+
+/// The number of pixels that differ by more than [`CHANNEL_TOLERANCE`] in any
+/// channel, or `None` if the images aren't the same size
+pub fn changed_pixels(image: &RgbaImage, reference: &RgbaImage) -> Option<usize> {
+    if image.dimensions() != reference.dimensions() {
+        return None;
+    }
+    let changed = image
+        .pixels()
+        .zip(reference.pixels())
+        .filter(|(a, b)| {
+            a.0.iter()
+                .zip(b.0.iter())
+                .any(|(a, b)| a.abs_diff(*b) > CHANNEL_TOLERANCE)
+        })
+        .count();
+    Some(changed)
+}
+
+/// Compares a render to the reference image `tests/golden/<name>.png`. When
+/// they don't match, or there is no reference, the render is saved next to
+/// where the reference goes as `<name>.actual.png` to look at, and the test
+/// fails. With `LILY_BLESS` set, the render is saved as the reference
+/// instead.
+pub fn assert_golden(name: &str, image: &RgbaImage) {
+    let dir = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests/golden");
+    let path = dir.join(format!("{}.png", name));
+    let actual = dir.join(format!("{}.actual.png", name));
+    std::fs::create_dir_all(&dir).unwrap();
+    if std::env::var_os("LILY_BLESS").is_some() {
+        image.save(&path).unwrap();
+        return;
+    }
+    if !path.exists() {
+        image.save(&actual).unwrap();
+        panic!(
+            "`{}` has no reference image, see {} and run with LILY_BLESS=1 to record it",
+            name,
+            actual.display()
+        );
+    }
+
+    let reference = image::open(&path).unwrap().into_rgba8();
+    let allowed = (image.width() * image.height()) as f32 * PIXEL_TOLERANCE;
+    match changed_pixels(image, &reference) {
+        Some(changed) if changed as f32 <= allowed => (),
+        changed => {
+            image.save(&actual).unwrap();
+            match changed {
+                Some(changed) => panic!(
+                    "{} pixels of `{}` changed, see {}",
+                    changed,
+                    name,
+                    actual.display()
+                ),
+                None => panic!(
+                    "`{}` is {:?} instead of {:?}, see {}",
+                    name,
+                    image.dimensions(),
+                    reference.dimensions(),
+                    actual.display()
+                ),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use image::Rgba;
+
+    use super::*;
+
+    #[test]
+    fn tolerates_small_differences() {
+        let reference = RgbaImage::from_pixel(4, 4, Rgba([100, 100, 100, 255]));
+        let mut image = reference.clone();
+        image.put_pixel(0, 0, Rgba([104, 96, 100, 255]));
+        assert_eq!(changed_pixels(&image, &reference), Some(0));
+        image.put_pixel(1, 0, Rgba([200, 100, 100, 255]));
+        assert_eq!(changed_pixels(&image, &reference), Some(1));
+        let smaller = RgbaImage::new(2, 2);
+        assert_eq!(changed_pixels(&smaller, &reference), None);
+    }
+}
@@ -0,0 +1,503 @@
+//! Color palettes for lily's widgets.
+//!
+//! [`DEFAULT_STYLE`](crate::DEFAULT_STYLE) lays out every widget and colors it
+//! with [`Palette::dark`]. A [`Palette`] turns into a stylesheet that only sets
+//! colors, so applying one on top of the default style recolors every widget
+//! without touching its layout.
+//!
+//! ```ignore
+//! cx.add_theme(DEFAULT_STYLE);
+//! Palette {
+//!     accent: Color::rgb(70, 160, 245),
+//!     ..Palette::dark()
+//! }
+//! .apply(cx);
+//! ```
+//!
+//! To switch themes while the UI is running, build a [`ThemeProvider`] near
+//! the root of the UI instead and call [`set_theme`] from an event handler.
+//! Widgets are restyled in place, without being rebuilt.
+//!
+//! ```ignore
+//! cx.add_theme(DEFAULT_STYLE);
+//! ThemeProvider::install(cx, Theme::Dark);
+//! Button::new(cx, |cx| set_theme(cx, Theme::Light), |cx| Label::new(cx, "Light"));
+//! ```
+
+use std::fmt::Write;
+
+use vizia::prelude::*;
+
+use crate::util::ColorExt;
+
+/// The colors every lily widget is drawn with
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Palette {
+    /// The background of widgets
+    pub background: Color,
+    /// Raised surfaces such as headers, tooltips and menus
+    pub surface: Color,
+    /// Filled tracks, bars and selections
+    pub track: Color,
+    /// Handles, outlines and other highlights
+    pub accent: Color,
+    /// Draggable points
+    pub point: Color,
+    /// Guide lines such as crosshairs
+    pub guide: Color,
+    /// The live modulation drawn over controls
+    pub modulation: Color,
+    /// Text and plain lines. Grids and secondary text use it faded.
+    pub text: Color,
+    /// Values that need attention, such as clipping, and invalid data
+    pub warning: Color,
+    /// White piano keys and drawbars
+    pub light_key: Color,
+    /// Black piano keys and drawbars
+    pub dark_key: Color,
+}
+
+impl Default for Palette {
+    fn default() -> Self {
+        Self::dark()
+    }
+}
+
+impl Palette {
+    /// The dark red palette of the default style
+    pub fn dark() -> Self {
+        Self {
+            background: Color::rgb(0x29, 0x27, 0x28),
+            surface: Color::rgb(0x1a, 0x18, 0x19),
+            track: Color::rgb(0x8f, 0x3b, 0x37),
+            accent: Color::rgb(0xf5, 0x4e, 0x46),
+            point: Color::rgb(0xf5, 0x4e, 0x46),
+            guide: Color::rgb(0x66, 0x32, 0x31),
+            modulation: Color::rgb(0x46, 0xb4, 0xf5),
+            text: Color::rgb(0xff, 0xff, 0xff),
+            warning: Color::rgb(0xf5, 0xc1, 0x46),
+            light_key: Color::rgb(0xe8, 0xe4, 0xdc),
+            dark_key: Color::rgb(0x1a, 0x18, 0x19),
+        }
+    }
+
+    /// A light palette with the red accents of the default style
+    pub fn light() -> Self {
+        Self {
+            background: Color::rgb(0xf2, 0xf0, 0xee),
+            surface: Color::rgb(0xff, 0xff, 0xff),
+            track: Color::rgb(0xf0, 0xa2, 0x9c),
+            accent: Color::rgb(0xd9, 0x3a, 0x32),
+            point: Color::rgb(0xd9, 0x3a, 0x32),
+            guide: Color::rgb(0xe8, 0xb4, 0xb0),
+            modulation: Color::rgb(0x1e, 0x88, 0xd0),
+            text: Color::rgb(0x1a, 0x18, 0x19),
+            warning: Color::rgb(0xc9, 0x8a, 0x00),
+            light_key: Color::rgb(0xff, 0xff, 0xff),
+            dark_key: Color::rgb(0x29, 0x27, 0x28),
+        }
+    }
+
+    /// Adds the palette's stylesheet to the application. Add it after
+    /// [`DEFAULT_STYLE`](crate::DEFAULT_STYLE) so its colors take precedence.
+    pub fn apply(&self, cx: &mut Context) {
+        cx.add_theme(&self.stylesheet());
+    }
+
+    /// Builds a stylesheet that sets the colors of every lily widget from the
+    /// palette
+    pub fn stylesheet(&self) -> String {
+        let background = css(self.background);
+        let surface = css(self.surface);
+        let track = css(self.track);
+        let accent = css(self.accent);
+        let point = css(self.point);
+        let text = css(self.text);
+        let faded = css(self.text.with_alpha(0x80));
+        let grid = css(self.text.with_alpha(0x1a));
+        let disabled = css(self.text.with_alpha(0x40));
+
+        let rules: &[(&str, &[(&str, String)])] = &[
+            ("mseg", &[("background-color", background.clone())]),
+            (".line", &[("border-color", faded.clone())]),
+            ("zoomer .bar", &[("background-color", track.clone())]),
+            (
+                "zoomer .handle",
+                &[
+                    ("background-color", accent.clone()),
+                    ("border-color", css(self.text.with_alpha(0x5b))),
+                ],
+            ),
+            ("slider", &[("background-color", background.clone())]),
+            (
+                "slider .bar",
+                &[
+                    ("background-color", track.clone()),
+                    ("border-color", accent.clone()),
+                ],
+            ),
+            (
+                "fader .track",
+                &[
+                    ("background-color", track.clone()),
+                    ("border-color", accent.clone()),
+                ],
+            ),
+            (
+                "fader .cap",
+                &[
+                    ("background-color", css(self.surface)),
+                    ("border-color", text.clone()),
+                ],
+            ),
+            (
+                "xy",
+                &[
+                    ("background-color", background.clone()),
+                    ("border-color", grid.clone()),
+                ],
+            ),
+            (
+                "xy .grid",
+                &[
+                    ("background-color", css(self.accent.with_alpha(0x26))),
+                    ("border-color", grid.clone()),
+                ],
+            ),
+            (
+                "xy-display",
+                &[
+                    ("background-color", background.clone()),
+                    ("border-color", grid.clone()),
+                ],
+            ),
+            (
+                "xy-display .trail",
+                &[("border-color", css(self.modulation))],
+            ),
+            (
+                ".point",
+                &[
+                    ("background-color", point.clone()),
+                    ("border-color", point.clone()),
+                ],
+            ),
+            (
+                ".curve-handle",
+                &[
+                    ("background-color", point.clone()),
+                    ("border-color", css(self.point.with_alpha(0x80))),
+                ],
+            ),
+            (
+                ".region",
+                &[
+                    ("background-color", css(self.accent.with_alpha(0x26))),
+                    ("border-color", accent.clone()),
+                ],
+            ),
+            (
+                ".selection",
+                &[
+                    ("background-color", css(self.text.with_alpha(0x1a))),
+                    ("border-color", text.clone()),
+                ],
+            ),
+            (
+                ".grid",
+                &[("border-color", css(self.text.with_alpha(0x14)))],
+            ),
+            (".morph", &[("border-color", css(self.modulation))]),
+            (
+                ".tag",
+                &[
+                    ("background-color", text.clone()),
+                    ("border-color", css(self.guide)),
+                ],
+            ),
+            (
+                ".hover-ring",
+                &[("border-color", css(self.point.with_alpha(0)))],
+            ),
+            (".hover-ring:hover", &[("border-color", point.clone())]),
+            (".tab-stop:focus", &[("outline-color", point.clone())]),
+            (".crosshair", &[("border-color", css(self.guide))]),
+            (
+                ".modulation",
+                &[
+                    ("background-color", css(self.modulation.with_alpha(0x66))),
+                    ("border-color", css(self.modulation)),
+                ],
+            ),
+            (".warning", &[("color", css(self.warning))]),
+            (".invalid", &[("border-color", css(self.warning))]),
+            (
+                "threshold-scope",
+                &[("background-color", background.clone())],
+            ),
+            (
+                "threshold-scope .level",
+                &[("background-color", track.clone())],
+            ),
+            (
+                "threshold-scope .threshold",
+                &[("border-color", accent.clone())],
+            ),
+            (
+                "threshold-scope .hysteresis",
+                &[("background-color", css(self.accent.with_alpha(0x26)))],
+            ),
+            ("drawbars", &[("background-color", background.clone())]),
+            (
+                "drawbars .white",
+                &[
+                    ("background-color", css(self.light_key)),
+                    ("border-color", css(self.light_key.lerp(self.dark_key, 0.3))),
+                ],
+            ),
+            (
+                "drawbars .black",
+                &[
+                    ("background-color", css(self.dark_key)),
+                    (
+                        "border-color",
+                        css(self.dark_key.lerp(self.light_key, 0.25)),
+                    ),
+                ],
+            ),
+            ("drawbars .number", &[("color", faded.clone())]),
+            (
+                "key-zones",
+                &[
+                    ("background-color", background.clone()),
+                    ("border-color", css(Color::rgba(0, 0, 0, 0x80))),
+                ],
+            ),
+            (
+                "key-zones .zone",
+                &[
+                    ("background-color", track.clone()),
+                    ("border-color", accent.clone()),
+                    ("color", text.clone()),
+                ],
+            ),
+            (
+                "key-zones .white-key",
+                &[("background-color", css(self.light_key))],
+            ),
+            (
+                "key-zones .black-key",
+                &[("background-color", css(self.dark_key))],
+            ),
+            ("tuning-table", &[("background-color", background.clone())]),
+            ("tuning-table .degree", &[("color", faded.clone())]),
+            ("tap-editor", &[("background-color", background.clone())]),
+            (
+                "tap-editor .tap",
+                &[
+                    ("background-color", track.clone()),
+                    ("border-color", accent.clone()),
+                ],
+            ),
+            ("tap-editor .ruler", &[("border-color", grid.clone())]),
+            (
+                "band-curve",
+                &[
+                    ("background-color", background.clone()),
+                    ("border-color", text.clone()),
+                ],
+            ),
+            ("band-curve .grid", &[("border-color", grid.clone())]),
+            ("band-curve .tick", &[("color", faded.clone())]),
+            ("grain-cloud", &[("background-color", background.clone())]),
+            (
+                "grain-cloud .waveform",
+                &[("background-color", grid.clone())],
+            ),
+            ("grain-cloud .grain", &[("background-color", point.clone())]),
+            ("voice-display", &[("background-color", background.clone())]),
+            ("voice-display .voice", &[("color", text.clone())]),
+            (
+                "voice-display .idle",
+                &[
+                    ("background-color", surface.clone()),
+                    ("color", disabled.clone()),
+                ],
+            ),
+            (
+                "voice-display .attack",
+                &[("background-color", accent.clone())],
+            ),
+            (
+                "voice-display .decay",
+                &[("background-color", css(self.accent.lerp(self.track, 0.5)))],
+            ),
+            (
+                "voice-display .sustain",
+                &[("background-color", track.clone())],
+            ),
+            (
+                "voice-display .release",
+                &[(
+                    "background-color",
+                    css(self.track.lerp(self.background, 0.5)),
+                )],
+            ),
+            (
+                "fx-chain",
+                &[
+                    ("background-color", background.clone()),
+                    ("border-color", accent.clone()),
+                ],
+            ),
+            (
+                "fx-chain .slot.selected",
+                &[("background-color", track.clone())],
+            ),
+            ("fx-chain .slot.bypassed .name", &[("color", disabled)]),
+            ("fx-chain .name", &[("color", text.clone())]),
+            (
+                "drop-zone",
+                &[
+                    ("background-color", background.clone()),
+                    ("border-color", accent.clone()),
+                ],
+            ),
+            ("drop-zone .placeholder", &[("color", faded.clone())]),
+            ("section", &[("background-color", background)]),
+            ("section .header", &[("background-color", surface.clone())]),
+            (
+                "section .header .arrow, section .header .title",
+                &[("color", text.clone())],
+            ),
+            ("section .summary", &[("color", faded)]),
+            (
+                "tooltip",
+                &[
+                    ("background-color", surface.clone()),
+                    ("border-color", accent.clone()),
+                ],
+            ),
+            ("tooltip .text", &[("color", text.clone())]),
+            (
+                "context-menu",
+                &[
+                    ("background-color", surface),
+                    ("border-color", accent.clone()),
+                ],
+            ),
+            ("context-menu .item:hover", &[("background-color", track)]),
+            ("context-menu .item label", &[("color", text.clone())]),
+            (
+                "midi-learn.armed",
+                &[
+                    ("background-color", css(self.accent.with_alpha(0x26))),
+                    ("border-color", accent.clone()),
+                ],
+            ),
+            (
+                "midi-learn.learning",
+                &[("background-color", css(self.accent.with_alpha(0x66)))],
+            ),
+            (
+                "midi-learn .badge",
+                &[("background-color", accent), ("color", text)],
+            ),
+        ];
+
+        let mut stylesheet = String::new();
+        for (selector, properties) in rules {
+            let _ = writeln!(stylesheet, "{selector} {{");
+            for (property, value) in properties.iter() {
+                let _ = writeln!(stylesheet, "    {property}: {value};");
+            }
+            stylesheet.push_str("}\n");
+        }
+        stylesheet
+    }
+}
+
+/// The palettes lily widgets can be switched between at runtime
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum Theme {
+    /// [`Palette::dark`], which matches the default style
+    #[default]
+    Dark,
+    /// [`Palette::light`]
+    Light,
+    /// A palette of the application's own
+    Custom(Palette),
+}
+
+impl Data for Theme {
+    fn same(&self, other: &Self) -> bool {
+        self == other
+    }
+}
+
+impl Theme {
+    /// The colors of the theme
+    pub fn palette(&self) -> Palette {
+        match self {
+            Theme::Dark => Palette::dark(),
+            Theme::Light => Palette::light(),
+            Theme::Custom(palette) => *palette,
+        }
+    }
+}
+
+pub enum ThemeEvent {
+    Set(Theme),
+}
+
+/// Holds the current [`Theme`] and applies its palette whenever it changes
+#[derive(Lens)]
+pub struct ThemeProvider {
+    pub theme: Theme,
+}
+
+impl ThemeProvider {
+    /// Builds the provider starting with `theme`. Build it after adding
+    /// [`DEFAULT_STYLE`](crate::DEFAULT_STYLE) so the palette takes precedence.
+    pub fn install(cx: &mut Context, theme: Theme) {
+        Self { theme }.build(cx);
+        // Stylesheets can only be added with a `Context`, which a binding gets
+        // each time the theme changes. Each switch adds the new palette on top
+        // of the ones before it.
+        Binding::new(cx, ThemeProvider::theme, |cx, theme| {
+            theme.get(cx).palette().apply(cx);
+        });
+    }
+}
+
+impl Model for ThemeProvider {
+    fn event(&mut self, _cx: &mut EventContext, event: &mut Event) {
+        event.map(|ev: &ThemeEvent, meta| {
+            match ev {
+                ThemeEvent::Set(theme) => self.theme = *theme,
+            }
+            meta.consume();
+        });
+    }
+}
+
+/// Switches every lily widget to `theme` at runtime. Needs a [`ThemeProvider`]
+/// above the view handling the event, otherwise the theme is left unchanged.
+pub fn set_theme(cx: &mut EventContext, theme: Theme) {
+    cx.emit(ThemeEvent::Set(theme));
+}
+
+/// Formats a color as a CSS hex color
+fn css(color: Color) -> String {
+    if color.a() == 0xff {
+        format!("#{:02x}{:02x}{:02x}", color.r(), color.g(), color.b())
+    } else {
+        format!(
+            "#{:02x}{:02x}{:02x}{:02x}",
+            color.r(),
+            color.g(),
+            color.b(),
+            color.a()
+        )
+    }
+}
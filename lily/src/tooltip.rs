@@ -0,0 +1,164 @@
+//! A shared tooltip for showing formatted values while hovering over or
+//! dragging lily widgets.
+//!
+//! Build a [`TooltipProvider`] near the root of the UI and a [`Tooltip`] after
+//! the rest of the content so it draws on top. Widgets then call the
+//! [`TooltipExt`] methods on their [`EventContext`] and the tooltip is placed
+//! next to them. Without a provider, tooltip requests are ignored. Tooltip
+//! text is localized with the installed [`Locale`](crate::locale::Locale).
+//!
+//! Hover tooltips fade in after the delay set by the `transition` of the
+//! `tooltip.visible` class in the stylesheet, while tooltips shown during a
+//! drag also get the `immediate` class and appear straight away.
+
+use vizia::cache::BoundingBox;
+use vizia::prelude::*;
+
+use crate::locale::LocaleExt;
+
+/// The gap in pixels between a widget and its tooltip
+const TOOLTIP_OFFSET: f32 = 4f32;
+/// The height in pixels reserved for a tooltip placed above a widget
+const TOOLTIP_HEIGHT: f32 = 20f32;
+
+/// Where a tooltip is placed relative to the widget that shows it
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Data)]
+pub enum TooltipPlacement {
+    Above,
+    Below,
+}
+
+pub enum TooltipEvent {
+    Show {
+        text: String,
+        /// The bounds of the widget showing the tooltip
+        bounds: BoundingBox,
+        /// Whether to skip the hover delay, e.g. while dragging
+        immediate: bool,
+    },
+    Hide,
+}
+
+/// Holds the state of the shared tooltip
+#[derive(Lens)]
+pub struct TooltipProvider {
+    pub text: String,
+    pub visible: bool,
+    pub immediate: bool,
+    pub placement: TooltipPlacement,
+    pub x: f32,
+    pub y: f32,
+}
+
+impl TooltipProvider {
+    pub fn new(placement: TooltipPlacement) -> Self {
+        Self {
+            text: String::new(),
+            visible: false,
+            immediate: false,
+            placement,
+            x: 0f32,
+            y: 0f32,
+        }
+    }
+}
+
+impl Default for TooltipProvider {
+    fn default() -> Self {
+        Self::new(TooltipPlacement::Above)
+    }
+}
+
+impl Model for TooltipProvider {
+    fn event(&mut self, _cx: &mut EventContext, event: &mut Event) {
+        event.map(|ev: &TooltipEvent, meta| {
+            match ev {
+                TooltipEvent::Show {
+                    text,
+                    bounds,
+                    immediate,
+                } => {
+                    self.text = text.clone();
+                    self.visible = true;
+                    self.immediate = *immediate;
+                    self.x = bounds.left();
+                    self.y = match self.placement {
+                        TooltipPlacement::Above => bounds.top() - TOOLTIP_OFFSET - TOOLTIP_HEIGHT,
+                        TooltipPlacement::Below => bounds.bottom() + TOOLTIP_OFFSET,
+                    };
+                }
+                TooltipEvent::Hide => {
+                    self.visible = false;
+                    self.immediate = false;
+                }
+            }
+            meta.consume();
+        });
+    }
+}
+
+/// The view that displays the shared tooltip. Build this once, after the rest
+/// of the UI, below a [`TooltipProvider`].
+pub struct Tooltip;
+
+impl Tooltip {
+    pub fn new(cx: &mut Context) -> Handle<Self> {
+        Self.build(cx, |cx| {
+            Label::new(cx, TooltipProvider::text).class("text");
+        })
+        .position_type(PositionType::SelfDirected)
+        .bind(TooltipProvider::x, |handle, x| {
+            let x = x.get(handle.cx);
+            handle.left(Pixels(x));
+        })
+        .bind(TooltipProvider::y, |handle, y| {
+            let y = y.get(handle.cx);
+            handle.top(Pixels(y));
+        })
+        .toggle_class("visible", TooltipProvider::visible)
+        .toggle_class("immediate", TooltipProvider::immediate)
+        .hoverable(false)
+    }
+}
+
+impl View for Tooltip {
+    fn element(&self) -> Option<&'static str> {
+        Some("tooltip")
+    }
+}
+
+/// Shows and hides the shared tooltip from a widget's event handler
+pub trait TooltipExt {
+    /// Show a tooltip next to the current widget after the hover delay
+    fn show_tooltip(&mut self, text: impl Into<String>);
+    /// Show a tooltip next to the current widget straight away, for values
+    /// that change while dragging
+    fn show_value_tooltip(&mut self, text: impl Into<String>);
+    fn hide_tooltip(&mut self);
+}
+
+impl TooltipExt for EventContext<'_> {
+    fn show_tooltip(&mut self, text: impl Into<String>) {
+        let bounds = self.cache.get_bounds(self.current());
+        let text = self.localize(&text.into());
+        self.emit(TooltipEvent::Show {
+            text,
+            bounds,
+            immediate: false,
+        });
+    }
+
+    fn show_value_tooltip(&mut self, text: impl Into<String>) {
+        let bounds = self.cache.get_bounds(self.current());
+        let text = self.localize(&text.into());
+        self.emit(TooltipEvent::Show {
+            text,
+            bounds,
+            immediate: true,
+        });
+    }
+
+    fn hide_tooltip(&mut self) {
+        self.emit(TooltipEvent::Hide);
+    }
+}
@@ -0,0 +1,97 @@
+//! Recording a dragged point over time, to capture performance gestures into
+//! envelopes
+
+use glam::Vec2;
+use std::time::Instant;
+
+use super::CurvePoints;
+
+/// The time in seconds within which a position replaces the one recorded
+/// before it, since the times of envelope points must increase
+const MIN_SAMPLE_INTERVAL: f32 = 0.001f32;
+
+/// Records the positions of a point while it's dragged, with the time since
+/// the drag started, and turns them into an envelope per axis
+#[derive(Clone, Debug, Default)]
+pub struct MotionCapture {
+    /// When the recording started, while recording
+    started: Option<Instant>,
+    /// The times in seconds and the positions recorded at them
+    samples: Vec<(f32, Vec2)>,
+}
+
+impl MotionCapture {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Starts recording from `position`, dropping anything recorded before
+    pub fn start(&mut self, position: Vec2) {
+        self.started = Some(Instant::now());
+        self.samples.clear();
+        self.record_at(0f32, position);
+    }
+
+    /// Records `position` as of now, if recording
+    pub fn record(&mut self, position: Vec2) {
+        if let Some(started) = self.started {
+            self.record_at(started.elapsed().as_secs_f32(), position);
+        }
+    }
+
+    /// Records `position` at `time` seconds into the recording. A position
+    /// recorded within a millisecond of the last one replaces it.
+    pub fn record_at(&mut self, time: f32, position: Vec2) {
+        match self.samples.last_mut() {
+            Some(last) if time - last.0 < MIN_SAMPLE_INTERVAL => last.1 = position,
+            _ => self.samples.push((time, position)),
+        }
+    }
+
+    /// Stops recording and drops what was recorded
+    pub fn cancel(&mut self) {
+        self.started = None;
+        self.samples.clear();
+    }
+
+    /// Stops recording and returns the positions recorded as two envelopes,
+    /// one of the `x` and one of the `y` of the positions over time in
+    /// seconds. Returns `None` if nothing was being recorded.
+    pub fn finish(&mut self) -> Option<(CurvePoints, CurvePoints)> {
+        self.started.take()?;
+        let samples = std::mem::take(&mut self.samples);
+        let axis = |value: fn(Vec2) -> f32| {
+            CurvePoints::new(
+                samples
+                    .iter()
+                    .map(|(time, position)| (*time, value(*position)).into())
+                    .collect(),
+            )
+        };
+        Some((axis(|position| position.x), axis(|position| position.y)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn records_an_envelope_per_axis() {
+        let mut capture = MotionCapture::new();
+        assert!(capture.finish().is_none());
+        capture.start(Vec2::new(0.5f32, 0.5f32));
+        capture.record_at(0.1f32, Vec2::new(0.6f32, 0.4f32));
+        // Too soon after the last position, which it replaces
+        capture.record_at(0.1005f32, Vec2::new(0.7f32, 0.3f32));
+        capture.record_at(0.2f32, Vec2::new(1f32, 0f32));
+        let (x, y) = capture.finish().unwrap();
+        let times: Vec<f32> = x.iter().map(|point| point.x).collect();
+        assert_eq!(times, vec![0f32, 0.1f32, 0.2f32]);
+        let xs: Vec<f32> = x.iter().map(|point| point.y).collect();
+        assert_eq!(xs, vec![0.5f32, 0.7f32, 1f32]);
+        let ys: Vec<f32> = y.iter().map(|point| point.y).collect();
+        assert_eq!(ys, vec![0.5f32, 0.3f32, 0f32]);
+        assert!(capture.finish().is_none());
+    }
+}
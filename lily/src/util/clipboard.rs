@@ -0,0 +1,87 @@
+//! Copying curves to and from the system clipboard as JSON text, so shapes
+//! can be moved between plugin instances or shared as text.
+//!
+//! ```ignore
+//! Mseg::new(cx, Data::points, Data::range, 4f32)
+//!     .on_paste(|cx, points| cx.emit(AppEvent::SetPoints(points)));
+//! ```
+
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use vizia::prelude::*;
+
+use super::{CurveKind, CurvePoints};
+
+/// Copies and pastes the points of a curve editor, set up by
+/// [`CurveClipboardExt::on_paste`]
+#[allow(clippy::type_complexity)]
+pub(crate) struct CurveClipboard<T> {
+    kind: CurveKind,
+    copy: fn(&CurvePoints<T>, CurveKind) -> String,
+    paste: fn(&str, CurveKind) -> Option<CurvePoints<T>>,
+    on_paste: Box<dyn Fn(&mut EventContext, CurvePoints<T>)>,
+}
+
+impl<T> CurveClipboard<T> {
+    pub(crate) fn new<F>(kind: CurveKind, on_paste: F) -> Self
+    where
+        T: Serialize + DeserializeOwned + Default,
+        F: 'static + Fn(&mut EventContext, CurvePoints<T>),
+    {
+        Self {
+            kind,
+            copy: CurvePoints::to_clipboard,
+            paste: CurvePoints::from_clipboard,
+            on_paste: Box::new(on_paste),
+        }
+    }
+
+    /// Puts the points on the system clipboard
+    pub(crate) fn copy(&self, cx: &mut EventContext, points: &CurvePoints<T>) {
+        let text = (self.copy)(points, self.kind);
+        // Nothing to tell the user if there's no clipboard to copy to
+        let _ = cx.set_clipboard(text);
+    }
+
+    /// Reads points from the system clipboard and passes them to the
+    /// `on_paste` callback, ignoring text that isn't a curve of this kind
+    pub(crate) fn paste(&self, cx: &mut EventContext) {
+        let points = cx
+            .get_clipboard()
+            .ok()
+            .and_then(|text| (self.paste)(&text, self.kind));
+        if let Some(points) = points {
+            (self.on_paste)(cx, points);
+        }
+    }
+
+    /// Copies or pastes for the platform's copy and paste shortcuts. Returns
+    /// whether the key was one of them.
+    pub(crate) fn handle_key(
+        &self,
+        cx: &mut EventContext,
+        code: Code,
+        points: &CurvePoints<T>,
+    ) -> bool {
+        if !(cx.modifiers.contains(Modifiers::CTRL) || cx.modifiers.contains(Modifiers::LOGO)) {
+            return false;
+        }
+        match code {
+            Code::KeyC => self.copy(cx, points),
+            Code::KeyV => self.paste(cx),
+            _ => return false,
+        }
+        true
+    }
+}
+
+/// Adds copy and paste of the whole curve to a curve editor, through its
+/// context menu and the platform's copy and paste shortcuts
+pub trait CurveClipboardExt<T> {
+    /// Enables copy and paste. `callback` is called with the points pasted
+    /// from the clipboard, which the application stores in place of the
+    /// current points.
+    fn on_paste<F>(self, callback: F) -> Self
+    where
+        F: 'static + Fn(&mut EventContext, CurvePoints<T>);
+}
@@ -0,0 +1,84 @@
+//! Handling a burst of mouse moves once per frame.
+//!
+//! High polling rate mice report many moves between frames, and handling each
+//! of them runs a widget's change callback, and so a host's set-parameter
+//! call, several times for a frame that only shows the last. A view keeps a
+//! [`MoveCoalescer`], pushes the cursor of every `MouseMove` to it and handles
+//! the move once the [`CoalescedMove`] it sends to itself arrives, after the
+//! rest of the frame's events:
+//!
+//! ```ignore
+//! event.map(|ev: &WindowEvent, _| match *ev {
+//!     WindowEvent::MouseMove(x, y) => self.moves.push(cx, x, y),
+//!     WindowEvent::MouseUp(MouseButton::Left) => {
+//!         if let Some(cursor) = self.moves.take() {
+//!             self.drag_to(cx, cursor);
+//!         }
+//!         cx.release_active();
+//!     }
+//!     _ => (),
+//! });
+//! event.map(|_: &CoalescedMove, meta| {
+//!     if let Some(cursor) = self.moves.take() {
+//!         self.drag_to(cx, cursor);
+//!     }
+//!     meta.consume();
+//! });
+//! ```
+
+use glam::Vec2;
+use vizia::prelude::*;
+
+/// Sent by a view to itself to handle the last cursor pushed to its
+/// [`MoveCoalescer`]
+pub struct CoalescedMove;
+
+/// Keeps the last of a burst of mouse moves until the view handles it. A view
+/// using one only pushes the cursor in its `MouseMove` arm and handles the
+/// drag once a frame, when the [`CoalescedMove`] arrives.
+#[derive(Clone, Debug, Default)]
+pub struct MoveCoalescer {
+    pending: Option<Vec2>,
+}
+
+impl MoveCoalescer {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Remembers the cursor at `(x, y)`, replacing any move that wasn't
+    /// handled yet. The first move of a burst sends a [`CoalescedMove`] to the
+    /// current view, which is queued behind the moves already waiting.
+    pub fn push(&mut self, cx: &mut EventContext, x: f32, y: f32) {
+        if self.replace(Vec2::new(x, y)) {
+            cx.emit_to(cx.current(), CoalescedMove);
+        }
+    }
+
+    /// Remembers the cursor, returning whether it starts a new burst
+    fn replace(&mut self, cursor: Vec2) -> bool {
+        self.pending.replace(cursor).is_none()
+    }
+
+    /// Takes the last cursor pushed, if it wasn't handled yet. Views also call
+    /// this before handling a release so the drag ends where the cursor did.
+    pub fn take(&mut self) -> Option<Vec2> {
+        self.pending.take()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn keeps_the_last_move() {
+        let mut moves = MoveCoalescer::new();
+        assert!(moves.replace(Vec2::new(1f32, 1f32)));
+        assert!(!moves.replace(Vec2::new(2f32, 2f32)));
+        assert!(!moves.replace(Vec2::new(3f32, 1f32)));
+        assert_eq!(moves.take(), Some(Vec2::new(3f32, 1f32)));
+        assert_eq!(moves.take(), None);
+        assert!(moves.replace(Vec2::new(4f32, 1f32)));
+    }
+}
@@ -0,0 +1,241 @@
+//! Color interpolation for heatmaps, color maps and meter zones
+
+use vizia::prelude::*;
+
+/// Interpolation and alpha helpers for vizia's [`Color`]
+pub trait ColorExt {
+    /// Gets the color `t` of the way to `other`, mixing the sRGB channels
+    /// directly
+    fn lerp(&self, other: Color, t: f32) -> Color;
+    /// Gets the color `t` of the way to `other` through OKLab, which keeps
+    /// the brightness and hue of the steps in between even
+    fn lerp_oklab(&self, other: Color, t: f32) -> Color;
+    /// Gets the same color with a different alpha
+    fn with_alpha(&self, alpha: u8) -> Color;
+}
+
+impl ColorExt for Color {
+    fn lerp(&self, other: Color, t: f32) -> Color {
+        let t = t.clamp(0f32, 1f32);
+        let channel = |a: u8, b: u8| (a as f32 + (b as f32 - a as f32) * t).round() as u8;
+        Color::rgba(
+            channel(self.r(), other.r()),
+            channel(self.g(), other.g()),
+            channel(self.b(), other.b()),
+            channel(self.a(), other.a()),
+        )
+    }
+
+    fn lerp_oklab(&self, other: Color, t: f32) -> Color {
+        let t = t.clamp(0f32, 1f32);
+        let alpha = self.a() as f32 + (other.a() as f32 - self.a() as f32) * t;
+        Oklab::from(*self)
+            .lerp(Oklab::from(other), t)
+            .to_color(alpha.round() as u8)
+    }
+
+    fn with_alpha(&self, alpha: u8) -> Color {
+        Color::rgba(self.r(), self.g(), self.b(), alpha)
+    }
+}
+
+/// A color in the OKLab perceptual color space
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Oklab {
+    /// Lightness, from `0.0` for black to `1.0` for white
+    pub l: f32,
+    /// Green to red
+    pub a: f32,
+    /// Blue to yellow
+    pub b: f32,
+}
+
+impl Oklab {
+    pub fn lerp(&self, other: Oklab, t: f32) -> Oklab {
+        Oklab {
+            l: self.l + (other.l - self.l) * t,
+            a: self.a + (other.a - self.a) * t,
+            b: self.b + (other.b - self.b) * t,
+        }
+    }
+
+    /// Converts back to an sRGB color with the given alpha, clamping colors
+    /// outside of sRGB
+    pub fn to_color(&self, alpha: u8) -> Color {
+        let l = (self.l + 0.396_337_78 * self.a + 0.215_803_76 * self.b).powi(3);
+        let m = (self.l - 0.105_561_346 * self.a - 0.063_854_17 * self.b).powi(3);
+        let s = (self.l - 0.089_484_18 * self.a - 1.291_485_5 * self.b).powi(3);
+        let r = 4.076_741_7 * l - 3.307_711_6 * m + 0.230_969_94 * s;
+        let g = -1.268_438 * l + 2.609_757_4 * m - 0.341_319_38 * s;
+        let b = -0.004_196_086_3 * l - 0.703_418_6 * m + 1.707_614_7 * s;
+        Color::rgba(to_srgb(r), to_srgb(g), to_srgb(b), alpha)
+    }
+}
+
+impl From<Color> for Oklab {
+    fn from(color: Color) -> Self {
+        let (r, g, b) = (
+            to_linear(color.r()),
+            to_linear(color.g()),
+            to_linear(color.b()),
+        );
+        let l = (0.412_221_46 * r + 0.536_332_55 * g + 0.051_445_995 * b).cbrt();
+        let m = (0.211_903_5 * r + 0.680_699_5 * g + 0.107_396_96 * b).cbrt();
+        let s = (0.088_302_46 * r + 0.281_718_85 * g + 0.629_978_7 * b).cbrt();
+        Oklab {
+            l: 0.210_454_26 * l + 0.793_617_8 * m - 0.004_072_047 * s,
+            a: 1.977_998_5 * l - 2.428_592_2 * m + 0.450_593_7 * s,
+            b: 0.025_904_037 * l + 0.782_771_77 * m - 0.808_675_77 * s,
+        }
+    }
+}
+
+/// Converts an sRGB channel to linear light
+fn to_linear(channel: u8) -> f32 {
+    let c = channel as f32 / 255f32;
+    if c <= 0.04045f32 {
+        c / 12.92f32
+    } else {
+        ((c + 0.055f32) / 1.055f32).powf(2.4f32)
+    }
+}
+
+/// Converts linear light to an sRGB channel
+fn to_srgb(linear: f32) -> u8 {
+    let c = linear.clamp(0f32, 1f32);
+    let c = if c <= 0.003_130_8 {
+        c * 12.92f32
+    } else {
+        1.055f32 * c.powf(1f32 / 2.4f32) - 0.055f32
+    };
+    (c * 255f32).round() as u8
+}
+
+/// Colors along a line of stops, such as a spectrogram color map or the
+/// green to red zones of a meter
+#[derive(Clone, Debug, PartialEq)]
+pub struct Gradient {
+    /// Positions in `0..=1` with their colors, sorted by position
+    stops: Vec<(f32, Color)>,
+    /// Whether to interpolate between stops through OKLab
+    perceptual: bool,
+}
+
+impl Gradient {
+    /// Creates a gradient interpolating in sRGB. Stops can be given in any
+    /// order.
+    pub fn new(stops: impl IntoIterator<Item = (f32, Color)>) -> Self {
+        let mut stops: Vec<(f32, Color)> = stops.into_iter().collect();
+        stops.sort_by(|a, b| a.0.total_cmp(&b.0));
+        Self {
+            stops,
+            perceptual: false,
+        }
+    }
+
+    /// Interpolates between stops through OKLab instead of sRGB
+    pub fn perceptual(mut self) -> Self {
+        self.perceptual = true;
+        self
+    }
+
+    /// A dark blue through red to yellow heatmap, for spectrograms
+    pub fn heatmap() -> Self {
+        Self::new([
+            (0f32, Color::rgb(0x0b, 0x0b, 0x1e)),
+            (0.35f32, Color::rgb(0x5a, 0x1e, 0x8c)),
+            (0.65f32, Color::rgb(0xe0, 0x3c, 0x3c)),
+            (0.85f32, Color::rgb(0xf5, 0xa0, 0x2a)),
+            (1f32, Color::rgb(0xff, 0xf5, 0xb4)),
+        ])
+        .perceptual()
+    }
+
+    /// Gets the color at position `t`. Positions before the first stop or
+    /// after the last get the color of that stop.
+    pub fn at(&self, t: f32) -> Color {
+        let (first, last) = match (self.stops.first(), self.stops.last()) {
+            (Some(first), Some(last)) => (first, last),
+            _ => return Color::rgba(0, 0, 0, 0),
+        };
+        if t <= first.0 {
+            return first.1;
+        }
+        if t >= last.0 {
+            return last.1;
+        }
+        let index = self.stops.partition_point(|stop| stop.0 <= t);
+        let (start, end) = (self.stops[index - 1], self.stops[index]);
+        let t = (t - start.0) / (end.0 - start.0);
+        if self.perceptual {
+            start.1.lerp_oklab(end.1, t)
+        } else {
+            start.1.lerp(end.1, t)
+        }
+    }
+
+    /// Samples `count` evenly spaced colors, such as for a lookup table
+    pub fn samples(&self, count: usize) -> Vec<Color> {
+        (0..count)
+            .map(|i| self.at(i as f32 / (count.max(2) - 1) as f32))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn channels(color: Color) -> (u8, u8, u8, u8) {
+        (color.r(), color.g(), color.b(), color.a())
+    }
+
+    #[test]
+    fn lerps_srgb() {
+        let black = Color::rgb(0, 0, 0);
+        let white = Color::rgba(255, 255, 255, 0);
+        assert_eq!(channels(black.lerp(white, 0.5f32)), (128, 128, 128, 128));
+        assert_eq!(channels(black.lerp(white, 2f32)), (255, 255, 255, 0));
+        assert_eq!(channels(black.with_alpha(0x80)), (0, 0, 0, 0x80));
+    }
+
+    #[test]
+    fn round_trips_oklab() {
+        for color in [
+            Color::rgb(0xf5, 0x4e, 0x46),
+            Color::rgb(0x29, 0x27, 0x28),
+            Color::rgb(0, 0, 255),
+            Color::rgb(255, 255, 255),
+        ] {
+            assert_eq!(channels(Oklab::from(color).to_color(255)), channels(color));
+        }
+        let white = Oklab::from(Color::rgb(255, 255, 255));
+        assert!((white.l - 1f32).abs() < 1e-3f32);
+        assert!(white.a.abs() < 1e-3f32 && white.b.abs() < 1e-3f32);
+    }
+
+    #[test]
+    fn lerps_oklab() {
+        let black = Color::rgb(0, 0, 0);
+        let white = Color::rgb(255, 255, 255);
+        let middle = black.lerp_oklab(white, 0.5f32);
+        // Perceptual middle gray is darker than the sRGB middle
+        assert_eq!(middle.r(), middle.g());
+        assert!(middle.r() < 128 && middle.r() > 90);
+    }
+
+    #[test]
+    fn evaluates_gradients() {
+        let red = Color::rgb(255, 0, 0);
+        let green = Color::rgb(0, 255, 0);
+        let blue = Color::rgb(0, 0, 255);
+        let gradient = Gradient::new([(1f32, blue), (0f32, red), (0.5f32, green)]);
+        assert_eq!(channels(gradient.at(-1f32)), channels(red));
+        assert_eq!(channels(gradient.at(0.5f32)), channels(green));
+        assert_eq!(channels(gradient.at(0.75f32)), (0, 128, 128, 255));
+        assert_eq!(channels(gradient.at(2f32)), channels(blue));
+        let samples = Gradient::heatmap().samples(16);
+        assert_eq!(samples.len(), 16);
+        assert_eq!(channels(samples[15]), (0xff, 0xf5, 0xb4, 255));
+    }
+}
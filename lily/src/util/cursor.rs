@@ -0,0 +1,90 @@
+//! The mouse cursor shown over widgets
+
+use vizia::prelude::*;
+
+/// What can be done with the mouse where the cursor is, shown the same way
+/// by every widget
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Affordance {
+    /// Nothing in particular, shown with the default cursor
+    None,
+    /// A point or handle can be pressed and dragged, shown with a hand
+    Grab,
+    /// Something is being dragged
+    Grabbing,
+    /// Pressing anywhere picks a position, as on an XY pad, shown with a
+    /// crosshair
+    Pick,
+    /// The end of a range can be dragged sideways
+    ResizeHorizontal,
+}
+
+impl Affordance {
+    /// What a point or handle affords: grabbing while it's dragged, and a
+    /// grab while it's hovered
+    pub fn of_point(hovered: bool, dragging: bool) -> Self {
+        match (hovered, dragging) {
+            (_, true) => Affordance::Grabbing,
+            (true, false) => Affordance::Grab,
+            (false, false) => Affordance::None,
+        }
+    }
+
+    pub fn cursor(self) -> CursorIcon {
+        match self {
+            Affordance::None => CursorIcon::Default,
+            Affordance::Grab => CursorIcon::Hand,
+            Affordance::Grabbing => CursorIcon::Grabbing,
+            Affordance::Pick => CursorIcon::Crosshair,
+            Affordance::ResizeHorizontal => CursorIcon::EwResize,
+        }
+    }
+}
+
+/// Keeps the cursor in step with what a view affords, setting it only when
+/// that changes so views don't fight over the cursor.
+///
+/// The cursor shows the view's affordance while it's over the view, and while
+/// something is being dragged even when it leaves the view. Views call
+/// [`CursorAffordance::update`] at the end of `View::event` with what they
+/// afford at the cursor.
+#[derive(Clone, Debug)]
+pub struct CursorAffordance {
+    /// Whether the cursor is over the view
+    over: bool,
+    shown: Affordance,
+}
+
+impl Default for CursorAffordance {
+    fn default() -> Self {
+        Self {
+            over: false,
+            shown: Affordance::None,
+        }
+    }
+}
+
+impl CursorAffordance {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Follows the cursor into and out of the view, and shows `affordance`
+    /// if it changed
+    pub fn update(&mut self, cx: &mut EventContext, event: &mut Event, affordance: Affordance) {
+        event.map(|ev: &WindowEvent, _| match ev {
+            WindowEvent::MouseEnter => self.over = true,
+            WindowEvent::MouseLeave => self.over = false,
+            _ => (),
+        });
+        let affordance = if self.over || affordance == Affordance::Grabbing {
+            affordance
+        } else {
+            Affordance::None
+        };
+        if affordance != self.shown {
+            self.shown = affordance;
+            cx.emit(WindowEvent::SetCursor(affordance.cursor()));
+        }
+    }
+}
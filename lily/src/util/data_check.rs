@@ -0,0 +1,83 @@
+//! Checking the data a widget is bound to before drawing or editing it.
+//!
+//! Data from a host or a preset can break what a widget assumes about it, such
+//! as curve points that are unsorted or NaN. Rather than drawing it wrongly or
+//! editing the wrong point, the widget checks the data each time it changes
+//! and, while it's invalid, draws itself crossed out with [`draw_invalid`] and
+//! ignores input:
+//!
+//! ```ignore
+//! Graph { points: points.clone(), data_error: None }
+//!     .build(cx, |_| {})
+//!     .check_data(points, |points| points.validate(0f32..=1f32, 0f32..=1f32));
+//!
+//! // In `View::event`
+//! event.map(|checked: &DataChecked<CurveError>, _| {
+//!     self.data_error = checked.0;
+//!     cx.request_redraw();
+//! });
+//! ```
+
+use vizia::prelude::*;
+use vizia::vg;
+
+use super::FrameExt;
+
+/// The width in pixels of the cross drawn over invalid data
+const CROSS_WIDTH: f32 = 2f32;
+
+/// What is wrong with a widget's data, or `None` when it's valid. Sent to the
+/// widget by [`DataCheckExt::check_data`] when it changes.
+#[derive(Clone, Debug, PartialEq)]
+pub struct DataChecked<E>(pub Option<E>);
+
+impl<E: 'static + Clone + PartialEq> Data for DataChecked<E> {
+    fn same(&self, other: &Self) -> bool {
+        self == other
+    }
+}
+
+pub trait DataCheckExt {
+    /// Runs `check` on the target of `lens` whenever it changes, sending the
+    /// view a [`DataChecked`] each time the result changes, starting with the
+    /// data it's built with
+    fn check_data<L, E, F>(self, lens: L, check: F) -> Self
+    where
+        L: Lens,
+        E: 'static + Clone + PartialEq,
+        F: 'static + Fn(&L::Target) -> Result<(), E>;
+}
+
+impl<V: View> DataCheckExt for Handle<'_, V> {
+    fn check_data<L, E, F>(self, lens: L, check: F) -> Self
+    where
+        L: Lens,
+        E: 'static + Clone + PartialEq,
+        F: 'static + Fn(&L::Target) -> Result<(), E>,
+    {
+        let checked = lens.map(move |data| DataChecked(check(data).err()));
+        self.bind(checked, |handle, checked| {
+            let checked = checked.get(handle.cx);
+            let entity = handle.entity;
+            handle.cx.emit_to(entity, checked);
+        })
+    }
+}
+
+/// Draws a view whose data is invalid: its background crossed out from corner
+/// to corner in `color`, and its border and outline
+pub fn draw_invalid(cx: &mut DrawContext, canvas: &mut Canvas, color: Color) {
+    let bounds = cx.bounds();
+    cx.draw_background(canvas);
+    let mut cross = vg::Path::new();
+    cross.move_to(bounds.left(), bounds.top());
+    cross.line_to(bounds.right(), bounds.bottom());
+    cross.move_to(bounds.right(), bounds.top());
+    cross.line_to(bounds.left(), bounds.bottom());
+    canvas.stroke_path(
+        &mut cross,
+        &vg::Paint::color(color.into()).with_line_width(CROSS_WIDTH),
+    );
+    cx.draw_border(canvas);
+    cx.draw_outline(canvas);
+}
@@ -0,0 +1,171 @@
+//! The press, drag and release state shared by draggable views
+
+use glam::Vec2;
+use vizia::prelude::*;
+
+use super::PseudoClassExt;
+
+/// How much slower the cursor moves things while fine dragging by default
+const DEFAULT_FINE_SCALE: f32 = 0.1f32;
+
+/// Tracks a mouse drag from press to release, so views don't each keep their
+/// own capture state and deltas.
+///
+/// The gesture remembers a value of type `T` from when it was pressed, such as
+/// the position of the point being dragged, which is handed back if the drag
+/// is cancelled so the view can restore it. Holding shift fine drags, scaling
+/// down the cursor's movement without jumping when it's pressed or released
+/// mid drag.
+#[derive(Clone, Debug)]
+pub struct DragGesture<T = ()> {
+    /// The movement scale while fine dragging
+    fine_scale: f32,
+    state: Option<DragState<T>>,
+}
+
+#[derive(Clone, Debug)]
+struct DragState<T> {
+    start: T,
+    /// Where the cursor was pressed
+    origin: Vec2,
+    /// The cursor position as last reported
+    cursor: Vec2,
+    /// The dragged position, which lags behind the cursor while fine dragging
+    position: Vec2,
+}
+
+/// The movement of a [`DragGesture`] after the cursor moved
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct DragDelta {
+    /// The dragged position, which matches the cursor unless fine dragging
+    pub position: Vec2,
+    /// The movement since the last update
+    pub delta: Vec2,
+    /// The movement since the press
+    pub total: Vec2,
+}
+
+impl<T> Default for DragGesture<T> {
+    fn default() -> Self {
+        Self {
+            fine_scale: DEFAULT_FINE_SCALE,
+            state: None,
+        }
+    }
+}
+
+impl<T> DragGesture<T> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets how much slower the cursor moves things while fine dragging
+    pub fn with_fine_scale(mut self, fine_scale: f32) -> Self {
+        self.fine_scale = fine_scale;
+        self
+    }
+
+    /// Starts a drag at the cursor and captures the mouse, marking the view
+    /// `:active` and remembering the `start` value to hand back if the drag is
+    /// cancelled
+    pub fn begin(&mut self, cx: &mut EventContext, start: T) {
+        cx.capture_active();
+        self.begin_at((cx.mouse.cursorx, cx.mouse.cursory).into(), start);
+    }
+
+    /// Starts a drag at `origin` without capturing the mouse
+    pub fn begin_at(&mut self, origin: Vec2, start: T) {
+        self.state = Some(DragState {
+            start,
+            origin,
+            cursor: origin,
+            position: origin,
+        });
+    }
+
+    /// Moves the drag to the cursor at `(x, y)`, fine dragging while shift is
+    /// held. Returns `None` when not dragging.
+    pub fn update(&mut self, cx: &EventContext, x: f32, y: f32) -> Option<DragDelta> {
+        let fine = cx.modifiers.contains(Modifiers::SHIFT);
+        self.update_to((x, y).into(), fine)
+    }
+
+    /// Moves the drag to the cursor at `cursor`, scaling the movement down if
+    /// `fine`. Returns `None` when not dragging.
+    pub fn update_to(&mut self, cursor: Vec2, fine: bool) -> Option<DragDelta> {
+        let scale = if fine { self.fine_scale } else { 1f32 };
+        let state = self.state.as_mut()?;
+        let delta = (cursor - state.cursor) * scale;
+        state.cursor = cursor;
+        state.position += delta;
+        Some(DragDelta {
+            position: state.position,
+            delta,
+            total: state.position - state.origin,
+        })
+    }
+
+    /// Ends the drag and releases the mouse, returning whether there was one
+    pub fn end(&mut self, cx: &mut EventContext) -> bool {
+        let was_dragging = self.state.take().is_some();
+        if was_dragging {
+            cx.release_active();
+        }
+        was_dragging
+    }
+
+    /// Abandons the drag and releases the mouse, handing back the value it
+    /// started with so the view can restore it
+    pub fn cancel(&mut self, cx: &mut EventContext) -> Option<T> {
+        let state = self.state.take()?;
+        cx.release_active();
+        Some(state.start)
+    }
+
+    pub fn is_dragging(&self) -> bool {
+        self.state.is_some()
+    }
+
+    /// Where the cursor was pressed, while dragging
+    pub fn origin(&self) -> Option<Vec2> {
+        self.state.as_ref().map(|state| state.origin)
+    }
+
+    /// The value the drag started with, while dragging
+    pub fn start(&self) -> Option<&T> {
+        self.state.as_ref().map(|state| &state.start)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tracks_deltas() {
+        let mut gesture = DragGesture::new();
+        assert_eq!(gesture.update_to(Vec2::ONE, false), None);
+        gesture.begin_at(Vec2::new(10f32, 10f32), 0.5f32);
+        assert_eq!(gesture.origin(), Some(Vec2::new(10f32, 10f32)));
+        let drag = gesture.update_to(Vec2::new(15f32, 20f32), false).unwrap();
+        assert_eq!(drag.position, Vec2::new(15f32, 20f32));
+        assert_eq!(drag.delta, Vec2::new(5f32, 10f32));
+        let drag = gesture.update_to(Vec2::new(20f32, 20f32), false).unwrap();
+        assert_eq!(drag.delta, Vec2::new(5f32, 0f32));
+        assert_eq!(drag.total, Vec2::new(10f32, 10f32));
+        assert_eq!(gesture.start(), Some(&0.5f32));
+    }
+
+    #[test]
+    fn scales_fine_drags() {
+        let mut gesture = DragGesture::new().with_fine_scale(0.5f32);
+        gesture.begin_at(Vec2::ZERO, ());
+        gesture.update_to(Vec2::new(10f32, 0f32), false);
+        let drag = gesture.update_to(Vec2::new(20f32, 0f32), true).unwrap();
+        assert_eq!(drag.position, Vec2::new(15f32, 0f32));
+        // Letting go of fine dragging doesn't jump to the cursor
+        let drag = gesture.update_to(Vec2::new(22f32, 0f32), false).unwrap();
+        assert_eq!(drag.position, Vec2::new(17f32, 0f32));
+        assert_eq!(drag.total, Vec2::new(17f32, 0f32));
+    }
+}
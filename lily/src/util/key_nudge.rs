@@ -0,0 +1,114 @@
+//! Editing values from the keyboard
+
+use glam::Vec2;
+use vizia::prelude::*;
+
+/// The normalized step of the arrow keys
+const ARROW_STEP: f32 = 0.01f32;
+/// The normalized step of the page up and page down keys
+const PAGE_STEP: f32 = 0.1f32;
+/// How much smaller steps are while shift is held
+const FINE_SCALE: f32 = 0.1f32;
+
+/// How a key press moves a value edited from the keyboard, so every widget
+/// answers to the same keys. The arrow keys step, page up and page down take
+/// larger steps and home and end go to the ends of the range. Holding shift
+/// takes finer steps, like fine dragging.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum KeyNudge {
+    /// A normalized step, along x for the left and right keys and along y for
+    /// the others, with up and right being positive
+    Step(Vec2),
+    /// The home key
+    Start,
+    /// The end key
+    End,
+}
+
+impl KeyNudge {
+    /// The nudge of a key pressed on a view, fine while shift is held
+    pub fn from_event(cx: &EventContext, code: Code) -> Option<Self> {
+        Self::from_key(code, cx.modifiers.contains(Modifiers::SHIFT))
+    }
+
+    /// The nudge of a key, or `None` if the key doesn't edit values
+    pub fn from_key(code: Code, fine: bool) -> Option<Self> {
+        let step = match code {
+            Code::ArrowLeft => Vec2::new(-ARROW_STEP, 0f32),
+            Code::ArrowRight => Vec2::new(ARROW_STEP, 0f32),
+            Code::ArrowDown => Vec2::new(0f32, -ARROW_STEP),
+            Code::ArrowUp => Vec2::new(0f32, ARROW_STEP),
+            Code::PageDown => Vec2::new(0f32, -PAGE_STEP),
+            Code::PageUp => Vec2::new(0f32, PAGE_STEP),
+            Code::Home => return Some(Self::Start),
+            Code::End => return Some(Self::End),
+            _ => return None,
+        };
+        let scale = if fine { FINE_SCALE } else { 1f32 };
+        Some(Self::Step(step * scale))
+    }
+
+    /// Moves a normalized value, which the up and right keys increase
+    pub fn apply(&self, normalized: f32) -> f32 {
+        match *self {
+            Self::Step(step) => (normalized + step.x + step.y).clamp(0f32, 1f32),
+            Self::Start => 0f32,
+            Self::End => 1f32,
+        }
+    }
+
+    /// Moves a point within the area from `bottom_left` to `top_right`, such
+    /// as the bounds of a view, which may be reversed like UI coordinates
+    /// are. Steps are relative to the size of the area, and home and end move
+    /// the point to the left and right edges.
+    pub fn apply_point(&self, point: Vec2, bottom_left: Vec2, top_right: Vec2) -> Vec2 {
+        let (min, max) = (bottom_left.min(top_right), bottom_left.max(top_right));
+        match *self {
+            Self::Step(step) => (point + step * (top_right - bottom_left)).clamp(min, max),
+            Self::Start => Vec2::new(bottom_left.x, point.y),
+            Self::End => Vec2::new(top_right.x, point.y),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use assert_approx_eq::assert_approx_eq;
+
+    #[test]
+    fn steps_values() {
+        let up = KeyNudge::from_key(Code::ArrowUp, false).unwrap();
+        assert_approx_eq!(up.apply(0.5f32), 0.51f32);
+        let fine_left = KeyNudge::from_key(Code::ArrowLeft, true).unwrap();
+        assert_approx_eq!(fine_left.apply(0.5f32), 0.499f32);
+        let page_down = KeyNudge::from_key(Code::PageDown, false).unwrap();
+        assert_approx_eq!(page_down.apply(0.05f32), 0f32);
+        assert_eq!(
+            KeyNudge::from_key(Code::End, false).unwrap().apply(0.2f32),
+            1f32
+        );
+        assert_eq!(KeyNudge::from_key(Code::KeyA, false), None);
+    }
+
+    #[test]
+    fn steps_points_in_ui_coordinates() {
+        // A 100 by 100 view at the origin, where y grows downwards
+        let (bottom_left, top_right) = (Vec2::new(0f32, 100f32), Vec2::new(100f32, 0f32));
+        let point = Vec2::new(50f32, 50f32);
+        let up = KeyNudge::from_key(Code::ArrowUp, false).unwrap();
+        assert_eq!(
+            up.apply_point(point, bottom_left, top_right),
+            Vec2::new(50f32, 49f32)
+        );
+        let right = KeyNudge::Step(Vec2::new(1f32, 0f32));
+        assert_eq!(
+            right.apply_point(point, bottom_left, top_right),
+            Vec2::new(100f32, 50f32)
+        );
+        assert_eq!(
+            KeyNudge::Start.apply_point(point, bottom_left, top_right),
+            Vec2::new(0f32, 50f32)
+        );
+    }
+}
@@ -1,4 +1,34 @@
-mod curve_point;
-mod extensions;
+mod capture;
+mod clipboard;
+mod coalesce;
+mod color;
+mod cursor;
+mod data_check;
+mod drag_gesture;
+mod key_nudge;
+mod modulation;
+mod path_batch;
+#[cfg(feature = "ring-buffer")]
+mod ring_buffer;
+mod rotary_drag;
+#[cfg(feature = "scala")]
+mod scala;
+mod shared;
+mod source;
+mod ticker;
+mod ticks;
 mod vizia_extensions;
-pub use {curve_point::*, extensions::*, vizia_extensions::*};
+#[cfg(feature = "ring-buffer")]
+pub use ring_buffer::*;
+#[cfg(feature = "scala")]
+pub use scala::*;
+pub use lily_core::{
+    curve_shape, Ballistics, BeatGrid, CurveError, CurveKind, CurvePoint, CurvePoints,
+    DetentMapping, LogMapping, MeterChannels, OverDetector, Peak, PeakPyramid, PeakTiles, PointTag,
+    PowerMapping, RangeExt, SteppedMapping, ValueMapping, MAX_CURVE,
+};
+pub use {
+    capture::*, clipboard::*, coalesce::*, color::*, cursor::*, data_check::*, drag_gesture::*,
+    key_nudge::*, modulation::*, path_batch::*, rotary_drag::*, shared::*, source::*, ticker::*,
+    ticks::*, vizia_extensions::*,
+};
@@ -0,0 +1,111 @@
+//! The modulation of a control, drawn on top of its base value
+
+use glam::Vec2;
+use vizia::prelude::*;
+
+/// How a modulation source moves a control away from the value the user set.
+/// Amounts are relative to that base value and in the control's normalized
+/// units, so `Offset(0.25)` on a slider at `0.5` shows a live value of `0.75`.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum Modulation<T = f32> {
+    /// Nothing is drawn
+    #[default]
+    None,
+    /// The live modulated value is the base value moved by the offset
+    Offset(T),
+    /// The modulation sweeps the base value between `min` and `max`, such as
+    /// an LFO's depth
+    Range { min: T, max: T },
+}
+
+impl<T: 'static + Clone + PartialEq> Data for Modulation<T> {
+    fn same(&self, other: &Self) -> bool {
+        self == other
+    }
+}
+
+impl Modulation<f32> {
+    /// The normalized span covered by the modulation of `base`, clamped to
+    /// `0..=1`
+    pub fn span(&self, base: f32) -> Option<(f32, f32)> {
+        let (a, b) = match *self {
+            Modulation::None => return None,
+            Modulation::Offset(offset) => (base, base + offset),
+            Modulation::Range { min, max } => (base + min, base + max),
+        };
+        Some((a.min(b).clamp(0f32, 1f32), a.max(b).clamp(0f32, 1f32)))
+    }
+
+    /// The live modulated value of `base`, if the modulation is an offset
+    pub fn live(&self, base: f32) -> Option<f32> {
+        match *self {
+            Modulation::Offset(offset) => Some((base + offset).clamp(0f32, 1f32)),
+            _ => None,
+        }
+    }
+}
+
+impl Modulation<Vec2> {
+    /// The corners of the area covered by the modulation of `base`, clamped
+    /// to `(-1,-1)..=(1,1)`
+    pub fn span(&self, base: Vec2) -> Option<(Vec2, Vec2)> {
+        let (a, b) = match *self {
+            Modulation::None => return None,
+            Modulation::Offset(offset) => (base, base + offset),
+            Modulation::Range { min, max } => (base + min, base + max),
+        };
+        let clamp = |v: Vec2| v.clamp(Vec2::splat(-1f32), Vec2::splat(1f32));
+        Some((clamp(a.min(b)), clamp(a.max(b))))
+    }
+
+    /// The live modulated position of `base`, if the modulation is an offset
+    pub fn live(&self, base: Vec2) -> Option<Vec2> {
+        match *self {
+            Modulation::Offset(offset) => {
+                Some((base + offset).clamp(Vec2::splat(-1f32), Vec2::splat(1f32)))
+            }
+            _ => None,
+        }
+    }
+}
+
+/// Shows the live modulation of a control on top of its base value
+pub trait ModulationExt<T> {
+    /// Binds the modulation drawn on the control to a [`Lens`], which the
+    /// application updates as its modulation sources move
+    fn modulation<L>(self, modulation: L) -> Self
+    where
+        L: Lens<Target = Modulation<T>>;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use assert_approx_eq::assert_approx_eq;
+
+    #[test]
+    fn spans_offsets_and_ranges() {
+        assert_eq!(Modulation::<f32>::None.span(0.5f32), None);
+        let (start, end) = Modulation::Offset(-0.25f32).span(0.5f32).unwrap();
+        assert_approx_eq!(start, 0.25f32);
+        assert_approx_eq!(end, 0.5f32);
+        let range = Modulation::Range {
+            min: -0.2f32,
+            max: 0.6f32,
+        };
+        let (start, end) = range.span(0.5f32).unwrap();
+        assert_approx_eq!(start, 0.3f32);
+        assert_approx_eq!(end, 1f32);
+        assert_eq!(range.live(0.5f32), None);
+        assert_eq!(Modulation::Offset(0.75f32).live(0.5f32), Some(1f32));
+    }
+
+    #[test]
+    fn spans_points() {
+        let modulation = Modulation::Offset(Vec2::new(0.5f32, -2f32));
+        let (min, max) = modulation.span(Vec2::new(0.75f32, 0f32)).unwrap();
+        assert_eq!(min, Vec2::new(0.75f32, -1f32));
+        assert_eq!(max, Vec2::new(1f32, 0f32));
+        assert_eq!(modulation.live(Vec2::ZERO), Some(Vec2::new(0.5f32, -1f32)));
+    }
+}
@@ -0,0 +1,48 @@
+//! Drawing many markers with few draw calls
+
+use vizia::prelude::*;
+use vizia::vg;
+
+/// Collects shapes into one path per color, so that many markers, such as
+/// the points of a graph, are filled or stroked with one draw call per color
+/// instead of one per marker
+#[derive(Default)]
+pub struct PathBatch {
+    paths: Vec<(Color, vg::Path)>,
+}
+
+impl PathBatch {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Gets the path of the shapes drawn in `color`. Shapes added to it are
+    /// drawn with the rest of the batch.
+    pub fn path(&mut self, color: Color) -> &mut vg::Path {
+        let index = match self.paths.iter().position(|(c, _)| *c == color) {
+            Some(index) => index,
+            None => {
+                self.paths.push((color, vg::Path::new()));
+                self.paths.len() - 1
+            }
+        };
+        &mut self.paths[index].1
+    }
+
+    /// Fills the shapes of each color in one draw call
+    pub fn fill(&mut self, canvas: &mut Canvas) {
+        for (color, path) in &mut self.paths {
+            canvas.fill_path(path, &vg::Paint::color((*color).into()));
+        }
+    }
+
+    /// Strokes the shapes of each color in one draw call
+    pub fn stroke(&mut self, canvas: &mut Canvas, line_width: f32) {
+        for (color, path) in &mut self.paths {
+            canvas.stroke_path(
+                path,
+                &vg::Paint::color((*color).into()).with_line_width(line_width),
+            );
+        }
+    }
+}
@@ -0,0 +1,238 @@
+//! A lock-free single producer, single consumer ring buffer for streaming
+//! samples or levels from the audio thread to the GUI.
+//!
+//! Pushing never allocates, locks or blocks, so the [`Producer`] is safe to use
+//! on the audio thread. The [`Consumer`] lives on the GUI side, where it can be
+//! drained into the histories shown by meters, scopes and analyzers, either by
+//! hand or as a [`ScopeSource`].
+
+use std::cell::UnsafeCell;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+
+use super::{MeterSource, ScopeSource};
+
+struct Inner<T> {
+    /// One more slot than the capacity, so a full buffer can be told apart
+    /// from an empty one
+    slots: Box<[UnsafeCell<T>]>,
+    /// The index of the next slot to read, only written by the consumer
+    head: AtomicUsize,
+    /// The index of the next slot to write, only written by the producer
+    tail: AtomicUsize,
+}
+
+// The producer only writes slots the consumer has finished reading and the
+// consumer only reads slots the producer has finished writing, which the
+// acquire and release orderings on `head` and `tail` guarantee.
+unsafe impl<T: Send> Sync for Inner<T> {}
+
+impl<T> Inner<T> {
+    fn next(&self, index: usize) -> usize {
+        (index + 1) % self.slots.len()
+    }
+
+    fn len(&self) -> usize {
+        let head = self.head.load(Ordering::Acquire);
+        let tail = self.tail.load(Ordering::Acquire);
+        (tail + self.slots.len() - head) % self.slots.len()
+    }
+}
+
+/// Creates a ring buffer holding up to `capacity` values, returning its two
+/// ends
+pub fn ring_buffer<T: Copy + Default>(capacity: usize) -> (Producer<T>, Consumer<T>) {
+    let slots = (0..capacity + 1)
+        .map(|_| UnsafeCell::new(T::default()))
+        .collect();
+    let inner = Arc::new(Inner {
+        slots,
+        head: AtomicUsize::new(0),
+        tail: AtomicUsize::new(0),
+    });
+    (
+        Producer {
+            inner: inner.clone(),
+        },
+        Consumer { inner },
+    )
+}
+
+/// The writing end of a ring buffer, for the audio thread
+pub struct Producer<T> {
+    inner: Arc<Inner<T>>,
+}
+
+impl<T: Copy> Producer<T> {
+    /// Pushes a value, handing it back if the buffer is full
+    pub fn push(&mut self, value: T) -> Result<(), T> {
+        let tail = self.inner.tail.load(Ordering::Relaxed);
+        let next = self.inner.next(tail);
+        if next == self.inner.head.load(Ordering::Acquire) {
+            return Err(value);
+        }
+        // Safety: the consumer never reads the slot at `tail` until we
+        // publish it below
+        unsafe { *self.inner.slots[tail].get() = value };
+        self.inner.tail.store(next, Ordering::Release);
+        Ok(())
+    }
+
+    /// Pushes as many values as fit and returns how many were pushed. Values
+    /// that don't fit are dropped, so a stalled GUI never holds up the audio
+    /// thread.
+    pub fn push_slice(&mut self, values: &[T]) -> usize {
+        values
+            .iter()
+            .take_while(|value| self.push(**value).is_ok())
+            .count()
+    }
+
+    /// The number of values waiting to be read
+    pub fn len(&self) -> usize {
+        self.inner.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.inner.slots.len() - 1
+    }
+}
+
+/// The reading end of a ring buffer, for the GUI
+pub struct Consumer<T> {
+    inner: Arc<Inner<T>>,
+}
+
+impl<T: Copy> Consumer<T> {
+    /// Pops the oldest value, if any
+    pub fn pop(&mut self) -> Option<T> {
+        let head = self.inner.head.load(Ordering::Relaxed);
+        if head == self.inner.tail.load(Ordering::Acquire) {
+            return None;
+        }
+        // Safety: the producer never writes the slot at `head` until we
+        // release it below
+        let value = unsafe { *self.inner.slots[head].get() };
+        self.inner
+            .head
+            .store(self.inner.next(head), Ordering::Release);
+        Some(value)
+    }
+
+    /// Pops every value currently waiting, oldest first
+    pub fn drain(&mut self) -> impl Iterator<Item = T> + '_ {
+        std::iter::from_fn(move || self.pop())
+    }
+
+    /// Appends every waiting value to `history`, then drops the oldest values
+    /// so at most `max_len` remain. This suits the level histories used by
+    /// widgets like the `ThresholdScope`.
+    pub fn drain_into(&mut self, history: &mut Vec<T>, max_len: usize) {
+        history.extend(self.drain());
+        if history.len() > max_len {
+            history.drain(..history.len() - max_len);
+        }
+    }
+
+    /// The number of values waiting to be read
+    pub fn len(&self) -> usize {
+        self.inner.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+impl<T: Copy + Send> ScopeSource for Consumer<T> {
+    type Sample = T;
+
+    fn drain_samples(&mut self, samples: &mut Vec<T>) {
+        samples.extend(self.drain());
+    }
+}
+
+/// Read as a meter, a ring buffer gives the newest value waiting and skips the
+/// others
+impl<T: Copy + Send> MeterSource for Consumer<T> {
+    type Value = T;
+
+    fn latest(&mut self) -> Option<T> {
+        self.drain().last()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn pushes_and_pops_in_order() {
+        let (mut producer, mut consumer) = ring_buffer::<f32>(4);
+        assert_eq!(producer.capacity(), 4);
+        assert!(consumer.pop().is_none());
+        for i in 0..4 {
+            producer.push(i as f32).unwrap();
+        }
+        assert_eq!(producer.push(4f32), Err(4f32));
+        assert_eq!(consumer.len(), 4);
+        assert_eq!(consumer.pop(), Some(0f32));
+        producer.push(4f32).unwrap();
+        let values: Vec<f32> = consumer.drain().collect();
+        assert_eq!(values, vec![1f32, 2f32, 3f32, 4f32]);
+        assert!(consumer.is_empty());
+    }
+
+    #[test]
+    fn drops_values_that_dont_fit() {
+        let (mut producer, mut consumer) = ring_buffer::<u32>(3);
+        assert_eq!(producer.push_slice(&[1, 2, 3, 4, 5]), 3);
+        assert_eq!(consumer.drain().collect::<Vec<_>>(), vec![1, 2, 3]);
+    }
+
+    #[test]
+    fn drains_into_history() {
+        let (mut producer, mut consumer) = ring_buffer::<u32>(8);
+        let mut history = vec![0, 1, 2];
+        producer.push_slice(&[3, 4, 5]);
+        consumer.drain_into(&mut history, 4);
+        assert_eq!(history, vec![2, 3, 4, 5]);
+    }
+
+    #[test]
+    fn reads_as_a_source() {
+        let (mut producer, mut consumer) = ring_buffer::<u32>(8);
+        producer.push_slice(&[1, 2]);
+        let mut samples = vec![0];
+        consumer.drain_samples(&mut samples);
+        assert_eq!(samples, vec![0, 1, 2]);
+        producer.push_slice(&[3, 4]);
+        assert_eq!(consumer.latest(), Some(4));
+        assert_eq!(consumer.latest(), None);
+    }
+
+    #[test]
+    fn streams_between_threads() {
+        let (mut producer, mut consumer) = ring_buffer::<u32>(16);
+        let writer = std::thread::spawn(move || {
+            let mut next = 0;
+            while next < 1000 {
+                if producer.push(next).is_ok() {
+                    next += 1;
+                }
+            }
+        });
+        let mut expected = 0;
+        while expected < 1000 {
+            if let Some(value) = consumer.pop() {
+                assert_eq!(value, expected);
+                expected += 1;
+            }
+        }
+        writer.join().unwrap();
+    }
+}
@@ -0,0 +1,121 @@
+//! How dragging turns rotary controls
+
+use glam::Vec2;
+use std::f32::consts::{PI, TAU};
+use vizia::prelude::*;
+
+/// How many pixels of vertical drag cover the whole range by default
+const DEFAULT_DRAG_LENGTH: f32 = 200f32;
+/// The distance in pixels from the center of a control within which circular
+/// drags are ignored, since the angle of the cursor swings wildly there
+const DEAD_ZONE: f32 = 4f32;
+
+/// How dragging a rotary control such as a knob changes its value. Users are
+/// split on which they expect, so controls take the mode per instance or from
+/// a lens to a user preference.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Data)]
+pub enum RotaryDrag {
+    /// Dragging up turns the control up and dragging down turns it down, the
+    /// same whichever side of the control the cursor is on
+    #[default]
+    Vertical,
+    /// Dragging around the control turns it with the cursor, from wherever it
+    /// was rather than jumping to the cursor's angle
+    Circular,
+}
+
+impl RotaryDrag {
+    /// Gets how much the normalized value changes as the cursor moves from
+    /// `from` to `to`, for a control centered at `center` whose range sweeps
+    /// `sweep` radians clockwise. Vertical drags cover the whole range over
+    /// `drag_length` pixels.
+    pub fn delta(&self, center: Vec2, from: Vec2, to: Vec2, sweep: f32, drag_length: f32) -> f32 {
+        match self {
+            RotaryDrag::Vertical => (from.y - to.y) / drag_length,
+            RotaryDrag::Circular => {
+                let (from, to) = (from - center, to - center);
+                if from.length() < DEAD_ZONE || to.length() < DEAD_ZONE || sweep == 0f32 {
+                    return 0f32;
+                }
+                // The y axis points down, so the angle grows clockwise, and
+                // crossing the far side of the circle wraps around
+                let turn = (to.y.atan2(to.x) - from.y.atan2(from.x) + PI).rem_euclid(TAU) - PI;
+                turn / sweep
+            }
+        }
+    }
+
+    /// Gets how much the normalized value changes with [`RotaryDrag::delta`],
+    /// covering the whole range over the default length of vertical drags
+    pub fn default_delta(&self, center: Vec2, from: Vec2, to: Vec2, sweep: f32) -> f32 {
+        self.delta(center, from, to, sweep, DEFAULT_DRAG_LENGTH)
+    }
+}
+
+/// Lets controls take a fixed mode where they also take a lens to one
+impl Res<RotaryDrag> for RotaryDrag {
+    fn get_val(&self, _: &Context) -> RotaryDrag {
+        *self
+    }
+
+    fn set_or_bind<F>(&self, cx: &mut Context, entity: Entity, closure: F)
+    where
+        F: 'static + Clone + Fn(&mut Context, Entity, RotaryDrag),
+    {
+        (closure)(cx, entity, *self);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use assert_approx_eq::assert_approx_eq;
+
+    #[test]
+    fn drags_vertically() {
+        let drag = RotaryDrag::Vertical;
+        let (center, sweep) = (Vec2::ZERO, 1.5f32 * PI);
+        let up = drag.delta(
+            center,
+            Vec2::new(50f32, 0f32),
+            Vec2::new(-50f32, -50f32),
+            sweep,
+            100f32,
+        );
+        assert_approx_eq!(up, 0.5f32);
+        assert_approx_eq!(
+            drag.default_delta(center, Vec2::ZERO, Vec2::new(0f32, 100f32), sweep),
+            -0.5f32
+        );
+    }
+
+    #[test]
+    fn drags_around_the_center() {
+        let drag = RotaryDrag::Circular;
+        let (center, sweep) = (Vec2::new(10f32, 10f32), 1.5f32 * PI);
+        // A quarter turn clockwise, from the top to the right
+        let right = drag.delta(
+            center,
+            Vec2::new(10f32, 0f32),
+            Vec2::new(20f32, 10f32),
+            sweep,
+            1f32,
+        );
+        assert_approx_eq!(right, 1f32 / 3f32);
+        // Going up past the left of the center, where the angle wraps
+        // around, turns a little clockwise
+        let across = drag.delta(
+            center,
+            Vec2::new(-10f32, 11f32),
+            Vec2::new(-10f32, 9f32),
+            sweep,
+            1f32,
+        );
+        assert!(across > 0f32 && across < 0.1f32);
+        // Drags through the center are ignored
+        assert_eq!(
+            drag.delta(center, center, Vec2::new(20f32, 10f32), sweep, 1f32),
+            0f32
+        );
+    }
+}
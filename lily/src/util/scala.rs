@@ -0,0 +1,165 @@
+//! Parsing of [Scala](https://www.huygens-fokker.org/scala/scl_format.html)
+//! `.scl` tuning files
+
+use std::fmt;
+
+/// A scale read from a Scala `.scl` file
+#[derive(Clone, Debug, PartialEq)]
+pub struct ScalaScale {
+    /// The description line of the file
+    pub description: String,
+    /// The pitch of every degree after the first in cents, as listed in the
+    /// file. The last pitch is the period of the scale, usually `1200.0`.
+    pub pitches: Vec<f32>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ScalaError {
+    /// The file ended before the description and note count were read
+    MissingHeader,
+    /// The note count line could not be parsed
+    InvalidNoteCount(String),
+    /// A pitch line could not be parsed as cents or a ratio
+    InvalidPitch(String),
+    /// The number of pitches did not match the note count
+    WrongNoteCount { expected: usize, found: usize },
+}
+
+impl fmt::Display for ScalaError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ScalaError::MissingHeader => write!(f, "missing description or note count"),
+            ScalaError::InvalidNoteCount(line) => write!(f, "invalid note count `{line}`"),
+            ScalaError::InvalidPitch(line) => write!(f, "invalid pitch `{line}`"),
+            ScalaError::WrongNoteCount { expected, found } => {
+                write!(f, "expected {expected} pitches but found {found}")
+            }
+        }
+    }
+}
+
+impl std::error::Error for ScalaError {}
+
+impl ScalaScale {
+    /// Parse the contents of a `.scl` file
+    pub fn parse(text: &str) -> Result<Self, ScalaError> {
+        // Lines starting with `!` are comments
+        let mut lines = text.lines().filter(|line| !line.starts_with('!'));
+        let description = lines.next().ok_or(ScalaError::MissingHeader)?.trim();
+        let count_line = lines.next().ok_or(ScalaError::MissingHeader)?.trim();
+        let count = first_word(count_line)
+            .parse::<usize>()
+            .map_err(|_| ScalaError::InvalidNoteCount(count_line.to_owned()))?;
+
+        let pitches = lines
+            .map(str::trim)
+            .filter(|line| !line.is_empty())
+            .map(parse_pitch)
+            .collect::<Result<Vec<f32>, _>>()?;
+        if pitches.len() != count {
+            return Err(ScalaError::WrongNoteCount {
+                expected: count,
+                found: pitches.len(),
+            });
+        }
+
+        Ok(Self {
+            description: description.to_owned(),
+            pitches,
+        })
+    }
+
+    /// The interval in cents after which the scale repeats
+    pub fn period(&self) -> f32 {
+        self.pitches.last().copied().unwrap_or(1200f32)
+    }
+
+    /// Gets the offset in cents of each degree from an equal division of the
+    /// period with the same number of degrees. For a 12 note octave scale,
+    /// these are the offsets from 12-TET. The first degree is always `0.0`.
+    pub fn cents_offsets(&self) -> Vec<f32> {
+        let degrees = self.pitches.len().max(1);
+        let step = self.period() / degrees as f32;
+        std::iter::once(0f32)
+            .chain(self.pitches.iter().copied())
+            .take(degrees)
+            .enumerate()
+            .map(|(i, cents)| cents - step * i as f32)
+            .collect()
+    }
+}
+
+fn first_word(line: &str) -> &str {
+    line.split_whitespace().next().unwrap_or_default()
+}
+
+/// Parses a pitch line. Values containing a `.` are cents, anything else is a
+/// ratio such as `3/2` or `2`.
+fn parse_pitch(line: &str) -> Result<f32, ScalaError> {
+    let invalid = || ScalaError::InvalidPitch(line.to_owned());
+    let value = first_word(line);
+    if value.contains('.') {
+        return value.parse::<f32>().map_err(|_| invalid());
+    }
+    let (numerator, denominator) = value.split_once('/').unwrap_or((value, "1"));
+    let numerator = numerator.parse::<f64>().map_err(|_| invalid())?;
+    let denominator = denominator.parse::<f64>().map_err(|_| invalid())?;
+    if numerator <= 0f64 || denominator <= 0f64 {
+        return Err(invalid());
+    }
+    Ok((1200f64 * (numerator / denominator).log2()) as f32)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use assert_approx_eq::assert_approx_eq;
+
+    const PENTATONIC: &str = "! pentatonic.scl
+!
+Just pentatonic
+ 5
+!
+ 9/8
+ 5/4
+ 3/2
+ 5/3
+ 2/1
+";
+
+    #[test]
+    fn parses_ratios() {
+        let scale = ScalaScale::parse(PENTATONIC).unwrap();
+        assert_eq!(scale.description, "Just pentatonic");
+        assert_eq!(scale.pitches.len(), 5);
+        assert_approx_eq!(scale.pitches[2], 701.955, 1e-3);
+        assert_approx_eq!(scale.period(), 1200f32, 1e-3);
+    }
+
+    #[test]
+    fn parses_cents_with_comments() {
+        let scale = ScalaScale::parse("12-TET\n 2\n600.0 tritone\n1200.0\n").unwrap();
+        assert_eq!(scale.pitches, vec![600f32, 1200f32]);
+    }
+
+    #[test]
+    fn offsets_from_equal_temperament() {
+        let scale = ScalaScale::parse("quarter\n4\n310.0\n600.0\n900.0\n1200.0").unwrap();
+        let offsets = scale.cents_offsets();
+        assert_eq!(offsets.len(), 4);
+        assert_approx_eq!(offsets[0], 0f32);
+        assert_approx_eq!(offsets[1], 10f32);
+        assert_approx_eq!(offsets[2], 0f32);
+    }
+
+    #[test]
+    fn rejects_wrong_count() {
+        assert_eq!(
+            ScalaScale::parse("bad\n3\n1/1\n"),
+            Err(ScalaError::WrongNoteCount {
+                expected: 3,
+                found: 1
+            })
+        );
+    }
+}
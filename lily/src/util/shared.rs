@@ -0,0 +1,141 @@
+//! A triple buffer for sharing the latest value of some state, such as meter
+//! levels, from the audio thread with the GUI.
+//!
+//! The writer and reader each own one of three copies of the value and swap
+//! theirs with the spare one through a single atomic, so neither side ever
+//! waits for the other. The reader always sees the most recently published
+//! value and skips any it missed. The reader is a
+//! [`MeterSource`](super::MeterSource).
+
+use std::cell::UnsafeCell;
+use std::sync::atomic::{AtomicU8, Ordering};
+use std::sync::Arc;
+
+/// Set on the spare buffer index when it holds a value the reader hasn't seen
+const NEW_DATA: u8 = 0b100;
+const INDEX_MASK: u8 = 0b011;
+
+/// The state shared by a [`SharedWriter`] and [`SharedReader`]
+pub struct Shared<T> {
+    buffers: [UnsafeCell<T>; 3],
+    /// The index of the spare buffer, with [`NEW_DATA`] set if it was
+    /// published since the reader last swapped
+    spare: AtomicU8,
+}
+
+// The writer and reader only ever access the buffer they own, and ownership is
+// only exchanged through `spare`.
+unsafe impl<T: Send> Sync for Shared<T> {}
+
+impl<T: Clone + Send> Shared<T> {
+    /// Creates a triple buffer starting out with `initial`, returning its two
+    /// ends
+    #[allow(clippy::new_ret_no_self)]
+    pub fn new(initial: T) -> (SharedWriter<T>, SharedReader<T>) {
+        let shared = Arc::new(Shared {
+            buffers: [
+                UnsafeCell::new(initial.clone()),
+                UnsafeCell::new(initial.clone()),
+                UnsafeCell::new(initial),
+            ],
+            spare: AtomicU8::new(2),
+        });
+        (
+            SharedWriter {
+                shared: shared.clone(),
+                index: 0,
+            },
+            SharedReader { shared, index: 1 },
+        )
+    }
+}
+
+/// The writing end of a [`Shared`] value, for the audio thread
+pub struct SharedWriter<T> {
+    shared: Arc<Shared<T>>,
+    index: u8,
+}
+
+impl<T> SharedWriter<T> {
+    /// Publishes a new value. The value it replaces is dropped here, so
+    /// prefer [`write_with`](SharedWriter::write_with) for values that own
+    /// allocations.
+    pub fn write(&mut self, value: T) {
+        self.write_with(|buffer| *buffer = value);
+    }
+
+    /// Updates the writer's buffer in place and publishes it. The buffer
+    /// holds an older value than the last one written, so `update` should
+    /// overwrite all of it.
+    pub fn write_with(&mut self, update: impl FnOnce(&mut T)) {
+        // Safety: only the writer accesses the buffer at `index`
+        update(unsafe { &mut *self.shared.buffers[self.index as usize].get() });
+        let spare = self
+            .shared
+            .spare
+            .swap(self.index | NEW_DATA, Ordering::AcqRel);
+        self.index = spare & INDEX_MASK;
+    }
+}
+
+/// The reading end of a [`Shared`] value, for the GUI
+pub struct SharedReader<T> {
+    shared: Arc<Shared<T>>,
+    index: u8,
+}
+
+impl<T> SharedReader<T> {
+    /// Whether a value was published since the last read
+    pub fn has_update(&self) -> bool {
+        self.shared.spare.load(Ordering::Relaxed) & NEW_DATA != 0
+    }
+
+    /// Gets the most recently published value
+    pub fn read(&mut self) -> &T {
+        if self.has_update() {
+            let spare = self.shared.spare.swap(self.index, Ordering::AcqRel);
+            self.index = spare & INDEX_MASK;
+        }
+        // Safety: only the reader accesses the buffer at `index`
+        unsafe { &*self.shared.buffers[self.index as usize].get() }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reads_latest_value() {
+        let (mut writer, mut reader) = Shared::new(0u32);
+        assert!(!reader.has_update());
+        assert_eq!(*reader.read(), 0);
+        writer.write(1);
+        writer.write(2);
+        assert!(reader.has_update());
+        assert_eq!(*reader.read(), 2);
+        assert!(!reader.has_update());
+        assert_eq!(*reader.read(), 2);
+        writer.write_with(|value| *value = 3);
+        assert_eq!(*reader.read(), 3);
+    }
+
+    #[test]
+    fn shares_between_threads() {
+        let (mut writer, mut reader) = Shared::new([0u32; 4]);
+        let handle = std::thread::spawn(move || {
+            for i in 1..=1000 {
+                writer.write([i; 4]);
+            }
+        });
+        let mut last = 0;
+        while last < 1000 {
+            let value = *reader.read();
+            // Values are never torn and never go backwards
+            assert!(value.iter().all(|v| *v == value[0]));
+            assert!(value[0] >= last);
+            last = value[0];
+        }
+        handle.join().unwrap();
+    }
+}
@@ -0,0 +1,116 @@
+//! Where meters, scopes and analyzers get their data from the audio thread.
+//!
+//! A [`MeterSource`] gives the latest value of some state, like the
+//! [`SharedReader`] of a triple buffer, and a [`ScopeSource`] streams values
+//! to be drained, like the `Consumer` of a ring buffer. Widgets showing audio
+//! data can be fed straight from a source with the functions in
+//! [`crate::feed`], while [`spawn_poll`] and [`spawn_drain`] feed a source
+//! into an application's own models. Other channels can stand in for lily's
+//! buffers by implementing the traits.
+
+use std::time::Duration;
+
+use vizia::prelude::*;
+
+use super::SharedReader;
+
+/// About one frame at 60 Hz, how often widgets fed by a source poll it
+pub(crate) const FEED_INTERVAL: Duration = Duration::from_millis(16);
+
+/// The latest value of some state published by the audio thread, such as
+/// meter levels
+pub trait MeterSource: Send {
+    type Value;
+
+    /// Takes the latest value, or `None` if nothing was published since the
+    /// last call
+    fn latest(&mut self) -> Option<Self::Value>;
+}
+
+/// A stream of values sent by the audio thread, such as samples or levels
+pub trait ScopeSource: Send {
+    type Sample;
+
+    /// Appends every waiting value to `samples`, oldest first
+    fn drain_samples(&mut self, samples: &mut Vec<Self::Sample>);
+}
+
+impl<T: Clone + Send> MeterSource for SharedReader<T> {
+    type Value = T;
+
+    fn latest(&mut self) -> Option<T> {
+        self.has_update().then(|| self.read().clone())
+    }
+}
+
+/// Calls `poll` on a background thread every `interval` and emits the events
+/// it returns into vizia's event loop, to `target` or from the root. The
+/// thread stops once the application closes.
+pub(crate) fn spawn_emit<E, F>(
+    cx: &mut Context,
+    target: Option<Entity>,
+    interval: Duration,
+    mut poll: F,
+) where
+    E: 'static + Send,
+    F: 'static + Send + FnMut() -> Option<E>,
+{
+    cx.spawn(move |proxy| loop {
+        std::thread::sleep(interval);
+        if let Some(event) = poll() {
+            let sent = match target {
+                Some(target) => proxy.emit_to(target, event),
+                None => proxy.emit(event),
+            };
+            if sent.is_err() {
+                break;
+            }
+        }
+    });
+}
+
+/// Polls a [`MeterSource`] on a background thread every `interval`, such as
+/// once per frame, and emits each new value as an event, built with `event`,
+/// into vizia's event loop. Handle the event in a model to update a lensable
+/// field that widgets bind to. The thread stops once the application closes.
+pub fn spawn_poll<S, E, F>(cx: &mut Context, mut source: S, interval: Duration, event: F)
+where
+    S: 'static + MeterSource,
+    E: 'static + Send,
+    F: 'static + Send + Fn(S::Value) -> E,
+{
+    spawn_emit(cx, None, interval, move || source.latest().map(&event));
+}
+
+/// Drains a [`ScopeSource`] on a background thread every `interval` and emits
+/// the values read as an event, built with `event`, into vizia's event loop.
+/// No event is emitted when nothing was read. The thread stops once the
+/// application closes.
+pub fn spawn_drain<S, E, F>(cx: &mut Context, mut source: S, interval: Duration, event: F)
+where
+    S: 'static + ScopeSource,
+    E: 'static + Send,
+    F: 'static + Send + Fn(Vec<S::Sample>) -> E,
+{
+    spawn_emit(cx, None, interval, move || {
+        let mut samples = Vec::new();
+        source.drain_samples(&mut samples);
+        (!samples.is_empty()).then(|| event(samples))
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::util::Shared;
+
+    #[test]
+    fn polls_shared_values() {
+        let (mut writer, mut reader) = Shared::new(0u32);
+        assert_eq!(reader.latest(), None);
+        writer.write(1);
+        writer.write(2);
+        assert_eq!(reader.latest(), Some(2));
+        assert_eq!(reader.latest(), None);
+    }
+}
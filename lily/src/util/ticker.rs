@@ -0,0 +1,106 @@
+//! Waking views about once a frame while they animate on their own.
+//!
+//! A view that moves without input, such as a fader gliding to automation,
+//! asks for a [`FrameTick`] with [`TickExt::request_tick`] when it starts
+//! moving and asks again from its handler for as long as it's still moving,
+//! like `requestAnimationFrame` in a browser. The views of a window share one
+//! background thread, started by [`install_ticker`], which sleeps while no
+//! view is waiting, so idle and removed views cost nothing.
+//!
+//! ```ignore
+//! event.map(|_: &FrameTick, meta| {
+//!     self.advance(cx);
+//!     if self.is_moving() {
+//!         cx.request_tick(cx.current());
+//!     }
+//!     meta.consume();
+//! });
+//! ```
+
+use std::sync::{Arc, Condvar, Mutex};
+
+use vizia::prelude::*;
+
+use super::FEED_INTERVAL;
+
+/// Sent to a view about a frame after it asked for it with
+/// [`TickExt::request_tick`]
+pub(crate) struct FrameTick;
+
+/// The views waiting for their next tick, shared with the ticking thread
+#[derive(Default)]
+struct Waiting {
+    views: Mutex<Vec<Entity>>,
+    wake: Condvar,
+}
+
+impl Waiting {
+    fn request(&self, view: Entity) {
+        let mut views = self.views.lock().unwrap();
+        if !views.contains(&view) {
+            views.push(view);
+        }
+        self.wake.notify_one();
+    }
+
+    /// Blocks until a view is waiting for a tick
+    fn wait(&self) {
+        let views = self.views.lock().unwrap();
+        let _views = self.wake.wait_while(views, |views| views.is_empty());
+    }
+
+    /// Takes the views waiting for a tick, which wait again if they ask again
+    fn take(&self) -> Vec<Entity> {
+        std::mem::take(&mut *self.views.lock().unwrap())
+    }
+}
+
+/// Built at the root of the window, where views find it
+struct Ticker(Arc<Waiting>);
+
+impl Model for Ticker {}
+
+/// Starts the ticking thread of the window and builds the model views find
+/// it through, unless it's already there. Views that ask for ticks call this
+/// when they're built.
+pub(crate) fn install_ticker(cx: &mut Context) {
+    if cx.data::<Ticker>().is_some() {
+        return;
+    }
+    let waiting = Arc::new(Waiting::default());
+    let shared = waiting.clone();
+    cx.spawn(move |proxy| loop {
+        shared.wait();
+        std::thread::sleep(FEED_INTERVAL);
+        for view in shared.take() {
+            // The application has closed
+            if proxy.emit_to(view, FrameTick).is_err() {
+                return;
+            }
+        }
+    });
+    cx.with_current(Entity::root(), |cx| Ticker(waiting).build(cx));
+}
+
+/// Asks for a [`FrameTick`], from views built after [`install_ticker`]
+pub(crate) trait TickExt {
+    /// Sends `view` a [`FrameTick`] about a frame from now. Asking again
+    /// before it arrives still sends one.
+    fn request_tick(&self, view: Entity);
+}
+
+impl TickExt for Context {
+    fn request_tick(&self, view: Entity) {
+        if let Some(Ticker(waiting)) = self.data::<Ticker>() {
+            waiting.request(view);
+        }
+    }
+}
+
+impl TickExt for EventContext<'_> {
+    fn request_tick(&self, view: Entity) {
+        if let Some(Ticker(waiting)) = self.data::<Ticker>() {
+            waiting.request(view);
+        }
+    }
+}
@@ -0,0 +1,125 @@
+//! Tick marks for logarithmic frequency axes, so every widget showing
+//! frequencies draws the same grid and labels
+
+use std::ops::RangeInclusive;
+
+/// The mantissas of the ticks placed in each decade, in the order their
+/// labels are kept when there isn't room for all of them
+const MANTISSAS: [f32; 3] = [1f32, 5f32, 2f32];
+
+/// A tick mark along an axis
+#[derive(Clone, Debug, PartialEq)]
+pub struct Tick {
+    /// The value at the tick
+    pub value: f32,
+    /// The normalized position of the tick along the axis
+    pub position: f32,
+    /// The text shown at the tick, if there's room for it
+    pub label: Option<String>,
+}
+
+/// Gets the normalized position of a frequency on a logarithmic axis
+pub fn log_position(frequencies: &RangeInclusive<f32>, hz: f32) -> f32 {
+    let (low, high) = (*frequencies.start(), *frequencies.end());
+    (hz / low).ln() / (high / low).ln()
+}
+
+/// Gets the short label of a frequency tick, e.g. `500` or `2k`
+pub fn frequency_label(hz: f32) -> String {
+    if hz >= 1000f32 {
+        format!("{}k", hz / 1000f32)
+    } else {
+        format!("{hz}")
+    }
+}
+
+/// Gets ticks at 1, 2 and 5 times each power of ten within `frequencies`,
+/// such as 20, 50, 100, 200, 500, 1k... on a `20f32..=20000f32` axis.
+///
+/// Labels are thinned so their centers are at least `min_spacing` apart on an
+/// axis `width` long. Ticks at powers of ten keep their labels first, then
+/// fives, then twos. Ticks without a label can still be drawn as grid lines.
+pub fn frequency_ticks(
+    frequencies: &RangeInclusive<f32>,
+    width: f32,
+    min_spacing: f32,
+) -> Vec<Tick> {
+    let (low, high) = (*frequencies.start(), *frequencies.end());
+    if low <= 0f32 || high <= low {
+        return Vec::new();
+    }
+    let mut ticks: Vec<(Tick, usize)> = Vec::new();
+    let mut decade = 10f32.powi(low.log10().floor() as i32);
+    while decade <= high {
+        for (priority, mantissa) in MANTISSAS.iter().enumerate() {
+            // Round away the error of repeated multiplication
+            let hz = (decade * mantissa * 1000f32).round() / 1000f32;
+            if frequencies.contains(&hz) {
+                let tick = Tick {
+                    value: hz,
+                    position: log_position(frequencies, hz),
+                    label: None,
+                };
+                ticks.push((tick, priority));
+            }
+        }
+        decade *= 10f32;
+    }
+
+    let mut labelled: Vec<f32> = Vec::new();
+    for priority in 0..MANTISSAS.len() {
+        for (tick, _) in ticks.iter_mut().filter(|(_, p)| *p == priority) {
+            let x = tick.position * width;
+            if labelled
+                .iter()
+                .all(|other| (other - x).abs() >= min_spacing)
+            {
+                labelled.push(x);
+                tick.label = Some(frequency_label(tick.value));
+            }
+        }
+    }
+    ticks.sort_by(|a, b| a.0.value.total_cmp(&b.0.value));
+    ticks.into_iter().map(|(tick, _)| tick).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn labels(ticks: &[Tick]) -> Vec<&str> {
+        ticks.iter().filter_map(|t| t.label.as_deref()).collect()
+    }
+
+    #[test]
+    fn generates_ticks() {
+        let ticks = frequency_ticks(&(20f32..=20000f32), 1000f32, 0f32);
+        let values: Vec<f32> = ticks.iter().map(|t| t.value).collect();
+        assert_eq!(
+            values,
+            vec![
+                20f32, 50f32, 100f32, 200f32, 500f32, 1000f32, 2000f32, 5000f32, 10000f32, 20000f32
+            ]
+        );
+        assert_eq!(ticks[0].position, 0f32);
+        assert!((ticks[9].position - 1f32).abs() < 1e-6f32);
+        assert_eq!(labels(&ticks)[5], "1k");
+    }
+
+    #[test]
+    fn thins_labels() {
+        // Decades are 100 pixels apart, with twos and fives 30 pixels from them
+        let ticks = frequency_ticks(&(20f32..=20000f32), 300f32, 50f32);
+        assert_eq!(ticks.len(), 10);
+        assert_eq!(labels(&ticks), vec!["20", "100", "1k", "10k"]);
+        // With more room every tick is labelled
+        let ticks = frequency_ticks(&(20f32..=20000f32), 300f32, 25f32);
+        assert_eq!(labels(&ticks).len(), 10);
+    }
+
+    #[test]
+    fn skips_invalid_ranges() {
+        assert!(frequency_ticks(&(0f32..=100f32), 100f32, 10f32).is_empty());
+        assert!(frequency_ticks(&(100f32..=10f32), 100f32, 10f32).is_empty());
+    }
+}
@@ -1,14 +1,39 @@
 //! Trait extensions for making working with Vizia even nicer
 
+use std::ops::RangeInclusive;
+
 use glam::Vec2;
 use vizia::cache::BoundingBox;
+use vizia::prelude::{
+    Canvas, Direction, DrawContext, Entity, EventContext, Handle, PseudoClass, Res, Units, View,
+};
+use vizia::vg;
 use vizia::*;
 
+use super::RangeExt;
+
 pub trait BoundingBoxExt {
     fn map_ui_point(&self, point: Vec2, centered: bool) -> Vec2;
     fn map_data_point(&self, point: Vec2, centered: bool) -> Vec2;
     fn map_ui_point_unbounded(&self, point: Vec2, centered: bool) -> Vec2;
+    fn map_ui_point_to_range(
+        &self,
+        point: Vec2,
+        x_range: RangeInclusive<f32>,
+        y_range: RangeInclusive<f32>,
+    ) -> Vec2;
+    fn map_range_point_to_ui(
+        &self,
+        point: Vec2,
+        x_range: RangeInclusive<f32>,
+        y_range: RangeInclusive<f32>,
+    ) -> Vec2;
     fn contains_point(&self, point: Vec2) -> bool;
+    fn inset(&self, amount: f32) -> BoundingBox;
+    fn outset(&self, amount: f32) -> BoundingBox;
+    fn aspect_fit(&self, aspect: f32) -> BoundingBox;
+    fn aspect_fill(&self, aspect: f32) -> BoundingBox;
+    fn grid_cell(&self, column: usize, row: usize, columns: usize, rows: usize) -> BoundingBox;
 }
 
 impl BoundingBoxExt for BoundingBox {
@@ -48,12 +73,317 @@ impl BoundingBoxExt for BoundingBox {
         }
     }
 
+    /// Maps an absolute UI point to a point in arbitrary ranges, where the
+    /// start of `x_range` is at the left edge and the start of `y_range` is at
+    /// the top edge. Pass a reversed `y_range` such as `1f32..=0f32` to have
+    /// values increase upwards. Points outside the rect are not clamped.
+    fn map_ui_point_to_range(
+        &self,
+        point: Vec2,
+        x_range: RangeInclusive<f32>,
+        y_range: RangeInclusive<f32>,
+    ) -> Vec2 {
+        let ratio = self.map_ui_point_unbounded(point, false);
+        Vec2::new(
+            x_range.start() + ratio.x * x_range.width(),
+            y_range.start() + ratio.y * y_range.width(),
+        )
+    }
+
+    /// Maps a point in arbitrary ranges to absolute UI coordinates. This is
+    /// the inverse of [`map_ui_point_to_range`].
+    ///
+    /// [`map_ui_point_to_range`]: BoundingBoxExt::map_ui_point_to_range
+    fn map_range_point_to_ui(
+        &self,
+        point: Vec2,
+        x_range: RangeInclusive<f32>,
+        y_range: RangeInclusive<f32>,
+    ) -> Vec2 {
+        self.map_data_point(Vec2::new(x_range.map(point.x), y_range.map(point.y)), false)
+    }
+
     fn contains_point(&self, point: Vec2) -> bool {
         point.x <= self.right()
             && point.x >= self.left()
             && point.y <= self.bottom()
             && point.y >= self.top()
     }
+
+    /// Shrinks the rect by `amount` on every side, never past its center
+    fn inset(&self, amount: f32) -> BoundingBox {
+        let x_amount = amount.min(self.w / 2f32);
+        let y_amount = amount.min(self.h / 2f32);
+        BoundingBox {
+            x: self.x + x_amount,
+            y: self.y + y_amount,
+            w: self.w - x_amount * 2f32,
+            h: self.h - y_amount * 2f32,
+        }
+    }
+
+    /// Grows the rect by `amount` on every side
+    fn outset(&self, amount: f32) -> BoundingBox {
+        self.inset(-amount)
+    }
+
+    /// Gets the largest rect with a width to height ratio of `aspect` that
+    /// fits inside this one, centered
+    fn aspect_fit(&self, aspect: f32) -> BoundingBox {
+        let (w, h) = if self.w / self.h > aspect {
+            (self.h * aspect, self.h)
+        } else {
+            (self.w, self.w / aspect)
+        };
+        centered_rect(self, w, h)
+    }
+
+    /// Gets the smallest rect with a width to height ratio of `aspect` that
+    /// covers this one, centered
+    fn aspect_fill(&self, aspect: f32) -> BoundingBox {
+        let (w, h) = if self.w / self.h > aspect {
+            (self.w, self.w / aspect)
+        } else {
+            (self.h * aspect, self.h)
+        };
+        centered_rect(self, w, h)
+    }
+
+    /// Gets a cell of this rect split into an evenly sized grid, counting
+    /// columns from the left and rows from the top
+    fn grid_cell(&self, column: usize, row: usize, columns: usize, rows: usize) -> BoundingBox {
+        let w = self.w / columns.max(1) as f32;
+        let h = self.h / rows.max(1) as f32;
+        BoundingBox {
+            x: self.x + w * column as f32,
+            y: self.y + h * row as f32,
+            w,
+            h,
+        }
+    }
+}
+
+fn centered_rect(bounds: &BoundingBox, w: f32, h: f32) -> BoundingBox {
+    BoundingBox {
+        x: bounds.x + (bounds.w - w) / 2f32,
+        y: bounds.y + (bounds.h - h) / 2f32,
+        w,
+        h,
+    }
+}
+
+/// Pseudo-class state for views, so stylesheets can restyle them with
+/// `:active` and `:focus` while they're pressed.
+pub trait PseudoClassExt {
+    fn set_pseudo_class(&mut self, entity: Entity, class: PseudoClass, enabled: bool);
+    fn capture_active(&mut self);
+    fn release_active(&mut self);
+    fn is_disabled(&self) -> bool;
+}
+
+impl PseudoClassExt for EventContext<'_> {
+    /// Sets or clears `class` on `entity`, restyling only if it changed
+    fn set_pseudo_class(&mut self, entity: Entity, class: PseudoClass, enabled: bool) {
+        if let Some(pseudo_classes) = self.style.pseudo_classes.get_mut(entity) {
+            if pseudo_classes.contains(class) != enabled {
+                pseudo_classes.set(class, enabled);
+                self.style.needs_restyle = true;
+            }
+        }
+    }
+
+    /// Captures the mouse, focuses the current view and marks it `:active`
+    fn capture_active(&mut self) {
+        self.capture();
+        self.focus();
+        self.set_pseudo_class(self.current(), PseudoClass::ACTIVE, true);
+    }
+
+    /// Releases the mouse and clears `:active` on the current view
+    fn release_active(&mut self) {
+        self.release();
+        self.set_pseudo_class(self.current(), PseudoClass::ACTIVE, false);
+    }
+
+    /// Whether the current view or any of its ancestors is `:disabled`, in
+    /// which case it should ignore input
+    fn is_disabled(&self) -> bool {
+        let mut entity = Some(self.current());
+        while let Some(current) = entity {
+            let classes = self.style.pseudo_classes.get(current);
+            if matches!(classes, Some(classes) if classes.contains(PseudoClass::DISABLED)) {
+                return true;
+            }
+            entity = self.tree.get_parent(current);
+        }
+        false
+    }
+}
+
+/// Redrawing only when what a view shows has changed, so an idle editor
+/// doesn't keep repainting static content
+pub trait RedrawExt {
+    /// Asks for the window to be redrawn
+    fn request_redraw(&mut self);
+
+    /// Asks for a redraw if the visual state of a view, taken before and
+    /// after handling an event, has changed
+    fn redraw_if_changed<T: PartialEq>(&mut self, before: &T, after: &T) {
+        if before != after {
+            self.request_redraw();
+        }
+    }
+}
+
+impl RedrawExt for EventContext<'_> {
+    fn request_redraw(&mut self) {
+        self.style.needs_redraw = true;
+    }
+}
+
+/// Disabling views whose parameters don't currently apply
+pub trait DisabledExt {
+    fn disabled(self, disabled: impl Res<bool>) -> Self;
+}
+
+impl<V: View> DisabledExt for Handle<'_, V> {
+    /// Disables the view and everything in it while `disabled` is true, or a
+    /// [`Lens`](vizia::prelude::Lens) to it is. Disabled lily widgets ignore
+    /// input, and are dimmed by `:disabled` rules in the stylesheet.
+    fn disabled(self, disabled: impl Res<bool>) -> Self {
+        disabled.set_or_bind(self.cx, self.entity, |cx, entity, disabled| {
+            if let Some(pseudo_classes) = cx.style.pseudo_classes.get_mut(entity) {
+                pseudo_classes.set(PseudoClass::DISABLED, disabled);
+            }
+            cx.style.needs_restyle = true;
+        });
+        self
+    }
+}
+
+/// Interaction distances such as hover radii are given in logical pixels,
+/// while cursor positions and bounds are in physical pixels, which are twice
+/// as dense on a HiDPI display with a scale factor of 2.
+pub trait ScaleExt {
+    fn scale_factor(&self) -> f32;
+
+    /// Converts a distance in logical pixels to physical pixels
+    fn to_physical(&self, logical: f32) -> f32 {
+        logical * self.scale_factor()
+    }
+}
+
+impl ScaleExt for EventContext<'_> {
+    fn scale_factor(&self) -> f32 {
+        self.style.dpi_factor as f32
+    }
+}
+
+impl ScaleExt for DrawContext<'_> {
+    fn scale_factor(&self) -> f32 {
+        self.style.dpi_factor as f32
+    }
+}
+
+/// Drawing the CSS background, border and outline of views that draw
+/// themselves, which vizia only does for views that keep its default `draw`
+pub trait FrameExt {
+    fn frame_path(&self, inset: f32) -> vg::Path;
+    fn draw_background(&self, canvas: &mut Canvas);
+    fn draw_border(&self, canvas: &mut Canvas);
+    fn draw_outline(&self, canvas: &mut Canvas);
+}
+
+impl FrameExt for DrawContext<'_> {
+    /// The bounds of the view as a path with the `border-radius` of each
+    /// corner, shrunk by `inset` on every side
+    fn frame_path(&self, inset: f32) -> vg::Path {
+        let bounds = self.bounds().inset(inset);
+        let radius = |units: Option<&Units>| match units {
+            Some(Units::Pixels(px)) => (*px - inset).max(0f32),
+            Some(Units::Percentage(pct)) => {
+                (bounds.w.min(bounds.h) * pct / 100f32 - inset).max(0f32)
+            }
+            _ => 0f32,
+        };
+        let mut path = vg::Path::new();
+        path.rounded_rect_varying(
+            bounds.x,
+            bounds.y,
+            bounds.w,
+            bounds.h,
+            radius(self.border_radius_top_left()),
+            radius(self.border_radius_top_right()),
+            radius(self.border_radius_bottom_right()),
+            radius(self.border_radius_bottom_left()),
+        );
+        path
+    }
+
+    /// Fills the view with its `background-image` gradient, or else its
+    /// `background-color`
+    fn draw_background(&self, canvas: &mut Canvas) {
+        let bounds = self.bounds();
+        let paint = match self.background_gradient() {
+            Some(gradient) => {
+                let (length, (x0, y0, x1, y1)) = match gradient.direction {
+                    Direction::LeftToRight => {
+                        (bounds.w, (bounds.left(), 0f32, bounds.right(), 0f32))
+                    }
+                    Direction::RightToLeft => {
+                        (bounds.w, (bounds.right(), 0f32, bounds.left(), 0f32))
+                    }
+                    Direction::TopToBottom => {
+                        (bounds.h, (0f32, bounds.top(), 0f32, bounds.bottom()))
+                    }
+                    Direction::BottomToTop => {
+                        (bounds.h, (0f32, bounds.bottom(), 0f32, bounds.top()))
+                    }
+                };
+                let stops: Vec<(f32, vg::Color)> = gradient
+                    .get_stops(length)
+                    .into_iter()
+                    .map(|(position, color)| (position, color.into()))
+                    .collect();
+                vg::Paint::linear_gradient_stops(x0, y0, x1, y1, &stops)
+            }
+            None => {
+                let color = self.background_color().copied().unwrap_or_default();
+                vg::Paint::color(color.into())
+            }
+        };
+        canvas.fill_path(&mut self.frame_path(0f32), &paint);
+    }
+
+    /// Strokes the inside of the view with its `border-color`, if it has a
+    /// `border-width`
+    fn draw_border(&self, canvas: &mut Canvas) {
+        let width = match self.border_width() {
+            Some(Units::Pixels(px)) if *px > 0f32 => *px,
+            _ => return,
+        };
+        let color = self.border_color().copied().unwrap_or_default();
+        canvas.stroke_path(
+            &mut self.frame_path(width / 2f32),
+            &vg::Paint::color(color.into()).with_line_width(width),
+        );
+    }
+
+    /// Strokes the inside of the view with its `outline-color`, if it has an
+    /// `outline-width`, on top of what the view drew. Views that clip their
+    /// content would hide an outline drawn outside of their bounds, so the
+    /// focus ring of `.tab-stop:focus` is drawn within them.
+    fn draw_outline(&self, canvas: &mut Canvas) {
+        let width = match self.outline_width() {
+            Some(Units::Pixels(px)) if *px > 0f32 => *px,
+            _ => return,
+        };
+        let color = self.outline_color().copied().unwrap_or_default();
+        canvas.stroke_path(
+            &mut self.frame_path(width / 2f32),
+            &vg::Paint::color(color.into()).with_line_width(width),
+        );
+    }
 }
 
 #[cfg(test)]
@@ -114,4 +444,53 @@ mod tests {
         let data = Vec2::splat(0.0);
         assert_eq!(rect.map_data_point(data, true), Vec2::new(150f32, 150f32));
     }
+
+    #[test]
+    fn maps_to_and_from_ranges() {
+        let rect = rect();
+        let point = rect.map_ui_point_to_range(Vec2::new(125f32, 150f32), 0f32..=8f32, 1f32..=0f32);
+        assert_eq!(point, Vec2::new(2f32, 0.5f32));
+        let ui = rect.map_range_point_to_ui(point, 0f32..=8f32, 1f32..=0f32);
+        assert_eq!(ui, Vec2::new(125f32, 150f32));
+    }
+
+    #[test]
+    fn insets_and_outsets() {
+        let rect = rect();
+        let inset = rect.inset(10f32);
+        assert_eq!(
+            (inset.x, inset.y, inset.w, inset.h),
+            (110f32, 110f32, 80f32, 80f32)
+        );
+        assert_eq!(rect.inset(80f32).w, 0f32);
+        let outset = rect.outset(10f32);
+        assert_eq!(
+            (outset.x, outset.y, outset.w, outset.h),
+            (90f32, 90f32, 120f32, 120f32)
+        );
+    }
+
+    #[test]
+    fn fits_and_fills_aspect() {
+        let rect = rect();
+        let fit = rect.aspect_fit(2f32);
+        assert_eq!(
+            (fit.x, fit.y, fit.w, fit.h),
+            (100f32, 125f32, 100f32, 50f32)
+        );
+        let fill = rect.aspect_fill(2f32);
+        assert_eq!(
+            (fill.x, fill.y, fill.w, fill.h),
+            (50f32, 100f32, 200f32, 100f32)
+        );
+    }
+
+    #[test]
+    fn gets_grid_cell() {
+        let cell = rect().grid_cell(1, 3, 2, 4);
+        assert_eq!(
+            (cell.x, cell.y, cell.w, cell.h),
+            (150f32, 175f32, 50f32, 25f32)
+        );
+    }
 }
@@ -0,0 +1,551 @@
+use std::{marker::PhantomData, ops::RangeInclusive};
+
+use glam::Vec2;
+use lily_derive::Handle;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use vizia::cache::BoundingBox;
+use vizia::prelude::*;
+use vizia::vg;
+
+use super::mseg::util::closest_point;
+use crate::context_menu::{ContextMenuAction, ContextMenuExt, ContextMenuItem};
+use crate::focus::FocusExt;
+use crate::gesture::GestureExt;
+use crate::input::{InputAction, InputMapExt, MouseBinding, PressReader};
+use crate::locale::LocaleExt;
+use crate::tooltip::TooltipExt;
+use crate::units::format_hz;
+use crate::util::{
+    draw_invalid, frequency_label, frequency_ticks, log_position, Affordance, CoalescedMove,
+    CursorAffordance, CurveClipboard, CurveClipboardExt, CurveError, CurveKind, CurvePoints,
+    DataCheckExt, DataChecked, FrameExt, KeyNudge, MoveCoalescer, PathBatch, PseudoClassExt,
+    RedrawExt, ScaleExt,
+};
+
+/// The default distance in logical pixels before a breakpoint is considered
+/// hovered
+const HOVER_RADIUS: f32 = 12f32;
+/// The smallest frequency ratio allowed between two neighbouring breakpoints
+const MIN_RATIO: f32 = 1.01f32;
+/// The smallest distance in pixels between the centers of two axis labels
+const LABEL_SPACING: f32 = 32f32;
+
+/// Edits a smooth value curve over a logarithmic frequency axis, such as a
+/// per-band reverb decay time or a dynamic EQ threshold.
+///
+/// Points are stored as `(hz, value)` with values normalized to `0..=1`.
+/// Breakpoints are dragged like the points of an MSEG: double-click to insert
+/// one and right-click to remove one, or the presses bound in the installed
+/// [`InputMap`](crate::input::InputMap). Once copy and paste are enabled with
+/// [`CurveClipboardExt::on_paste`], right-clicking elsewhere opens a menu to
+/// copy or paste the whole curve.
+///
+/// Breakpoints that aren't sorted by frequency, aren't finite, or lie outside
+/// of the frequency range or `0..=1` are invalid. The editor is then crossed
+/// out in the border color of the `.invalid` class and ignores input until
+/// the breakpoints are fixed.
+#[allow(clippy::type_complexity)]
+#[derive(Handle)]
+#[style_class("point", "grid", "invalid")]
+pub struct BandCurveEditor<P, T>
+where
+    P: Lens<Target = CurvePoints<T>>,
+    T: 'static + Clone,
+{
+    /// A [`Lens`] of the breakpoints, sorted by frequency
+    points: P,
+    /// The type of the user data attached to each breakpoint
+    data: PhantomData<T>,
+    /// The frequency range shown, in Hz
+    frequencies: RangeInclusive<f32>,
+    /// The index of the currently hovered or pressed breakpoint
+    #[state]
+    active_point_id: Option<usize>,
+    /// Whether we are in the process of dragging a breakpoint
+    #[state]
+    is_dragging_point: bool,
+    /// The index of the breakpoint edited from the keyboard, the last one
+    /// pressed
+    selected_point_id: Option<usize>,
+    styles: BandCurveEditorStyles,
+    /// The distance in logical pixels before a breakpoint is considered hovered
+    #[property(res)]
+    hover_radius: f32,
+    /// The label of each frequency tick, hidden when there's no room for it
+    labels: Vec<(f32, Entity)>,
+    /// Copies and pastes the breakpoints, once enabled with
+    /// [`CurveClipboardExt::on_paste`]
+    clipboard: Option<CurveClipboard<T>>,
+    /// The last cursor moved to, until it is handled
+    moves: MoveCoalescer,
+    /// The presses of the mouse buttons
+    presses: PressReader,
+    /// The cursor over breakpoints and while dragging them
+    cursor: CursorAffordance,
+    /// What is wrong with the breakpoints, while they are invalid
+    data_error: Option<CurveError>,
+
+    #[callback(usize, Vec2)]
+    on_changing_point: Option<Box<dyn Fn(&mut EventContext, usize, Vec2)>>,
+
+    #[callback(usize)]
+    on_remove_point: Option<Box<dyn Fn(&mut EventContext, usize)>>,
+
+    /// Asked before a breakpoint is removed. Returning `false` keeps it.
+    #[callback(usize -> bool)]
+    can_remove_point: Option<Box<dyn Fn(&mut EventContext, usize) -> bool>>,
+
+    #[callback(BandCurveEditorState)]
+    on_state_change: Option<Box<dyn Fn(&mut EventContext, BandCurveEditorState)>>,
+
+    #[callback(usize, Vec2)]
+    on_insert_point: Option<Box<dyn Fn(&mut EventContext, usize, Vec2)>>,
+
+    /// Called with the problem found when the breakpoints become invalid
+    #[callback(CurveError)]
+    on_data_error: Option<Box<dyn Fn(&mut EventContext, CurveError)>>,
+}
+
+impl<P, T> BandCurveEditor<P, T>
+where
+    P: Lens<Target = CurvePoints<T>>,
+    T: 'static + Clone,
+{
+    /// Create a new `BandCurveEditor`
+    ///
+    /// # Parameters
+    ///
+    /// * `cx` - the current [`Context`]
+    /// * `points` - a [`Lens`] of the breakpoints as `(hz, value)`, sorted by
+    ///   frequency
+    /// * `frequencies` - the frequency range of the axis in Hz, for example
+    ///   `20f32..=20000f32`
+    pub fn new(cx: &mut Context, points: P, frequencies: RangeInclusive<f32>) -> Handle<Self> {
+        let axis = frequencies.clone();
+        let valid_range = frequencies.clone();
+        let mut labels = Vec::new();
+        let handle = Self {
+            points: points.clone(),
+            data: PhantomData::default(),
+            frequencies,
+            active_point_id: None,
+            is_dragging_point: false,
+            selected_point_id: None,
+            styles: BandCurveEditorStyles::new(cx),
+            hover_radius: HOVER_RADIUS,
+            labels: Vec::new(),
+            clipboard: None,
+            moves: MoveCoalescer::new(),
+            presses: PressReader::new(),
+            cursor: CursorAffordance::new(),
+            data_error: None,
+            on_changing_point: None,
+            on_remove_point: None,
+            can_remove_point: None,
+            on_state_change: None,
+            on_insert_point: None,
+            on_data_error: None,
+        }
+        .build(cx, |cx| {
+            // Which labels fit is only known once the editor has been laid out
+            for tick in frequency_ticks(&axis, 0f32, 0f32) {
+                let label = cx.localize(&frequency_label(tick.value));
+                let entity = Label::new(cx, &label)
+                    .class("tick")
+                    .position_type(PositionType::SelfDirected)
+                    .left(Percentage(tick.position * 100f32))
+                    .bottom(Pixels(0f32))
+                    .top(Stretch(1f32))
+                    .entity;
+                labels.push((tick.value, entity));
+            }
+        })
+        .tab_stop()
+        .check_data(points, move |points| {
+            points.validate(valid_range.clone(), 0f32..=1f32)
+        });
+        if let Some(view) = handle.cx.views.get_mut(&handle.entity) {
+            if let Some(editor) = view.downcast_mut::<Self>() {
+                editor.labels = labels;
+            }
+        }
+        handle
+    }
+
+    fn data_to_ui(&self, bounds: BoundingBox, point: Vec2) -> Vec2 {
+        Vec2::new(
+            bounds.left() + log_position(&self.frequencies, point.x) * bounds.w,
+            bounds.bottom() - point.y * bounds.h,
+        )
+    }
+
+    fn ui_to_data(&self, bounds: BoundingBox, point: Vec2) -> Vec2 {
+        let position = ((point.x - bounds.left()) / bounds.w).clamp(0f32, 1f32);
+        let (low, high) = (*self.frequencies.start(), *self.frequencies.end());
+        let hz = low * (high / low).powf(position);
+        let value = ((bounds.bottom() - point.y) / bounds.h).clamp(0f32, 1f32);
+        Vec2::new(hz, value)
+    }
+
+    /// Moves a breakpoint to a position in UI space, keeping the breakpoints
+    /// in frequency order
+    fn set_point(&self, cx: &mut EventContext, bounds: BoundingBox, index: usize, ui: Vec2) {
+        let points = self.points.get(cx);
+        let (previous, next) = points.neighbours(index);
+        let left_bound = previous
+            .map(|p| p.x * MIN_RATIO)
+            .unwrap_or(*self.frequencies.start());
+        let right_bound = next
+            .map(|p| p.x / MIN_RATIO)
+            .unwrap_or(*self.frequencies.end());
+        let mut point = self.ui_to_data(bounds, ui);
+        point.x = point.x.clamp(left_bound, right_bound.max(left_bound));
+        if let Some(callback) = &self.on_changing_point {
+            (callback)(cx, index, point);
+        }
+        cx.perform_edit(index, &[point.x, point.y]);
+        cx.show_value_tooltip(format!("{}, {:.2}", format_hz(point.x), point.y));
+    }
+
+    /// Removes a breakpoint, if `can_remove_point` allows it
+    fn remove_point(&mut self, cx: &mut EventContext, index: usize) {
+        let allowed = self
+            .can_remove_point
+            .as_ref()
+            .is_none_or(|callback| (callback)(cx, index));
+        if allowed {
+            self.active_point_id = None;
+            self.selected_point_id = None;
+            cx.release_active();
+            if self.is_dragging_point {
+                self.is_dragging_point = false;
+                cx.end_edit();
+            }
+            if let Some(callback) = &self.on_remove_point {
+                (callback)(cx, index);
+            }
+        }
+    }
+
+    /// Performs what a press of a mouse button is bound to: dragging or
+    /// removing the hovered breakpoint, or inserting one or opening the menu
+    /// elsewhere
+    fn press(&mut self, cx: &mut EventContext, bounds: BoundingBox, press: MouseBinding) {
+        let input = cx.input_map();
+        if let Some(index) = self.active_point_id {
+            if input.triggers(InputAction::DeletePoint, press) {
+                self.remove_point(cx, index);
+            } else if press.button == MouseButton::Left {
+                self.selected_point_id = self.active_point_id;
+                cx.capture_active();
+                self.is_dragging_point = true;
+                cx.begin_edit();
+            }
+        } else if input.triggers(InputAction::InsertPoint, press) {
+            let cursor = Vec2::new(cx.mouse.cursorx, cx.mouse.cursory);
+            let point = self.ui_to_data(bounds, cursor);
+            let points = self.points.get(cx);
+            // Breakpoints can't be closer than the drag allows
+            let too_close = points
+                .iter()
+                .any(|p| p.x.max(point.x) / p.x.min(point.x) < MIN_RATIO);
+            if too_close {
+                return;
+            }
+            let index = points
+                .iter()
+                .position(|p| p.x > point.x)
+                .unwrap_or(points.len());
+            if let Some(callback) = &self.on_insert_point {
+                (callback)(cx, index, point);
+            }
+        } else if input.triggers(InputAction::OpenMenu, press) && self.clipboard.is_some() {
+            cx.open_context_menu(vec![ContextMenuItem::copy(), ContextMenuItem::paste()]);
+        }
+    }
+
+    /// Drags the active breakpoint to the cursor, or finds the breakpoint it
+    /// hovers
+    fn mouse_move(&mut self, cx: &mut EventContext, bounds: BoundingBox, cursor: Vec2) {
+        if self.is_dragging_point {
+            if let Some(index) = self.active_point_id {
+                self.set_point(cx, bounds, index, cursor);
+            }
+        } else {
+            let hover_radius = cx.to_physical(self.hover_radius);
+            let points = self.points.get(cx);
+            let ui_points: Vec<Vec2> = points
+                .iter()
+                .map(|p| self.data_to_ui(bounds, Vec2::new(p.x, p.y)))
+                .collect();
+            self.active_point_id = closest_point(&ui_points, cursor, hover_radius);
+        }
+    }
+
+    /// Stores the result of checking the breakpoints, reporting a new problem
+    /// and dropping any drag or selection, whose indices can't be trusted
+    fn data_checked(&mut self, cx: &mut EventContext, error: Option<CurveError>) {
+        if let Some(error) = error {
+            if self.is_dragging_point {
+                self.is_dragging_point = false;
+                cx.release_active();
+                cx.end_edit();
+                cx.hide_tooltip();
+            }
+            self.active_point_id = None;
+            self.selected_point_id = None;
+            if let Some(callback) = &self.on_data_error {
+                (callback)(cx, error);
+            }
+        }
+        self.data_error = error;
+        cx.request_redraw();
+    }
+}
+
+impl<P, T> CurveClipboardExt<T> for Handle<'_, BandCurveEditor<P, T>>
+where
+    P: Lens<Target = CurvePoints<T>>,
+    T: 'static + Clone + Serialize + DeserializeOwned + Default,
+{
+    fn on_paste<F>(self, callback: F) -> Self
+    where
+        F: 'static + Fn(&mut EventContext, CurvePoints<T>),
+    {
+        if let Some(view) = self.cx.views.get_mut(&self.entity) {
+            if let Some(editor) = view.downcast_mut::<BandCurveEditor<P, T>>() {
+                editor.clipboard = Some(CurveClipboard::new(CurveKind::Bands, callback));
+            }
+        }
+        self
+    }
+}
+
+impl<P, T> View for BandCurveEditor<P, T>
+where
+    P: Lens<Target = CurvePoints<T>>,
+    T: 'static + Clone,
+{
+    fn element(&self) -> Option<&'static str> {
+        Some("band-curve")
+    }
+
+    fn event(&mut self, cx: &mut EventContext, event: &mut Event) {
+        let bounds = cx.cache.get_bounds(cx.current());
+        let state = self.state();
+        event.map(|checked: &DataChecked<CurveError>, meta| {
+            self.data_checked(cx, checked.0);
+            meta.consume();
+        });
+        let disabled = cx.is_disabled() || self.data_error.is_some();
+        event.map(|ev: &WindowEvent, _| match *ev {
+            WindowEvent::GeometryChanged(_) => {
+                // Thin out the labels to the ones that fit the new width
+                let ticks = frequency_ticks(&self.frequencies, bounds.w, LABEL_SPACING);
+                for (hz, entity) in &self.labels {
+                    let shown = ticks
+                        .iter()
+                        .any(|tick| tick.value == *hz && tick.label.is_some());
+                    let display = if shown { Display::Flex } else { Display::None };
+                    cx.style.display.insert(*entity, display);
+                }
+            }
+            // Only the labels are kept up to date while disabled or invalid
+            _ if disabled => (),
+            WindowEvent::MouseDown(_) => {
+                // Find the hovered breakpoint before pressing it
+                if let Some(cursor) = self.moves.take() {
+                    self.mouse_move(cx, bounds, cursor);
+                }
+                if let Some(press) = self.presses.read(cx, ev) {
+                    self.press(cx, bounds, press);
+                }
+            }
+            WindowEvent::MouseUp(button) => {
+                // End the drag where the cursor was released
+                if let Some(cursor) = self.moves.take() {
+                    self.mouse_move(cx, bounds, cursor);
+                }
+                if button == MouseButton::Left {
+                    cx.release_active();
+                    if self.is_dragging_point {
+                        self.is_dragging_point = false;
+                        cx.end_edit();
+                    }
+                    cx.hide_tooltip();
+                }
+            }
+            WindowEvent::MouseMove(x, y) => self.moves.push(cx, x, y),
+            // The keys move the last pressed breakpoint in steps of the
+            // editor's size, and delete removes it
+            WindowEvent::KeyDown(code, _) => {
+                let points = self.points.get(cx);
+                if let Some(clipboard) = &self.clipboard {
+                    if clipboard.handle_key(cx, code, &points) {
+                        return;
+                    }
+                }
+                let selected = self.selected_point_id.filter(|i| *i < points.len());
+                if let (Some(index), false) = (selected, self.is_dragging_point) {
+                    if let Code::Delete | Code::Backspace = code {
+                        self.remove_point(cx, index);
+                    } else if let Some(nudge) = KeyNudge::from_event(cx, code) {
+                        let point = &points[index];
+                        let ui = nudge.apply_point(
+                            self.data_to_ui(bounds, Vec2::new(point.x, point.y)),
+                            Vec2::new(bounds.left(), bounds.bottom()),
+                            Vec2::new(bounds.right(), bounds.top()),
+                        );
+                        cx.begin_edit();
+                        self.set_point(cx, bounds, index, ui);
+                        cx.end_edit();
+                    }
+                }
+            }
+            _ => (),
+        });
+        event.map(|_: &CoalescedMove, meta| {
+            if let Some(cursor) = self.moves.take() {
+                if !disabled {
+                    self.mouse_move(cx, bounds, cursor);
+                }
+            }
+            meta.consume();
+        });
+        event.map(|action: &ContextMenuAction, _| {
+            if let Some(clipboard) = &self.clipboard {
+                match action {
+                    ContextMenuAction::Copy => {
+                        let points = self.points.get(cx);
+                        clipboard.copy(cx, &points);
+                    }
+                    ContextMenuAction::Paste => clipboard.paste(cx),
+                    _ => (),
+                }
+            }
+        });
+        let (hovered, dragging) = (self.active_point_id.is_some(), self.is_dragging_point);
+        self.styles
+            .set_point_pseudo_class(cx, PseudoClass::HOVER, hovered && !dragging);
+        self.styles
+            .set_point_pseudo_class(cx, PseudoClass::ACTIVE, dragging);
+        let affordance = if disabled {
+            Affordance::None
+        } else {
+            Affordance::of_point(hovered, dragging)
+        };
+        self.cursor.update(cx, event, affordance);
+        self.notify_state_change(cx, &state);
+    }
+
+    fn draw(&self, cx: &mut DrawContext, canvas: &mut Canvas) {
+        let bounds = cx.bounds();
+        let line_color = cx.border_color().copied().unwrap_or_default();
+
+        let grid_color = self.styles.grid_border_color(cx);
+        let point_color = self.styles.point_border_color(cx);
+        let active_point_color = self.styles.point_background_color(cx);
+
+        if self.data_error.is_some() {
+            draw_invalid(cx, canvas, self.styles.invalid_border_color(cx));
+            return;
+        }
+        cx.draw_background(canvas);
+
+        // Frequency grid lines at each tick
+        let mut grid = vg::Path::new();
+        for tick in frequency_ticks(&self.frequencies, bounds.w, LABEL_SPACING) {
+            let x = bounds.left() + tick.position * bounds.w;
+            grid.move_to(x, bounds.top());
+            grid.line_to(x, bounds.bottom());
+        }
+        canvas.stroke_path(&mut grid, &vg::Paint::color(grid_color.into()));
+
+        self.points.view(cx.data().unwrap(), |points| {
+            let ui_points: Vec<Vec2> = points
+                .unwrap()
+                .iter()
+                .map(|p| self.data_to_ui(bounds, Vec2::new(p.x, p.y)))
+                .collect();
+            let (first, last) = match (ui_points.first(), ui_points.last()) {
+                (Some(first), Some(last)) => (*first, *last),
+                _ => return,
+            };
+
+            // Smooth curve through the breakpoints as a Catmull-Rom spline,
+            // held flat beyond the first and last points
+            let mut curve = vg::Path::new();
+            curve.move_to(bounds.left(), first.y);
+            curve.line_to(first.x, first.y);
+            for i in 1..ui_points.len() {
+                let p0 = ui_points[i.saturating_sub(2)];
+                let p1 = ui_points[i - 1];
+                let p2 = ui_points[i];
+                let p3 = *ui_points.get(i + 1).unwrap_or(&p2);
+                let c1 = p1 + (p2 - p0) / 6f32;
+                let c2 = p2 - (p3 - p1) / 6f32;
+                curve.bezier_to(c1.x, c1.y, c2.x, c2.y, p2.x, p2.y);
+            }
+            curve.line_to(bounds.right(), last.y);
+            canvas.stroke_path(
+                &mut curve,
+                &vg::Paint::color(line_color.into()).with_line_width(2f32),
+            );
+
+            let mut dots = PathBatch::new();
+            for (i, point) in ui_points.iter().enumerate() {
+                let color = if self.active_point_id == Some(i) {
+                    active_point_color
+                } else {
+                    point_color
+                };
+                dots.path(color).circle(point.x, point.y, 4f32);
+            }
+            dots.fill(canvas);
+        });
+        cx.draw_border(canvas);
+        cx.draw_outline(canvas);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{Recorder, TestContext};
+    use assert_approx_eq::assert_approx_eq;
+
+    #[derive(Lens)]
+    struct Bands {
+        points: CurvePoints,
+    }
+
+    impl Model for Bands {}
+
+    #[test]
+    fn inserts_band_points_in_order() {
+        // 20 Hz to 20 kHz across 300 pixels, so each decade is 100 pixels
+        let mut test = TestContext::new(300f32, 100f32);
+        let inserted = Recorder::new();
+        let record = inserted.clone();
+        test.build(|cx| {
+            Bands {
+                points: CurvePoints::new(vec![(100f32, 0.5f32).into(), (1000f32, 0.5f32).into()]),
+            }
+            .build(cx);
+            BandCurveEditor::new(cx, Bands::points, 20f32..=20000f32)
+                .width(Pixels(300f32))
+                .height(Pixels(100f32))
+                .on_insert_point(move |_, index, point| record.push((index, point)));
+        });
+
+        // Between 100 Hz and 1 kHz, away from both breakpoints
+        test.double_click(120f32, 20f32);
+        let (index, point) = inserted.last().unwrap();
+        assert_eq!(index, 1);
+        assert_approx_eq!(point.x, 317f32, 1f32);
+        // Double clicking a breakpoint doesn't insert another
+        test.double_click(70f32, 50f32);
+        assert_eq!(inserted.len(), 1);
+    }
+}
@@ -0,0 +1,285 @@
+use glam::Vec2;
+use lily_derive::Handle;
+use vizia::prelude::*;
+use vizia::vg;
+
+use crate::focus::FocusExt;
+use crate::gesture::GestureExt;
+use crate::tooltip::TooltipExt;
+use crate::util::{CoalescedMove, KeyNudge, MoveCoalescer, PseudoClassExt};
+
+/// The classic tonewheel organ drawbar colors, from the 16' sub-octave to the
+/// 1' harmonic. Each bar gets the matching class so stylesheets can color
+/// them.
+const REGISTER_CLASSES: [&str; 9] = [
+    "brown", "brown", "white", "white", "black", "white", "black", "black", "white",
+];
+
+/// A bank of organ-style inverted sliders. A value of `0` is a bar pushed all
+/// the way in, and `1` is a bar pulled all the way out (down).
+///
+/// Pressing a bar and dragging across its neighbours sets each bar the cursor
+/// passes over, so a whole registration can be drawn in one gesture.
+#[allow(clippy::type_complexity)]
+#[derive(Handle)]
+pub struct Drawbars<L>
+where
+    L: Lens<Target = Vec<f32>>,
+{
+    /// A [`Lens`] of the normalized bar values, from left to right
+    values: L,
+    /// The index of the last bar set while dragging, used to fill in any bars
+    /// skipped over by fast cursor movement
+    last_index: Option<usize>,
+    /// The bar edited from the keyboard, which is the last one pressed until
+    /// the left and right keys pick another
+    selected: usize,
+    /// The last cursor moved to while dragging, until it is handled
+    moves: MoveCoalescer,
+
+    #[callback(usize, f32)]
+    on_changing_bar: Option<Box<dyn Fn(&mut EventContext, usize, f32)>>,
+}
+
+impl<L> Drawbars<L>
+where
+    L: Lens<Target = Vec<f32>>,
+{
+    /// Create a new `Drawbars` bank
+    ///
+    /// # Parameters
+    ///
+    /// * `cx` - the current [`Context`]
+    /// * `values` - a [`Lens`] of the normalized bar values. One bar is shown
+    ///   per value, numbered from 1.
+    pub fn new(cx: &mut Context, values: L) -> Handle<Self> {
+        Self {
+            values: values.clone(),
+            last_index: None,
+            selected: 0,
+            moves: MoveCoalescer::new(),
+            on_changing_bar: None,
+        }
+        .build(cx, |cx| {
+            Binding::new(cx, values.clone().map(|v| v.len()), move |cx, len| {
+                for index in 0..len.get(cx) {
+                    DrawbarBar::new(cx, values.clone().index(index), index)
+                        .class("bar")
+                        .class(REGISTER_CLASSES[index % REGISTER_CLASSES.len()]);
+                }
+            });
+        })
+        .layout_type(LayoutType::Row)
+        .tab_stop()
+    }
+
+    /// Gets the bar index and normalized value under the cursor. Bars are
+    /// assumed to be laid out evenly across the width of the bank.
+    fn value_at(&self, cx: &EventContext, x: f32, y: f32) -> Option<(usize, f32)> {
+        let len = self.values.get(cx).len();
+        if len == 0 {
+            return None;
+        }
+        let rect = cx.cache.get_bounds(cx.current());
+        let index = (((x - rect.left()) / rect.w) * len as f32).floor();
+        let index = (index.max(0f32) as usize).min(len - 1);
+        let value = ((y - rect.top()) / rect.h).clamp(0f32, 1f32);
+        Some((index, value))
+    }
+
+    fn set_bar(&self, cx: &mut EventContext, index: usize, value: f32) {
+        if let Some(callback) = &self.on_changing_bar {
+            (callback)(cx, index, value);
+        }
+        cx.perform_edit(index, &[value]);
+        cx.show_value_tooltip(format!("{}: {:.2}", index + 1, value));
+    }
+
+    /// Sets the bar under the cursor while dragging
+    fn drag_to(&mut self, cx: &mut EventContext, cursor: Vec2) {
+        if let Some(last_index) = self.last_index {
+            if let Some((index, value)) = self.value_at(cx, cursor.x, cursor.y) {
+                // Set every bar between the last one and this one so that
+                // quick sweeps don't leave gaps
+                let (from, to) = if last_index < index {
+                    (last_index + 1, index)
+                } else {
+                    (index, last_index.saturating_sub(1).max(index))
+                };
+                for i in from..=to {
+                    self.set_bar(cx, i, value);
+                }
+                self.last_index = Some(index);
+            }
+        }
+    }
+}
+
+impl<L> View for Drawbars<L>
+where
+    L: Lens<Target = Vec<f32>>,
+{
+    fn element(&self) -> Option<&'static str> {
+        Some("drawbars")
+    }
+
+    fn event(&mut self, cx: &mut EventContext, event: &mut Event) {
+        if cx.is_disabled() {
+            return;
+        }
+        event.map(|ev: &WindowEvent, _| match *ev {
+            WindowEvent::MouseDown(button) => {
+                if button == MouseButton::Left {
+                    cx.capture_active();
+                    if let Some((index, value)) =
+                        self.value_at(cx, cx.mouse.cursorx, cx.mouse.cursory)
+                    {
+                        self.last_index = Some(index);
+                        self.selected = index;
+                        cx.begin_edit();
+                        self.set_bar(cx, index, value);
+                    }
+                }
+            }
+            WindowEvent::MouseMove(x, y) => {
+                if self.last_index.is_some() {
+                    self.moves.push(cx, x, y);
+                }
+            }
+            WindowEvent::MouseUp(button) => {
+                if button == MouseButton::Left {
+                    // End the drag where the cursor was released
+                    if let Some(cursor) = self.moves.take() {
+                        self.drag_to(cx, cursor);
+                    }
+                    cx.release_active();
+                    if self.last_index.take().is_some() {
+                        cx.end_edit();
+                    }
+                    cx.hide_tooltip();
+                }
+            }
+            WindowEvent::KeyDown(code, _) => {
+                let values = self.values.get(cx);
+                if values.is_empty() {
+                    return;
+                }
+                let index = self.selected.min(values.len() - 1);
+                let value = match KeyNudge::from_event(cx, code) {
+                    // left and right pick a bar and show its value
+                    Some(KeyNudge::Step(step)) if step.x != 0f32 => {
+                        let index = if step.x < 0f32 {
+                            index.saturating_sub(1)
+                        } else {
+                            (index + 1).min(values.len() - 1)
+                        };
+                        self.selected = index;
+                        cx.show_value_tooltip(format!("{}: {:.2}", index + 1, values[index]));
+                        return;
+                    }
+                    // the bars are pulled out downwards
+                    Some(KeyNudge::Step(step)) => (values[index] - step.y).clamp(0f32, 1f32),
+                    Some(KeyNudge::Start) => 0f32,
+                    Some(KeyNudge::End) => 1f32,
+                    None => return,
+                };
+                cx.begin_edit();
+                self.set_bar(cx, index, value);
+                cx.end_edit();
+            }
+            _ => (),
+        });
+        event.map(|_: &CoalescedMove, meta| {
+            if let Some(cursor) = self.moves.take() {
+                self.drag_to(cx, cursor);
+            }
+            meta.consume();
+        });
+    }
+}
+
+/// A single drawbar. The bar is drawn with the background color and its tip
+/// with the border color, both taken from the register class.
+struct DrawbarBar<L>
+where
+    L: Lens<Target = f32>,
+{
+    value: L,
+}
+
+impl<L> DrawbarBar<L>
+where
+    L: Lens<Target = f32>,
+{
+    fn new(cx: &mut Context, value: L, index: usize) -> Handle<Self> {
+        Self { value }.build(cx, |cx| {
+            Label::new(cx, &(index + 1).to_string())
+                .class("number")
+                .width(Stretch(1f32))
+                .height(Auto);
+        })
+    }
+}
+
+impl<L> View for DrawbarBar<L>
+where
+    L: Lens<Target = f32>,
+{
+    fn draw(&self, cx: &mut DrawContext, canvas: &mut Canvas) {
+        let rect = cx.bounds();
+        let bar_color = cx.background_color().copied().unwrap_or_default();
+        let tip_color = cx.border_color().copied().unwrap_or_default();
+
+        let value = self
+            .value
+            .view(cx.data().unwrap(), |value| {
+                value.copied().unwrap_or_default()
+            })
+            .clamp(0f32, 1f32);
+        let height = rect.h * value;
+
+        // Bar pulled out from the top
+        let mut path = vg::Path::new();
+        path.rect(rect.x, rect.y, rect.w, height);
+        canvas.fill_path(&mut path, &vg::Paint::color(bar_color.into()));
+
+        // Tip of the bar
+        let tip_height = 4f32.min(height);
+        let mut path = vg::Path::new();
+        path.rect(rect.x, rect.y + height - tip_height, rect.w, tip_height);
+        canvas.fill_path(&mut path, &vg::Paint::color(tip_color.into()));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    #[cfg(feature = "golden")]
+    use crate::testing::assert_golden;
+    use crate::testing::TestContext;
+
+    #[derive(Lens)]
+    struct Registration {
+        bars: Vec<f32>,
+    }
+
+    impl Model for Registration {}
+
+    #[cfg(feature = "golden")]
+    #[test]
+    #[ignore = "reference images not recorded yet, record them with LILY_BLESS=1"]
+    fn renders_drawbars() {
+        let mut test = TestContext::new(180f32, 120f32);
+        test.build(|cx| {
+            cx.add_theme(crate::DEFAULT_STYLE);
+            Registration {
+                bars: vec![1f32, 0.75f32, 0.5f32, 0f32, 0.25f32, 0f32, 0f32, 0f32, 0f32],
+            }
+            .build(cx);
+            Drawbars::new(cx, Registration::bars)
+                .width(Pixels(180f32))
+                .height(Pixels(120f32));
+        });
+        assert_golden("drawbars", &test.render());
+    }
+}
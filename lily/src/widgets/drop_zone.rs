@@ -0,0 +1,129 @@
+use std::path::PathBuf;
+
+use lily_derive::Handle;
+use vizia::prelude::*;
+use vizia::vg;
+
+use super::waveform::draw_peaks;
+use crate::util::{FrameExt, PseudoClassExt};
+
+/// What a [`DropZone`] is currently showing
+#[derive(Clone, Debug, PartialEq, Data)]
+pub enum DropZoneState {
+    /// Nothing has been loaded, a placeholder is shown
+    Empty,
+    /// A file was dropped and is being loaded
+    Loading,
+    /// A file is loaded, with its peaks normalized to `0..=1` for the preview
+    Loaded(Vec<f32>),
+}
+
+impl DropZoneState {
+    fn text(&self) -> String {
+        match self {
+            DropZoneState::Empty => String::from("Drop a file here"),
+            DropZoneState::Loading => String::from("Loading..."),
+            DropZoneState::Loaded(_) => String::new(),
+        }
+    }
+}
+
+/// Accepts files dragged onto it and previews the loaded audio as a waveform,
+/// for sampler and impulse response loaders.
+///
+/// Dropping a file only emits `on_drop`. It is up to the host to set the
+/// state to [`DropZoneState::Loading`] while it loads the file and then to
+/// [`DropZoneState::Loaded`] with the peaks to preview.
+#[allow(clippy::type_complexity)]
+#[derive(Handle)]
+pub struct DropZone<S>
+where
+    S: Lens<Target = DropZoneState>,
+{
+    state: S,
+    /// Whether a file is currently being dragged over the zone
+    drag_over: bool,
+
+    #[callback(PathBuf)]
+    on_drop: Option<Box<dyn Fn(&mut EventContext, PathBuf)>>,
+}
+
+impl<S> DropZone<S>
+where
+    S: Lens<Target = DropZoneState>,
+{
+    /// Create a new `DropZone`
+    ///
+    /// # Parameters
+    ///
+    /// * `cx` - the current [`Context`]
+    /// * `state` - a [`Lens`] of what the zone is showing
+    pub fn new(cx: &mut Context, state: S) -> Handle<Self> {
+        Self {
+            state: state.clone(),
+            drag_over: false,
+            on_drop: None,
+        }
+        .build(cx, |cx| {
+            Label::new(cx, state.map(DropZoneState::text))
+                .class("placeholder")
+                .width(Stretch(1f32))
+                .height(Stretch(1f32));
+        })
+    }
+}
+
+impl<S> View for DropZone<S>
+where
+    S: Lens<Target = DropZoneState>,
+{
+    fn element(&self) -> Option<&'static str> {
+        Some("drop-zone")
+    }
+
+    fn event(&mut self, cx: &mut EventContext, event: &mut Event) {
+        if cx.is_disabled() {
+            return;
+        }
+        event.map(|ev: &WindowEvent, _| match ev {
+            WindowEvent::DragEnter => self.drag_over = true,
+            WindowEvent::DragLeave => self.drag_over = false,
+            WindowEvent::Drop(DropData::File(path)) => {
+                self.drag_over = false;
+                if let Some(callback) = &self.on_drop {
+                    (callback)(cx, path.clone());
+                }
+            }
+            _ => (),
+        });
+    }
+
+    fn draw(&self, cx: &mut DrawContext, canvas: &mut Canvas) {
+        let bounds = cx.bounds();
+        let border = cx.border_color().copied().unwrap_or_default();
+
+        cx.draw_background(canvas);
+
+        self.state.view(cx.data().unwrap(), |state| {
+            if let Some(DropZoneState::Loaded(peaks)) = state {
+                draw_peaks(canvas, bounds, peaks, border);
+            }
+        });
+
+        // Highlight the zone while a file is dragged over it
+        if self.drag_over {
+            let mut path = vg::Path::new();
+            path.rect(
+                bounds.x + 1f32,
+                bounds.y + 1f32,
+                bounds.w - 2f32,
+                bounds.h - 2f32,
+            );
+            canvas.stroke_path(
+                &mut path,
+                &vg::Paint::color(border.into()).with_line_width(2f32),
+            );
+        }
+        cx.draw_border(canvas);
+    }
+}
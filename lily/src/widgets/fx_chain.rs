@@ -0,0 +1,227 @@
+use std::marker::PhantomData;
+
+use lily_derive::Handle;
+use vizia::prelude::*;
+use vizia::vg;
+
+use crate::util::{FrameExt, PseudoClassExt, ScaleExt};
+
+/// The distance in logical pixels the cursor must travel with a slot pressed
+/// before it is dragged rather than selected
+const DRAG_THRESHOLD: f32 = 4f32;
+
+/// Describes an effect or plugin in a chain
+#[derive(Clone, Debug, PartialEq, Data)]
+pub struct FxSlot {
+    pub name: String,
+    pub bypassed: bool,
+}
+
+impl FxSlot {
+    pub fn new(name: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            bypassed: false,
+        }
+    }
+}
+
+enum FxChainEvent {
+    Bypass(usize),
+    Remove(usize),
+}
+
+/// A vertical list of effect slots that can be reordered by dragging, and
+/// bypassed, removed or selected individually. Slots are assumed to share the
+/// height of the list evenly, so the list should be sized to fit them.
+#[allow(clippy::type_complexity)]
+#[derive(Handle)]
+pub struct FxChain<L, S>
+where
+    L: Lens<Target = Vec<FxSlot>>,
+    S: Lens<Target = Option<usize>>,
+{
+    slots: L,
+    selected: PhantomData<S>,
+    /// The index of the pressed slot and the cursor y position when pressed
+    pressed: Option<(usize, f32)>,
+    /// The index the pressed slot would be moved to if released now, set once
+    /// the cursor has moved past the drag threshold
+    drop_index: Option<usize>,
+
+    #[callback(usize, usize)]
+    on_reorder: Option<Box<dyn Fn(&mut EventContext, usize, usize)>>,
+
+    #[callback(usize, bool)]
+    on_bypass: Option<Box<dyn Fn(&mut EventContext, usize, bool)>>,
+
+    #[callback(usize)]
+    on_remove: Option<Box<dyn Fn(&mut EventContext, usize)>>,
+
+    #[callback(usize)]
+    on_select: Option<Box<dyn Fn(&mut EventContext, usize)>>,
+}
+
+impl<L, S> FxChain<L, S>
+where
+    L: Lens<Target = Vec<FxSlot>>,
+    S: Lens<Target = Option<usize>>,
+{
+    /// Create a new `FxChain`
+    ///
+    /// # Parameters
+    ///
+    /// * `cx` - the current [`Context`]
+    /// * `slots` - a [`Lens`] of the [`FxSlot`]s in processing order
+    /// * `selected` - a [`Lens`] of the index of the selected slot, if any
+    pub fn new(cx: &mut Context, slots: L, selected: S) -> Handle<Self> {
+        Self {
+            slots: slots.clone(),
+            selected: PhantomData::default(),
+            pressed: None,
+            drop_index: None,
+            on_reorder: None,
+            on_bypass: None,
+            on_remove: None,
+            on_select: None,
+        }
+        .build(cx, |cx| {
+            Binding::new(cx, slots.clone().map(|s| s.len()), move |cx, len| {
+                for index in 0..len.get(cx) {
+                    let slot = slots.clone().index(index);
+                    HStack::new(cx, |cx| {
+                        Button::new(
+                            cx,
+                            move |cx| cx.emit(FxChainEvent::Bypass(index)),
+                            |cx| Label::new(cx, "\u{23fb}"),
+                        )
+                        .class("bypass");
+                        Label::new(cx, slot.clone().map(|s| s.name.clone())).class("name");
+                        Button::new(
+                            cx,
+                            move |cx| cx.emit(FxChainEvent::Remove(index)),
+                            |cx| Label::new(cx, "\u{00d7}"),
+                        )
+                        .class("remove");
+                    })
+                    .class("slot")
+                    .toggle_class("bypassed", slot.map(|s| s.bypassed))
+                    .toggle_class("selected", selected.clone().map(move |s| *s == Some(index)));
+                }
+            });
+        })
+    }
+
+    /// Gets the index of the slot under a y position
+    fn index_at(&self, cx: &EventContext, y: f32) -> Option<usize> {
+        let len = self.slots.get(cx).len();
+        if len == 0 {
+            return None;
+        }
+        let bounds = cx.cache.get_bounds(cx.current());
+        let index = (((y - bounds.top()) / bounds.h) * len as f32).floor();
+        Some((index.max(0f32) as usize).min(len - 1))
+    }
+}
+
+impl<L, S> View for FxChain<L, S>
+where
+    L: Lens<Target = Vec<FxSlot>>,
+    S: Lens<Target = Option<usize>>,
+{
+    fn element(&self) -> Option<&'static str> {
+        Some("fx-chain")
+    }
+
+    fn event(&mut self, cx: &mut EventContext, event: &mut Event) {
+        if cx.is_disabled() {
+            return;
+        }
+        let drag_threshold = cx.to_physical(DRAG_THRESHOLD);
+        event.map(|ev: &FxChainEvent, _| match *ev {
+            FxChainEvent::Bypass(index) => {
+                let bypassed = self
+                    .slots
+                    .get(cx)
+                    .get(index)
+                    .map(|s| s.bypassed)
+                    .unwrap_or_default();
+                if let Some(callback) = &self.on_bypass {
+                    (callback)(cx, index, !bypassed);
+                }
+            }
+            FxChainEvent::Remove(index) => {
+                if let Some(callback) = &self.on_remove {
+                    (callback)(cx, index);
+                }
+            }
+        });
+
+        event.map(|ev: &WindowEvent, _| match *ev {
+            WindowEvent::MouseDown(button) => {
+                if button == MouseButton::Left {
+                    if let Some(index) = self.index_at(cx, cx.mouse.cursory) {
+                        cx.capture_active();
+                        self.pressed = Some((index, cx.mouse.cursory));
+                    }
+                }
+            }
+            WindowEvent::MouseMove(_, y) => {
+                if let Some((index, pressed_y)) = self.pressed {
+                    if self.drop_index.is_some() || (y - pressed_y).abs() > drag_threshold {
+                        self.drop_index = self.index_at(cx, y).or(Some(index));
+                    }
+                }
+            }
+            WindowEvent::MouseUp(button) => {
+                if button == MouseButton::Left {
+                    if let Some((index, _)) = self.pressed.take() {
+                        cx.release_active();
+                        match self.drop_index.take() {
+                            Some(to) if to != index => {
+                                if let Some(callback) = &self.on_reorder {
+                                    (callback)(cx, index, to);
+                                }
+                            }
+                            Some(_) => (),
+                            None => {
+                                if let Some(callback) = &self.on_select {
+                                    (callback)(cx, index);
+                                }
+                            }
+                        }
+                    }
+                }
+            }
+            _ => (),
+        });
+    }
+
+    fn draw(&self, cx: &mut DrawContext, canvas: &mut Canvas) {
+        let bounds = cx.bounds();
+        let indicator = cx.border_color().copied().unwrap_or_default();
+
+        cx.draw_background(canvas);
+
+        // Draw where the dragged slot will be dropped
+        if let (Some((from, _)), Some(to)) = (self.pressed, self.drop_index) {
+            let len = self
+                .slots
+                .view(cx.data().unwrap(), |slots| slots.map(|s| s.len()))
+                .unwrap_or_default()
+                .max(1);
+            let row_height = bounds.h / len as f32;
+            // Dropping below the pressed slot inserts after the target slot
+            let row = if to > from { to + 1 } else { to };
+            let y = bounds.top() + row_height * row as f32;
+            let mut path = vg::Path::new();
+            path.move_to(bounds.left(), y);
+            path.line_to(bounds.right(), y);
+            canvas.stroke_path(
+                &mut path,
+                &vg::Paint::color(indicator.into()).with_line_width(2f32),
+            );
+        }
+        cx.draw_border(canvas);
+    }
+}
@@ -0,0 +1,168 @@
+use std::ops::RangeInclusive;
+
+use lily_derive::Handle;
+use vizia::prelude::*;
+use vizia::vg;
+
+use super::waveform::draw_peaks;
+use crate::util::FrameExt;
+
+/// The pitch in semitones drawn at the top and bottom edges of the cloud
+const PITCH_RANGE: f32 = 24f32;
+/// The radius in pixels of a grain at full amplitude
+const MAX_RADIUS: f32 = 8f32;
+
+/// A single grain being played by a granular engine
+#[derive(Clone, Copy, Debug, PartialEq, Data)]
+pub struct Grain {
+    /// The normalized read position in the source, `0..=1`
+    pub position: f32,
+    /// The pitch offset in semitones
+    pub pitch: f32,
+    /// The normalized amplitude, `0..=1`
+    pub amplitude: f32,
+    /// How far through its life the grain is, from `0` when it starts to `1`
+    /// when it ends
+    pub age: f32,
+}
+
+impl From<(f32, f32, f32, f32)> for Grain {
+    fn from((position, pitch, amplitude, age): (f32, f32, f32, f32)) -> Self {
+        Self {
+            position,
+            pitch,
+            amplitude,
+            age,
+        }
+    }
+}
+
+/// How grains are colored
+#[derive(Clone, Debug, PartialEq)]
+pub enum GrainColor {
+    /// Every grain uses the background color of the `grain` class
+    Uniform,
+    /// Grains are blended between two colors by pitch
+    Pitch {
+        low: Color,
+        high: Color,
+        /// The pitches in semitones mapped to `low` and `high`
+        range: RangeInclusive<f32>,
+    },
+}
+
+/// Renders the active grains of a granular engine as fading particles over
+/// the source waveform. Grains are placed by read position (x) and pitch (y)
+/// and sized by amplitude.
+#[derive(Handle)]
+#[style_class("grain", "waveform")]
+pub struct GrainCloud<G, W>
+where
+    G: Lens<Target = Vec<Grain>>,
+    W: Lens<Target = Vec<f32>>,
+{
+    grains: G,
+    /// A [`Lens`] of the source waveform peaks, normalized to `0..=1`
+    waveform: W,
+    /// The exponent applied to a grain's remaining life to get its opacity.
+    /// `1.0` fades linearly, higher values fade out sooner.
+    fade: f32,
+    color: GrainColor,
+    styles: GrainCloudStyles,
+}
+
+impl<G, W> GrainCloud<G, W>
+where
+    G: Lens<Target = Vec<Grain>>,
+    W: Lens<Target = Vec<f32>>,
+{
+    /// Create a new `GrainCloud`
+    ///
+    /// # Parameters
+    ///
+    /// * `cx` - the current [`Context`]
+    /// * `grains` - a [`Lens`] of the currently active [`Grain`]s
+    /// * `waveform` - a [`Lens`] of the source waveform peaks, normalized to
+    ///   `0..=1`, drawn mirrored around the center
+    /// * `fade` - the exponent applied to a grain's remaining life to get its
+    ///   opacity. `1.0` fades linearly, higher values fade out sooner.
+    /// * `color` - how the grains are colored
+    pub fn new(
+        cx: &mut Context,
+        grains: G,
+        waveform: W,
+        fade: f32,
+        color: GrainColor,
+    ) -> Handle<Self> {
+        Self {
+            grains,
+            waveform,
+            fade,
+            color,
+            styles: GrainCloudStyles::new(cx),
+        }
+        .build(cx, |_| {})
+    }
+
+    fn grain_color(&self, grain: &Grain, uniform: Color) -> Color {
+        let color = match &self.color {
+            GrainColor::Uniform => uniform,
+            GrainColor::Pitch { low, high, range } => {
+                let t = ((grain.pitch - range.start()) / (range.end() - range.start()))
+                    .clamp(0f32, 1f32);
+                let lerp = |a: u8, b: u8| (a as f32 + (b as f32 - a as f32) * t) as u8;
+                Color::rgba(
+                    lerp(low.r(), high.r()),
+                    lerp(low.g(), high.g()),
+                    lerp(low.b(), high.b()),
+                    lerp(low.a(), high.a()),
+                )
+            }
+        };
+        let life = (1f32 - grain.age).clamp(0f32, 1f32).powf(self.fade);
+        let alpha = color.a() as f32 * life * grain.amplitude.clamp(0f32, 1f32);
+        Color::rgba(color.r(), color.g(), color.b(), alpha as u8)
+    }
+}
+
+impl<G, W> View for GrainCloud<G, W>
+where
+    G: Lens<Target = Vec<Grain>>,
+    W: Lens<Target = Vec<f32>>,
+{
+    fn element(&self) -> Option<&'static str> {
+        Some("grain-cloud")
+    }
+
+    fn draw(&self, cx: &mut DrawContext, canvas: &mut Canvas) {
+        let bounds = cx.bounds();
+        let center_y = bounds.top() + bounds.h / 2f32;
+
+        let waveform_color = self.styles.waveform_background_color(cx);
+        let grain_color = self.styles.grain_background_color(cx);
+
+        cx.draw_background(canvas);
+
+        // Source waveform, mirrored around the center
+        self.waveform.view(cx.data().unwrap(), |waveform| {
+            draw_peaks(canvas, bounds, waveform.unwrap(), waveform_color);
+        });
+
+        // Grains
+        self.grains.view(cx.data().unwrap(), |grains| {
+            for grain in grains.unwrap() {
+                let x = bounds.left() + grain.position.clamp(0f32, 1f32) * bounds.w;
+                let pitch = (grain.pitch / PITCH_RANGE).clamp(-1f32, 1f32);
+                let y = center_y - pitch * bounds.h / 2f32;
+                let radius = 1f32 + grain.amplitude.clamp(0f32, 1f32) * (MAX_RADIUS - 1f32);
+                let mut path = vg::Path::new();
+                path.circle(x, y, radius);
+                canvas.fill_path(
+                    &mut path,
+                    &vg::Paint::color(self.grain_color(grain, grain_color).into()),
+                );
+            }
+        });
+        cx.draw_border(canvas);
+    }
+}
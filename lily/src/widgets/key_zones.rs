@@ -0,0 +1,348 @@
+use std::ops::RangeInclusive;
+
+use lily_derive::Handle;
+use vizia::cache::BoundingBox;
+use vizia::prelude::*;
+use vizia::vg;
+
+use crate::focus::FocusExt;
+use crate::gesture::GestureExt;
+use crate::tooltip::TooltipExt;
+use crate::units::note_name;
+use crate::util::{FrameExt, KeyNudge, PseudoClassExt, ScaleExt};
+
+/// The default distance in logical pixels before a zone boundary is considered
+/// hovered
+const HOVER_RADIUS: f32 = 4f32;
+/// The fraction of the editor height taken up by the zone lanes. The rest is
+/// used to draw the keyboard.
+const ZONE_AREA: f32 = 0.6;
+
+/// A zone of keys mapped to one timbre or sample
+#[derive(Clone, Debug, PartialEq, Data)]
+pub struct KeyZone {
+    /// The lowest MIDI note of the zone
+    pub low: u8,
+    /// The highest MIDI note of the zone, inclusive
+    pub high: u8,
+    /// The name drawn on the zone
+    pub label: String,
+    /// The zone color. If `None`, the color of the `zone` class is used
+    pub color: Option<Color>,
+}
+
+impl KeyZone {
+    /// Creates a zone covering `keys`, which may be given in either order
+    pub fn new(label: impl Into<String>, keys: RangeInclusive<u8>) -> Self {
+        let (start, end) = keys.into_inner();
+        Self {
+            low: start.min(end),
+            high: start.max(end),
+            label: label.into(),
+            color: None,
+        }
+    }
+
+    pub fn with_color(mut self, color: Color) -> Self {
+        self.color = Some(color);
+        self
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Edge {
+    Low,
+    High,
+}
+
+/// Renders a range of keys with zones above them. The edges of each zone can
+/// be dragged to change which keys it covers.
+#[allow(clippy::type_complexity)]
+#[derive(Handle)]
+#[style_class("white-key", "black-key")]
+pub struct KeyZoneEditor<Z>
+where
+    Z: Lens<Target = Vec<KeyZone>>,
+{
+    /// A [`Lens`] of the zones. Each zone is drawn in its own lane, so zones
+    /// are free to overlap.
+    zones: Z,
+    /// The range of MIDI notes shown
+    keys: RangeInclusive<u8>,
+    /// The zone index and edge currently hovered or being dragged
+    active_edge: Option<(usize, Edge)>,
+    /// The zone edge edited from the keyboard, the last one pressed
+    selected_edge: Option<(usize, Edge)>,
+    /// Whether we are in the process of dragging a zone edge
+    is_dragging: bool,
+    styles: KeyZoneEditorStyles,
+    /// The distance in logical pixels before a zone boundary is considered
+    /// hovered
+    #[property(res)]
+    hover_radius: f32,
+
+    #[callback(usize, u8, u8)]
+    on_changing_zone: Option<Box<dyn Fn(&mut EventContext, usize, u8, u8)>>,
+}
+
+impl<Z> KeyZoneEditor<Z>
+where
+    Z: Lens<Target = Vec<KeyZone>>,
+{
+    /// Create a new `KeyZoneEditor`
+    ///
+    /// # Parameters
+    ///
+    /// * `cx` - the current [`Context`]
+    /// * `zones` - a [`Lens`] of the [`KeyZone`]s to edit
+    /// * `keys` - the range of MIDI notes to show, e.g. `21..=108` for an 88
+    ///   key piano. A reversed range shows the same keys.
+    pub fn new(cx: &mut Context, zones: Z, keys: RangeInclusive<u8>) -> Handle<Self> {
+        let (start, end) = keys.into_inner();
+        let keys = start.min(end)..=start.max(end);
+        let key_count = (keys.end() - keys.start()) as f32 + 1f32;
+        let first_key = *keys.start();
+        Self {
+            zones: zones.clone(),
+            keys,
+            active_edge: None,
+            selected_edge: None,
+            is_dragging: false,
+            styles: KeyZoneEditorStyles::new(cx),
+            hover_radius: HOVER_RADIUS,
+            on_changing_zone: None,
+        }
+        .build(cx, |cx| {
+            Binding::new(cx, zones.clone(), move |cx, zones| {
+                let zones = zones.get(cx);
+                let lane_height = ZONE_AREA * 100f32 / zones.len().max(1) as f32;
+                for (i, zone) in zones.iter().enumerate() {
+                    let left = (zone.low.saturating_sub(first_key)) as f32 / key_count;
+                    let width = (zone.high as f32 - zone.low as f32 + 1f32) / key_count;
+                    let handle = Label::new(cx, &zone.label)
+                        .class("zone")
+                        .position_type(PositionType::SelfDirected)
+                        .left(Percentage(left * 100f32))
+                        .width(Percentage(width * 100f32))
+                        .top(Percentage(lane_height * i as f32))
+                        .height(Percentage(lane_height));
+                    if let Some(color) = zone.color {
+                        handle.background_color(color);
+                    }
+                }
+            });
+        })
+        .tab_stop()
+    }
+
+    fn key_count(&self) -> f32 {
+        (self.keys.end() - self.keys.start()) as f32 + 1f32
+    }
+
+    /// Gets the x position of a key edge. `key` may be one past the last key
+    /// to get its right edge.
+    fn key_to_x(&self, bounds: BoundingBox, key: f32) -> f32 {
+        bounds.left() + ((key - *self.keys.start() as f32) / self.key_count()) * bounds.w
+    }
+
+    /// Moves an edge of a zone to a key edge, keeping the zone at least one
+    /// key wide
+    fn set_edge(&self, cx: &mut EventContext, index: usize, zone: &KeyZone, edge: Edge, key: f32) {
+        let (low, high) = match edge {
+            Edge::Low => {
+                let low = key.clamp(*self.keys.start() as f32, zone.high as f32);
+                (low as u8, zone.high)
+            }
+            Edge::High => {
+                let high = (key - 1f32).clamp(zone.low as f32, *self.keys.end() as f32);
+                (zone.low, high as u8)
+            }
+        };
+        if let Some(callback) = &self.on_changing_zone {
+            (callback)(cx, index, low, high);
+        }
+        cx.perform_edit(index, &[low as f32, high as f32]);
+        cx.show_value_tooltip(format!("{} - {}", note_name(low), note_name(high)));
+    }
+
+    /// Gets the nearest key edge to an x position
+    fn x_to_key_edge(&self, bounds: BoundingBox, x: f32) -> f32 {
+        (((x - bounds.left()) / bounds.w) * self.key_count()).round() + *self.keys.start() as f32
+    }
+}
+
+fn is_black_key(note: u8) -> bool {
+    matches!(note % 12, 1 | 3 | 6 | 8 | 10)
+}
+
+impl<Z> View for KeyZoneEditor<Z>
+where
+    Z: Lens<Target = Vec<KeyZone>>,
+{
+    fn element(&self) -> Option<&'static str> {
+        Some("key-zones")
+    }
+
+    fn event(&mut self, cx: &mut EventContext, event: &mut Event) {
+        if cx.is_disabled() {
+            return;
+        }
+        let hover_radius = cx.to_physical(self.hover_radius);
+        event.map(|ev: &WindowEvent, _| match *ev {
+            WindowEvent::MouseMove(x, _) => {
+                let bounds = cx.cache.get_bounds(cx.current());
+                let zones = self.zones.get(cx);
+                if self.is_dragging {
+                    if let Some((index, edge)) = self.active_edge {
+                        if let Some(zone) = zones.get(index) {
+                            let key = self.x_to_key_edge(bounds, x);
+                            self.set_edge(cx, index, zone, edge, key);
+                        }
+                    }
+                } else {
+                    // Find the closest zone edge within the hover radius
+                    self.active_edge = zones
+                        .iter()
+                        .enumerate()
+                        .flat_map(|(i, zone)| {
+                            [
+                                ((i, Edge::Low), self.key_to_x(bounds, zone.low as f32)),
+                                (
+                                    (i, Edge::High),
+                                    self.key_to_x(bounds, zone.high as f32 + 1f32),
+                                ),
+                            ]
+                        })
+                        .map(|(edge, edge_x)| (edge, (edge_x - x).abs()))
+                        .filter(|(_, distance)| *distance <= hover_radius)
+                        .min_by(|a, b| a.1.total_cmp(&b.1))
+                        .map(|(edge, _)| edge);
+                }
+            }
+            WindowEvent::MouseDown(button) => {
+                if button == MouseButton::Left && self.active_edge.is_some() {
+                    self.selected_edge = self.active_edge;
+                    cx.capture_active();
+                    self.is_dragging = true;
+                    cx.begin_edit();
+                }
+            }
+            WindowEvent::MouseUp(button) => {
+                if button == MouseButton::Left && self.is_dragging {
+                    cx.release_active();
+                    self.is_dragging = false;
+                    cx.end_edit();
+                    cx.hide_tooltip();
+                }
+            }
+            // The keys move the last pressed edge one key at a time
+            WindowEvent::KeyDown(code, _) => {
+                let zones = self.zones.get(cx);
+                let selected = self
+                    .selected_edge
+                    .and_then(|(index, edge)| Some((index, edge, zones.get(index)?)));
+                let nudge = KeyNudge::from_event(cx, code);
+                if let (Some((index, edge, zone)), Some(nudge), false) =
+                    (selected, nudge, self.is_dragging)
+                {
+                    let key = match edge {
+                        Edge::Low => zone.low as f32,
+                        Edge::High => zone.high as f32 + 1f32,
+                    };
+                    let key = match nudge {
+                        KeyNudge::Step(step) => key + (step.x + step.y).signum(),
+                        KeyNudge::Start => *self.keys.start() as f32,
+                        KeyNudge::End => *self.keys.end() as f32 + 1f32,
+                    };
+                    cx.begin_edit();
+                    self.set_edge(cx, index, zone, edge, key);
+                    cx.end_edit();
+                }
+            }
+            _ => (),
+        });
+    }
+
+    fn draw(&self, cx: &mut DrawContext, canvas: &mut Canvas) {
+        let bounds = cx.bounds();
+        let border = cx.border_color().copied().unwrap_or_default();
+
+        cx.draw_background(canvas);
+
+        // Keyboard, one column per key so that zone edges line up with keys
+        let keyboard_top = bounds.top() + bounds.h * ZONE_AREA;
+        let keyboard_height = bounds.bottom() - keyboard_top;
+        let key_width = bounds.w / self.key_count();
+        let mut white_keys = vg::Path::new();
+        let mut black_keys = vg::Path::new();
+        for note in self.keys.clone() {
+            let x = self.key_to_x(bounds, note as f32);
+            if is_black_key(note) {
+                black_keys.rect(x, keyboard_top, key_width, keyboard_height);
+            } else {
+                white_keys.rect(x, keyboard_top, key_width, keyboard_height);
+            }
+        }
+        let white_color = self.styles.white_key_background_color(cx);
+        let black_color = self.styles.black_key_background_color(cx);
+        canvas.fill_path(&mut white_keys, &vg::Paint::color(white_color.into()));
+        canvas.fill_path(&mut black_keys, &vg::Paint::color(black_color.into()));
+        canvas.stroke_path(&mut white_keys, &vg::Paint::color(border.into()));
+
+        // Highlight the hovered or dragged edge
+        if let Some((index, edge)) = self.active_edge {
+            self.zones.view(cx.data().unwrap(), |zones| {
+                if let Some(zone) = zones.unwrap().get(index) {
+                    let key = match edge {
+                        Edge::Low => zone.low as f32,
+                        Edge::High => zone.high as f32 + 1f32,
+                    };
+                    let x = self.key_to_x(bounds, key);
+                    let mut path = vg::Path::new();
+                    path.move_to(x, bounds.top());
+                    path.line_to(x, bounds.bottom());
+                    canvas.stroke_path(
+                        &mut path,
+                        &vg::Paint::color(border.into()).with_line_width(2f32),
+                    );
+                }
+            });
+        }
+        cx.draw_border(canvas);
+        cx.draw_outline(canvas);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{Recorder, TestContext};
+
+    #[derive(Lens)]
+    struct Mapping {
+        zones: Vec<KeyZone>,
+    }
+
+    impl Model for Mapping {}
+
+    #[test]
+    fn orders_reversed_key_ranges() {
+        let zone = KeyZone::new("Lower", 66..=60);
+        assert_eq!((zone.low, zone.high), (60, 66));
+
+        // 72 down to 60 shows the 13 keys from 60, 10 pixels each
+        let mut test = TestContext::new(130f32, 100f32);
+        let changes = Recorder::new();
+        let record = changes.clone();
+        test.build(|cx| {
+            Mapping { zones: vec![zone] }.build(cx);
+            KeyZoneEditor::new(cx, Mapping::zones, 72..=60)
+                .width(Pixels(130f32))
+                .height(Pixels(100f32))
+                .on_changing_zone(move |_, index, low, high| record.push((index, low, high)));
+        });
+
+        test.drag((70f32, 10f32), (100f32, 10f32));
+        assert_eq!(changes.last(), Some((0, 60, 69)));
+    }
+}
@@ -0,0 +1,540 @@
+use crate::context_menu::{ContextMenuAction, ContextMenuExt, ContextMenuItem};
+use crate::focus::FocusExt;
+use crate::format::{Decimal, ValueFormatter};
+use crate::gesture::GestureExt;
+use crate::input::{InputAction, InputMapExt, PressReader};
+use crate::param::NormalizedParam;
+use crate::tooltip::TooltipExt;
+use crate::util::{
+    CoalescedMove, DragGesture, FrameExt, KeyNudge, MoveCoalescer, RedrawExt, RotaryDrag,
+    ValueMapping,
+};
+use glam::Vec2;
+use lily_derive::Handle;
+use std::f32::consts::PI;
+use std::ops::RangeInclusive;
+use std::rc::Rc;
+use vizia::prelude::*;
+use vizia::vg::{Paint, Path, Solidity};
+
+/// The angle in radians of the start of the range, at the bottom left. The
+/// y axis points down, so angles grow clockwise from the right.
+const START_ANGLE: f32 = 0.75f32 * PI;
+/// The angle in radians the range sweeps clockwise from the start
+const SWEEP: f32 = 1.5f32 * PI;
+/// The thickness in pixels of the track when the `.track` class sets no
+/// `width`
+const TRACK_WIDTH: f32 = 4f32;
+/// The thickness in pixels of the pointer when the `.pointer` class sets no
+/// `width`
+const POINTER_WIDTH: f32 = 2f32;
+/// Where the pointer starts, as a fraction of the radius of the track
+const POINTER_START: f32 = 0.3f32;
+/// The length in pixels of the ticks of stepped knobs when the `.tick` class
+/// sets no `width`
+const TICK_LENGTH: f32 = 4f32;
+/// The gap in pixels between the track and the ticks
+const TICK_GAP: f32 = 2f32;
+/// The most steps a knob labels. Knobs with more steps only get ticks.
+const MAX_LABELS: usize = 12;
+/// The radius of the track of a labelled knob, as a fraction of the knob's
+/// half width, leaving the rest for the labels
+const LABELLED_RADIUS: f32 = 0.6f32;
+/// The distance of the labels from the center, as a fraction of the knob's
+/// half width
+const LABEL_RADIUS: f32 = 0.85f32;
+/// The width and height of the labels, as a percentage of the knob's
+const LABEL_SIZE: f32 = 24f32;
+
+/// A rotary control, turned by dragging or with the keys once focused. Its
+/// range sweeps clockwise from the bottom left to the bottom right. Dragging
+/// up and down turns it by default, and dragging around it with a
+/// [`drag_mode`](KnobHandle::drag_mode) of [`RotaryDrag::Circular`].
+///
+/// The knob follows the [`ValueMapping`] it's created with. A
+/// [`DetentMapping`](crate::util::DetentMapping) snaps the knob to its detent,
+/// such as the center of a pan, as it's dragged past. A stepped mapping such
+/// as a [`SteppedMapping`](crate::util::SteppedMapping) snaps it to its
+/// positions, and the knob is drawn with a tick at each position instead of a
+/// filled track, labelled with the values of the positions when there are no
+/// more than twelve. Labelled knobs need the room for their labels, such as
+/// `80px` square.
+///
+/// The track and its fill are drawn in the `background-color` and
+/// `border-color` of the `.track` class, the pointer in the
+/// `background-color` of the `.pointer` class, and the ticks in the
+/// `background-color` of the `.tick` class, with the tick of the current
+/// position in its `border-color`. The labels have the `.tick-label` class.
+#[derive(Handle)]
+#[style_class("track", "pointer", "tick")]
+pub struct Knob<L>
+where
+    L: Lens<Target = f32>,
+{
+    value: L,
+    mapping: Rc<dyn ValueMapping>,
+    formatter: Rc<dyn ValueFormatter>,
+    /// The value that resetting goes back to, if known
+    default: Option<f32>,
+    /// How dragging turns the knob, fixed or from a lens to a user preference
+    #[property(res)]
+    drag_mode: RotaryDrag,
+    /// The drag turning the knob, which remembers the value it started from
+    drag: DragGesture<f32>,
+    /// The normalized position the drag has turned the knob to, before the
+    /// mapping snaps it, so small moves add up to the next step
+    turned: f32,
+    /// The last cursor moved to while dragging, until it is handled
+    moves: MoveCoalescer,
+    /// The presses of the mouse buttons on the knob
+    presses: PressReader,
+    hover: bool,
+    styles: KnobStyles,
+    #[callback(f32)]
+    on_changing: Option<Box<dyn Fn(&mut EventContext, f32)>>,
+    #[callback(ContextMenuAction)]
+    on_context_menu: Option<Box<dyn Fn(&mut EventContext, ContextMenuAction)>>,
+}
+
+impl<L> Knob<L>
+where
+    L: Lens<Target = f32>,
+{
+    /// Create a new `Knob` over `range`, such as `0f32..=1f32`
+    pub fn new(cx: &mut Context, value: L, range: RangeInclusive<f32>) -> Handle<Self> {
+        Self::with_mapping(cx, value, range)
+    }
+
+    /// Create a new `Knob` with a non-linear taper, a detent or steps
+    pub fn with_mapping(
+        cx: &mut Context,
+        value: L,
+        mapping: impl ValueMapping + 'static,
+    ) -> Handle<Self> {
+        Self::build_knob(
+            cx,
+            value,
+            Rc::new(mapping),
+            Rc::new(Decimal::default()),
+            None,
+        )
+    }
+
+    /// Create a new `Knob` for a parameter, which provides the knob's taper,
+    /// steps, readout and default. Choosing reset from the knob's context
+    /// menu, or double-clicking the knob, changes the value back to the
+    /// parameter's default.
+    pub fn with_param<P>(cx: &mut Context, value: L, param: P) -> Handle<Self>
+    where
+        P: 'static + NormalizedParam + Clone,
+    {
+        let default = param.default_plain();
+        Self::build_knob(
+            cx,
+            value,
+            Rc::new(param.clone()),
+            Rc::new(param),
+            Some(default),
+        )
+    }
+
+    fn build_knob(
+        cx: &mut Context,
+        value: L,
+        mapping: Rc<dyn ValueMapping>,
+        formatter: Rc<dyn ValueFormatter>,
+        default: Option<f32>,
+    ) -> Handle<Self> {
+        let steps = mapping.steps().filter(|&steps| steps <= MAX_LABELS);
+        let labels = steps.map(|steps| {
+            (0..steps)
+                .map(|step| {
+                    let normalized = step as f32 / (steps - 1) as f32;
+                    (
+                        normalized,
+                        formatter.format(mapping.denormalize(normalized)),
+                    )
+                })
+                .collect::<Vec<_>>()
+        });
+        let handle = Self {
+            value: value.clone(),
+            mapping,
+            formatter,
+            default,
+            drag_mode: RotaryDrag::default(),
+            drag: DragGesture::new(),
+            turned: 0f32,
+            moves: MoveCoalescer::new(),
+            presses: PressReader::new(),
+            hover: false,
+            styles: KnobStyles::new(cx),
+            on_changing: None,
+            on_context_menu: None,
+        }
+        .build(cx, |cx| {
+            // The labels are placed around the middle of the knob, as a
+            // percentage of its size
+            for (normalized, text) in labels.into_iter().flatten() {
+                let angle = START_ANGLE + SWEEP * normalized;
+                let offset = 50f32 * LABEL_RADIUS;
+                Label::new(cx, &text)
+                    .class("tick-label")
+                    .hoverable(false)
+                    .position_type(PositionType::SelfDirected)
+                    .left(Percentage(50f32 + offset * angle.cos() - LABEL_SIZE / 2f32))
+                    .top(Percentage(50f32 + offset * angle.sin() - LABEL_SIZE / 2f32))
+                    .width(Percentage(LABEL_SIZE))
+                    .height(Percentage(LABEL_SIZE));
+            }
+        })
+        .tab_stop();
+        handle.bind(value, |handle, _| handle.cx.style.needs_redraw = true)
+    }
+
+    /// Changes the value from the knob itself
+    fn change(&mut self, cx: &mut EventContext, value: f32) {
+        if let Some(callback) = &self.on_changing {
+            (callback)(cx, value);
+            cx.request_redraw();
+        }
+    }
+
+    /// Changes the value back to the default, if known
+    fn reset(&mut self, cx: &mut EventContext) {
+        if let Some(default) = self.default {
+            self.change(cx, default);
+            cx.single_edit(0, &[default]);
+        }
+    }
+
+    /// Turns the knob by the drag to `cursor`
+    fn drag_to(&mut self, cx: &mut EventContext, cursor: Vec2) {
+        // Circular drags follow the cursor's angle, so they can't be fine
+        let fine =
+            cx.modifiers.contains(Modifiers::SHIFT) && self.drag_mode == RotaryDrag::Vertical;
+        let drag = match self.drag.update_to(cursor, fine) {
+            Some(drag) => drag,
+            None => return,
+        };
+        let bounds = cx.cache.get_bounds(cx.current());
+        let center = Vec2::new(bounds.x + bounds.w / 2f32, bounds.y + bounds.h / 2f32);
+        let from = drag.position - drag.delta;
+        let turn = self
+            .drag_mode
+            .default_delta(center, from, drag.position, SWEEP);
+        // The mapping snaps to its steps and detent, so only the moves that
+        // leave them change the value
+        let before = self.mapping.denormalize(self.turned);
+        self.turned = (self.turned + turn).clamp(0f32, 1f32);
+        let value = self.mapping.denormalize(self.turned);
+        if value != before {
+            self.change(cx, value);
+            cx.perform_edit(0, &[value]);
+        }
+        cx.show_value_tooltip(self.formatter.format(value));
+    }
+
+    /// Gets the value a key moves the knob to. Nudges too small to leave a
+    /// step or the detent carry on until the value changes, so the keys don't
+    /// get stuck on them.
+    fn nudged(&self, value: f32, nudge: KeyNudge) -> f32 {
+        let mut normalized = self.mapping.normalize(value);
+        loop {
+            let next = nudge.apply(normalized);
+            let nudged = self.mapping.denormalize(next);
+            if nudged != value || next == normalized {
+                return nudged;
+            }
+            normalized = next;
+        }
+    }
+}
+
+impl<L> View for Knob<L>
+where
+    L: Lens<Target = f32>,
+{
+    fn element(&self) -> Option<&'static str> {
+        Some("knob")
+    }
+
+    fn event(&mut self, cx: &mut EventContext, event: &mut Event) {
+        if cx.is_disabled() {
+            return;
+        }
+        event.map(|ev: &WindowEvent, _| match *ev {
+            WindowEvent::MouseEnter => {
+                self.hover = true;
+                cx.show_tooltip(self.formatter.format(self.value.get(cx)));
+            }
+            WindowEvent::MouseLeave => {
+                self.hover = false;
+                if !self.drag.is_dragging() {
+                    cx.hide_tooltip();
+                }
+            }
+            WindowEvent::MouseDown(_) => {
+                let press = match self.presses.read(cx, ev) {
+                    Some(press) => press,
+                    None => return,
+                };
+                let input = cx.input_map();
+                if input.triggers(InputAction::Reset, press) && self.default.is_some() {
+                    self.reset(cx);
+                } else if input.triggers(InputAction::OpenMenu, press)
+                    && (self.on_context_menu.is_some() || self.default.is_some())
+                {
+                    cx.open_context_menu(ContextMenuItem::parameter_items());
+                } else if press.button == MouseButton::Left {
+                    let value = self.value.get(cx);
+                    self.turned = self.mapping.normalize(value);
+                    self.drag.begin(cx, value);
+                    cx.begin_edit();
+                }
+            }
+            WindowEvent::MouseUp(MouseButton::Left) => {
+                if let Some(cursor) = self.moves.take() {
+                    self.drag_to(cx, cursor);
+                }
+                if self.drag.end(cx) {
+                    if !self.hover {
+                        cx.hide_tooltip();
+                    }
+                    cx.end_edit();
+                }
+            }
+            WindowEvent::MouseMove(x, y) => {
+                if self.drag.is_dragging() {
+                    self.moves.push(cx, x, y);
+                }
+            }
+            // Escape puts the knob back where the drag started
+            WindowEvent::KeyDown(Code::Escape, _) if self.drag.is_dragging() => {
+                self.moves.take();
+                if let Some(start) = self.drag.cancel(cx) {
+                    self.change(cx, start);
+                    cx.perform_edit(0, &[start]);
+                    cx.end_edit();
+                }
+                if !self.hover {
+                    cx.hide_tooltip();
+                }
+            }
+            WindowEvent::KeyDown(code, _) => {
+                if let Some(nudge) = KeyNudge::from_event(cx, code) {
+                    let value = self.nudged(self.value.get(cx), nudge);
+                    self.change(cx, value);
+                    cx.single_edit(0, &[value]);
+                    cx.show_value_tooltip(self.formatter.format(value));
+                }
+            }
+            _ => (),
+        });
+        event.map(|_: &CoalescedMove, meta| {
+            if let Some(cursor) = self.moves.take() {
+                self.drag_to(cx, cursor);
+            }
+            meta.consume();
+        });
+        event.map(|action: &ContextMenuAction, _| {
+            if let ContextMenuAction::Reset = action {
+                self.reset(cx);
+            }
+            if let Some(callback) = &self.on_context_menu {
+                (callback)(cx, action.clone());
+            }
+        });
+    }
+
+    fn draw(&self, cx: &mut DrawContext, canvas: &mut Canvas) {
+        let bounds = cx.bounds();
+        let (x, y) = (bounds.x + bounds.w / 2f32, bounds.y + bounds.h / 2f32);
+        let half_width = bounds.w.min(bounds.h) / 2f32;
+        let track_width = self.styles.track_width(cx).unwrap_or(TRACK_WIDTH);
+        let pointer_width = self.styles.pointer_width(cx).unwrap_or(POINTER_WIDTH);
+        let tick_length = self.styles.tick_width(cx).unwrap_or(TICK_LENGTH);
+        let steps = self.mapping.steps();
+        // Stepped knobs leave room around the track for their ticks, and for
+        // their labels if they have any
+        let radius = match steps {
+            Some(steps) if steps <= MAX_LABELS => half_width * LABELLED_RADIUS,
+            Some(_) => half_width - tick_length - TICK_GAP,
+            None => half_width,
+        } - track_width / 2f32;
+        let normalized = self.value.view(cx.data().unwrap(), |value| {
+            self.mapping.normalize(value.cloned().unwrap_or_default())
+        });
+        let angle = START_ANGLE + SWEEP * normalized;
+        let at = |angle: f32, radius: f32| (x + radius * angle.cos(), y + radius * angle.sin());
+
+        cx.draw_background(canvas);
+
+        let mut track = Path::new();
+        track.arc(
+            x,
+            y,
+            radius,
+            START_ANGLE,
+            START_ANGLE + SWEEP,
+            Solidity::Hole,
+        );
+        let track_color = self.styles.track_background_color(cx);
+        canvas.stroke_path(
+            &mut track,
+            &Paint::color(track_color.into()).with_line_width(track_width),
+        );
+
+        match steps {
+            // Stepped knobs mark each position, highlighting the current one
+            Some(steps) => {
+                let current = (normalized * (steps - 1) as f32).round() as usize;
+                let inner = radius + track_width / 2f32 + TICK_GAP;
+                for step in 0..steps {
+                    let angle = START_ANGLE + SWEEP * step as f32 / (steps - 1) as f32;
+                    let mut tick = Path::new();
+                    let (x, y) = at(angle, inner);
+                    tick.move_to(x, y);
+                    let (x, y) = at(angle, inner + tick_length);
+                    tick.line_to(x, y);
+                    let color = if step == current {
+                        self.styles.tick_border_color(cx)
+                    } else {
+                        self.styles.tick_background_color(cx)
+                    };
+                    canvas.stroke_path(
+                        &mut tick,
+                        &Paint::color(color.into()).with_line_width(pointer_width),
+                    );
+                }
+            }
+            // Continuous knobs fill the track up to the value
+            None => {
+                let mut fill = Path::new();
+                fill.arc(x, y, radius, START_ANGLE, angle, Solidity::Hole);
+                let fill_color = self.styles.track_border_color(cx);
+                canvas.stroke_path(
+                    &mut fill,
+                    &Paint::color(fill_color.into()).with_line_width(track_width),
+                );
+            }
+        }
+
+        let mut pointer = Path::new();
+        let (from_x, from_y) = at(angle, radius * POINTER_START);
+        pointer.move_to(from_x, from_y);
+        let (to_x, to_y) = at(angle, radius);
+        pointer.line_to(to_x, to_y);
+        let pointer_color = self.styles.pointer_background_color(cx);
+        canvas.stroke_path(
+            &mut pointer,
+            &Paint::color(pointer_color.into()).with_line_width(pointer_width),
+        );
+
+        cx.draw_border(canvas);
+        cx.draw_outline(canvas);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{Recorder, TestContext};
+    use crate::util::{DetentMapping, SteppedMapping};
+    use assert_approx_eq::assert_approx_eq;
+
+    #[derive(Lens)]
+    struct Level {
+        value: f32,
+    }
+
+    impl Model for Level {}
+
+    #[derive(Lens)]
+    struct Preferences {
+        drag_mode: RotaryDrag,
+    }
+
+    impl Model for Preferences {}
+
+    #[test]
+    fn drags_knobs_vertically() {
+        let mut test = TestContext::new(100f32, 100f32);
+        let changes = Recorder::new();
+        let record = changes.clone();
+        test.build(|cx| {
+            Level { value: 0.25f32 }.build(cx);
+            Knob::new(cx, Level::value, 0f32..=1f32)
+                .width(Pixels(100f32))
+                .height(Pixels(100f32))
+                .on_changing(move |_, value| record.push(value));
+        });
+        // The whole range is 200 pixels up, wherever the drag starts
+        test.drag((90f32, 90f32), (90f32, 40f32));
+        assert_approx_eq!(changes.last().unwrap(), 0.5f32, 1e-3f32);
+        test.drag((10f32, 50f32), (10f32, 150f32));
+        assert_eq!(changes.last(), Some(0f32));
+    }
+
+    #[test]
+    fn drags_knobs_around_from_a_preference() {
+        let mut test = TestContext::new(100f32, 100f32);
+        let changes = Recorder::new();
+        let record = changes.clone();
+        test.build(|cx| {
+            Level { value: 0.5f32 }.build(cx);
+            Preferences {
+                drag_mode: RotaryDrag::Circular,
+            }
+            .build(cx);
+            Knob::new(cx, Level::value, 0f32..=1f32)
+                .width(Pixels(100f32))
+                .height(Pixels(100f32))
+                .drag_mode(Preferences::drag_mode)
+                .on_changing(move |_, value| record.push(value));
+        });
+        // A quarter turn from the top to the right is a third of the sweep,
+        // from the middle of the range where the knob points up
+        test.drag((50f32, 10f32), (90f32, 50f32));
+        assert_approx_eq!(changes.last().unwrap(), 0.5f32 + 1f32 / 3f32, 1e-3f32);
+    }
+
+    #[test]
+    fn snaps_to_steps_and_detents() {
+        let mut test = TestContext::new(100f32, 200f32);
+        let steps = Recorder::new();
+        let record_steps = steps.clone();
+        let detents = Recorder::new();
+        let record_detents = detents.clone();
+        let detent = test.build(|cx| {
+            Level { value: 0f32 }.build(cx);
+            Knob::with_mapping(cx, Level::value, SteppedMapping::new(0f32..=4f32, 5))
+                .width(Pixels(100f32))
+                .height(Pixels(100f32))
+                .on_changing(move |_, value| record_steps.push(value));
+            Knob::with_mapping(
+                cx,
+                Level::value,
+                DetentMapping::new(-1f32..=1f32, 0f32, 0.05f32),
+            )
+            .width(Pixels(100f32))
+            .height(Pixels(100f32))
+            .on_changing(move |_, value| record_detents.push(value))
+            .entity
+        });
+        // 60 pixels turns the stepped knob 0.3 of the way, closest to the
+        // second step, and the steps in between are never sent
+        test.drag((50f32, 50f32), (50f32, -10f32));
+        assert_eq!(steps.values(), vec![1f32]);
+        // The detent knob starts at its detent, and stays there until the
+        // drag leaves the threshold around it
+        test.drag((50f32, 150f32), (50f32, 142f32));
+        assert!(detents.is_empty());
+        test.drag((50f32, 150f32), (50f32, 130f32));
+        assert_approx_eq!(detents.last().unwrap(), 0.2f32, 1e-3f32);
+        // The keys step off the detent rather than getting stuck on it
+        test.focus(detent);
+        test.press_key(Code::ArrowDown);
+        assert!(detents.last().unwrap() < 0f32);
+    }
+}
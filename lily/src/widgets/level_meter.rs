@@ -0,0 +1,305 @@
+use crate::util::{Ballistics, FrameExt, MeterChannels, OverDetector, RedrawExt, ValueMapping};
+use lily_derive::Handle;
+use std::ops::RangeInclusive;
+use std::time::Instant;
+use vizia::prelude::*;
+use vizia::vg::{Paint, Path};
+
+/// The decibels shown from the bottom to the top of a meter by default
+const RANGE: RangeInclusive<f32> = -60f32..=6f32;
+/// The time constant in seconds of the level rising
+const ATTACK: f32 = 0.005f32;
+/// The time constant in seconds of the level falling
+const RELEASE: f32 = 0.3f32;
+/// How long in seconds peaks hold by default
+const HOLD: f32 = 1.5f32;
+/// How fast held peaks fall by default, in decibels per second
+const DECAY: f32 = 20f32;
+/// The over threshold of true-peak meters in dBTP, the ceiling most loudness
+/// standards leave below full scale
+const TRUE_PEAK_CEILING: f32 = -1f32;
+/// The gap in pixels between the bars of the channels
+const CHANNEL_GAP: f32 = 2f32;
+/// The thickness in pixels of the peak-hold markers when the `.peak` class
+/// sets no `width`
+const PEAK_HEIGHT: f32 = 2f32;
+/// The height in pixels of the clip indicators when the `.clip` class sets no
+/// `width`
+const CLIP_HEIGHT: f32 = 4f32;
+
+/// Sent to a meter when its levels change
+struct LevelsChanged;
+
+/// A meter with a vertical bar for each channel, such as left and right,
+/// rising with its level in decibels.
+///
+/// The levels are smoothed with the meter's [`Ballistics`], so a lens updated
+/// about once a frame with the peak of each channel since the last frame
+/// moves like a hardware meter. Levels above the threshold of its
+/// [`OverDetector`], 0 dBFS by default, are reported to
+/// [`on_clip`](LevelMeterHandle::on_clip) with the channel and how many
+/// decibels it went over by, so the host can log overs or light a global
+/// indicator. See [`TruePeakMeter`] for metering true peaks.
+///
+/// Each channel marks its held peak, which holds for a second and a half and
+/// then falls by 20 dB a second unless the ballistics say otherwise, and
+/// lights its clip indicator above the bar once it goes over. Clicking the
+/// meter drops the markers to the levels, clears the clip indicators, and
+/// calls [`on_reset`](LevelMeterHandle::on_reset).
+///
+/// The bars are drawn in the `background-color` of the `.level` class and the
+/// peak-hold markers in the `background-color` of the `.peak` class. The clip
+/// indicators are drawn in the `background-color` of the `.clip` class when
+/// lit, and in its `border-color` otherwise.
+#[derive(Handle)]
+#[style_class("level", "peak", "clip")]
+pub struct LevelMeter<L>
+where
+    L: Lens<Target = Vec<f32>>,
+{
+    /// A [`Lens`] of the latest level of each channel in decibels
+    levels: L,
+    /// The decibels shown from the bottom to the top of the meter
+    #[property]
+    range: RangeInclusive<f32>,
+    channels: MeterChannels,
+    /// When the levels last changed, to move the channels by the time since
+    last_update: Instant,
+    styles: LevelMeterStyles,
+    #[callback(usize, f32)]
+    on_clip: Option<Box<dyn Fn(&mut EventContext, usize, f32)>>,
+    #[callback]
+    on_reset: Option<Box<dyn Fn(&mut EventContext)>>,
+}
+
+impl<L> LevelMeter<L>
+where
+    L: Lens<Target = Vec<f32>>,
+{
+    /// Create a new `LevelMeter` with `count` channels, which go over at
+    /// 0 dBFS
+    pub fn new(cx: &mut Context, levels: L, count: usize) -> Handle<Self> {
+        Self::with_channels(cx, levels, Self::default_channels(count, 0f32))
+    }
+
+    /// Create a new `LevelMeter` whose channels move with their own
+    /// ballistics, such as with a different peak hold, and go over at their
+    /// own threshold
+    pub fn with_channels(cx: &mut Context, levels: L, channels: MeterChannels) -> Handle<Self> {
+        Self {
+            levels: levels.clone(),
+            range: RANGE,
+            channels,
+            last_update: Instant::now(),
+            styles: LevelMeterStyles::new(cx),
+            on_clip: None,
+            on_reset: None,
+        }
+        .build(cx, |_| {})
+        .bind(levels, |handle, _| {
+            let entity = handle.entity;
+            handle.cx.emit_to(entity, LevelsChanged);
+        })
+    }
+
+    fn default_channels(count: usize, threshold: f32) -> MeterChannels {
+        let ballistics = Ballistics::new(ATTACK, RELEASE).with_peak_hold(HOLD, DECAY);
+        MeterChannels::new(count, ballistics, OverDetector::new(threshold))
+    }
+
+    /// Moves the channels to the latest levels, reporting their overs
+    fn update(&mut self, cx: &mut EventContext) {
+        let now = Instant::now();
+        let elapsed = now.duration_since(self.last_update).as_secs_f32();
+        self.last_update = now;
+        let levels = self.levels.get(cx);
+        let mut overs = Vec::new();
+        self.channels
+            .advance_with(&levels, elapsed, |channel, overshoot| {
+                overs.push((channel, overshoot))
+            });
+        if let Some(callback) = &self.on_clip {
+            for (channel, overshoot) in overs {
+                (callback)(cx, channel, overshoot);
+            }
+        }
+        cx.request_redraw();
+    }
+
+    /// Clears the held peaks and clip indicators
+    fn reset(&mut self, cx: &mut EventContext) {
+        self.channels.reset_holds();
+        if let Some(callback) = &self.on_reset {
+            (callback)(cx);
+        }
+        cx.request_redraw();
+    }
+}
+
+/// Builds [`LevelMeter`]s for true-peak levels, which go over at -1 dBTP.
+///
+/// The meter only shows the levels it's given, so the host measures the true
+/// peaks, such as by oversampling on the audio thread.
+pub struct TruePeakMeter;
+
+impl TruePeakMeter {
+    /// Create a new true-peak `LevelMeter` with `count` channels
+    #[allow(clippy::new_ret_no_self)]
+    pub fn new<L>(cx: &mut Context, levels: L, count: usize) -> Handle<LevelMeter<L>>
+    where
+        L: Lens<Target = Vec<f32>>,
+    {
+        let channels = LevelMeter::<L>::default_channels(count, TRUE_PEAK_CEILING);
+        LevelMeter::with_channels(cx, levels, channels).class("true-peak")
+    }
+}
+
+impl<L> View for LevelMeter<L>
+where
+    L: Lens<Target = Vec<f32>>,
+{
+    fn element(&self) -> Option<&'static str> {
+        Some("level-meter")
+    }
+
+    fn event(&mut self, cx: &mut EventContext, event: &mut Event) {
+        event.map(|_: &LevelsChanged, meta| {
+            self.update(cx);
+            meta.consume();
+        });
+        if cx.is_disabled() {
+            return;
+        }
+        event.map(|ev: &WindowEvent, meta| {
+            if let WindowEvent::MouseDown(MouseButton::Left) = ev {
+                self.reset(cx);
+                meta.consume();
+            }
+        });
+    }
+
+    fn draw(&self, cx: &mut DrawContext, canvas: &mut Canvas) {
+        let bounds = cx.bounds();
+        let count = self.channels.len();
+        cx.draw_background(canvas);
+        if count > 0 {
+            let width = (bounds.w - CHANNEL_GAP * (count - 1) as f32) / count as f32;
+            let peak_height = self.styles.peak_width(cx).unwrap_or(PEAK_HEIGHT);
+            let clip_height = self.styles.clip_width(cx).unwrap_or(CLIP_HEIGHT);
+            // The bars rise below the clip indicators
+            let meter_height = (bounds.h - clip_height - CHANNEL_GAP).max(0f32);
+            let (mut bars, mut peaks, mut lit, mut unlit) =
+                (Path::new(), Path::new(), Path::new(), Path::new());
+            for channel in 0..count {
+                let x = bounds.x + (width + CHANNEL_GAP) * channel as f32;
+                let height = |db: Option<f32>| {
+                    self.range.normalize(db.unwrap_or(*self.range.start())) * meter_height
+                };
+                let level = height(self.channels.level(channel));
+                bars.rect(x, bounds.bottom() - level, width, level);
+                let peak = height(self.channels.peak(channel)).max(peak_height);
+                peaks.rect(x, bounds.bottom() - peak, width, peak_height);
+                let clip = if self.channels.clipped(channel) {
+                    &mut lit
+                } else {
+                    &mut unlit
+                };
+                clip.rect(x, bounds.top(), width, clip_height);
+            }
+            let paint = |color: Color| Paint::color(color.into());
+            canvas.fill_path(&mut bars, &paint(self.styles.level_background_color(cx)));
+            canvas.fill_path(&mut peaks, &paint(self.styles.peak_background_color(cx)));
+            canvas.fill_path(&mut lit, &paint(self.styles.clip_background_color(cx)));
+            canvas.fill_path(&mut unlit, &paint(self.styles.clip_border_color(cx)));
+        }
+        cx.draw_border(canvas);
+        cx.draw_outline(canvas);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{Recorder, TestContext};
+
+    #[derive(Lens)]
+    struct Meter {
+        levels: Vec<f32>,
+    }
+
+    enum MeterEvent {
+        Set(Vec<f32>),
+    }
+
+    impl Model for Meter {
+        fn event(&mut self, _: &mut EventContext, event: &mut Event) {
+            event.map(|ev: &MeterEvent, _| match ev {
+                MeterEvent::Set(levels) => self.levels = levels.clone(),
+            });
+        }
+    }
+
+    #[test]
+    fn reports_overs_with_their_overshoot() {
+        let mut test = TestContext::new(40f32, 200f32);
+        let overs = Recorder::new();
+        let record = overs.clone();
+        let true_overs = Recorder::new();
+        let record_true = true_overs.clone();
+        test.build(|cx| {
+            Meter {
+                levels: vec![-100f32; 2],
+            }
+            .build(cx);
+            LevelMeter::new(cx, Meter::levels, 2).on_clip(move |_, channel, overshoot| {
+                record.push((channel, overshoot));
+            });
+            TruePeakMeter::new(cx, Meter::levels, 2).on_clip(move |_, channel, overshoot| {
+                record_true.push((channel, overshoot));
+            });
+        });
+        let mut set = |levels: Vec<f32>| {
+            test.cx().emit(MeterEvent::Set(levels));
+            test.update();
+        };
+        set(vec![-6f32, -0.5f32]);
+        assert!(overs.is_empty());
+        assert_eq!(true_overs.values(), vec![(1, 0.5f32)]);
+        // An over is reported when it starts and when it goes higher
+        set(vec![-6f32, 1.5f32]);
+        set(vec![-6f32, 1f32]);
+        set(vec![-6f32, 2f32]);
+        assert_eq!(overs.values(), vec![(1, 1.5f32), (1, 2f32)]);
+        set(vec![3f32, -6f32]);
+        assert_eq!(overs.last(), Some((0, 3f32)));
+    }
+
+    #[test]
+    fn resets_holds_when_clicked() {
+        let mut test = TestContext::new(20f32, 200f32);
+        let overs = Recorder::new();
+        let record_overs = overs.clone();
+        let resets = Recorder::new();
+        let record_resets = resets.clone();
+        test.build(|cx| {
+            Meter {
+                levels: vec![-100f32],
+            }
+            .build(cx);
+            LevelMeter::new(cx, Meter::levels, 1)
+                .width(Pixels(20f32))
+                .height(Pixels(200f32))
+                .on_clip(move |_, _, overshoot| record_overs.push(overshoot))
+                .on_reset(move |_| record_resets.push(()));
+        });
+        test.cx().emit(MeterEvent::Set(vec![1f32]));
+        test.update();
+        assert_eq!(overs.len(), 1);
+        test.click(10f32, 100f32);
+        assert_eq!(resets.len(), 1);
+        // The over still going on starts again once it's cleared
+        test.cx().emit(MeterEvent::Set(vec![0.5f32]));
+        test.update();
+        assert_eq!(overs.values(), vec![1f32, 0.5f32]);
+    }
+}
@@ -1,11 +1,49 @@
+mod band_curve;
+mod drawbars;
+mod drop_zone;
+mod fx_chain;
+mod grain_cloud;
+mod key_zones;
+mod knob;
 mod label;
+mod level_meter;
 mod mseg;
+mod section;
 mod slider;
-// mod xy_pad;
+mod spectrum_display;
+mod step_sequencer;
+mod tap_editor;
+mod threshold_scope;
+mod tuning_table;
+mod voice_display;
+mod waveform;
+mod xy_display;
+mod xy_pad;
 mod zoomer;
 
+pub use band_curve::{BandCurveEditor, BandCurveEditorHandle, BandCurveEditorState};
+pub use drawbars::{Drawbars, DrawbarsHandle};
+pub use drop_zone::{DropZone, DropZoneHandle, DropZoneState};
+pub use fx_chain::{FxChain, FxChainHandle, FxSlot};
+pub use grain_cloud::{Grain, GrainCloud, GrainColor};
+pub use key_zones::{KeyZone, KeyZoneEditor, KeyZoneEditorHandle};
+pub use knob::{Knob, KnobHandle};
 pub use label::DragLabel;
-pub use mseg::{Mseg, MsegHandle};
-pub use slider::{DragSlider, DragSliderHandle};
-// pub use xy_pad::{XyPad, XyPadHandle};
+pub use level_meter::{LevelMeter, LevelMeterHandle, TruePeakMeter};
+pub use mseg::util::{
+    closest_point, simplify_for_display, AxisScale, ProjectedPoints, SnapGrid, ViewTransform,
+};
+pub use mseg::{
+    MorphPreviewExt, Mseg, MsegGraph, MsegGraphHandle, MsegHandle, SnapGridExt, ValueRangeExt,
+};
+pub use section::{Section, SectionHandle};
+pub use slider::{DragSlider, DragSliderHandle, Fader, FaderHandle};
+pub use spectrum_display::{SpectrumDisplay, SpectrumDisplayHandle, SpectrumTrace};
+pub use step_sequencer::{StepSequencer, StepSequencerHandle};
+pub use tap_editor::{DelayTap, TapEditor, TapEditorHandle};
+pub use threshold_scope::{ThresholdScope, ThresholdScopeHandle, ThresholdScopeState};
+pub use tuning_table::{TuningTable, TuningTableHandle};
+pub use voice_display::{EnvelopeStage, VoiceDisplay, VoiceState};
+pub use xy_display::{XyDisplay, XyDisplayHandle};
+pub use xy_pad::{XyPad, XyPadHandle};
 pub use zoomer::{Zoomer, ZoomerHandle};
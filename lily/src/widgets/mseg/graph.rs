@@ -1,29 +1,257 @@
-use crate::util::CurvePoints;
+use crate::anim::Inertia;
+use crate::focus::FocusExt;
+use crate::gesture::GestureExt;
+use crate::input::{InputAction, InputMapExt, MouseBinding, PressReader};
+use crate::tooltip::TooltipExt;
+use crate::units::format_seconds;
+use crate::util::{
+    draw_invalid, install_ticker, Affordance, BeatGrid, CoalescedMove, CursorAffordance,
+    CurveError, CurvePoints, DataCheckExt, DataChecked, DragGesture, FrameExt, FrameTick, KeyNudge,
+    MoveCoalescer, PointTag, PseudoClassExt, RangeExt, RedrawExt, ScaleExt, TickExt, ValueMapping,
+};
 use glam::Vec2;
 use lily_derive::Handle;
-use std::{cmp::Ordering, collections::HashMap, ops::RangeInclusive};
+use std::time::Instant;
+use std::{cell::RefCell, marker::PhantomData, ops::RangeInclusive, rc::Rc};
 use vizia::prelude::*;
 use vizia::vg;
 
-use super::util::{data_to_bounds_pos_range, data_to_ui_pos_range, ui_to_data_pos_range};
+use super::util::{
+    extend_curve, simplify_for_display, time_range, zoom_window, ProjectedPoints, SnapGrid,
+    ViewTransform,
+};
+use super::MsegEvent;
 
-/// The distance in pixels before a node is considered hovered
+/// The default distance in logical pixels before a node is considered hovered
 const HOVER_RADIUS: f32 = 16f32;
 /// The distance in seconds before two points cannot get closer
 const MIN_RESOLUTION: f32 = 0.01f32;
+/// The radius in pixels of points when the `.point` class sets no `width`
+const POINT_RADIUS: f32 = 4f32;
+/// The radius in pixels of the ring around the active point when the
+/// `.hover-ring` class sets no `width`. Stylesheets animate the ring by
+/// transitioning the `width` and `border-color` of `.hover-ring:hover`.
+const HOVER_RING_RADIUS: f32 = 8f32;
+/// The width in pixels of lines and rings when no `border-width` is set on
+/// the `.line` and `.point` classes
+const LINE_WIDTH: f32 = 2f32;
+/// The width in pixels of the guides through tagged points when the `.tag`
+/// class sets no `border-width`
+const TAG_GUIDE_WIDTH: f32 = 1f32;
+/// The width in pixels of the end of the selected region when the `.region`
+/// class sets no `border-width`
+const REGION_END_WIDTH: f32 = 2f32;
+/// The width in pixels of the outline of the marquee when the `.selection`
+/// class sets no `border-width`
+const MARQUEE_WIDTH: f32 = 1f32;
+/// The width in pixels of the lines of the snap grid when the `.grid` class
+/// sets no `border-width`
+const GRID_LINE_WIDTH: f32 = 1f32;
+/// The closest in logical pixels the lines of the snap grid are drawn, since
+/// denser lines would fill the graph
+const MIN_GRID_GAP: f32 = 4f32;
+/// The radius in pixels of the handles that bend segments when the
+/// `.curve-handle` class sets no `width`
+const CURVE_HANDLE_RADIUS: f32 = 3f32;
+/// The narrowest segment in logical pixels with a handle to bend it, since
+/// the handle of a narrower one would cover its points
+const MIN_CURVE_HANDLE_SEGMENT: f32 = 24f32;
+/// The most pixels apart in `x` the lines drawn along curved segments are
+const CURVE_STEP: f32 = 2f32;
+/// How much one step of scrolling zooms the times shown
+const TIME_ZOOM_STEP: f32 = 1.25f32;
+/// The smallest part of the envelope the graph can be zoomed into by
+/// scrolling, the same as the zoomer allows
+const MIN_TIME_SPAN: f32 = 0.1f32;
+/// How much of the times shown one step of sideways scrolling pans by
+const SCROLL_PAN_STEP: f32 = 0.1f32;
+/// How much one step of Ctrl+scroll zooms the values shown
+const VALUE_ZOOM_STEP: f32 = 1.25f32;
+/// The smallest span of values the graph can be zoomed into with Ctrl+scroll
+const MIN_VALUE_SPAN: f32 = 0.01f32;
 
-/// The visuals of the graph
+/// Morphs the points of a graph for the preview drawn over them, reading the
+/// envelope morphed towards and how far from the data of the view
+pub(super) type MorphPreview<T> =
+    Box<dyn Fn(&DrawContext, &CurvePoints<T>) -> Option<CurvePoints<T>>>;
+/// The speed in pixels per second below which the view stops panning on
+/// after it's let go
+const COAST_STOP_VELOCITY: f32 = 30f32;
+
+/// A drag of the selected time region of the graph
+enum RegionDrag<T> {
+    /// Selecting a region from the time the drag started at
+    Selecting(f32),
+    /// Stretching the region, from the region and the points before the
+    /// stretch
+    Stretching(RangeInclusive<f32>, CurvePoints<T>),
+}
+
+/// A rectangle dragged out from empty space to select the points within it
+struct Marquee {
+    /// Where the drag started, in the view
+    from: Vec2,
+    /// Where the cursor is, in the view
+    to: Vec2,
+    /// The points selected before the drag, which stay selected when the drag
+    /// started with Shift held
+    kept: Vec<usize>,
+}
+
+/// The paths of the graph, kept between draws and only built again when the
+/// projected points, the size of the points or the active point change. Only
+/// the points in view and the lines to the nearest points beyond its edges
+/// are drawn, and dense curves are simplified to a few points per pixel.
+#[derive(Default)]
+struct GraphGeometry {
+    projected: ProjectedPoints,
+    /// The projected points, with the points along the curved segments
+    /// between them
+    curved: Vec<Vec2>,
+    /// The projected points the line is drawn through
+    simplified: Vec<Vec2>,
+    /// The radius and the active point the dots were built with
+    dots_key: (f32, Option<usize>),
+    /// The line through every point
+    lines: vg::Path,
+    /// A dot on every point but the active one, which is drawn in its own
+    /// color, so they are all filled at once
+    dots: vg::Path,
+}
+
+impl GraphGeometry {
+    /// Projects the points and builds the paths again if anything changed
+    /// since the last draw
+    fn update<T>(
+        &mut self,
+        transform: &ViewTransform,
+        points: &CurvePoints<T>,
+        point_radius: f32,
+        active: Option<usize>,
+    ) {
+        let moved = self.projected.update(transform, points);
+        if moved {
+            let first = self.projected.first_index();
+            self.curved.clear();
+            for (i, point) in self.projected.ui_points().iter().enumerate() {
+                match self.curved.last() {
+                    Some(&last) => {
+                        let curve = self.projected.curve(first + i);
+                        extend_curve(&mut self.curved, last, *point, curve, CURVE_STEP);
+                    }
+                    None => self.curved.push(*point),
+                }
+            }
+            simplify_for_display(&self.curved, &mut self.simplified);
+            self.lines = vg::Path::new();
+            for (i, point) in self.simplified.iter().enumerate() {
+                if i == 0 {
+                    self.lines.move_to(point.x, point.y);
+                } else {
+                    self.lines.line_to(point.x, point.y);
+                }
+            }
+        }
+        if moved || self.dots_key != (point_radius, active) {
+            self.dots_key = (point_radius, active);
+            self.dots = vg::Path::new();
+            let first = self.projected.first_index();
+            // Dots closer than their radius to the last dot drawn would be
+            // hidden under it, so they are left out
+            let mut last_dot: Option<Vec2> = None;
+            for (i, point) in self.projected.ui_points().iter().enumerate() {
+                let hidden = last_dot.is_some_and(|dot| dot.distance(*point) < point_radius);
+                if active != Some(first + i) && !hidden {
+                    self.dots.circle(point.x, point.y, point_radius);
+                    last_dot = Some(*point);
+                }
+            }
+        }
+    }
+}
+
+/// The editable graph of an [`Mseg`](super::Mseg), without the zoomer below
+/// it, for envelope editors that always show the whole envelope or zoom some
+/// other way.
+///
+/// Points that aren't sorted by time, aren't finite, or have a `y` outside of
+/// `0..=1` are invalid. The graph is then crossed out in the border color of
+/// the `.invalid` class and ignores input until the points are fixed.
+///
+/// Points are deleted, inserted and the view panned with the mouse presses of
+/// the installed [`InputMap`](crate::input::InputMap), which also sets how
+/// quick a double click is. A pan let go of while still moving carries on,
+/// slowing down, until it stops or the view is clicked. Scrolling zooms the
+/// times shown in and out around the cursor, and sideways scrolling pans
+/// them. Pans and zooms report the range to show to `on_changing_range`.
+///
+/// A time region is selected by dragging with the `SelectRegion` press of the
+/// input map, Alt and the left button by default, and drawn in the `.region`
+/// class. Dragging the end of the region stretches or squeezes the points
+/// within it, leaving the rest of the envelope where it is, and reports each
+/// point it moves to `on_changing_point`. Escape clears the region.
+///
+/// Clicking a point selects it, and Shift-clicking adds it to the selection or
+/// takes it out. Dragging from empty space with the left button, where the
+/// input map binds no other action to the press, draws a marquee in the
+/// `.selection` class that selects the points within it, adding to the
+/// selection while Shift is held. Selected points are drawn in the
+/// `border-color` of the `.selection` class. Dragging one of them or nudging
+/// them with the keys moves them all together, and Delete removes them. Points
+/// moved together are reported to `on_changing_points` at once, or else to
+/// `on_changing_point` one by one. Escape clears the selection.
+///
+/// Each segment wide enough has a handle halfway along it, drawn in the
+/// `.curve-handle` class. Dragging the handle up or down bends the segment
+/// through the cursor, from an exponential rise to a logarithmic one, and
+/// reports the new curve of the segment to `on_changing_tension`. Double
+/// clicking the handle straightens the segment.
+///
+/// Points tagged with a [`PointTag`] stand out from the rest. Loop, sustain and
+/// stage points are drawn in the `background-color` of the `.tag` class with a
+/// guide through them in its `border-color`, and points tagged with a color are
+/// drawn in that color.
+///
+/// With [`MorphPreviewExt::morph_preview`], the points morphed towards another
+/// envelope are drawn over the graph as a line in the `border-color` of the
+/// `.morph` class.
+///
+/// With [`ValueRangeExt::value_range`], the graph shows a window of values
+/// other than `0..=1`, such as a detailed view of the values near `0` or some
+/// room above and below the points. Ctrl+scroll then zooms the values in and
+/// out around the cursor through `on_changing_value_range`, within `0..=1`.
+///
+/// With [`SnapGridExt::snap_grid`], dragged and inserted points snap to the
+/// lines of a [`SnapGrid`], which are drawn behind the envelope in the
+/// `border-color` of the `.grid` class. Holding Ctrl frees the points from the
+/// grid. Dragging several points together snaps the one under the cursor, and
+/// the keys still nudge points off the grid.
 #[allow(clippy::type_complexity)]
 #[derive(Handle)]
-pub(crate) struct MsegGraph<P, R>
+#[style_class(
+    "line",
+    "point",
+    "hover-ring",
+    "curve-handle",
+    "invalid",
+    "tag",
+    "morph",
+    "region",
+    "selection",
+    "grid"
+)]
+pub struct MsegGraph<P, R, T>
 where
-    P: Lens<Target = CurvePoints>,
+    P: Lens<Target = CurvePoints<T>>,
+    T: 'static + Clone,
     R: Lens<Target = RangeInclusive<f32>>,
 {
     /// A [`Lens`] of type `P` representing the points on an envelope. Points
     /// have a minimum and maximum float range of (0,0) and (inf, 1)
     /// respectively
     points: P,
+    /// The type of the user data attached to each point
+    data: PhantomData<T>,
     /// A [`Lens`] of type `R` representing the section of the graph of which we
     /// are zoomed. This can be any set of numbers between 0 and 1 inclusive
     /// where the start is less than the end.
@@ -31,26 +259,115 @@ where
     /// the max `x`, in `f32` seconds, of the envelope visualization. For
     /// example, if the max is `8.0`, the maximum length of the envelope is then
     /// 8 seconds.
+    #[property(res)]
     max: f32,
+    /// The values shown from the bottom to the top of the graph, in the
+    /// normalized `y` of points. `0..=1` unless set with
+    /// [`ValueRangeExt::value_range`].
+    pub(super) value_range: RangeInclusive<f32>,
+    /// The grid points snap to, free on both axes unless set with
+    /// [`SnapGridExt::snap_grid`]
+    pub(super) snap_grid: SnapGrid,
+    /// Maps the normalized `y` of points to the values shown in readouts
+    value_mapping: Rc<dyn ValueMapping>,
     /// The index of the currently hovered or pressed graph point
     active_point_id: Option<usize>,
-    classes: HashMap<&'static str, Entity>,
-    /// Whether we are in the process of dragging a graph point
-    is_dragging_point: bool,
+    /// The index of the point the hover ring was last drawn around, kept
+    /// after the point is left so the ring can shrink away
+    ring_point_id: Option<usize>,
+    /// The indices of the selected points, in order, which are dragged,
+    /// nudged and deleted together
+    selection: Vec<usize>,
+    /// The marquee selecting points, while it's dragged out
+    marquee: Option<Marquee>,
+    /// The points when the drag of several selected points started, while
+    /// they are dragged together
+    group_drag: Option<CurvePoints<T>>,
+    /// The index of the point ending the segment whose curve handle is
+    /// hovered or dragged
+    active_handle_id: Option<usize>,
+    /// The index of the point ending the segment being bent and the curve of
+    /// the segment when the drag started, while its handle is dragged
+    bend: Option<(usize, f32)>,
+    /// The selected time region, in seconds
+    region: Option<RangeInclusive<f32>>,
+    /// The selecting or stretching of the region, while the region is dragged
+    region_drag: Option<RegionDrag<T>>,
+    /// Whether the cursor is over the end of the region, away from points
+    over_region_end: bool,
+    styles: MsegGraphStyles,
+    /// The distance in logical pixels before a node is considered hovered
+    #[property(res)]
+    hover_radius: f32,
+    /// The drag of the active point, which remembers where the point started
+    drag: DragGesture<Vec2>,
+    /// The last cursor moved to, until it is handled
+    moves: MoveCoalescer,
+    /// The presses of the mouse buttons
+    presses: PressReader,
+    /// The cursor over points and while dragging or panning
+    cursor: CursorAffordance,
+    /// The cursor `x` and the zoomed range when panning started, while the
+    /// view is panned
+    pan: Option<(f32, RangeInclusive<f32>)>,
+    /// When the view was last panned, and the cursor `x` then
+    last_pan: (Instant, f32),
+    /// Keeps the view panning after it's let go while still moving, in
+    /// pixels per second. The graph asks for a [`FrameTick`] each frame while
+    /// it coasts.
+    inertia: Inertia,
+    /// The paths drawn last, reused while nothing changes
+    geometry: RefCell<GraphGeometry>,
+    /// What is wrong with the points, while they are invalid
+    data_error: Option<CurveError>,
+    /// Morphs the points for the preview drawn over them, once enabled with
+    /// [`MorphPreviewExt::morph_preview`]
+    pub(super) morph_preview: Option<MorphPreview<T>>,
 
     #[callback(usize, Vec2)]
     on_changing_point: Option<Box<dyn Fn(&mut EventContext, usize, Vec2)>>,
 
+    /// Called with the index and the new position of each point moved when
+    /// several move at once, such as while the selected points are dragged.
+    /// Without it, they are reported to `on_changing_point` one at a time.
+    #[callback(Vec<(usize, Vec2)>)]
+    on_changing_points: Option<Box<dyn Fn(&mut EventContext, Vec<(usize, Vec2)>)>>,
+
     #[callback(usize)]
     on_remove_point: Option<Box<dyn Fn(&mut EventContext, usize)>>,
 
     #[callback(usize, Vec2)]
     on_insert_point: Option<Box<dyn Fn(&mut EventContext, usize, Vec2)>>,
+
+    /// Called with the index of the point ending a segment, which holds the
+    /// curve of the segment, and the new curve while the segment is bent
+    #[callback(usize, f32)]
+    on_changing_tension: Option<Box<dyn Fn(&mut EventContext, usize, f32)>>,
+
+    /// Called with the zoomed range while the view is panned or zoomed
+    #[callback(RangeInclusive<f32>)]
+    on_changing_range: Option<Box<dyn Fn(&mut EventContext, RangeInclusive<f32>)>>,
+
+    /// Called with the values to show when the graph is zoomed vertically
+    /// with Ctrl+scroll
+    #[callback(RangeInclusive<f32>)]
+    on_changing_value_range: Option<Box<dyn Fn(&mut EventContext, RangeInclusive<f32>)>>,
+
+    /// Called with the problem found when the points become invalid
+    #[callback(CurveError)]
+    on_data_error: Option<Box<dyn Fn(&mut EventContext, CurveError)>>,
+
+    /// Called with the index of the point under the cursor, if any, and the
+    /// cursor in data space when the graph is double-clicked with the left
+    /// button, before the double click performs what it's bound to
+    #[callback(Option<usize>, Vec2)]
+    on_double_click: Option<Box<dyn Fn(&mut EventContext, Option<usize>, Vec2)>>,
 }
 
-impl<P, R> MsegGraph<P, R>
+impl<P, R, T> MsegGraph<P, R, T>
 where
-    P: Lens<Target = CurvePoints>,
+    P: Lens<Target = CurvePoints<T>>,
+    T: 'static + Clone,
     R: Lens<Target = RangeInclusive<f32>>,
 {
     /// Create a new `MsegGraph`
@@ -65,227 +382,1672 @@ where
     ///   representing the section of the graph of which we are zoomed. This can
     ///   be any set of numbers between 0 and 1 inclusive where the start is
     ///   less than the end.
-    /// * `max` - the max `x`, in `f32` seconds, of the envelope visualization.
-    ///   For example, if the max is `8.0`, the maximum length of the envelope
-    ///   is then 8 seconds.
-    pub fn new(cx: &mut Context, points: P, range: R, max: f32) -> Handle<MsegGraph<P, R>> {
-        let mut classes = HashMap::<&'static str, Entity>::default();
-        let mut insert_color = |name| {
-            let e = Element::new(cx).class(name).display(Display::None).entity;
-            classes.insert(name, e);
-        };
-        insert_color("point");
-        Self {
-            points,
-            max,
+    /// * `max` - the max `x`, in `f32` seconds, of the envelope visualization,
+    ///   or a [`Lens`] to it. For example, if the max is `8.0`, the maximum
+    ///   length of the envelope is then 8 seconds.
+    /// * `value_mapping` - maps the normalized `y` of points to the values
+    ///   shown in readouts
+    pub fn new(
+        cx: &mut Context,
+        points: P,
+        range: R,
+        max: impl Res<f32>,
+        value_mapping: Rc<dyn ValueMapping>,
+    ) -> Handle<MsegGraph<P, R, T>> {
+        install_ticker(cx);
+        let handle = Self {
+            points: points.clone(),
+            data: PhantomData::default(),
+            max: 1f32,
+            value_range: 0f32..=1f32,
+            snap_grid: SnapGrid::default(),
+            value_mapping,
             active_point_id: None,
-            is_dragging_point: false,
+            ring_point_id: None,
+            selection: Vec::new(),
+            marquee: None,
+            group_drag: None,
+            active_handle_id: None,
+            bend: None,
+            region: None,
+            region_drag: None,
+            over_region_end: false,
+            drag: DragGesture::new(),
+            moves: MoveCoalescer::new(),
+            presses: PressReader::new(),
+            cursor: CursorAffordance::new(),
+            pan: None,
+            last_pan: (Instant::now(), 0f32),
+            inertia: Inertia::new(COAST_STOP_VELOCITY),
+            geometry: RefCell::default(),
+            data_error: None,
+            morph_preview: None,
             on_changing_point: None,
+            on_changing_points: None,
             range,
             on_remove_point: None,
             on_insert_point: None,
-            classes,
+            on_changing_tension: None,
+            on_changing_range: None,
+            on_changing_value_range: None,
+            on_data_error: None,
+            on_double_click: None,
+            styles: MsegGraphStyles::new(cx),
+            hover_radius: HOVER_RADIUS,
         }
         .build(cx, |_cx| {})
+        .tab_stop();
+        handle
+            .check_data(points, |points| {
+                points.validate(0f32..=f32::INFINITY, 0f32..=1f32)
+            })
+            .max(max)
+    }
+
+    /// What the graph shows besides its points: the hovered point, the point
+    /// with the hover ring, the hovered curve handle and whether a point is
+    /// being dragged. The graph only asks for a redraw when this or the
+    /// points change.
+    fn visual_state(&self) -> (Option<usize>, Option<usize>, Option<usize>, bool) {
+        (
+            self.active_point_id,
+            self.ring_point_id,
+            self.active_handle_id,
+            self.drag.is_dragging(),
+        )
+    }
+
+    /// Maps points to the view and back, through the zoomed range of times and
+    /// the values shown
+    fn transform(&self, cx: &EventContext) -> ViewTransform {
+        ViewTransform::from_cx(cx, self.range.clone(), self.max)
+            .with_value_range(self.value_range.clone())
+    }
+
+    /// Gets the point within the hover radius of `cursor`, reusing the
+    /// points projected by the last draw unless they or the view changed
+    fn hovered_point(
+        &mut self,
+        cx: &EventContext,
+        points: &CurvePoints<T>,
+        cursor: Vec2,
+    ) -> Option<usize> {
+        let hover_radius = cx.to_physical(self.hover_radius);
+        let transform = self.transform(cx);
+        let projected = &mut self.geometry.get_mut().projected;
+        projected.update(&transform, points);
+        projected.closest(cursor, hover_radius)
+    }
+
+    /// Gets the point ending the segment whose curve handle is within the
+    /// hover radius of `cursor`, from the points projected by
+    /// [`hovered_point`](Self::hovered_point)
+    fn hovered_handle(&mut self, cx: &EventContext, cursor: Vec2) -> Option<usize> {
+        let hover_radius = cx.to_physical(self.hover_radius);
+        let min_width = cx.to_physical(MIN_CURVE_HANDLE_SEGMENT);
+        let projected = &self.geometry.get_mut().projected;
+        projected.closest_midpoint(cursor, hover_radius, min_width)
+    }
+
+    /// Snaps `point` in data space to the grid, unless Ctrl is held
+    fn snap(&self, cx: &EventContext, point: Vec2) -> Vec2 {
+        if cx.modifiers.contains(Modifiers::CTRL) {
+            point
+        } else {
+            self.snap_grid.snap(point)
+        }
+    }
+
+    /// The time in seconds under `cursor`, within the envelope
+    fn time_at(&self, cx: &EventContext, cursor: Vec2) -> f32 {
+        let transform = self.transform(cx);
+        transform.to_data(cursor).x.clamp(0f32, self.max)
+    }
+
+    /// Whether `cursor` is within the hover radius of the end of the selected
+    /// region
+    fn near_region_end(&self, cx: &EventContext, cursor: Vec2) -> bool {
+        let transform = self.transform(cx);
+        self.region.as_ref().is_some_and(|region| {
+            let end = transform.to_ui(Vec2::new(*region.end(), 0f32));
+            (end.x - cursor.x).abs() <= cx.to_physical(self.hover_radius)
+        })
+    }
+
+    /// Moves a point to a position in data space, keeping the first point at
+    /// the origin, the last one at the bottom and every point between its
+    /// neighbours
+    fn set_point(
+        &self,
+        cx: &mut EventContext,
+        points: &CurvePoints<T>,
+        index: usize,
+        position: Vec2,
+    ) {
+        // Up to the user to move the point around
+        if let Some(callback) = &self.on_changing_point {
+            let mut new_v = if index != 0 { position } else { Vec2::ZERO };
+            if index == points.len() - 1 {
+                new_v.y = 0f32;
+            }
+
+            // Clamp the point (and check for left and right bounds)
+            let new_v = points
+                .clamp_point(index, new_v, MIN_RESOLUTION, 0f32..=self.max)
+                .unwrap_or(new_v);
+
+            (callback)(cx, index, new_v);
+            cx.request_redraw();
+            cx.perform_edit(index, &[new_v.x, new_v.y]);
+            cx.show_value_tooltip(self.readout(new_v));
+        }
+    }
+
+    /// The time and value of a point in data space, as shown in tooltips
+    fn readout(&self, point: Vec2) -> String {
+        let value = self.value_mapping.denormalize(point.y);
+        format!("{}, {:.2}", format_seconds(point.x), value)
+    }
+
+    /// Moves the selected points together by `offset` in data space from
+    /// where they are in `from`, keeping the first point at the origin and the
+    /// last one at the bottom. Returns the points as they were moved.
+    fn move_selection(
+        &self,
+        cx: &mut EventContext,
+        points: &CurvePoints<T>,
+        from: &CurvePoints<T>,
+        offset: Vec2,
+    ) -> CurvePoints<T> {
+        let last = from.len().saturating_sub(1);
+        let indices: Vec<usize> = self
+            .selection
+            .iter()
+            .copied()
+            .filter(|&i| i != 0 && i <= last)
+            .collect();
+        let mut moved = from.clone();
+        moved.move_group(&indices, offset, MIN_RESOLUTION, 0f32..=self.max);
+        if indices.contains(&last) {
+            moved[last].y = 0f32;
+        }
+        self.move_points(cx, points, &moved);
+        moved
+    }
+
+    /// Bends the segment ending at the point at `index` with `curve`
+    fn set_tension(&self, cx: &mut EventContext, index: usize, curve: f32) {
+        if let Some(callback) = &self.on_changing_tension {
+            (callback)(cx, index, curve);
+            cx.request_redraw();
+            cx.perform_edit(index, &[curve]);
+            cx.show_value_tooltip(format!("{:.2}", curve));
+        }
+    }
+
+    /// Bends the segment ending at the point at `index` so that it passes
+    /// through the value under `cursor` halfway through its time. Flat
+    /// segments stay as they are.
+    fn bend_to(&self, cx: &mut EventContext, index: usize, cursor: Vec2) {
+        let y = self.transform(cx).to_data(cursor).y;
+        if let Some(curve) = self.points.get(cx).curve_through(index, y) {
+            self.set_tension(cx, index, curve);
+        }
+    }
+
+    /// Stops bending the segment whose handle is dragged
+    fn end_bend(&mut self, cx: &mut EventContext) {
+        if self.bend.take().is_some() {
+            cx.release();
+            cx.end_edit();
+            cx.hide_tooltip();
+        }
+    }
+
+    /// Cancels bending the segment whose handle is dragged, putting its
+    /// curve back
+    fn cancel_bend(&mut self, cx: &mut EventContext) -> bool {
+        let (index, curve) = match self.bend.take() {
+            Some(bend) => bend,
+            None => return false,
+        };
+        self.set_tension(cx, index, curve);
+        cx.release();
+        cx.end_edit();
+        cx.hide_tooltip();
+        true
+    }
+
+    /// Reports the points that moved from `points` to `moved`, the same
+    /// points at other times. Points moving later are reported from the last
+    /// one, and points moving earlier from the first one, so that none is
+    /// moved past a neighbour that hasn't moved yet.
+    fn move_points(&self, cx: &mut EventContext, points: &CurvePoints<T>, moved: &CurvePoints<T>) {
+        if self.on_changing_point.is_none() && self.on_changing_points.is_none() {
+            return;
+        }
+        let mut changed: Vec<(usize, Vec2)> = (0..points.len().min(moved.len()))
+            .filter(|&i| points[i].x != moved[i].x || points[i].y != moved[i].y)
+            .map(|i| (i, Vec2::new(moved[i].x, moved[i].y)))
+            .collect();
+        if changed
+            .first()
+            .is_some_and(|&(i, point)| point.x > points[i].x)
+        {
+            changed.reverse();
+        }
+        for &(index, point) in &changed {
+            cx.perform_edit(index, &[point.x, point.y]);
+        }
+        match (&self.on_changing_points, &self.on_changing_point) {
+            (Some(callback), _) => (callback)(cx, changed),
+            (None, Some(callback)) => {
+                for (index, point) in changed {
+                    (callback)(cx, index, point);
+                }
+            }
+            (None, None) => (),
+        }
+        cx.request_redraw();
+    }
+
+    /// Selects the points within the marquee, as well as the ones it keeps
+    /// selected
+    fn drag_marquee(&mut self, cx: &mut EventContext, cursor: Vec2) {
+        let transform = self.transform(cx);
+        let marquee = match &mut self.marquee {
+            Some(marquee) => marquee,
+            None => return,
+        };
+        marquee.to = cursor;
+        let (a, b) = (transform.to_data(marquee.from), transform.to_data(cursor));
+        let (min, max) = (a.min(b), a.max(b));
+        let mut selection = marquee.kept.clone();
+        self.points.view(cx.data().unwrap(), |points| {
+            let points = points.unwrap();
+            selection.extend(points.iter().enumerate().filter_map(|(i, point)| {
+                let inside =
+                    (min.x..=max.x).contains(&point.x) && (min.y..=max.y).contains(&point.y);
+                inside.then_some(i)
+            }));
+        });
+        selection.sort_unstable();
+        selection.dedup();
+        self.selection = selection;
+        cx.request_redraw();
+    }
+
+    /// Stops dragging out the marquee, keeping the points it selected
+    fn end_marquee(&mut self, cx: &mut EventContext) {
+        if self.marquee.take().is_some() {
+            cx.release();
+            cx.request_redraw();
+        }
+    }
+
+    /// Cancels dragging out the marquee, selecting the points selected
+    /// before it again
+    fn cancel_marquee(&mut self, cx: &mut EventContext) -> bool {
+        let marquee = match self.marquee.take() {
+            Some(marquee) => marquee,
+            None => return false,
+        };
+        self.selection = marquee.kept;
+        cx.release();
+        cx.request_redraw();
+        true
+    }
+
+    /// Removes the selected points, from the last one so the indices of the
+    /// rest stay the same
+    fn remove_selection(&mut self, cx: &mut EventContext) {
+        if let Some(callback) = &self.on_remove_point {
+            for &index in self.selection.iter().rev() {
+                (callback)(cx, index);
+            }
+            cx.request_redraw();
+        }
+        self.selection.clear();
+    }
+
+    /// Selects the region from where the drag started to the cursor, or
+    /// stretches the selected region so that it ends at the cursor
+    fn drag_region(&mut self, cx: &mut EventContext, cursor: Vec2) {
+        let time = self.time_at(cx, cursor);
+        match &self.region_drag {
+            Some(RegionDrag::Selecting(from)) => {
+                self.region = Some(from.min(time)..=from.max(time));
+            }
+            Some(RegionDrag::Stretching(region, before)) => {
+                let mut stretched = before.clone();
+                let end =
+                    stretched.stretch_region(region.clone(), time, MIN_RESOLUTION, 0f32..=self.max);
+                let points = self.points.get(cx);
+                self.move_points(cx, &points, &stretched);
+                self.region = Some(*region.start()..=end);
+                cx.show_value_tooltip(format_seconds(end - region.start()));
+            }
+            None => return,
+        }
+        cx.request_redraw();
+    }
+
+    /// Stops selecting or stretching the region. A region too short to be
+    /// stretched is cleared.
+    fn end_region_drag(&mut self, cx: &mut EventContext) {
+        match self.region_drag.take() {
+            Some(RegionDrag::Selecting(_)) => {
+                if self
+                    .region
+                    .as_ref()
+                    .is_some_and(|region| region.width() < MIN_RESOLUTION)
+                {
+                    self.region = None;
+                }
+            }
+            Some(RegionDrag::Stretching(..)) => {
+                cx.end_edit();
+                cx.hide_tooltip();
+            }
+            None => return,
+        }
+        cx.release();
+        cx.request_redraw();
+    }
+
+    /// Cancels selecting or stretching the region, putting the points back
+    /// where the stretch started
+    fn cancel_region_drag(&mut self, cx: &mut EventContext) -> bool {
+        match self.region_drag.take() {
+            Some(RegionDrag::Selecting(_)) => self.region = None,
+            Some(RegionDrag::Stretching(region, before)) => {
+                let points = self.points.get(cx);
+                self.move_points(cx, &points, &before);
+                self.region = Some(region);
+                cx.end_edit();
+                cx.hide_tooltip();
+            }
+            None => return false,
+        }
+        cx.release();
+        cx.request_redraw();
+        true
+    }
+
+    /// Performs what a press of a mouse button is bound to: selecting,
+    /// dragging or deleting the hovered point, bending or straightening the
+    /// segment of the hovered curve handle, or inserting a point, opening the
+    /// menu, panning or dragging out a marquee elsewhere, and selecting a
+    /// region or stretching the selected one
+    fn press(&mut self, cx: &mut EventContext, press: MouseBinding) {
+        if press.double_click && press.button == MouseButton::Left {
+            if let Some(callback) = &self.on_double_click {
+                let cursor = Vec2::new(cx.mouse.cursorx, cx.mouse.cursory);
+                let transform = self.transform(cx);
+                (callback)(cx, self.active_point_id, transform.to_data(cursor));
+            }
+        }
+        if press == MouseBinding::click(MouseButton::Left) && self.over_region_end {
+            if let Some(region) = self.region.clone() {
+                self.region_drag = Some(RegionDrag::Stretching(region, self.points.get(cx)));
+                cx.capture();
+                cx.begin_edit();
+                return;
+            }
+        }
+        let input = cx.input_map();
+        if input.triggers(InputAction::SelectRegion, press) {
+            let cursor = Vec2::new(cx.mouse.cursorx, cx.mouse.cursory);
+            let time = self.time_at(cx, cursor);
+            self.region = Some(time..=time);
+            self.region_drag = Some(RegionDrag::Selecting(time));
+            cx.capture();
+            cx.request_redraw();
+        } else if let Some(index) = self.active_point_id {
+            if input.triggers(InputAction::DeletePoint, press) {
+                // The first press of a double click started a drag
+                if self.drag.cancel(cx).is_some() {
+                    cx.end_edit();
+                    cx.hide_tooltip();
+                }
+                self.group_drag = None;
+                if let Some(callback) = &self.on_remove_point {
+                    (callback)(cx, index);
+                    cx.request_redraw();
+                }
+                self.active_point_id = None;
+                self.selection.clear();
+            } else if press.button == MouseButton::Left {
+                // TODO: only set active point if cursor is within the element.
+                // Right now it will activate even if the cursor is off the element.
+                let points = self.points.get(cx);
+                let point = match points.get(index) {
+                    Some(point) => Vec2::new(point.x, point.y),
+                    None => return,
+                };
+                self.selection.retain(|&i| i < points.len());
+                let selected = self.selection.binary_search(&index);
+                // Shift-clicking a selected point takes it out of the
+                // selection, and clicking an unselected one selects it alone
+                match (press.modifiers.contains(Modifiers::SHIFT), selected) {
+                    (true, Ok(position)) => {
+                        self.selection.remove(position);
+                        cx.request_redraw();
+                        return;
+                    }
+                    (true, Err(position)) => self.selection.insert(position, index),
+                    (false, Ok(_)) => (),
+                    (false, Err(_)) => self.selection = vec![index],
+                }
+                self.group_drag = (self.selection.len() > 1).then(|| points.clone());
+                self.drag.begin(cx, point);
+                cx.begin_edit();
+                cx.request_redraw();
+            }
+        } else if let (Some(index), MouseButton::Left) = (self.active_handle_id, press.button) {
+            if press.double_click {
+                cx.begin_edit();
+                self.set_tension(cx, index, 0f32);
+                cx.end_edit();
+                cx.hide_tooltip();
+            } else if let Some(point) = self.points.get(cx).get(index) {
+                self.bend = Some((index, point.curve));
+                cx.capture();
+                cx.begin_edit();
+            }
+        } else if input.triggers(InputAction::InsertPoint, press) {
+            let cursor = Vec2::new(cx.mouse.cursorx, cx.mouse.cursory);
+            let at = self.snap(cx, self.transform(cx).to_data(cursor));
+            // Points can't be closer in time than the drag allows, so a point
+            // inserted next to another is moved away from it, or left out if
+            // there's no room between its neighbours
+            let inserted = self
+                .points
+                .get(cx)
+                .insertion_point(at, MIN_RESOLUTION, 0f32..=self.max);
+            let (index, point) = match inserted {
+                Some(inserted) => inserted,
+                None => return,
+            };
+            if let Some(callback) = &self.on_insert_point {
+                (callback)(cx, index, point);
+                cx.request_redraw();
+                // The new point is under the cursor, ready to be dragged
+                self.active_point_id = Some(index);
+                // and the selected points after it moved up by one
+                for selected in self.selection.iter_mut().filter(|i| **i >= index) {
+                    *selected += 1;
+                }
+            }
+        } else if input.triggers(InputAction::OpenMenu, press) {
+            cx.emit(MsegEvent::OpenContextMenu);
+        } else if input.triggers(InputAction::Pan, press) {
+            self.pan = Some((cx.mouse.cursorx, self.range.get(cx)));
+            self.last_pan = (Instant::now(), cx.mouse.cursorx);
+            cx.capture();
+        } else if press.button == MouseButton::Left && !press.double_click {
+            let cursor = Vec2::new(cx.mouse.cursorx, cx.mouse.cursory);
+            if !press.modifiers.contains(Modifiers::SHIFT) {
+                self.selection.clear();
+            }
+            self.marquee = Some(Marquee {
+                from: cursor,
+                to: cursor,
+                kept: self.selection.clone(),
+            });
+            cx.capture();
+            cx.request_redraw();
+        }
+    }
+
+    /// Stops panning, letting the view pan on by itself if it was let go
+    /// while still moving
+    fn end_pan(&mut self, cx: &mut EventContext) {
+        if self.pan.take().is_some() {
+            cx.release();
+            let idle = self.last_pan.0.elapsed().as_secs_f32();
+            if self.inertia.release(idle) {
+                self.last_pan.0 = Instant::now();
+                cx.request_tick(cx.current());
+            }
+        }
+    }
+
+    /// Pans on from the last pan at the speed the view was let go at, slowing
+    /// down until it stops or reaches either end of the envelope, and asks
+    /// for another tick until then
+    fn coast(&mut self, cx: &mut EventContext) {
+        let now = Instant::now();
+        let elapsed = now.duration_since(self.last_pan.0).as_secs_f32();
+        self.last_pan.0 = now;
+        let distance = self.inertia.advance(elapsed);
+        match &self.on_changing_range {
+            Some(callback) if distance != 0f32 => {
+                let bounds = cx.cache.get_bounds(cx.current());
+                let range = self.range.get(cx);
+                let width = range.width();
+                let unclamped = range.start() - distance / bounds.w * width;
+                let from = unclamped.clamp(0f32, 1f32 - width);
+                if from != unclamped {
+                    self.inertia.stop();
+                }
+                (callback)(cx, from..=from + width);
+            }
+            _ => self.inertia.stop(),
+        }
+        if self.inertia.is_coasting() {
+            cx.request_tick(cx.current());
+        }
+    }
+
+    /// Stops the view panning on by itself
+    fn stop_coasting(&mut self) -> bool {
+        let coasting = self.inertia.is_coasting();
+        self.inertia.stop();
+        coasting
+    }
+
+    /// Cancels the drag of the active point, if any, putting the point back
+    /// where the drag started
+    fn cancel_drag(&mut self, cx: &mut EventContext) -> bool {
+        let start = match self.drag.cancel(cx) {
+            Some(start) => start,
+            None => return false,
+        };
+        if let Some(before) = self.group_drag.take() {
+            let points = self.points.get(cx);
+            self.move_points(cx, &points, &before);
+        } else if let Some(index) = self.active_point_id {
+            if let Some(callback) = &self.on_changing_point {
+                (callback)(cx, index, start);
+            }
+            cx.request_redraw();
+            cx.perform_edit(index, &[start.x, start.y]);
+        }
+        cx.end_edit();
+        cx.hide_tooltip();
+        true
+    }
+
+    /// Zooms the values shown in by `steps` of Ctrl+scroll around the value
+    /// under the cursor, or out for negative steps. Returns whether the
+    /// graph zoomed.
+    fn zoom_values(&self, cx: &mut EventContext, steps: f32) -> bool {
+        let callback = match &self.on_changing_value_range {
+            Some(callback) => callback,
+            None => return false,
+        };
+        let cursor = Vec2::new(cx.mouse.cursorx, cx.mouse.cursory);
+        let anchor = self.transform(cx).to_data(cursor).y;
+        let values = zoom_window(
+            self.value_range.clone(),
+            anchor,
+            VALUE_ZOOM_STEP.powf(-steps),
+            MIN_VALUE_SPAN,
+            0f32..=1f32,
+        );
+        (callback)(cx, values);
+        true
+    }
+
+    /// Zooms the times shown in by `steps` of scrolling around the time under
+    /// the cursor, or out for negative steps, and pans them by `pan` steps of
+    /// sideways scrolling. Returns whether the graph zoomed or panned.
+    fn scroll_range(&self, cx: &mut EventContext, pan: f32, steps: f32) -> bool {
+        let callback = match &self.on_changing_range {
+            Some(callback) => callback,
+            None => return false,
+        };
+        let bounds = cx.cache.get_bounds(cx.current());
+        let range = self.range.get(cx);
+        let anchor = range.start() + (cx.mouse.cursorx - bounds.x) / bounds.w * range.width();
+        let zoomed = zoom_window(
+            range,
+            anchor,
+            TIME_ZOOM_STEP.powf(-steps),
+            MIN_TIME_SPAN,
+            0f32..=1f32,
+        );
+        let width = zoomed.width();
+        let from = (zoomed.start() - pan * SCROLL_PAN_STEP * width).clamp(0f32, 1f32 - width);
+        (callback)(cx, from..=from + width);
+        true
+    }
+
+    /// Pans the zoomed range by how far the cursor moved since panning
+    /// started
+    fn pan_to(&self, cx: &mut EventContext, cursor: Vec2) {
+        if let (Some((origin, start)), Some(callback)) = (&self.pan, &self.on_changing_range) {
+            let bounds = cx.cache.get_bounds(cx.current());
+            let width = start.width();
+            let offset = (origin - cursor.x) / bounds.w * width;
+            let from = (start.start() + offset).clamp(0f32, 1f32 - width);
+            (callback)(cx, from..=from + width);
+        }
+    }
+
+    /// Drags the active point to the cursor, or finds the point it hovers
+    fn mouse_move(&mut self, cx: &mut EventContext, cursor: Vec2) {
+        if self.pan.is_some() {
+            let now = Instant::now();
+            let (last_time, last_x) = self.last_pan;
+            self.inertia.drag(
+                cursor.x - last_x,
+                now.duration_since(last_time).as_secs_f32(),
+            );
+            self.last_pan = (now, cursor.x);
+            self.pan_to(cx, cursor);
+        } else if self.region_drag.is_some() {
+            self.drag_region(cx, cursor);
+        } else if let Some((index, _)) = self.bend {
+            self.bend_to(cx, index, cursor);
+        } else if self.marquee.is_some() {
+            self.drag_marquee(cx, cursor);
+        }
+        // Drag around the point to match the current cursor
+        // position, which moves slower while fine dragging
+        else if let Some(drag) = self.drag.update(cx, cursor.x, cursor.y) {
+            let active_id = self.active_point_id.unwrap();
+            let points = self.points.get(cx);
+            let transform = self.transform(cx);
+            match &self.group_drag {
+                // The selected points move as far as the cursor did since
+                // the drag started
+                Some(before) => {
+                    let origin = transform.to_data(drag.position - drag.total);
+                    let mut offset = transform.to_data(drag.position) - origin;
+                    // The point under the cursor snaps and the rest keep
+                    // their distance from it
+                    if let Some(point) = before.get(active_id) {
+                        let start = Vec2::new(point.x, point.y);
+                        offset = self.snap(cx, start + offset) - start;
+                    }
+                    let moved = self.move_selection(cx, &points, before, offset);
+                    if let Some(point) = moved.get(active_id) {
+                        cx.show_value_tooltip(self.readout(Vec2::new(point.x, point.y)));
+                    }
+                }
+                None => {
+                    let position = self.snap(cx, transform.to_data(drag.position));
+                    self.set_point(cx, &points, active_id, position);
+                }
+            }
+        }
+        // If not dragging, perform some other checks
+        else {
+            // determine if we are hovering within the range of a
+            //point if we are not currently dragging points. The points are
+            // viewed rather than cloned, as there may be thousands of them.
+            let points = self.points.clone();
+            let (hovered, readout) = points.view(cx.data().unwrap(), |points| {
+                let points = points.unwrap();
+                let hovered = self.hovered_point(cx, points, cursor);
+                let readout = hovered
+                    .and_then(|i| points.get(i))
+                    .map(|point| self.readout(Vec2::new(point.x, point.y)));
+                (hovered, readout)
+            });
+            self.over_region_end = hovered.is_none() && self.near_region_end(cx, cursor);
+            // Points and the end of the region are grabbed before the curve
+            // handles between them
+            self.active_handle_id = if hovered.is_none() && !self.over_region_end {
+                self.hovered_handle(cx, cursor)
+            } else {
+                None
+            };
+            if hovered != self.active_point_id {
+                match readout {
+                    Some(readout) => cx.show_tooltip(readout),
+                    None => cx.hide_tooltip(),
+                }
+            }
+            self.active_point_id = hovered;
+        }
+    }
+
+    /// Stores the result of checking the points, reporting a new problem and
+    /// dropping any drag or selection, whose indices can't be trusted
+    fn data_checked(&mut self, cx: &mut EventContext, error: Option<CurveError>) {
+        if let Some(error) = error {
+            if self.drag.cancel(cx).is_some() {
+                cx.end_edit();
+                cx.hide_tooltip();
+            }
+            if let Some(drag) = self.region_drag.take() {
+                if let RegionDrag::Stretching(..) = drag {
+                    cx.end_edit();
+                    cx.hide_tooltip();
+                }
+                cx.release();
+            }
+            self.end_bend(cx);
+            self.end_marquee(cx);
+            self.group_drag = None;
+            self.active_point_id = None;
+            self.active_handle_id = None;
+            self.ring_point_id = None;
+            self.selection.clear();
+            if let Some(callback) = &self.on_data_error {
+                (callback)(cx, error);
+            }
+        }
+        self.data_error = error;
+        cx.request_redraw();
     }
 }
 
-impl<P, R> View for MsegGraph<P, R>
+impl<P, R, T> View for MsegGraph<P, R, T>
 where
-    P: Lens<Target = CurvePoints>,
+    P: Lens<Target = CurvePoints<T>>,
+    T: 'static + Clone,
     R: Lens<Target = RangeInclusive<f32>>,
 {
     fn event(&mut self, cx: &mut EventContext, event: &mut Event) {
-        let points = self.points.get(cx);
-        let ui_points: Vec<Vec2> = points
-            .iter()
-            .map(|point| {
-                data_to_ui_pos_range(
-                    cx,
-                    Vec2::new(point.x, point.y),
-                    self.range.clone(),
-                    self.max,
-                )
-            })
-            .collect();
-        // Window events to move points
+        event.map(|checked: &DataChecked<CurveError>, meta| {
+            self.data_checked(cx, checked.0);
+            meta.consume();
+        });
+        // Ticks are handled even while disabled, so coasting runs out
+        event.map(|_: &FrameTick, meta| {
+            if self.inertia.is_coasting() {
+                self.coast(cx);
+            }
+            meta.consume();
+        });
+        if cx.is_disabled() || self.data_error.is_some() {
+            return;
+        }
+        let before = self.visual_state();
+        // Scrolling zooms and pans the times, and Ctrl+scroll zooms the
+        // values. Without the callbacks, scrolling is left to the views around
+        // the graph.
+        event.map(|ev: &WindowEvent, meta| {
+            if let WindowEvent::MouseScroll(x, y) = *ev {
+                let scrolled = if cx.modifiers.contains(Modifiers::CTRL) {
+                    self.zoom_values(cx, y)
+                } else {
+                    self.stop_coasting();
+                    self.scroll_range(cx, x, y)
+                };
+                if scrolled {
+                    meta.consume();
+                }
+            }
+        });
+        // Window events to move points. Points are only read, and projected
+        // into the view, by the events that need them.
         event.map(|ev: &WindowEvent, _| match *ev {
             WindowEvent::MouseDown(button) => {
-                match button {
-                    MouseButton::Left => {
-                        // TODO: only set active point if cursor is within the element.
-                        // Right now it will activate even if the cursor is off the element.
-                        if self.active_point_id.is_some() {
-                            cx.capture();
-                            self.is_dragging_point = true;
-                        } else {
-                            // TODO: create a new point
-                        }
-                    }
-                    MouseButton::Right => {
-                        // Delete a currently active point
-                        if let Some(index) = self.active_point_id {
-                            cx.release();
-                            self.is_dragging_point = false;
-                            if let Some(callback) = &self.on_remove_point {
-                                (callback)(cx, index);
-                            }
-                        }
-                    }
-                    _ => (),
+                // Find the hovered point before pressing it
+                if let Some(cursor) = self.moves.take() {
+                    self.mouse_move(cx, cursor);
+                }
+                // Right-clicking while dragging cancels the drag, and any
+                // click stops the view panning on by itself
+                let cancelled = button == MouseButton::Right
+                    && (self.cancel_drag(cx)
+                        || self.cancel_region_drag(cx)
+                        || self.cancel_bend(cx)
+                        || self.cancel_marquee(cx));
+                if cancelled || self.stop_coasting() {
+                    return;
+                }
+                if let Some(press) = self.presses.read(cx, ev) {
+                    self.press(cx, press);
                 }
             }
             // Release the current context and signal that we are no longer
             // dragging a point
             WindowEvent::MouseUp(button) => {
+                // End the drag where the cursor was released
+                if let Some(cursor) = self.moves.take() {
+                    self.mouse_move(cx, cursor);
+                }
+                self.end_pan(cx);
                 if button == MouseButton::Left {
-                    cx.release();
-                    self.is_dragging_point = false;
+                    self.end_region_drag(cx);
+                    self.end_bend(cx);
+                    self.end_marquee(cx);
+                }
+                if button == MouseButton::Left && self.drag.end(cx) {
+                    self.group_drag = None;
+                    cx.end_edit();
+                    cx.hide_tooltip();
                 }
             }
-            // Perform dragging actions depending on state
-            WindowEvent::MouseMove(x, y) => {
-                let current_pos = Vec2::new(x, y);
-                // Drag around the point to match the current cursor
-                // position
-                if self.is_dragging_point {
-                    // Up to the user to drag the current point around
-                    if let Some(callback) = &self.on_changing_point {
-                        let active_id = self.active_point_id.unwrap();
-                        let mut new_v = if active_id != 0 {
-                            ui_to_data_pos_range(cx, &current_pos, self.range.clone(), self.max)
-                        } else {
-                            Vec2::ZERO
-                        };
-                        if active_id == points.len() - 1 {
-                            new_v.y = 0f32;
-                        }
-
-                        // Clamp the point (and check for left and right
-                        // bounds)
-                        let right_bound =
-                            points.get(active_id + 1).map(|p| p.x).unwrap_or(self.max)
-                                - MIN_RESOLUTION;
-                        let left_bound =
-                            points.get(active_id - 1).map(|p| p.x).unwrap_or(0f32) + MIN_RESOLUTION;
-                        let new_v =
-                            new_v.clamp(Vec2::new(left_bound, 0f32), Vec2::new(right_bound, 1f32));
-
-                        (callback)(cx, active_id, new_v);
-                    }
+            WindowEvent::MouseMove(x, y) => self.moves.push(cx, x, y),
+            // The keys move the selected points in steps of the view's size,
+            // and delete removes them
+            WindowEvent::KeyDown(code, _) => {
+                // Escape cancels the drag, or else clears the region, or else
+                // the selection
+                if code == Code::Escape
+                    && (self.cancel_drag(cx)
+                        || self.cancel_region_drag(cx)
+                        || self.cancel_bend(cx)
+                        || self.cancel_marquee(cx)
+                        || self.region.take().is_some()
+                        || !std::mem::take(&mut self.selection).is_empty())
+                {
+                    cx.request_redraw();
+                    return;
                 }
-                // If not dragging, perform some other checks
-                else {
-                    // determine if we are hovering within the range of a
-                    //point if we are not currently dragging points
-                    let mut filtered_points: Vec<(usize, Vec2)> = ui_points
-                        .iter()
-                        .enumerate()
-                        .filter_map(|(i, point)| {
-                            if point.distance_squared(current_pos) <= HOVER_RADIUS.powi(2) {
-                                Some((i, *point))
-                            } else {
-                                None
-                            }
-                        })
-                        .collect();
-                    // Sort points by shortest to furthest distance This is
-                    // important in the case that multiple hovered points
-                    // exist that we select the one closest to the cursor.
-                    filtered_points.sort_by(|a, b| {
-                        // Use distance squared to avoid `sqrt` operations
-                        a.1.distance_squared(current_pos)
-                            .partial_cmp(&b.1.distance_squared(current_pos))
-                            .unwrap_or(Ordering::Equal)
-                    });
-                    // Store our point ID in the case that it exists (i.e.,
-                    // our pointer is close enough to at least one node)
-                    match filtered_points.first() {
-                        Some((closest_point_id, ..)) => {
-                            self.active_point_id = Some(*closest_point_id);
-                        }
-                        _ => self.active_point_id = None,
+                let points = self.points.get(cx);
+                self.selection.retain(|&i| i < points.len());
+                if self.selection.is_empty() || self.drag.is_dragging() || self.marquee.is_some() {
+                    return;
+                }
+                if let Code::Delete | Code::Backspace = code {
+                    self.remove_selection(cx);
+                } else if let Some(nudge) = KeyNudge::from_event(cx, code) {
+                    // The keys step the first selected point, or the second
+                    // one when the first is the point held at the origin,
+                    // and the rest follow it
+                    let index = match self.selection[..] {
+                        [0, second, ..] => second,
+                        [first, ..] => first,
+                        [] => return,
+                    };
+                    let rect = cx.cache.get_bounds(cx.current());
+                    let transform = self.transform(cx);
+                    let point = Vec2::new(points[index].x, points[index].y);
+                    let position = nudge.apply_point(
+                        transform.to_ui(point),
+                        Vec2::new(rect.left(), rect.bottom()),
+                        Vec2::new(rect.right(), rect.top()),
+                    );
+                    let position = transform.to_data(position);
+                    cx.begin_edit();
+                    if self.selection.len() == 1 {
+                        self.set_point(cx, &points, index, position);
+                    } else {
+                        self.move_selection(cx, &points, &points, position - point);
                     }
+                    cx.end_edit();
                 }
             }
             // WindowEvent::MouseOut => todo!(),
             _ => (),
         });
+        event.map(|_: &CoalescedMove, meta| {
+            if let Some(cursor) = self.moves.take() {
+                self.mouse_move(cx, cursor);
+            }
+            meta.consume();
+        });
+        let (hovered, dragging) = (self.active_point_id.is_some(), self.drag.is_dragging());
+        self.styles
+            .set_point_pseudo_class(cx, PseudoClass::HOVER, hovered && !dragging);
+        self.styles
+            .set_point_pseudo_class(cx, PseudoClass::ACTIVE, dragging);
+        // The ring grows and fades in through the transitions of the
+        // `.hover-ring` class
+        if self.active_point_id.is_some() {
+            self.ring_point_id = self.active_point_id;
+        }
+        self.styles
+            .set_hover_ring_pseudo_class(cx, PseudoClass::HOVER, hovered || dragging);
+        let grabbing = dragging || self.bend.is_some() || self.pan.is_some();
+        let stretching = matches!(self.region_drag, Some(RegionDrag::Stretching(..)));
+        let affordance = if self.over_region_end || stretching {
+            Affordance::ResizeHorizontal
+        } else {
+            Affordance::of_point(hovered || self.active_handle_id.is_some(), grabbing)
+        };
+        self.cursor.update(cx, event, affordance);
+        cx.redraw_if_changed(&before, &self.visual_state());
     }
     fn draw(&self, cx: &mut DrawContext, canvas: &mut Canvas) {
-        let default_color: Color = cx.border_color().copied().unwrap_or_default();
+        if self.data_error.is_some() {
+            draw_invalid(cx, canvas, self.styles.invalid_border_color(cx));
+            return;
+        }
+        let line_color = self.styles.line_border_color(cx);
+        let line_width = self.styles.line_border_width(cx).unwrap_or(LINE_WIDTH);
+        let point_radius = self
+            .styles
+            .point_width(cx)
+            .map_or(POINT_RADIUS, |width| width / 2f32);
+        let ring_radius = self
+            .styles
+            .hover_ring_width(cx)
+            .map_or(HOVER_RING_RADIUS, |width| width / 2f32);
+        let ring_width = self.styles.point_border_width(cx).unwrap_or(LINE_WIDTH);
+        let ring_color = self.styles.hover_ring_border_color(cx);
+        let tag_color = self.styles.tag_background_color(cx);
+        let guide_color = self.styles.tag_border_color(cx);
+        let guide_width = self.styles.tag_border_width(cx).unwrap_or(TAG_GUIDE_WIDTH);
+        let region_color = self.styles.region_background_color(cx);
+        let region_end_color = self.styles.region_border_color(cx);
+        let region_end_width = self
+            .styles
+            .region_border_width(cx)
+            .unwrap_or(REGION_END_WIDTH);
+        let selection_color = self.styles.selection_border_color(cx);
+        let marquee_color = self.styles.selection_background_color(cx);
+        let marquee_width = self
+            .styles
+            .selection_border_width(cx)
+            .unwrap_or(MARQUEE_WIDTH);
+        let grid_color = self.styles.grid_border_color(cx);
+        let grid_width = self.styles.grid_border_width(cx).unwrap_or(GRID_LINE_WIDTH);
+        let min_grid_gap = cx.to_physical(MIN_GRID_GAP);
+        let morph_color = self.styles.morph_border_color(cx);
+        let morph_width = self.styles.morph_border_width(cx).unwrap_or(LINE_WIDTH);
+        let handle_radius = self
+            .styles
+            .curve_handle_width(cx)
+            .map_or(CURVE_HANDLE_RADIUS, |width| width / 2f32);
+        let handle_color = self.styles.curve_handle_border_color(cx);
+        let active_handle_color = self.styles.curve_handle_background_color(cx);
+        let handle_width = self
+            .styles
+            .curve_handle_border_width(cx)
+            .unwrap_or(TAG_GUIDE_WIDTH);
+        let min_handle_segment = cx.to_physical(MIN_CURVE_HANDLE_SEGMENT);
+
+        cx.draw_background(canvas);
 
         // points
         let range = self
             .range
             .view(cx.data().unwrap(), |range| range.unwrap().clone());
+        let times = time_range(range, self.max);
+        let transform =
+            ViewTransform::from_window(cx.bounds(), times.clone(), self.value_range.clone());
+        let mut geometry = self.geometry.borrow_mut();
+        let geometry = &mut *geometry;
         let bounds = cx.bounds();
+
+        // The snap grid, behind the region and the envelope, leaving out
+        // lines too close together to tell apart
+        let min_time_step = times.width() * min_grid_gap / bounds.w;
+        let min_value_step = self.value_range.width() * min_grid_gap / bounds.h;
+        let mut grid = vg::Path::new();
+        for time in self.snap_grid.time_lines(times, min_time_step) {
+            let x = transform.to_ui(Vec2::new(time, 0f32)).x;
+            grid.move_to(x, bounds.top());
+            grid.line_to(x, bounds.bottom());
+        }
+        for value in self
+            .snap_grid
+            .value_lines(self.value_range.clone(), min_value_step)
+        {
+            let y = transform.to_ui(Vec2::new(0f32, value)).y;
+            grid.move_to(bounds.left(), y);
+            grid.line_to(bounds.right(), y);
+        }
+        canvas.stroke_path(
+            &mut grid,
+            &vg::Paint::color(grid_color.into()).with_line_width(grid_width),
+        );
+
+        // The selected region, behind the envelope
+        if let Some(region) = &self.region {
+            let start = transform.to_ui(Vec2::new(*region.start(), 0f32)).x;
+            let end = transform.to_ui(Vec2::new(*region.end(), 0f32)).x;
+            let mut area = vg::Path::new();
+            area.rect(start, bounds.top(), end - start, bounds.h);
+            canvas.fill_path(&mut area, &vg::Paint::color(region_color.into()));
+            let mut edge = vg::Path::new();
+            edge.move_to(end, bounds.top());
+            edge.line_to(end, bounds.bottom());
+            canvas.stroke_path(
+                &mut edge,
+                &vg::Paint::color(region_end_color.into()).with_line_width(region_end_width),
+            );
+        }
+
+        // Points zoomed out of view are cut off at the edges of the graph
+        canvas.save();
+        canvas.scissor(bounds.x, bounds.y, bounds.w, bounds.h);
         self.points.view(cx.data().unwrap(), |points| {
             let points = points.unwrap();
-            let ui_points: Vec<(_, _)> = points
-                .iter()
-                .enumerate()
-                .map(|point| {
-                    (
-                        point.0,
-                        data_to_bounds_pos_range(
-                            bounds,
-                            Vec2::new(point.1.x, point.1.y),
-                            range.clone(),
-                            self.max,
-                        ),
-                    )
-                })
-                .collect();
+            geometry.update(&transform, points, point_radius, self.active_point_id);
+            let projected = &geometry.projected;
+
+            // Guides through the points that mark the structure of the
+            // envelope, behind the lines
+            let mut guides = vg::Path::new();
+            for (index, tag) in points.tagged() {
+                match (tag, projected.get(index)) {
+                    (PointTag::Color(_), _) | (_, None) => (),
+                    (_, Some(point)) => {
+                        guides.move_to(point.x, bounds.top());
+                        guides.line_to(point.x, bounds.bottom());
+                    }
+                }
+            }
+            canvas.stroke_path(
+                &mut guides,
+                &vg::Paint::color(guide_color.into()).with_line_width(guide_width),
+            );
 
             // Draw lines
-            let mut lines = vg::Path::new();
-            for (i, point) in &ui_points {
-                if i == &0 {
-                    lines.move_to(point.x, point.y);
+            canvas.stroke_path(
+                &mut geometry.lines,
+                &vg::Paint::color(line_color.into()).with_line_width(line_width),
+            );
+
+            // The morph preview, over the lines and under the points
+            let morphed = self
+                .morph_preview
+                .as_ref()
+                .and_then(|preview| preview(cx, points));
+            if let Some(morphed) = morphed {
+                let mut line: Vec<Vec2> = Vec::with_capacity(morphed.len());
+                for point in morphed.iter() {
+                    let ui_point = transform.to_ui(Vec2::new(point.x, point.y));
+                    match line.last() {
+                        Some(&last) => {
+                            extend_curve(&mut line, last, ui_point, point.curve, CURVE_STEP)
+                        }
+                        None => line.push(ui_point),
+                    }
+                }
+                let mut path = vg::Path::new();
+                for (i, point) in line.iter().enumerate() {
+                    if i == 0 {
+                        path.move_to(point.x, point.y);
+                    } else {
+                        path.line_to(point.x, point.y);
+                    }
+                }
+                canvas.stroke_path(
+                    &mut path,
+                    &vg::Paint::color(morph_color.into()).with_line_width(morph_width),
+                );
+            }
+
+            // The handles bending the segments wide enough to have one, with
+            // the hovered or dragged one filled in
+            let mut handles = vg::Path::new();
+            let first = projected.first_index();
+            for (i, segment) in projected.ui_points().windows(2).enumerate() {
+                let index = first + i + 1;
+                if segment[1].x - segment[0].x < min_handle_segment
+                    || self.active_handle_id == Some(index)
+                {
+                    continue;
+                }
+                if let Some(handle) = projected.midpoint(index) {
+                    handles.circle(handle.x, handle.y, handle_radius);
                 }
-                // Lines
-                lines.line_to(point.x, point.y);
             }
             canvas.stroke_path(
-                &mut lines,
-                &vg::Paint::color(default_color.into()).with_line_width(2f32),
+                &mut handles,
+                &vg::Paint::color(handle_color.into()).with_line_width(handle_width),
             );
+            if let Some(handle) = self.active_handle_id.and_then(|i| projected.midpoint(i)) {
+                let mut path = vg::Path::new();
+                path.circle(handle.x, handle.y, handle_radius);
+                canvas.fill_path(&mut path, &vg::Paint::color(active_handle_color.into()));
+            }
 
-            let point_entity = *self.classes.get("point").unwrap();
-            let active_point_color = cx.style
-                .background_color.get(point_entity)
-                .copied()
-                .unwrap_or_default();
-            let point_color = cx.style.border_color.get(point_entity).cloned().unwrap_or_default();
-
-            for (i, point) in &ui_points {
-                // check for hover
-                if self.active_point_id.map(|x| &x == i).unwrap_or_default() {
-                    let mut path = vg::Path::new();
-                    path.circle(point.x, point.y, 4.0);
-                    canvas.fill_path(&mut path, &vg::Paint::color(active_point_color.into()));
-
-                    let mut path = vg::Path::new();
-                    path.circle(point.x, point.y, 8.0);
-                    canvas.stroke_path(
-                        &mut path,
-                        &vg::Paint::color(active_point_color.into()).with_line_width(2f32),
-                    );
-                } else {
-                    let mut path = vg::Path::new();
-                    path.circle(point.x, point.y, 4.0);
-                    canvas.fill_path(&mut path, &vg::Paint::color(point_color.into()));
+            let active_point_color = self.styles.point_background_color(cx);
+            let point_color = self.styles.point_border_color(cx);
+            canvas.fill_path(&mut geometry.dots, &vg::Paint::color(point_color.into()));
+
+            // Tagged points are drawn again over the dots in their colors
+            for (index, tag) in points.tagged() {
+                let point = match projected.get(index) {
+                    Some(point) if self.active_point_id != Some(index) => point,
+                    _ => continue,
+                };
+                let color = match tag {
+                    PointTag::Color(rgba) => Color::rgba(
+                        (rgba >> 24) as u8,
+                        (rgba >> 16) as u8,
+                        (rgba >> 8) as u8,
+                        rgba as u8,
+                    ),
+                    _ => tag_color,
+                };
+                let mut path = vg::Path::new();
+                path.circle(point.x, point.y, point_radius);
+                canvas.fill_path(&mut path, &vg::Paint::color(color.into()));
+            }
+
+            // Selected points are drawn again over the dots in the selection
+            // color
+            let mut selected = vg::Path::new();
+            for index in &self.selection {
+                match projected.get(*index) {
+                    Some(point) if self.active_point_id != Some(*index) => {
+                        selected.circle(point.x, point.y, point_radius);
+                    }
+                    _ => (),
                 }
             }
+            canvas.fill_path(&mut selected, &vg::Paint::color(selection_color.into()));
+
+            // The hovered point is left out of the dots and drawn on its own
+            if let Some(point) = self.active_point_id.and_then(|i| projected.get(i)) {
+                let mut path = vg::Path::new();
+                path.circle(point.x, point.y, point_radius);
+                canvas.fill_path(&mut path, &vg::Paint::color(active_point_color.into()));
+            }
+
+            // The hover ring, which is still drawn while it shrinks and fades
+            // out after the point is left
+            if let Some(point) = self.ring_point_id.and_then(|i| projected.get(i)) {
+                let mut path = vg::Path::new();
+                path.circle(point.x, point.y, ring_radius);
+                canvas.stroke_path(
+                    &mut path,
+                    &vg::Paint::color(ring_color.into()).with_line_width(ring_width),
+                );
+            }
+        });
+
+        // The marquee, over everything else
+        if let Some(marquee) = &self.marquee {
+            let (min, max) = (marquee.from.min(marquee.to), marquee.from.max(marquee.to));
+            let mut path = vg::Path::new();
+            path.rect(min.x, min.y, max.x - min.x, max.y - min.y);
+            canvas.fill_path(&mut path, &vg::Paint::color(marquee_color.into()));
+            canvas.stroke_path(
+                &mut path,
+                &vg::Paint::color(selection_color.into()).with_line_width(marquee_width),
+            );
+        }
+        canvas.restore();
+        cx.draw_border(canvas);
+        cx.draw_outline(canvas);
+    }
+}
+
+/// Previews an envelope morphing towards another, such as for the envelope
+/// crossfades of a synth
+pub trait MorphPreviewExt<T> {
+    /// Draws the points morphed `amount` of the way towards `target`, as
+    /// [`CurvePoints::morph`] morphs them, over the graph. The preview follows
+    /// `amount` as it changes, and isn't drawn while `amount` or `target`
+    /// can't be read.
+    fn morph_preview<B, M>(self, target: B, amount: M) -> Self
+    where
+        B: Lens<Target = CurvePoints<T>>,
+        M: Lens<Target = f32>;
+}
 
-            // check to see if we are hovering near an interpolated point
-            if self.active_point_id.is_none() {
-                // TODO:  todo!()
-                // let mouse = Vec2::new(cx.mouse.cursorx, cx.mouse.cursory); let
-                // mouse_data_pos = ui_to_data_pos(cx, &mouse, self.range,
-                // self.max); let point_at_x = lerp(left., right.y, normalized);
+/// Morphs the points of a graph `amount` of the way towards `target`
+pub(super) fn morph_towards<B, M, T>(target: B, amount: M) -> MorphPreview<T>
+where
+    B: Lens<Target = CurvePoints<T>>,
+    M: Lens<Target = f32>,
+    T: 'static + Clone,
+{
+    Box::new(move |cx, points| {
+        let amount = amount.view(cx.data()?, |amount| amount.copied())?;
+        target.view(cx.data()?, |target| {
+            target.map(|target| CurvePoints::morph(points, target, amount))
+        })
+    })
+}
+
+impl<P, R, T> MorphPreviewExt<T> for Handle<'_, MsegGraph<P, R, T>>
+where
+    P: Lens<Target = CurvePoints<T>>,
+    T: 'static + Clone,
+    R: Lens<Target = RangeInclusive<f32>>,
+{
+    fn morph_preview<B, M>(self, target: B, amount: M) -> Self
+    where
+        B: Lens<Target = CurvePoints<T>>,
+        M: Lens<Target = f32>,
+    {
+        let preview = morph_towards(target, amount.clone());
+        if let Some(view) = self.cx.views.get_mut(&self.entity) {
+            if let Some(graph) = view.downcast_mut::<MsegGraph<P, R, T>>() {
+                graph.morph_preview = Some(preview);
             }
+        }
+        amount.set_or_bind(self.cx, self.entity, |cx, _, _| {
+            cx.style.needs_redraw = true;
+        });
+        self
+    }
+}
+
+/// Zooms a graph vertically, such as to edit the values near `0` in detail
+pub trait ValueRangeExt {
+    /// Shows the values of the [`Lens`] `values` from the bottom to the top of
+    /// the graph rather than `0..=1`, following them as they change. Points
+    /// outside of them are cut off at the edges of the graph.
+    fn value_range<V>(self, values: V) -> Self
+    where
+        V: Lens<Target = RangeInclusive<f32>>;
+}
+
+/// Shows `values` on `entity`, if it is a graph
+pub(super) fn set_value_range<P, R, T>(
+    cx: &mut Context,
+    entity: Entity,
+    values: RangeInclusive<f32>,
+) where
+    P: Lens<Target = CurvePoints<T>>,
+    T: 'static + Clone,
+    R: Lens<Target = RangeInclusive<f32>>,
+{
+    if let Some(view) = cx.views.get_mut(&entity) {
+        if let Some(graph) = view.downcast_mut::<MsegGraph<P, R, T>>() {
+            graph.value_range = values;
+            cx.style.needs_redraw = true;
+        }
+    }
+}
+
+impl<P, R, T> ValueRangeExt for Handle<'_, MsegGraph<P, R, T>>
+where
+    P: Lens<Target = CurvePoints<T>>,
+    T: 'static + Clone,
+    R: Lens<Target = RangeInclusive<f32>>,
+{
+    fn value_range<V>(self, values: V) -> Self
+    where
+        V: Lens<Target = RangeInclusive<f32>>,
+    {
+        values.set_or_bind(self.cx, self.entity, |cx, entity, values| {
+            set_value_range::<P, R, T>(cx, entity, values);
+        });
+        self
+    }
+}
+
+/// Snaps the points of a graph to a grid while they are dragged or inserted
+pub trait SnapGridExt {
+    /// Snaps points to the [`SnapGrid`] of the [`Lens`] `grid`, following it
+    /// as it changes, and draws its lines behind the envelope
+    fn snap_grid<G>(self, grid: G) -> Self
+    where
+        G: Lens<Target = SnapGrid>;
+
+    /// Snaps points to each step of the [`BeatGrid`] of the [`Lens`] `grid`,
+    /// so a tempo-synced envelope shares its timebase with a
+    /// [`StepSequencer`](crate::widgets::StepSequencer) editing against the
+    /// same grid
+    fn beat_grid<G>(self, grid: G) -> Self
+    where
+        Self: Sized,
+        G: Lens<Target = BeatGrid>,
+    {
+        self.snap_grid(grid.map(|grid| SnapGrid::beats(*grid)))
+    }
+}
+
+/// Snaps the points of `entity` to `grid`, if it is a graph
+pub(super) fn set_snap_grid<P, R, T>(cx: &mut Context, entity: Entity, grid: SnapGrid)
+where
+    P: Lens<Target = CurvePoints<T>>,
+    T: 'static + Clone,
+    R: Lens<Target = RangeInclusive<f32>>,
+{
+    if let Some(view) = cx.views.get_mut(&entity) {
+        if let Some(graph) = view.downcast_mut::<MsegGraph<P, R, T>>() {
+            graph.snap_grid = grid;
+            cx.style.needs_redraw = true;
+        }
+    }
+}
+
+impl<P, R, T> SnapGridExt for Handle<'_, MsegGraph<P, R, T>>
+where
+    P: Lens<Target = CurvePoints<T>>,
+    T: 'static + Clone,
+    R: Lens<Target = RangeInclusive<f32>>,
+{
+    fn snap_grid<G>(self, grid: G) -> Self
+    where
+        G: Lens<Target = SnapGrid>,
+    {
+        grid.set_or_bind(self.cx, self.entity, |cx, entity, grid| {
+            set_snap_grid::<P, R, T>(cx, entity, grid);
+        });
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    #[cfg(feature = "golden")]
+    use crate::testing::assert_golden;
+    use crate::testing::{Recorder, TestContext};
+    use assert_approx_eq::assert_approx_eq;
+
+    #[derive(Lens)]
+    struct Envelope {
+        points: CurvePoints,
+        range: RangeInclusive<f32>,
+        snap: SnapGrid,
+    }
+
+    impl Model for Envelope {}
+
+    /// Builds a graph of `points` 4 seconds long across 400 by 100 pixels, so
+    /// a second is 100 pixels and values run from 0 at the bottom to 1 at the
+    /// top. Its snap grid, `Envelope::snap`, has lines every half second and
+    /// every quarter of the values.
+    fn mseg_graph<'a>(
+        cx: &'a mut Context,
+        points: &[(f32, f32)],
+    ) -> Handle<
+        'a,
+        MsegGraph<impl Lens<Target = CurvePoints>, impl Lens<Target = RangeInclusive<f32>>, ()>,
+    > {
+        Envelope {
+            points: CurvePoints::new(points.iter().map(|&p| p.into()).collect()),
+            range: 0f32..=1f32,
+            snap: SnapGrid::new(Some(0.5f32), Some(0.25f32)),
+        }
+        .build(cx);
+        MsegGraph::new(
+            cx,
+            Envelope::points,
+            Envelope::range,
+            4f32,
+            Rc::new(0f32..=1f32),
+        )
+        .width(Pixels(400f32))
+        .height(Pixels(100f32))
+    }
+
+    #[test]
+    fn clamps_dragged_mseg_points_between_neighbours() {
+        let mut test = TestContext::new(400f32, 100f32);
+        let changes = Recorder::new();
+        let record = changes.clone();
+        test.build(|cx| {
+            mseg_graph(
+                cx,
+                &[(0f32, 0f32), (1f32, 1f32), (2f32, 0.5f32), (4f32, 0f32)],
+            )
+            .on_changing_point(move |_, index, point| record.push((index, point)));
+        });
+
+        // The second point is dragged past the third, and stops short of it
+        test.drag((100f32, 1f32), (300f32, 50f32));
+        let (index, point) = changes.last().unwrap();
+        assert_eq!(index, 1);
+        assert_approx_eq!(point.x, 1.99f32, 1e-3f32);
+        assert_approx_eq!(point.y, 0.5f32, 1e-3f32);
+
+        // The first point stays at the origin
+        test.drag((1f32, 99f32), (50f32, 20f32));
+        assert_eq!(changes.last(), Some((0, Vec2::ZERO)));
+    }
+
+    #[test]
+    fn cancels_mseg_drags_with_escape() {
+        let mut test = TestContext::new(400f32, 100f32);
+        let changes = Recorder::new();
+        let record = changes.clone();
+        test.build(|cx| {
+            mseg_graph(cx, &[(0f32, 0f32), (1f32, 1f32), (4f32, 0f32)])
+                .on_changing_point(move |_, index, point| record.push((index, point)));
+        });
+
+        test.move_mouse(100f32, 1f32);
+        test.press(MouseButton::Left);
+        test.move_mouse(200f32, 50f32);
+        assert_ne!(changes.last(), Some((1, Vec2::ONE)));
+        test.press_key(Code::Escape);
+        // The point goes back to where it started, and stays there
+        assert_eq!(changes.last(), Some((1, Vec2::ONE)));
+        test.move_mouse(250f32, 50f32);
+        test.release(MouseButton::Left);
+        assert_eq!(changes.last(), Some((1, Vec2::ONE)));
+    }
+
+    #[test]
+    fn stretches_mseg_regions() {
+        let mut test = TestContext::new(400f32, 100f32);
+        let changes = Recorder::new();
+        let record = changes.clone();
+        test.build(|cx| {
+            mseg_graph(
+                cx,
+                &[(0f32, 0f32), (1f32, 1f32), (2f32, 0.5f32), (4f32, 0f32)],
+            )
+            .on_changing_point(move |_, index, point| record.push((index, point)));
+        });
+
+        // Alt-dragging selects the region from 0.5 to 2.5 seconds
+        test.set_modifiers(Modifiers::ALT);
+        test.drag((50f32, 50f32), (250f32, 50f32));
+        test.set_modifiers(Modifiers::empty());
+        assert!(changes.is_empty());
+
+        // Dragging its end to 3 seconds stretches the points within it, the
+        // later one first
+        test.drag((250f32, 50f32), (300f32, 50f32));
+        let moved = changes.values();
+        assert!(moved.iter().all(|(index, _)| *index == 1 || *index == 2));
+        let (index, point) = moved[moved.len() - 2];
+        assert_eq!(index, 2);
+        assert_approx_eq!(point.x, 2.375f32, 1e-3f32);
+        let (index, point) = moved[moved.len() - 1];
+        assert_eq!(index, 1);
+        assert_approx_eq!(point.x, 1.125f32, 1e-3f32);
+        assert_eq!(point.y, 1f32);
+    }
+
+    #[test]
+    fn drags_mseg_points_selected_with_a_marquee() {
+        let mut test = TestContext::new(400f32, 100f32);
+        let changes = Recorder::new();
+        let record = changes.clone();
+        test.build(|cx| {
+            mseg_graph(
+                cx,
+                &[(0f32, 0f32), (1f32, 1f32), (2f32, 0.5f32), (4f32, 0f32)],
+            )
+            .on_changing_points(move |_, points| record.push(points));
+        });
+
+        // The marquee from 0.6 to 2.5 seconds selects the middle points
+        test.drag((60f32, 98f32), (250f32, -5f32));
+        assert!(changes.is_empty());
+
+        // which move together when either is dragged, the later one first
+        test.drag((200f32, 50f32), (220f32, 60f32));
+        let moved = changes.last().unwrap();
+        assert_eq!(moved.len(), 2);
+        let (index, point) = moved[0];
+        assert_eq!(index, 2);
+        assert_approx_eq!(point.x, 2.2f32, 1e-3f32);
+        assert_approx_eq!(point.y, 0.4f32, 1e-3f32);
+        let (index, point) = moved[1];
+        assert_eq!(index, 1);
+        assert_approx_eq!(point.x, 1.2f32, 1e-3f32);
+        assert_approx_eq!(point.y, 0.9f32, 1e-3f32);
+    }
+
+    #[test]
+    fn deletes_mseg_points_selected_with_shift() {
+        let mut test = TestContext::new(400f32, 100f32);
+        let removed = Recorder::new();
+        let record = removed.clone();
+        let graph = test.build(|cx| {
+            mseg_graph(
+                cx,
+                &[(0f32, 0f32), (1f32, 1f32), (2f32, 0.5f32), (4f32, 0f32)],
+            )
+            .on_remove_point(move |_, index| record.push(index))
+            .entity
+        });
+
+        test.click(100f32, 1f32);
+        test.set_modifiers(Modifiers::SHIFT);
+        test.click(200f32, 50f32);
+        test.set_modifiers(Modifiers::empty());
+        test.focus(graph);
+        // Removed from the last, so the first index stays the same
+        test.press_key(Code::Delete);
+        assert_eq!(removed.values(), vec![2, 1]);
+
+        // Shift-clicking a selected point takes it out of the selection
+        test.click(100f32, 1f32);
+        test.set_modifiers(Modifiers::SHIFT);
+        test.click(200f32, 50f32);
+        test.click(100f32, 1f32);
+        test.set_modifiers(Modifiers::empty());
+        test.press_key(Code::Delete);
+        assert_eq!(removed.values(), vec![2, 1, 2]);
+    }
+
+    #[test]
+    fn bends_mseg_segments_with_their_handles() {
+        let mut test = TestContext::new(400f32, 100f32);
+        let changes = Recorder::new();
+        let record = changes.clone();
+        test.build(|cx| {
+            mseg_graph(cx, &[(0f32, 0f32), (1f32, 1f32), (4f32, 0f32)])
+                .on_changing_tension(move |_, index, curve| record.push((index, curve)));
+        });
+
+        // Dragging the handle halfway along the falling segment down to 0.2
+        // bends the segment so it falls quickly and then slowly
+        test.drag((250f32, 50f32), (250f32, 80f32));
+        let (index, curve) = changes.last().unwrap();
+        assert_eq!(index, 2);
+        assert_approx_eq!(curve, 2f32 * 0.25f32.ln(), 1e-3f32);
+
+        // Double clicking the handle straightens the segment
+        test.double_click(250f32, 50f32);
+        assert_eq!(changes.last(), Some((2, 0f32)));
+    }
+
+    #[test]
+    fn inserts_mseg_points_between_neighbours() {
+        let mut test = TestContext::new(400f32, 100f32);
+        let inserted = Recorder::new();
+        let record = inserted.clone();
+        test.build(|cx| {
+            mseg_graph(cx, &[(0f32, 0f32), (1f32, 1f32), (4f32, 0f32)])
+                .on_insert_point(move |_, index, point| record.push((index, point)));
+        });
+
+        test.double_click(200f32, 30f32);
+        let (index, point) = inserted.last().unwrap();
+        assert_eq!(index, 2);
+        assert_approx_eq!(point.x, 2f32, 1e-3f32);
+        assert_approx_eq!(point.y, 0.7f32, 1e-3f32);
+
+        // Right next to a point in time, the new point keeps its distance
+        test.double_click(100.5f32, 60f32);
+        let (index, point) = inserted.last().unwrap();
+        assert_eq!(index, 2);
+        assert_approx_eq!(point.x, 1.01f32, 1e-3f32);
+    }
+
+    #[test]
+    fn zooms_mseg_times_around_the_cursor() {
+        let mut test = TestContext::new(400f32, 100f32);
+        let ranges = Recorder::new();
+        let record = ranges.clone();
+        test.build(|cx| {
+            mseg_graph(cx, &[(0f32, 0f32), (1f32, 1f32), (4f32, 0f32)])
+                .on_changing_range(move |_, range| record.push(range));
+        });
+
+        // The time under the cursor, a quarter of the way in, stays put
+        test.move_mouse(100f32, 50f32);
+        test.dispatch(WindowEvent::MouseScroll(0f32, 1f32));
+        let range = ranges.last().unwrap();
+        assert_approx_eq!(*range.start(), 0.05f32, 1e-3f32);
+        assert_approx_eq!(*range.end(), 0.85f32, 1e-3f32);
+        // Zooming out stops at the whole envelope
+        test.dispatch(WindowEvent::MouseScroll(0f32, -10f32));
+        assert_eq!(ranges.last(), Some(0f32..=1f32));
+    }
+
+    #[test]
+    fn snaps_mseg_points_to_the_grid() {
+        let mut test = TestContext::new(400f32, 100f32);
+        let (moves, inserts) = (Recorder::new(), Recorder::new());
+        let (record_move, record_insert) = (moves.clone(), inserts.clone());
+        test.build(|cx| {
+            mseg_graph(cx, &[(0f32, 0f32), (1f32, 0.5f32), (4f32, 0f32)])
+                .snap_grid(Envelope::snap)
+                .on_changing_point(move |_, _, point| record_move.push(point))
+                .on_insert_point(move |_, index, point| record_insert.push((index, point)));
+        });
+
+        // Dragged to 1.37 seconds and 0.8, the point snaps to 1.5 and 0.75
+        test.drag((100f32, 50f32), (137f32, 20f32));
+        assert_eq!(moves.last(), Some(Vec2::new(1.5f32, 0.75f32)));
+        // but follows the cursor while Ctrl is held
+        test.set_modifiers(Modifiers::CTRL);
+        test.drag((100f32, 50f32), (137f32, 20f32));
+        let point = moves.last().unwrap();
+        assert_approx_eq!(point.x, 1.37f32, 1e-3f32);
+        assert_approx_eq!(point.y, 0.8f32, 1e-3f32);
+        test.set_modifiers(Modifiers::empty());
+
+        // Inserted points snap too
+        test.double_click(263f32, 40f32);
+        assert_eq!(inserts.last(), Some((2, Vec2::new(2.5f32, 0.5f32))));
+    }
+
+    #[test]
+    fn double_clicks_mseg_points() {
+        let mut test = TestContext::new(400f32, 100f32);
+        let clicks = Recorder::new();
+        let record = clicks.clone();
+        test.build(|cx| {
+            mseg_graph(cx, &[(0f32, 0f32), (1f32, 1f32), (4f32, 0f32)])
+                .on_double_click(move |_, index, _| record.push(index));
+        });
+
+        test.double_click(100f32, 1f32);
+        assert_eq!(clicks.values(), vec![Some(1)]);
+        // Clicks too far apart aren't a double click
+        test.click(200f32, 50f32);
+        test.click(250f32, 50f32);
+        assert_eq!(clicks.len(), 1);
+        test.double_click(300f32, 50f32);
+        assert_eq!(clicks.values(), vec![Some(1), None]);
+    }
+
+    #[test]
+    fn ignores_invalid_mseg_points() {
+        let mut test = TestContext::new(400f32, 100f32);
+        let errors = Recorder::new();
+        let changes = Recorder::new();
+        let (record_error, record_change) = (errors.clone(), changes.clone());
+        test.build(|cx| {
+            mseg_graph(
+                cx,
+                &[(0f32, 0f32), (2f32, 1f32), (1f32, 0.5f32), (4f32, 0f32)],
+            )
+            .on_data_error(move |_, error| record_error.push(error))
+            .on_changing_point(move |_, index, point| record_change.push((index, point)));
+        });
+        assert_eq!(errors.values(), vec![CurveError::Unsorted { index: 2 }]);
+
+        // Dragging the point at 2 seconds leaves it alone
+        test.drag((200f32, 1f32), (250f32, 50f32));
+        assert!(changes.is_empty());
+    }
+
+    #[cfg(feature = "golden")]
+    #[test]
+    #[ignore = "reference images not recorded yet, record them with LILY_BLESS=1"]
+    fn renders_mseg_graphs() {
+        let mut test = TestContext::new(400f32, 100f32);
+        test.build(|cx| {
+            cx.add_theme(crate::DEFAULT_STYLE);
+            mseg_graph(
+                cx,
+                &[(0f32, 0f32), (0.5f32, 1f32), (1.5f32, 0.6f32), (4f32, 0f32)],
+            );
         });
+        assert_golden("mseg_graph", &test.render());
+        // The hovered point is drawn in its own color, ringed once the ring
+        // has finished growing in
+        test.move_mouse(50f32, 1f32);
+        test.wait(std::time::Duration::from_millis(250));
+        assert_golden("mseg_graph_hovered", &test.render());
     }
 }
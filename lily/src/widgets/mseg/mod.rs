@@ -1,96 +1,179 @@
 //! Multi-stage envelope generator widget
 
-pub(crate) mod graph;
+mod graph;
 pub(crate) mod util;
 
-use self::graph::{MsegGraph, MsegGraphHandle};
-use std::{marker::PhantomData, ops::RangeInclusive};
+pub use self::graph::{MorphPreviewExt, MsegGraph, MsegGraphHandle, SnapGridExt, ValueRangeExt};
+use self::util::SnapGrid;
+use std::{marker::PhantomData, ops::RangeInclusive, rc::Rc};
 
 use super::zoomer::{Zoomer, ZoomerHandle};
-use crate::util::CurvePoints;
+use crate::context_menu::{ContextMenuAction, ContextMenuExt, ContextMenuItem};
+use crate::util::{
+    CurveClipboard, CurveClipboardExt, CurveError, CurveKind, CurvePoints, PseudoClassExt,
+    ValueMapping,
+};
 use glam::Vec2;
 use lily_derive::Handle;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
 use vizia::prelude::*;
 
-#[allow(clippy::enum_variant_names)]
-enum MsegInternalEvent {
-    OnChangingRangeStart(f32),
-    OnChangingRangeEnd(f32),
-    OnChangingRangeBoth { start: f32, end: f32 },
-    OnChangingPoint { index: usize, point: Vec2 },
-    OnRemovePoint { index: usize },
-    OnInsertPoint { index: usize, point: Vec2 },
+/// The events of the graph that take more than calling the matching callback,
+/// which the rest go through with `MsegInternalEvent`
+enum MsegEvent {
+    ChangingPoints(Vec<(usize, Vec2)>),
+    RemovePoint(usize),
+    OpenContextMenu,
+    DataError(CurveError),
 }
 
 #[allow(clippy::type_complexity)]
 #[derive(Handle)]
-pub struct Mseg<P, R>
+pub struct Mseg<P, R, T>
 where
-    P: Lens<Target = CurvePoints>,
+    P: Lens<Target = CurvePoints<T>>,
+    T: 'static + Clone,
     R: Lens<Target = RangeInclusive<f32>>,
 {
     points: P,
     range: PhantomData<R>,
+    data: PhantomData<T>,
+    /// Copies and pastes the points, once enabled with
+    /// [`CurveClipboardExt::on_paste`]
+    clipboard: Option<CurveClipboard<T>>,
 
     #[callback(usize)]
     on_remove_point: Option<Box<dyn Fn(&mut EventContext, usize)>>,
 
+    /// Asked before a point is removed. Returning `false` keeps it. Without
+    /// it, the first and last points can't be removed.
+    #[callback(usize -> bool)]
+    can_remove_point: Option<Box<dyn Fn(&mut EventContext, usize) -> bool>>,
+
+    #[event]
     #[callback(usize, Vec2)]
     on_insert_point: Option<Box<dyn Fn(&mut EventContext, usize, Vec2)>>,
 
+    #[event]
     #[callback(usize, Vec2)]
     on_changing_point: Option<Box<dyn Fn(&mut EventContext, usize, Vec2)>>,
 
+    /// Called with the index and the new position of each point moved when
+    /// several move at once, such as while the selected points are dragged.
+    /// Without it, they are reported to `on_changing_point` one at a time.
+    #[callback(Vec<(usize, Vec2)>)]
+    on_changing_points: Option<Box<dyn Fn(&mut EventContext, Vec<(usize, Vec2)>)>>,
+
+    /// Called with the index of the point ending a segment, which holds the
+    /// curve of the segment, and the new curve while the segment is bent
+    /// with the handle halfway along it
+    #[event]
+    #[callback(usize, f32)]
+    on_changing_tension: Option<Box<dyn Fn(&mut EventContext, usize, f32)>>,
+
+    #[event]
     #[callback(f32)]
     on_changing_range_start: Option<Box<dyn Fn(&mut EventContext, f32)>>,
 
+    #[event]
     #[callback(f32)]
     on_changing_range_end: Option<Box<dyn Fn(&mut EventContext, f32)>>,
 
+    #[event]
     #[callback(RangeInclusive<f32>)]
     on_changing_range_both: Option<Box<dyn Fn(&mut EventContext, RangeInclusive<f32>)>>,
+
+    /// Called with the values to show when the graph is zoomed vertically
+    /// with Ctrl+scroll, once they are set with [`ValueRangeExt::value_range`]
+    #[event]
+    #[callback(RangeInclusive<f32>)]
+    on_changing_value_range: Option<Box<dyn Fn(&mut EventContext, RangeInclusive<f32>)>>,
+
+    #[callback(ContextMenuAction)]
+    on_context_menu: Option<Box<dyn Fn(&mut EventContext, ContextMenuAction)>>,
+
+    /// Called with the problem found when the points become invalid, such as
+    /// points that aren't sorted by time
+    #[callback(CurveError)]
+    on_data_error: Option<Box<dyn Fn(&mut EventContext, CurveError)>>,
 }
 
-impl<P, R> Mseg<P, R>
+impl<P, R, T> Mseg<P, R, T>
 where
-    P: Lens<Target = CurvePoints>,
+    P: Lens<Target = CurvePoints<T>>,
+    T: 'static + Clone,
     R: Lens<Target = RangeInclusive<f32>>,
 {
-    pub fn new(cx: &mut Context, points: P, range: R, max: f32) -> Handle<Mseg<P, R>> {
+    /// Create a new `Mseg` whose points are `max` seconds long at most.
+    /// `max` can also be a [`Lens`], resizing the graph when it changes.
+    pub fn new(cx: &mut Context, points: P, range: R, max: impl Res<f32>) -> Handle<Mseg<P, R, T>> {
+        Self::with_value_mapping(cx, points, range, max, 0f32..=1f32)
+    }
+
+    /// Create a new `Mseg` whose readouts show point values through a
+    /// [`ValueMapping`], such as a cutoff frequency. Points are still stored
+    /// with a normalized `y`.
+    pub fn with_value_mapping(
+        cx: &mut Context,
+        points: P,
+        range: R,
+        max: impl Res<f32>,
+        value_mapping: impl ValueMapping + 'static,
+    ) -> Handle<Mseg<P, R, T>> {
+        let value_mapping: Rc<dyn ValueMapping> = Rc::new(value_mapping);
         Self {
             points: points.clone(),
             range: Default::default(),
+            data: Default::default(),
+            clipboard: None,
             on_changing_point: None,
+            on_changing_points: None,
+            on_changing_tension: None,
             on_changing_range_start: None,
             on_changing_range_end: None,
             on_changing_range_both: None,
+            on_changing_value_range: None,
             on_remove_point: None,
+            can_remove_point: None,
             on_insert_point: None,
+            on_context_menu: None,
+            on_data_error: None,
         }
         .build(cx, |cx| {
-            MsegGraph::new(cx, points, range.clone(), max)
+            MsegGraph::new(cx, points, range.clone(), max, value_mapping)
                 .on_changing_point(|cx, index, point| {
-                    cx.emit(MsegInternalEvent::OnChangingPoint { index, point })
+                    cx.emit(MsegInternalEvent::ChangingPoint(index, point))
                 })
-                .on_remove_point(|cx, index| cx.emit(MsegInternalEvent::OnRemovePoint { index }))
+                .on_changing_points(|cx, points| cx.emit(MsegEvent::ChangingPoints(points)))
+                .on_remove_point(|cx, index| cx.emit(MsegEvent::RemovePoint(index)))
                 .on_insert_point(|cx, index, point| {
-                    cx.emit(MsegInternalEvent::OnInsertPoint { index, point })
+                    cx.emit(MsegInternalEvent::InsertPoint(index, point))
+                })
+                .on_changing_tension(|cx, index, curve| {
+                    cx.emit(MsegInternalEvent::ChangingTension(index, curve))
                 })
+                .on_changing_range(|cx, range| cx.emit(MsegInternalEvent::ChangingRangeBoth(range)))
+                .on_changing_value_range(|cx, values| {
+                    cx.emit(MsegInternalEvent::ChangingValueRange(values))
+                })
+                .on_data_error(|cx, error| cx.emit(MsegEvent::DataError(error)))
                 .class("graph");
 
             Zoomer::new(cx, range.clone())
-                .on_changing_start(|cx, x| cx.emit(MsegInternalEvent::OnChangingRangeStart(x)))
-                .on_changing_end(|cx, x| cx.emit(MsegInternalEvent::OnChangingRangeEnd(x)))
+                .on_changing_start(|cx, x| cx.emit(MsegInternalEvent::ChangingRangeStart(x)))
+                .on_changing_end(|cx, x| cx.emit(MsegInternalEvent::ChangingRangeEnd(x)))
                 .on_changing_both(|cx, start, end| {
-                    cx.emit(MsegInternalEvent::OnChangingRangeBoth { start, end })
+                    cx.emit(MsegInternalEvent::ChangingRangeBoth(start..=end))
                 });
         })
     }
 }
 
-impl<P, R> View for Mseg<P, R>
+impl<P, R, T> View for Mseg<P, R, T>
 where
-    P: Lens<Target = CurvePoints>,
+    P: Lens<Target = CurvePoints<T>>,
+    T: 'static + Clone,
     R: Lens<Target = RangeInclusive<f32>>,
 {
     fn element(&self) -> Option<&'static str> {
@@ -98,40 +181,163 @@ where
     }
 
     fn event(&mut self, cx: &mut EventContext, event: &mut Event) {
-        event.map(|ev: &MsegInternalEvent, _| match *ev {
-            MsegInternalEvent::OnChangingRangeStart(x) => {
-                if let Some(callback) = &self.on_changing_range_start {
-                    (callback)(cx, x);
-                }
+        // Invalid points are reported even while disabled
+        event.map(|ev: &MsegEvent, _| {
+            if let (MsegEvent::DataError(error), Some(callback)) = (ev, &self.on_data_error) {
+                (callback)(cx, *error);
             }
-            MsegInternalEvent::OnChangingRangeEnd(x) => {
-                if let Some(callback) = &self.on_changing_range_end {
-                    (callback)(cx, x);
+        });
+        if cx.is_disabled() {
+            return;
+        }
+        self.handle_internal_event(cx, event);
+        event.map(|ev: &MsegEvent, _| match *ev {
+            MsegEvent::ChangingPoints(ref points) => {
+                match (&self.on_changing_points, &self.on_changing_point) {
+                    (Some(callback), _) => (callback)(cx, points.clone()),
+                    (None, Some(callback)) => {
+                        for &(index, point) in points {
+                            (callback)(cx, index, point);
+                        }
+                    }
+                    (None, None) => (),
                 }
             }
-            MsegInternalEvent::OnChangingRangeBoth { start, end } => {
-                if let Some(callback) = &self.on_changing_range_both {
-                    (callback)(cx, start..=end);
+            MsegEvent::RemovePoint(index) => {
+                // Unless the host decides, delete the point if not the first
+                // or last in the vector
+                let allowed = match &self.can_remove_point {
+                    Some(callback) => (callback)(cx, index),
+                    None => index != 0 && index != self.points.get(cx).len() - 1,
+                };
+                if allowed {
+                    if let Some(callback) = &self.on_remove_point {
+                        (callback)(cx, index);
+                    }
                 }
             }
-            MsegInternalEvent::OnChangingPoint { index, point } => {
-                if let Some(callback) = &self.on_changing_point {
-                    (callback)(cx, index, point);
+            MsegEvent::OpenContextMenu => {
+                let mut items = Vec::new();
+                if self.on_context_menu.is_some() {
+                    items.extend([ContextMenuItem::reset(), ContextMenuItem::lock()]);
+                }
+                if self.clipboard.is_some() {
+                    items.extend([ContextMenuItem::copy(), ContextMenuItem::paste()]);
+                }
+                if !items.is_empty() {
+                    cx.open_context_menu(items);
                 }
             }
-            MsegInternalEvent::OnRemovePoint { index } => {
-                // Delete the point if not the first or last in the vector
-                if index != 0 && index != self.points.get(cx).len() - 1 {
-                    if let Some(callback) = &self.on_remove_point {
-                        (callback)(cx, index);
-                    }
+            MsegEvent::DataError(_) => (),
+        });
+
+        event.map(|ev: &WindowEvent, _| {
+            if let (WindowEvent::KeyDown(code, _), Some(clipboard)) = (ev, &self.clipboard) {
+                let points = self.points.get(cx);
+                clipboard.handle_key(cx, *code, &points);
+            }
+        });
+
+        event.map(|action: &ContextMenuAction, _| match (action, &self.clipboard) {
+            (ContextMenuAction::Copy, Some(clipboard)) => {
+                let points = self.points.get(cx);
+                clipboard.copy(cx, &points);
+            }
+            (ContextMenuAction::Paste, Some(clipboard)) => clipboard.paste(cx),
+            _ => {
+                if let Some(callback) = &self.on_context_menu {
+                    (callback)(cx, action.clone());
                 }
             }
-            MsegInternalEvent::OnInsertPoint { index, point } => {
-                if let Some(callback) = &self.on_insert_point {
-                    (callback)(cx, index, point);
+        });
+    }
+}
+
+impl<P, R, T> CurveClipboardExt<T> for Handle<'_, Mseg<P, R, T>>
+where
+    P: Lens<Target = CurvePoints<T>>,
+    T: 'static + Clone + Serialize + DeserializeOwned + Default,
+    R: Lens<Target = RangeInclusive<f32>>,
+{
+    fn on_paste<F>(self, callback: F) -> Self
+    where
+        F: 'static + Fn(&mut EventContext, CurvePoints<T>),
+    {
+        if let Some(view) = self.cx.views.get_mut(&self.entity) {
+            if let Some(mseg) = view.downcast_mut::<Mseg<P, R, T>>() {
+                mseg.clipboard = Some(CurveClipboard::new(CurveKind::Envelope, callback));
+            }
+        }
+        self
+    }
+}
+
+impl<P, R, T> MorphPreviewExt<T> for Handle<'_, Mseg<P, R, T>>
+where
+    P: Lens<Target = CurvePoints<T>>,
+    T: 'static + Clone,
+    R: Lens<Target = RangeInclusive<f32>>,
+{
+    fn morph_preview<B, M>(self, target: B, amount: M) -> Self
+    where
+        B: Lens<Target = CurvePoints<T>>,
+        M: Lens<Target = f32>,
+    {
+        // The graph draws the preview, so it is handed down to it
+        let mut preview = Some(graph::morph_towards(target, amount.clone()));
+        let children: Vec<Entity> = self.entity.child_iter(&self.cx.tree).collect();
+        for child in children {
+            if let Some(view) = self.cx.views.get_mut(&child) {
+                if let Some(graph) = view.downcast_mut::<MsegGraph<P, R, T>>() {
+                    graph.morph_preview = preview.take();
                 }
             }
+        }
+        amount.set_or_bind(self.cx, self.entity, |cx, _, _| {
+            cx.style.needs_redraw = true;
+        });
+        self
+    }
+}
+
+impl<P, R, T> ValueRangeExt for Handle<'_, Mseg<P, R, T>>
+where
+    P: Lens<Target = CurvePoints<T>>,
+    T: 'static + Clone,
+    R: Lens<Target = RangeInclusive<f32>>,
+{
+    fn value_range<V>(self, values: V) -> Self
+    where
+        V: Lens<Target = RangeInclusive<f32>>,
+    {
+        // The graph draws the values, so they are handed down to it
+        values.set_or_bind(self.cx, self.entity, |cx, entity, values| {
+            let children: Vec<Entity> = entity.child_iter(&cx.tree).collect();
+            for child in children {
+                graph::set_value_range::<P, R, T>(cx, child, values.clone());
+            }
+        });
+        self
+    }
+}
+
+impl<P, R, T> SnapGridExt for Handle<'_, Mseg<P, R, T>>
+where
+    P: Lens<Target = CurvePoints<T>>,
+    T: 'static + Clone,
+    R: Lens<Target = RangeInclusive<f32>>,
+{
+    fn snap_grid<G>(self, grid: G) -> Self
+    where
+        G: Lens<Target = SnapGrid>,
+    {
+        // The graph snaps and draws the grid, so it is handed down to it
+        grid.set_or_bind(self.cx, self.entity, |cx, entity, grid| {
+            let children: Vec<Entity> = entity.child_iter(&cx.tree).collect();
+            for child in children {
+                graph::set_snap_grid::<P, R, T>(cx, child, grid);
+            }
         });
+        self
     }
 }
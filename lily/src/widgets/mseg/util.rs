@@ -1,83 +1,427 @@
-use crate::util::RangeExt;
+use crate::util::{curve_shape, BeatGrid, BoundingBoxExt, CurvePoints, RangeExt};
 use glam::Vec2;
-use std::ops::RangeInclusive;
+use std::{
+    cmp::Ordering,
+    ops::{Range, RangeInclusive},
+};
 use vizia::cache::BoundingBox;
 // use vizia::context::Context;
 use vizia::prelude::*;
 
-/// Convert a screen value to its data position
-pub fn ui_to_data_pos_range(
-    cx: &EventContext,
-    ui_point: &Vec2,
-    range_data: impl Lens<Target = RangeInclusive<f32>>,
-    max_data: f32,
-) -> Vec2 {
-    _ui_to_data_pos_range(
-        cx.cache.get_bounds(cx.current()),
-        *ui_point,
-        range_data.get(cx),
-        max_data,
-    )
+/// Gets the range of times in seconds shown by a zoomed view
+pub fn time_range(range: RangeInclusive<f32>, max: f32) -> RangeInclusive<f32> {
+    range.start() * max..=range.end() * max
 }
-pub fn data_to_ui_pos_range(
-    cx: &EventContext,
-    point: Vec2,
-    range_data: impl Lens<Target = RangeInclusive<f32>>,
-    max: f32,
-) -> Vec2 {
-    data_to_bounds_pos_range(
-        cx.cache.get_bounds(cx.current()),
-        point,
-        range_data.get(cx),
-        max,
-    )
+
+/// Zooms the window a view shows of one axis by `factor` around `anchor`,
+/// which stays where it is in the view, so a factor below one zooms in. The
+/// window stays at least `min_span` wide and within `limits`.
+pub fn zoom_window(
+    window: RangeInclusive<f32>,
+    anchor: f32,
+    factor: f32,
+    min_span: f32,
+    limits: RangeInclusive<f32>,
+) -> RangeInclusive<f32> {
+    let span = window.width();
+    let zoomed = (span * factor).clamp(min_span, limits.width());
+    let ratio = if span > 0f32 { zoomed / span } else { 1f32 };
+    let start =
+        (anchor + (window.start() - anchor) * ratio).clamp(*limits.start(), limits.end() - zoomed);
+    start..=start + zoomed
 }
 
-fn _ui_to_data_pos_range(
-    bounds: BoundingBox,
-    ui_point: Vec2,
-    range: RangeInclusive<f32>,
-    max: f32,
-) -> Vec2 {
-    let (width, height) = (bounds.w, bounds.h);
-    // Assume `ui_point` is an absolute coordinate. We must convert it to
-    // relative coordinates
-    let mut ui_point = ui_point;
-    let offset = { Vec2::new(bounds.x, bounds.y) };
-    // Convert to relative point
-    ui_point -= offset;
-    // Scale points to fit within `(x,y) = ([0..=max], [0..=1])`
-    let y = (height - ui_point.y) / height;
-    let offset_data = range.start() * max;
-    let scale = range.width() * max;
-    let x = ((ui_point.x / width) * scale) + offset_data;
-    Vec2::new(x, y)
-}
-
-pub fn data_to_bounds_pos_range(
+/// The grid the points of a graph snap to while they are dragged or inserted,
+/// such as sixteenth notes in time and quarters of the range in value. Either
+/// axis can be left free, as it is by default.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Data)]
+pub struct SnapGrid {
+    /// The seconds between the lines of the grid across time, or `None` to
+    /// leave times free
+    pub time: Option<f32>,
+    /// The normalized values between the lines of the grid across values, or
+    /// `None` to leave values free
+    pub value: Option<f32>,
+}
+
+impl SnapGrid {
+    /// Creates a grid with lines every `time` seconds and every `value` of
+    /// the normalized values
+    pub fn new(time: Option<f32>, value: Option<f32>) -> Self {
+        Self { time, value }
+    }
+
+    /// Creates a grid with lines on each step of `beats`, leaving values free
+    pub fn beats(beats: BeatGrid) -> Self {
+        Self::new(Some(beats.step_length()), None)
+    }
+
+    /// Snaps values to every `step` of the normalized values, such as `0.25`
+    /// for `0`, `0.25`, `0.5`, `0.75` and `1`
+    pub fn with_value_step(self, step: f32) -> Self {
+        Self {
+            value: Some(step),
+            ..self
+        }
+    }
+
+    /// Moves `point` to the closest line of the grid on each axis that isn't
+    /// free. Steps that aren't positive leave their axis free.
+    pub fn snap(&self, point: Vec2) -> Vec2 {
+        Vec2::new(snap_to(point.x, self.time), snap_to(point.y, self.value))
+    }
+
+    /// The times in seconds of the lines of the grid within `times`, none
+    /// when the lines are less than `min_step` seconds apart
+    pub fn time_lines(&self, times: RangeInclusive<f32>, min_step: f32) -> Vec<f32> {
+        grid_lines(self.time, times, min_step)
+    }
+
+    /// The normalized values of the lines of the grid within `values`, none
+    /// when the lines are less than `min_step` apart
+    pub fn value_lines(&self, values: RangeInclusive<f32>, min_step: f32) -> Vec<f32> {
+        grid_lines(self.value, values, min_step)
+    }
+}
+
+/// Rounds `value` to the closest multiple of `step`, if there's a step
+fn snap_to(value: f32, step: Option<f32>) -> f32 {
+    match step {
+        Some(step) if step > 0f32 => (value / step).round() * step,
+        _ => value,
+    }
+}
+
+/// The multiples of `step` within `range`, none when the step is less than
+/// `min_step`
+fn grid_lines(step: Option<f32>, range: RangeInclusive<f32>, min_step: f32) -> Vec<f32> {
+    match step {
+        Some(step) if step > 0f32 && step >= min_step => {
+            let first = (range.start() / step).ceil() as i64;
+            let last = (range.end() / step).floor() as i64;
+            (first..=last).map(|i| i as f32 * step).collect()
+        }
+        _ => Vec::new(),
+    }
+}
+
+/// How an axis spreads its values across the view
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+pub enum AxisScale {
+    #[default]
+    Linear,
+    /// Logarithmic, for axes that only hold positive values
+    Log,
+}
+
+impl AxisScale {
+    /// The smallest value a logarithmic axis shows, since it can't reach zero
+    const LOG_FLOOR: f32 = 1e-3f32;
+
+    fn apply(&self, value: f32) -> f32 {
+        match self {
+            AxisScale::Linear => value,
+            AxisScale::Log => value.max(Self::LOG_FLOOR).ln(),
+        }
+    }
+
+    fn invert(&self, value: f32) -> f32 {
+        match self {
+            AxisScale::Linear => value,
+            AxisScale::Log => value.exp(),
+        }
+    }
+
+    fn apply_range(&self, range: &RangeInclusive<f32>) -> RangeInclusive<f32> {
+        self.apply(*range.start())..=self.apply(*range.end())
+    }
+}
+
+/// Maps points between data space, in seconds and values, and the pixels of
+/// a zoomed view. Build one per event or draw and reuse it for every point.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ViewTransform {
     bounds: BoundingBox,
-    point: Vec2,
-    range: RangeInclusive<f32>,
-    max: f32,
-) -> Vec2 {
-    let (width, height) = (bounds.w, bounds.h);
-    // y value is a simple scale
-    let y = height - (point.y * height);
-    // x value requires us to calculate our zoomed position TODO: Zoom too
-
-    // Calculate the x-offset determined by the current view zoom window This
-    // value shifts points to the left and right We calculate the offset by
-    // getting the view's starting x value, which is normalized. We then see how
-    // much time that offsets by multiplying that normalized value times the
-    // maximum X of our MSEG.
-    let offset = range.start() * max;
-    // Calculate the x-zoom scale to apply to points
-    let scale = 1f32 / (range.width() * max);
-    let x = ((point.x - offset) * scale) * width;
-    let relative = Vec2::new(x, y);
-    // adjust to be absolute by adding the container coords
-    let offset = { Vec2::new(bounds.x, bounds.y) };
-    relative + offset
+    /// The times in seconds shown across the view
+    times: RangeInclusive<f32>,
+    /// The values shown from the bottom to the top of the view
+    values: RangeInclusive<f32>,
+    time_scale: AxisScale,
+}
+
+impl ViewTransform {
+    /// Creates a transform for a view showing the section `range` of `0..=1`
+    /// of an envelope `max` seconds long, with values from `0` at the bottom
+    /// to `1` at the top
+    pub fn new(bounds: BoundingBox, range: RangeInclusive<f32>, max: f32) -> Self {
+        Self {
+            bounds,
+            times: time_range(range, max),
+            values: 0f32..=1f32,
+            time_scale: AxisScale::Linear,
+        }
+    }
+
+    /// Creates a transform for a view showing the window `times` by `values`
+    /// of data space, with times in seconds
+    pub fn from_window(
+        bounds: BoundingBox,
+        times: RangeInclusive<f32>,
+        values: RangeInclusive<f32>,
+    ) -> Self {
+        Self {
+            bounds,
+            times,
+            values,
+            time_scale: AxisScale::Linear,
+        }
+    }
+
+    /// Creates a transform for the bounds of the current view
+    pub fn from_cx(
+        cx: &EventContext,
+        range: impl Lens<Target = RangeInclusive<f32>>,
+        max: f32,
+    ) -> Self {
+        Self::new(cx.cache.get_bounds(cx.current()), range.get(cx), max)
+    }
+
+    /// Sets the values shown from the bottom to the top of the view
+    pub fn with_value_range(mut self, values: RangeInclusive<f32>) -> Self {
+        self.values = values;
+        self
+    }
+
+    /// Sets how times are spread across the view
+    pub fn with_time_scale(mut self, time_scale: AxisScale) -> Self {
+        self.time_scale = time_scale;
+        self
+    }
+
+    /// The values from the top to the bottom of the view, as UI `y` grows
+    /// downwards
+    fn ui_values(&self) -> RangeInclusive<f32> {
+        *self.values.end()..=*self.values.start()
+    }
+
+    /// Gets the indices of the points within the times shown, plus one point
+    /// beyond each edge so the lines leading out of the view are still drawn.
+    /// The points must be sorted by `x`.
+    pub fn visible_range<T>(&self, points: &CurvePoints<T>) -> Range<usize> {
+        let start = points.partition_point(|point| point.x < *self.times.start());
+        let end = points.partition_point(|point| point.x <= *self.times.end());
+        start.saturating_sub(1)..(end + 1).min(points.len())
+    }
+
+    /// Converts a data point to its position in the view
+    pub fn to_ui(&self, point: Vec2) -> Vec2 {
+        self.bounds.map_range_point_to_ui(
+            Vec2::new(self.time_scale.apply(point.x), point.y),
+            self.time_scale.apply_range(&self.times),
+            self.ui_values(),
+        )
+    }
+
+    /// Converts a position in the view to its data point
+    pub fn to_data(&self, ui_point: Vec2) -> Vec2 {
+        let point = self.bounds.map_ui_point_to_range(
+            ui_point,
+            self.time_scale.apply_range(&self.times),
+            self.ui_values(),
+        );
+        Vec2::new(self.time_scale.invert(point.x), point.y)
+    }
+}
+
+/// The visible points of a graph projected into its view, kept between draws
+/// so they are only projected again when the points or the view change.
+/// Points outside the view, apart from the one beyond each edge, are skipped.
+#[derive(Default)]
+pub struct ProjectedPoints {
+    /// The transform and the data points and curves of the last projection
+    transform: Option<ViewTransform>,
+    points: Vec<Vec2>,
+    curves: Vec<f32>,
+    /// The index of the first projected point
+    first: usize,
+    ui_points: Vec<Vec2>,
+}
+
+impl ProjectedPoints {
+    /// Projects `points` through `transform`, unless both are the same as
+    /// last time. Returns whether the points were projected again.
+    pub fn update<T>(&mut self, transform: &ViewTransform, points: &CurvePoints<T>) -> bool {
+        let unchanged = self.transform.as_ref() == Some(transform)
+            && self.points.len() == points.len()
+            && self.points.iter().zip(points.iter()).zip(&self.curves).all(
+                |((cached, point), curve)| {
+                    cached.x == point.x && cached.y == point.y && *curve == point.curve
+                },
+            );
+        if unchanged {
+            return false;
+        }
+        self.transform = Some(transform.clone());
+        self.points.clear();
+        self.points
+            .extend(points.iter().map(|point| Vec2::new(point.x, point.y)));
+        self.curves.clear();
+        self.curves.extend(points.iter().map(|point| point.curve));
+        let visible = transform.visible_range(points);
+        self.first = visible.start;
+        self.ui_points.clear();
+        self.ui_points.extend(
+            self.points[visible]
+                .iter()
+                .map(|point| transform.to_ui(*point)),
+        );
+        true
+    }
+
+    /// The projected points as positions in the view, as of the last update,
+    /// starting with the point at [`ProjectedPoints::first_index`]
+    pub fn ui_points(&self) -> &[Vec2] {
+        &self.ui_points
+    }
+
+    /// The index of the first projected point
+    pub fn first_index(&self) -> usize {
+        self.first
+    }
+
+    /// Gets the position in the view of the point at `index`, if it was
+    /// projected
+    pub fn get(&self, index: usize) -> Option<Vec2> {
+        let index = index.checked_sub(self.first)?;
+        self.ui_points.get(index).copied()
+    }
+
+    /// Gets the index of the projected point closest to `cursor`, if any lies
+    /// within `radius` pixels of it
+    pub fn closest(&self, cursor: Vec2, radius: f32) -> Option<usize> {
+        closest_point(&self.ui_points, cursor, radius).map(|i| self.first + i)
+    }
+
+    /// The curve of the segment ending at the point at `index`, as of the
+    /// last update
+    pub fn curve(&self, index: usize) -> f32 {
+        self.curves.get(index).copied().unwrap_or_default()
+    }
+
+    /// Gets the position in the view of the handle halfway along the
+    /// segment ending at the point at `index`, if both of its points were
+    /// projected
+    pub fn midpoint(&self, index: usize) -> Option<Vec2> {
+        let start = self.get(index.checked_sub(1)?)?;
+        let end = self.get(index)?;
+        Some(point_on_curve(start, end, self.curve(index), 0.5f32))
+    }
+
+    /// Gets the index of the point ending the segment whose handle is
+    /// closest to `cursor`, if any lies within `radius` pixels of it.
+    /// Segments narrower than `min_width` pixels have no handle, as it would
+    /// cover their points.
+    pub fn closest_midpoint(&self, cursor: Vec2, radius: f32, min_width: f32) -> Option<usize> {
+        let midpoints: Vec<Vec2> = self
+            .ui_points
+            .windows(2)
+            .enumerate()
+            .map(|(i, segment)| {
+                if segment[1].x - segment[0].x < min_width {
+                    // Out of reach, keeping the midpoints sorted by `x`
+                    Vec2::new(segment[0].x, f32::INFINITY)
+                } else {
+                    point_on_curve(
+                        segment[0],
+                        segment[1],
+                        self.curve(self.first + i + 1),
+                        0.5f32,
+                    )
+                }
+            })
+            .collect();
+        closest_point(&midpoints, cursor, radius).map(|i| self.first + i + 1)
+    }
+}
+
+/// Gets the point `t` of the way through the time of a segment from `start`
+/// to `end` in the view, on the `curve` of the segment. The `y` of the view
+/// maps to values linearly, so the segment curves as it does in data space.
+pub fn point_on_curve(start: Vec2, end: Vec2, curve: f32, t: f32) -> Vec2 {
+    Vec2::new(
+        start.x + (end.x - start.x) * t,
+        start.y + (end.y - start.y) * curve_shape(t, curve),
+    )
+}
+
+/// Adds the points of a line along the `curve` of the segment from `start`
+/// to `end` in the view to `line`, leaving out `start`, at most `step`
+/// pixels apart in `x`. Straight segments only add `end`.
+pub fn extend_curve(line: &mut Vec<Vec2>, start: Vec2, end: Vec2, curve: f32, step: f32) {
+    if curve != 0f32 {
+        let steps = ((end.x - start.x) / step)
+            .ceil()
+            .clamp(1f32, MAX_CURVE_STEPS);
+        let steps = steps as usize;
+        line.extend((1..steps).map(|i| point_on_curve(start, end, curve, i as f32 / steps as f32)));
+    }
+    line.push(end);
+}
+
+/// The most lines a curved segment is drawn with, however wide it is
+const MAX_CURVE_STEPS: f32 = 256f32;
+
+/// Gets the index of the point closest to `cursor`, if any point lies within
+/// `radius` pixels of it. The points must be sorted by `x`, as the points of
+/// a graph are, so only the points within `radius` of the cursor on the x
+/// axis are found with a binary search and checked.
+pub fn closest_point(ui_points: &[Vec2], cursor: Vec2, radius: f32) -> Option<usize> {
+    let start = ui_points.partition_point(|point| point.x < cursor.x - radius);
+    ui_points[start..]
+        .iter()
+        .take_while(|point| point.x <= cursor.x + radius)
+        .enumerate()
+        // Use distance squared to avoid `sqrt` operations
+        .map(|(i, point)| (start + i, point.distance_squared(cursor)))
+        .filter(|(_, distance)| *distance <= radius.powi(2))
+        // The first of equally close points wins, in case points overlap
+        .min_by(|a, b| a.1.partial_cmp(&b.1).unwrap_or(Ordering::Equal))
+        .map(|(i, _)| i)
+}
+
+/// Reduces a line through points sorted by `x` to at most four points per
+/// pixel column, the first, lowest, highest and last of the column in their
+/// original order, so dense curves are drawn with a few points per pixel and
+/// still look the same
+pub fn simplify_for_display(ui_points: &[Vec2], simplified: &mut Vec<Vec2>) {
+    simplified.clear();
+    let mut rest = ui_points;
+    while let Some(first) = rest.first() {
+        let column = first.x.floor();
+        let len = rest
+            .iter()
+            .take_while(|point| point.x.floor() <= column)
+            .count()
+            .max(1);
+        let (run, next) = rest.split_at(len);
+        if run.len() <= 4 {
+            simplified.extend_from_slice(run);
+        } else {
+            let by_y = |a: &(usize, &Vec2), b: &(usize, &Vec2)| a.1.y.total_cmp(&b.1.y);
+            let lowest = run.iter().enumerate().min_by(by_y).map_or(0, |(i, _)| i);
+            let highest = run.iter().enumerate().max_by(by_y).map_or(0, |(i, _)| i);
+            let mut kept = [0, lowest, highest, run.len() - 1];
+            kept.sort_unstable();
+            let mut last = None;
+            for i in kept {
+                if last != Some(i) {
+                    simplified.push(run[i]);
+                    last = Some(i);
+                }
+            }
+        }
+        rest = next;
+    }
 }
 
 #[cfg(test)]
@@ -96,17 +440,230 @@ mod tests {
 
     #[test]
     fn gets_ui_point_from_data() {
-        let rect = rect();
-        let ui_point = data_to_bounds_pos_range(rect, Vec2::new(0.6, 0.5), 0.2..=0.4, 2f32);
+        let transform = ViewTransform::new(rect(), 0.2..=0.4, 2f32);
+        let ui_point = transform.to_ui(Vec2::new(0.6, 0.5));
         assert_eq!(ui_point.x.round(), 110f32);
         assert_eq!(ui_point.y.round(), 60f32);
     }
 
+    #[test]
+    fn gets_closest_point_in_radius() {
+        let points = [
+            Vec2::new(0f32, 0f32),
+            Vec2::new(10f32, 0f32),
+            Vec2::new(14f32, 0f32),
+        ];
+        assert_eq!(
+            closest_point(&points, Vec2::new(13f32, 1f32), 4f32),
+            Some(2)
+        );
+        assert_eq!(closest_point(&points, Vec2::new(5f32, 8f32), 4f32), None);
+    }
+
+    #[test]
+    fn gets_closest_of_many_points() {
+        let points: Vec<Vec2> = (0..1000)
+            .map(|i| Vec2::new(i as f32, (i % 7) as f32))
+            .collect();
+        assert_eq!(
+            closest_point(&points, Vec2::new(500.4f32, 3f32), 2f32),
+            Some(500)
+        );
+        // Far below the points on the y axis, though within range on x
+        assert_eq!(closest_point(&points, Vec2::new(500f32, 20f32), 2f32), None);
+        assert_eq!(
+            closest_point(&points, Vec2::new(1001f32, 5f32), 2f32),
+            Some(999)
+        );
+        // Overlapping points pick the first one
+        let stacked = [Vec2::ZERO, Vec2::ZERO, Vec2::new(4f32, 0f32)];
+        assert_eq!(closest_point(&stacked, Vec2::ZERO, 1f32), Some(0));
+    }
+
     #[test]
     fn gets_data_point_from_ui() {
-        let rect = rect();
-        let data_point = _ui_to_data_pos_range(rect, Vec2::new(110f32, 60f32), 0.2..=0.4, 2f32);
+        let transform = ViewTransform::new(rect(), 0.2..=0.4, 2f32);
+        let data_point = transform.to_data(Vec2::new(110f32, 60f32));
         assert_approx_eq!(data_point.x, 0.6);
         assert_approx_eq!(data_point.y, 0.5);
     }
+
+    #[test]
+    fn maps_value_ranges() {
+        let transform =
+            ViewTransform::new(rect(), 0f32..=1f32, 1f32).with_value_range(-1f32..=1f32);
+        // Zero is centered and the top is the end of the range
+        assert_approx_eq!(transform.to_ui(Vec2::new(0f32, 0f32)).y, 60f32);
+        assert_approx_eq!(transform.to_ui(Vec2::new(0f32, 1f32)).y, 20f32);
+        assert_approx_eq!(transform.to_data(Vec2::new(10f32, 100f32)).y, -1f32);
+    }
+
+    #[test]
+    fn maps_windows() {
+        let transform = ViewTransform::from_window(rect(), 1f32..=3f32, 0.5f32..=0.7f32);
+        assert_eq!(
+            transform,
+            ViewTransform::new(rect(), 0.25f32..=0.75f32, 4f32).with_value_range(0.5f32..=0.7f32)
+        );
+        let corner = transform.to_ui(Vec2::new(3f32, 0.7f32));
+        assert_approx_eq!(corner.x, 210f32);
+        assert_approx_eq!(corner.y, 20f32);
+        assert_approx_eq!(transform.to_data(Vec2::new(110f32, 60f32)).y, 0.6f32);
+    }
+
+    #[test]
+    fn zooms_windows_around_anchor() {
+        // The anchor stays at the same place in the window
+        let zoomed = zoom_window(0f32..=1f32, 0.25f32, 0.5f32, 0.01f32, 0f32..=1f32);
+        assert_approx_eq!(*zoomed.start(), 0.125f32);
+        assert_approx_eq!(*zoomed.end(), 0.625f32);
+        // Zooming out stops at the limits, shifting the window into them
+        let zoomed = zoom_window(0.6f32..=1f32, 0.8f32, 2f32, 0.01f32, 0f32..=1f32);
+        assert_approx_eq!(*zoomed.start(), 0.2f32);
+        assert_approx_eq!(*zoomed.end(), 1f32);
+        let zoomed = zoom_window(0.2f32..=0.4f32, 0.3f32, 10f32, 0.01f32, 0f32..=1f32);
+        assert_eq!(zoomed, 0f32..=1f32);
+        // Zooming in stops at the smallest span
+        let zoomed = zoom_window(0f32..=0.02f32, 0f32, 0.1f32, 0.01f32, 0f32..=1f32);
+        assert_approx_eq!(*zoomed.start(), 0f32);
+        assert_approx_eq!(*zoomed.end(), 0.01f32);
+    }
+
+    #[test]
+    fn projects_points_only_when_changed() {
+        let mut projected = ProjectedPoints::default();
+        let transform = ViewTransform::new(rect(), 0.2..=0.4, 2f32);
+        let mut points: CurvePoints = CurvePoints::new(vec![(0.6f32, 0.5f32).into()]);
+        assert!(projected.update(&transform, &points));
+        assert!(!projected.update(&transform, &points));
+        assert_eq!(projected.ui_points()[0].round(), Vec2::new(110f32, 60f32));
+
+        points[0].y = 1f32;
+        assert!(projected.update(&transform, &points));
+        assert_eq!(projected.ui_points()[0].round(), Vec2::new(110f32, 20f32));
+        let zoomed = ViewTransform::new(rect(), 0f32..=1f32, 2f32);
+        assert!(projected.update(&zoomed, &points));
+    }
+
+    #[test]
+    fn projects_curves() {
+        let mut projected = ProjectedPoints::default();
+        let transform = ViewTransform::new(rect(), 0f32..=1f32, 2f32);
+        let mut points: CurvePoints =
+            CurvePoints::new(vec![(0f32, 0f32).into(), (2f32, 1f32).into()]);
+        projected.update(&transform, &points);
+        assert_eq!(projected.midpoint(1).unwrap(), Vec2::new(110f32, 60f32));
+        assert_eq!(
+            projected.closest_midpoint(Vec2::new(112f32, 58f32), 4f32, 8f32),
+            Some(1)
+        );
+        assert_eq!(
+            projected.closest_midpoint(Vec2::new(112f32, 58f32), 4f32, 400f32),
+            None
+        );
+
+        // Bending the segment projects it again, moving its handle down
+        points[1].curve = 4f32;
+        assert!(projected.update(&transform, &points));
+        let midpoint = projected.midpoint(1).unwrap();
+        assert!(midpoint.y > 60f32);
+        assert_approx_eq!(
+            transform.to_data(midpoint).y,
+            points.value_at(1f32).unwrap()
+        );
+
+        let mut line = vec![Vec2::ZERO];
+        extend_curve(&mut line, Vec2::ZERO, Vec2::new(10f32, 10f32), 0f32, 2f32);
+        assert_eq!(line.len(), 2);
+        extend_curve(
+            &mut line,
+            Vec2::new(10f32, 10f32),
+            Vec2::new(20f32, 0f32),
+            4f32,
+            2f32,
+        );
+        assert_eq!(line.len(), 7);
+        assert_eq!(line[6], Vec2::new(20f32, 0f32));
+    }
+
+    #[test]
+    fn culls_points_outside_the_view() {
+        // Shows 0.4 to 0.8 seconds
+        let transform = ViewTransform::new(rect(), 0.2..=0.4, 2f32);
+        let points: CurvePoints = CurvePoints::new(
+            [0f32, 0.1, 0.3, 0.5, 0.6, 0.8, 1f32, 1.5f32]
+                .into_iter()
+                .map(|x| (x, 0.5f32).into())
+                .collect(),
+        );
+        // One point of overlap beyond each edge
+        assert_eq!(transform.visible_range(&points), 2..7);
+        let wide = ViewTransform::new(rect(), 0f32..=1f32, 2f32);
+        assert_eq!(wide.visible_range(&points), 0..8);
+
+        let mut projected = ProjectedPoints::default();
+        projected.update(&transform, &points);
+        assert_eq!(projected.first_index(), 2);
+        assert_eq!(projected.ui_points().len(), 5);
+        assert_eq!(projected.get(1), None);
+        assert_eq!(projected.get(3).unwrap().round(), Vec2::new(60f32, 60f32));
+        assert_eq!(projected.closest(Vec2::new(61f32, 58f32), 4f32), Some(3));
+    }
+
+    #[test]
+    fn simplifies_dense_lines() {
+        // Ten points in each of ten pixel columns, rising and falling
+        let points: Vec<Vec2> = (0..100)
+            .map(|i| Vec2::new(i as f32 / 10f32, ((i * 7 + 3) % 10) as f32))
+            .collect();
+        let mut simplified = Vec::new();
+        simplify_for_display(&points, &mut simplified);
+        assert_eq!(simplified.len(), 40);
+        // The first column keeps its ends and extremes in order
+        assert_eq!(
+            &simplified[..4],
+            &[points[0], points[1], points[8], points[9]]
+        );
+        // Sparse points are kept as they are
+        let sparse = [Vec2::new(0f32, 1f32), Vec2::new(5f32, 2f32)];
+        simplify_for_display(&sparse, &mut simplified);
+        assert_eq!(simplified, sparse);
+    }
+
+    #[test]
+    fn maps_log_times() {
+        let transform =
+            ViewTransform::new(rect(), 0.01f32..=1f32, 10f32).with_time_scale(AxisScale::Log);
+        // 1 second is halfway between 0.1 and 10 seconds on a log axis
+        let ui_point = transform.to_ui(Vec2::new(1f32, 0f32));
+        assert_approx_eq!(ui_point.x, 110f32, 1e-3f32);
+        assert_approx_eq!(transform.to_data(ui_point).x, 1f32, 1e-4f32);
+    }
+
+    #[test]
+    fn snaps_to_the_grid() {
+        // Sixteenth notes at 120 BPM are 0.125 seconds long
+        let grid = SnapGrid::beats(BeatGrid::default()).with_value_step(0.25f32);
+        let snapped = grid.snap(Vec2::new(0.3f32, 0.6f32));
+        assert_approx_eq!(snapped.x, 0.25f32);
+        assert_approx_eq!(snapped.y, 0.5f32);
+        // Free axes and steps that aren't positive are left alone
+        let free = SnapGrid::new(Some(0f32), None).snap(Vec2::new(0.3f32, 0.6f32));
+        assert_eq!(free, Vec2::new(0.3f32, 0.6f32));
+    }
+
+    #[test]
+    fn lists_grid_lines_in_view() {
+        let grid = SnapGrid::new(Some(0.5f32), Some(0.25f32));
+        assert_eq!(
+            grid.time_lines(0.2f32..=1.6f32, 0.1f32),
+            vec![0.5f32, 1f32, 1.5f32]
+        );
+        assert_eq!(grid.value_lines(0f32..=1f32, 0f32).len(), 5);
+        // Lines too close together aren't drawn
+        assert!(grid.time_lines(0f32..=4f32, 1f32).is_empty());
+        assert!(SnapGrid::default()
+            .value_lines(0f32..=1f32, 0f32)
+            .is_empty());
+    }
 }
@@ -0,0 +1,119 @@
+use lily_derive::Handle;
+use vizia::prelude::*;
+
+use crate::focus::{Activation, FocusExt};
+use crate::util::PseudoClassExt;
+
+enum SectionEvent {
+    Toggle,
+}
+
+/// A collapsible container with a clickable header, for grouping the controls
+/// of a plugin GUI.
+///
+/// While collapsed, the header shows an optional summary in place of the
+/// content, e.g. `ADSR 5ms/200ms/0.7/300ms`. The collapse is animated through
+/// the `collapsed` class of the `content` element in the stylesheet. The header
+/// is a tab stop, and Enter or Space toggles the section while it's focused.
+#[allow(clippy::type_complexity)]
+#[derive(Handle)]
+pub struct Section<L>
+where
+    L: Lens<Target = bool>,
+{
+    open: L,
+
+    #[callback(bool)]
+    on_toggle: Option<Box<dyn Fn(&mut EventContext, bool)>>,
+}
+
+impl<L> Section<L>
+where
+    L: Lens<Target = bool>,
+{
+    /// Create a new `Section`
+    ///
+    /// # Parameters
+    ///
+    /// * `cx` - the current [`Context`]
+    /// * `title` - the text of the header
+    /// * `open` - a [`Lens`] of whether the section is expanded. Clicking the
+    ///   header calls `on_toggle` with the new state, which the host should
+    ///   store so that it persists.
+    /// * `summary` - builds the views shown in the header while collapsed
+    /// * `content` - builds the views shown while expanded
+    pub fn new<S, C>(cx: &mut Context, title: &str, open: L, summary: S, content: C) -> Handle<Self>
+    where
+        S: FnOnce(&mut Context),
+        C: FnOnce(&mut Context),
+    {
+        Self {
+            open: open.clone(),
+            on_toggle: None,
+        }
+        .build(cx, |cx| {
+            HStack::new(cx, |cx| {
+                Label::new(
+                    cx,
+                    open.clone()
+                        .map(|open| if *open { "\u{25be}" } else { "\u{25b8}" }),
+                )
+                .class("arrow");
+                Label::new(cx, title).class("title");
+                HStack::new(cx, summary)
+                    .class("summary")
+                    .display(open.clone().map(
+                        |open| {
+                            if *open {
+                                Display::None
+                            } else {
+                                Display::Flex
+                            }
+                        },
+                    ));
+            })
+            .class("header")
+            .tab_stop()
+            .on_press(|cx| cx.emit(SectionEvent::Toggle));
+
+            VStack::new(cx, content)
+                .class("content")
+                .toggle_class("collapsed", open.map(|open| !*open));
+        })
+    }
+}
+
+impl<L> View for Section<L>
+where
+    L: Lens<Target = bool>,
+{
+    fn element(&self) -> Option<&'static str> {
+        Some("section")
+    }
+
+    fn event(&mut self, cx: &mut EventContext, event: &mut Event) {
+        if cx.is_disabled() {
+            return;
+        }
+        event.map(|ev: &SectionEvent, _| match ev {
+            SectionEvent::Toggle => {
+                let open = self.open.get(cx);
+                if let Some(callback) = &self.on_toggle {
+                    (callback)(cx, !open);
+                }
+            }
+        });
+
+        // The header is the first child, and keys pressed on the content are
+        // left to it
+        event.map(|ev: &WindowEvent, meta| {
+            if let WindowEvent::KeyDown(code, _) = ev {
+                let header = cx.tree.get_first_child(cx.current());
+                if header == Some(meta.origin) && Activation::from_key(*code).is_some() {
+                    cx.emit(SectionEvent::Toggle);
+                    meta.consume();
+                }
+            }
+        });
+    }
+}
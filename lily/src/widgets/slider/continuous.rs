@@ -1,13 +1,26 @@
-use crate::util::{BoundingBoxExt, RangeExt};
+use crate::context_menu::{ContextMenuAction, ContextMenuExt, ContextMenuItem};
+use crate::focus::FocusExt;
+use crate::format::{Decimal, ValueFormatter};
+use crate::gesture::GestureExt;
+use crate::input::{InputAction, InputMapExt, PressReader};
+use crate::param::NormalizedParam;
+use crate::tooltip::TooltipExt;
+use crate::util::{
+    BoundingBoxExt, CoalescedMove, FrameExt, KeyNudge, Modulation, ModulationExt, MoveCoalescer,
+    PseudoClassExt, ValueMapping,
+};
 use glam::Vec2;
 use lily_derive::Handle;
-use std::{marker::PhantomData, ops::RangeInclusive};
+use std::{marker::PhantomData, ops::RangeInclusive, rc::Rc};
 // use vizia::context::Context;
 use vizia::prelude::*;
 use vizia::vg::{Paint, Path};
 
 const VERTICAL: bool = true;
 const HORIZONTAL: bool = false;
+/// The thickness in pixels of the modulation band when the `.modulation`
+/// class sets no `width`
+const MODULATION_WIDTH: f32 = 4f32;
 
 #[derive(Handle)]
 pub struct DragSlider<L>
@@ -16,12 +29,15 @@ where
 {
     value: PhantomData<L>,
     range: PhantomData<RangeInclusive<f32>>,
+    /// The value that the context menu's reset entry goes back to, if known
+    default: Option<f32>,
+    /// The presses of the mouse buttons on the slider
+    presses: PressReader,
+    #[event]
     #[callback(f32)]
     on_changing: Option<Box<dyn Fn(&mut EventContext, f32)>>,
-}
-
-pub enum InternalEvent {
-    Changing(f32),
+    #[callback(ContextMenuAction)]
+    on_context_menu: Option<Box<dyn Fn(&mut EventContext, ContextMenuAction)>>,
 }
 
 impl<L> DragSlider<L>
@@ -38,18 +54,101 @@ where
     /// * `range` - the arbitrary range of this slider. In most cases, you'll
     ///   want `0f32..=1f32` or `-1f32..=1f32` for a centered slider.
     pub fn new(cx: &mut Context, value: L, range: RangeInclusive<f32>) -> Handle<Self> {
+        Self::with_mapping(cx, value, range)
+    }
+
+    /// Create a new `DragSlider` with a non-linear taper
+    ///
+    /// # Parameters
+    ///
+    /// * `cx` - Vizia `Context`
+    /// * `value` - a `vizia::Lens` specifying the value of this slider
+    /// * `mapping` - the [`ValueMapping`] between the value and the position of
+    ///   the slider, such as a [`LogMapping`](crate::util::LogMapping)
+    pub fn with_mapping(
+        cx: &mut Context,
+        value: L,
+        mapping: impl ValueMapping + 'static,
+    ) -> Handle<Self> {
+        Self::with_formatter(cx, value, mapping, Decimal::default())
+    }
+
+    /// Create a new `DragSlider` with a non-linear taper and custom readout
+    ///
+    /// # Parameters
+    ///
+    /// * `cx` - Vizia `Context`
+    /// * `value` - a `vizia::Lens` specifying the value of this slider
+    /// * `mapping` - the [`ValueMapping`] between the value and the position of
+    ///   the slider
+    /// * `formatter` - the [`ValueFormatter`] for the value shown in the
+    ///   slider's tooltip, such as [`Decibels`](crate::format::Decibels)
+    pub fn with_formatter(
+        cx: &mut Context,
+        value: L,
+        mapping: impl ValueMapping + 'static,
+        formatter: impl ValueFormatter + 'static,
+    ) -> Handle<Self> {
+        Self::build_slider(cx, value, Rc::new(mapping), Rc::new(formatter), None)
+    }
+
+    /// Create a new `DragSlider` for a parameter, which provides the slider's
+    /// taper, readout and default. Choosing reset from the slider's context
+    /// menu, or double-clicking the slider, changes the value back to the
+    /// parameter's default.
+    ///
+    /// # Parameters
+    ///
+    /// * `cx` - Vizia `Context`
+    /// * `value` - a `vizia::Lens` specifying the plain value of this slider
+    /// * `param` - the [`NormalizedParam`] describing the value, such as a
+    ///   [`Param`](crate::param::Param)
+    pub fn with_param<P>(cx: &mut Context, value: L, param: P) -> Handle<Self>
+    where
+        P: 'static + NormalizedParam + Clone,
+    {
+        let default = param.default_plain();
+        Self::build_slider(
+            cx,
+            value,
+            Rc::new(param.clone()),
+            Rc::new(param),
+            Some(default),
+        )
+    }
+
+    fn build_slider(
+        cx: &mut Context,
+        value: L,
+        mapping: Rc<dyn ValueMapping>,
+        formatter: Rc<dyn ValueFormatter>,
+        default: Option<f32>,
+    ) -> Handle<Self> {
         Self {
             value: PhantomData::default(),
             on_changing: None,
+            on_context_menu: None,
             range: PhantomData::default(),
+            default,
+            presses: PressReader::new(),
         }
         .build(cx, |cx| {
             // Foreground interactive slider
-            SliderBar::new(cx, value.clone(), range.clone())
+            SliderBar::new(cx, value.clone(), mapping.clone(), formatter.clone())
                 .class("bar")
-                .on_changing(|cx, value| cx.emit(InternalEvent::Changing(value)));
+                .on_changing(|cx, value| cx.emit(DragSliderInternalEvent::Changing(value)));
         })
     }
+
+    /// Changes the value back to the default, if known
+    fn reset(&self, cx: &mut EventContext) {
+        if let Some(default) = self.default {
+            if let Some(callback) = &self.on_changing {
+                (callback)(cx, default);
+            }
+            cx.single_edit(0, &[default]);
+        }
+    }
 }
 
 impl<L> View for DragSlider<L>
@@ -61,136 +160,228 @@ where
     }
 
     fn event(&mut self, cx: &mut EventContext, event: &mut Event) {
-        event.map(|ev: &InternalEvent, _| match *ev {
-            InternalEvent::Changing(value) => {
-                if let Some(callback) = &self.on_changing {
-                    (callback)(cx, value);
+        if cx.is_disabled() {
+            return;
+        }
+        self.handle_internal_event(cx, event);
+
+        // Presses of the bar bubble up to the slider
+        event.map(|ev: &WindowEvent, _| {
+            if let Some(press) = self.presses.read(cx, ev) {
+                let input = cx.input_map();
+                if input.triggers(InputAction::Reset, press) && self.default.is_some() {
+                    self.reset(cx);
+                } else if input.triggers(InputAction::OpenMenu, press)
+                    && (self.on_context_menu.is_some() || self.default.is_some())
+                {
+                    cx.open_context_menu(ContextMenuItem::parameter_items());
                 }
             }
         });
+
+        event.map(|action: &ContextMenuAction, _| {
+            if let ContextMenuAction::Reset = action {
+                self.reset(cx);
+            }
+            if let Some(callback) = &self.on_context_menu {
+                (callback)(cx, action.clone());
+            }
+        });
     }
 }
+impl<L> ModulationExt<f32> for Handle<'_, DragSlider<L>>
+where
+    L: Lens<Target = f32>,
+{
+    fn modulation<M>(self, modulation: M) -> Self
+    where
+        M: Lens<Target = Modulation>,
+    {
+        modulation.set_or_bind(self.cx, self.entity, |cx, entity, modulation| {
+            // The bar draws the modulation, so it is handed down to it
+            let children: Vec<Entity> = entity.child_iter(&cx.tree).collect();
+            for child in children {
+                if let Some(view) = cx.views.get_mut(&child) {
+                    if let Some(bar) = view.downcast_mut::<SliderBar<L>>() {
+                        bar.modulation = modulation;
+                    }
+                }
+            }
+        });
+        self
+    }
+}
+
 #[derive(Handle)]
+#[style_class("modulation")]
 pub struct SliderBar<L>
 where
     L: Lens<Target = f32>,
 {
     value: L,
-    range: RangeInclusive<f32>,
+    mapping: Rc<dyn ValueMapping>,
+    formatter: Rc<dyn ValueFormatter>,
+    /// The modulation drawn on top of the value, in normalized units
+    modulation: Modulation,
+    styles: SliderBarStyles,
     hover: bool,
     active: bool,
-    /// The offset of the cursor to the handle, set when clicking. This ensures
-    /// that values don't skip when first dragging to to cursor position
+    /// The normalized offset of the cursor to the handle, set when clicking.
+    /// This ensures that values don't skip when first dragging to to cursor
+    /// position
     offset: f32,
+    /// The last cursor moved to while dragging, until it is handled
+    moves: MoveCoalescer,
     #[callback(f32)]
     on_changing: Option<Box<dyn Fn(&mut EventContext, f32)>>,
 }
 
+impl<L> SliderBar<L>
+where
+    L: Lens<Target = f32>,
+{
+    /// Sets the value from the cursor while dragging
+    // TODO: figure out a way to not rely on the map_ui_point and instead just
+    // have some sort of scalar
+    fn drag_to(&mut self, cx: &mut EventContext, cursor: Vec2) {
+        if let Some(callback) = &self.on_changing {
+            // determine whether we are reacting to a vertical
+            // or horizontal slider
+            let rect = cx.cache.get_bounds(cx.current());
+            let orientation = rect.h > rect.w;
+            // let scalar = match
+            //     cx.modifiers.contains(Modifiers::SHIFT) {
+            //     true => 0.1, false => 1f32, };
+            let normalized = {
+                // let mapped = rect.map_ui_point((x, y).into(),
+                // true);
+                let ratio = rect.map_ui_point_unbounded(cursor, false);
+                self.offset
+                    + match orientation {
+                        // values start from the bottom
+                        VERTICAL => 1f32 - ratio.y,
+                        HORIZONTAL => ratio.x,
+                    }
+            };
+
+            // TODO: Determine scalar based on size
+
+            // let delta_scaled = delta * scalar; Scale the
+            // value to just the small area of our widget let
+            // mut new_val = delta_scaled + self.value.get(cx);
+
+            // the mapping clamps to the ends of the range, which
+            // may be reversed
+            let val = self.mapping.denormalize(normalized);
+
+            (callback)(cx, val);
+            cx.perform_edit(0, &[val]);
+            cx.show_value_tooltip(self.formatter.format(val));
+        }
+    }
+}
+
 impl<L> View for SliderBar<L>
 where
     L: Lens<Target = f32>,
 {
     fn event(&mut self, cx: &mut EventContext, event: &mut Event) {
+        if cx.is_disabled() {
+            return;
+        }
         event.map(|ev: &WindowEvent, _| match *ev {
-            WindowEvent::MouseEnter => self.hover = true,
+            WindowEvent::MouseEnter => {
+                self.hover = true;
+                cx.show_tooltip(self.formatter.format(self.value.get(cx)));
+            }
             WindowEvent::MouseLeave => {
                 self.hover = false;
+                if !self.active {
+                    cx.hide_tooltip();
+                }
             }
             WindowEvent::MouseDown(button) => {
                 if button == MouseButton::Left {
-                    cx.capture();
+                    cx.capture_active();
                     self.active = true;
                     // set the offset
                     let rect = cx.cache.get_bounds(cx.current());
                     let mouse_pos: Vec2 = (cx.mouse.cursorx, cx.mouse.cursory).into();
                     // get the difference between the mapped mouse pos and
                     // the current value
-                    let mouse_mapped = rect.map_ui_point(mouse_pos, true);
-                    self.offset = self.value.get(cx)
+                    let mouse_mapped = rect.map_ui_point(mouse_pos, false);
+                    self.offset = self.mapping.normalize(self.value.get(cx))
                         - match rect.h > rect.w {
-                            VERTICAL => mouse_mapped.y,
+                            VERTICAL => 1f32 - mouse_mapped.y,
                             HORIZONTAL => mouse_mapped.x,
                         };
+                    cx.begin_edit();
                 }
             }
             WindowEvent::MouseUp(button) => {
-                if button == MouseButton::Left {
-                    cx.release();
+                if button == MouseButton::Left && self.active {
+                    if let Some(cursor) = self.moves.take() {
+                        self.drag_to(cx, cursor);
+                    }
+                    cx.release_active();
                     self.active = false;
+                    if !self.hover {
+                        cx.hide_tooltip();
+                    }
+                    cx.end_edit();
                 }
                 // reset offset
                 self.offset = 0f32;
             }
-            // TODO: figure out a way to not rely on the map_ui_point and
-            // instead just have some sort of scalar
             WindowEvent::MouseMove(x, y) => {
                 if self.active {
-                    if let Some(callback) = &self.on_changing {
-                        // determine whether we are reacting to a vertical
-                        // or horizontal slider
-                        let rect = cx.cache.get_bounds(cx.current());
-                        let orientation = rect.h > rect.w;
-                        // let scalar = match
-                        //     cx.modifiers.contains(Modifiers::SHIFT) {
-                        //     true => 0.1, false => 1f32, };
-                        let mut val = {
-                            // let mapped = rect.map_ui_point((x, y).into(),
-                            // true);
-                            let ratio = rect.map_ui_point_unbounded((x, y).into(), true);
-                            self.offset
-                                + match orientation {
-                                    VERTICAL => ratio.y,
-                                    HORIZONTAL => ratio.x,
-                                }
-                        };
-
-                        // TODO: Determine scalar based on size
-
-                        // let delta_scaled = delta * scalar; Scale the
-                        // value to just the small area of our widget let
-                        // mut new_val = delta_scaled + self.value.get(cx);
-
-                        // special checks for ranges of negative width
-                        if self.range.width().signum() == -1f32 {
-                            if val > *self.range.start() {
-                                val = *self.range.start();
-                            } else if val < *self.range.end() {
-                                val = *self.range.end();
-                            }
-                        } else {
-                            val = val.clamp(*self.range.start(), *self.range.end());
-                        }
-
-                        (callback)(cx, val);
-                    }
+                    self.moves.push(cx, x, y);
+                }
+            }
+            // once focused, the keys step the value like a drag would
+            WindowEvent::KeyDown(code, _) => {
+                if let (Some(nudge), Some(callback)) =
+                    (KeyNudge::from_event(cx, code), &self.on_changing)
+                {
+                    let normalized = self.mapping.normalize(self.value.get(cx));
+                    let val = self.mapping.denormalize(nudge.apply(normalized));
+                    (callback)(cx, val);
+                    cx.single_edit(0, &[val]);
+                    cx.show_value_tooltip(self.formatter.format(val));
                 }
             }
             _ => (),
         });
+        event.map(|_: &CoalescedMove, meta| {
+            if let Some(cursor) = self.moves.take() {
+                self.drag_to(cx, cursor);
+            }
+            meta.consume();
+        });
     }
     fn draw(&self, cx: &mut DrawContext, canvas: &mut Canvas) {
         let background_color = cx.background_color().cloned().unwrap_or_default();
         let active_color = cx.border_color().cloned().unwrap_or_default();
 
-        let mut rect = cx.bounds();
+        let bounds = cx.bounds();
+        let mut rect = bounds;
 
         // determine whether we are drawing a vertical or horizontal slider
         let orientation = rect.h > rect.w;
 
-        self.value.view(cx.data().unwrap(), |value| {
-            match orientation {
-                VERTICAL => {
-                    let old_height = rect.h;
-                    rect.h = rect.height() * self.range.map(value.cloned().unwrap_or_default());
-                    // A little trick since values start from the top and we
-                    // want the slider to start at the bottom and go up
-                    rect.y += old_height - rect.h;
-                }
-                HORIZONTAL => {
-                    rect.w = rect.width() * self.range.map(value.cloned().unwrap_or_default())
-                }
-            }
+        let normalized = self.value.view(cx.data().unwrap(), |value| {
+            self.mapping.normalize(value.cloned().unwrap_or_default())
         });
+        match orientation {
+            VERTICAL => {
+                let old_height = rect.h;
+                rect.h = rect.height() * normalized;
+                // A little trick since values start from the top and we
+                // want the slider to start at the bottom and go up
+                rect.y += old_height - rect.h;
+            }
+            HORIZONTAL => rect.w = rect.width() * normalized,
+        }
 
         // Draw bar background
         let mut path = Path::new();
@@ -223,22 +414,69 @@ where
         };
 
         canvas.fill_path(&mut path, &Paint::color(active_color.into()));
+
+        // Draw the modulation as a band along the edge of the slider, with a
+        // marker at the live value of offsets
+        if let Some((start, end)) = self.modulation.span(normalized) {
+            let band_color = self.styles.modulation_background_color(cx);
+            let live_color = self.styles.modulation_border_color(cx);
+            let thickness = self.styles.modulation_width(cx).unwrap_or(MODULATION_WIDTH);
+            let mut band = Path::new();
+            let mut live = Path::new();
+            let live_value = self.modulation.live(normalized);
+            match orientation {
+                VERTICAL => {
+                    let top = bounds.bottom() - end * bounds.h;
+                    band.rect(bounds.left(), top, thickness, (end - start) * bounds.h);
+                    if let Some(value) = live_value {
+                        let y = bounds.bottom() - value * bounds.h;
+                        live.rect(bounds.left(), y - 1f32, thickness * 2f32, 2f32);
+                    }
+                }
+                HORIZONTAL => {
+                    let top = bounds.bottom() - thickness;
+                    band.rect(
+                        bounds.left() + start * bounds.w,
+                        top,
+                        (end - start) * bounds.w,
+                        thickness,
+                    );
+                    if let Some(value) = live_value {
+                        let x = bounds.left() + value * bounds.w;
+                        live.rect(x - 1f32, top - thickness, 2f32, thickness * 2f32);
+                    }
+                }
+            }
+            canvas.fill_path(&mut band, &Paint::color(band_color.into()));
+            canvas.fill_path(&mut live, &Paint::color(live_color.into()));
+        }
+        cx.draw_outline(canvas);
     }
 }
 impl<L> SliderBar<L>
 where
     L: Lens<Target = f32>,
 {
-    fn new(cx: &mut Context, value: L, range: RangeInclusive<f32>) -> Handle<Self> {
+    fn new(
+        cx: &mut Context,
+        value: L,
+        mapping: Rc<dyn ValueMapping>,
+        formatter: Rc<dyn ValueFormatter>,
+    ) -> Handle<Self> {
         Self {
             value,
             on_changing: None,
-            range,
+            mapping,
+            formatter,
+            modulation: Modulation::None,
+            styles: SliderBarStyles::new(cx),
             hover: false,
             active: false,
             offset: 0f32,
+            moves: MoveCoalescer::new(),
         }
         .build(cx, |_| {})
+        .tab_stop()
     }
 }
 
@@ -248,3 +486,36 @@ where
 // self.cx.views.get_mut(&self.entity) { if let Some(slider) =
 //     view.downcast_mut::<SliderBar<P>>() { slider.on_changing =
 //     Some(Box::new(callback)); } } self } }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{Recorder, TestContext};
+    use assert_approx_eq::assert_approx_eq;
+
+    #[derive(Lens)]
+    struct Level {
+        value: f32,
+    }
+
+    impl Model for Level {}
+
+    #[test]
+    fn drags_sliders() {
+        let mut test = TestContext::new(200f32, 20f32);
+        let changes = Recorder::new();
+        let record = changes.clone();
+        test.build(|cx| {
+            Level { value: 0f32 }.build(cx);
+            DragSlider::new(cx, Level::value, 0f32..=1f32)
+                .width(Pixels(200f32))
+                .height(Pixels(20f32))
+                .on_changing(move |_, value| record.push(value));
+        });
+        test.drag((0f32, 10f32), (150f32, 10f32));
+        assert_approx_eq!(changes.last().unwrap(), 0.75f32, 1e-3f32);
+        // Dragging past the end stops at the end of the range
+        test.drag((150f32, 10f32), (400f32, 10f32));
+        assert_approx_eq!(changes.last().unwrap(), 1f32);
+    }
+}
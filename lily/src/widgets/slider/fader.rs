@@ -0,0 +1,475 @@
+use crate::anim::{Animator, Easing};
+use crate::context_menu::{ContextMenuAction, ContextMenuExt, ContextMenuItem};
+use crate::focus::FocusExt;
+use crate::format::{Decimal, ValueFormatter};
+use crate::gesture::GestureExt;
+use crate::input::{InputAction, InputMapExt, PressReader};
+use crate::param::NormalizedParam;
+use crate::tooltip::TooltipExt;
+use crate::util::{
+    install_ticker, BoundingBoxExt, CoalescedMove, FrameExt, FrameTick, KeyNudge, MoveCoalescer,
+    RedrawExt, TickExt, ValueMapping,
+};
+use glam::Vec2;
+use lily_derive::Handle;
+use std::cell::Cell;
+use std::ops::RangeInclusive;
+use std::rc::Rc;
+use std::time::Instant;
+use vizia::prelude::*;
+use vizia::vg::{Paint, Path};
+
+/// How long in seconds the cap glides to values set from elsewhere by
+/// default
+const GLIDE_TIME: f32 = 0.15f32;
+/// The length in pixels of the cap along the fader when the `.cap` class
+/// sets no `width`
+const CAP_LENGTH: f32 = 24f32;
+/// The radius in pixels of the corners of the cap
+const CAP_RADIUS: f32 = 3f32;
+/// The thickness in pixels of the line across the cap
+const CAP_LINE_WIDTH: f32 = 2f32;
+/// The thickness in pixels of the track when the `.track` class sets no
+/// `width`
+const TRACK_WIDTH: f32 = 4f32;
+
+/// A slider drawn as the fader of a mixing desk, a cap riding along a pill
+/// shaped track that is filled up to the cap. It's vertical when taller than
+/// it is wide, and horizontal otherwise.
+///
+/// The cap follows drags and keys at once, but glides to the values set from
+/// elsewhere, such as host automation, over the [`glide`](FaderHandle::glide)
+/// time rather than jumping, like a motorized fader.
+///
+/// The track and its fill are drawn in the `background-color` and
+/// `border-color` of the `.track` class, and the cap in the
+/// `background-color` of the `.cap` class with a line across it in its
+/// `border-color`.
+#[derive(Handle)]
+#[style_class("track", "cap")]
+pub struct Fader<L>
+where
+    L: Lens<Target = f32>,
+{
+    value: L,
+    mapping: Rc<dyn ValueMapping>,
+    formatter: Rc<dyn ValueFormatter>,
+    /// The value that resetting goes back to, if known
+    default: Option<f32>,
+    /// How long in seconds the cap glides to values set from elsewhere, or
+    /// `0` to jump to them
+    #[property]
+    glide: f32,
+    /// The normalized position the cap is drawn at
+    position: Animator<f32>,
+    /// When the glide was last advanced. The fader asks for a [`FrameTick`]
+    /// each frame while the cap glides.
+    last_tick: Instant,
+    /// The length in pixels of the cap as last drawn, which takes it off the
+    /// distance the cursor drags it
+    cap_length: Cell<f32>,
+    /// The last value the fader changed to itself, which the cap is already
+    /// at
+    sent: Option<f32>,
+    /// The normalized offset of the cursor to the value, while dragging, so
+    /// the value doesn't skip to the cursor when the drag starts
+    offset: Option<f32>,
+    /// The last cursor moved to while dragging, until it is handled
+    moves: MoveCoalescer,
+    /// The presses of the mouse buttons on the fader
+    presses: PressReader,
+    hover: bool,
+    styles: FaderStyles,
+    #[callback(f32)]
+    on_changing: Option<Box<dyn Fn(&mut EventContext, f32)>>,
+    #[callback(ContextMenuAction)]
+    on_context_menu: Option<Box<dyn Fn(&mut EventContext, ContextMenuAction)>>,
+}
+
+impl<L> Fader<L>
+where
+    L: Lens<Target = f32>,
+{
+    /// Create a new `Fader` over `range`, such as `0f32..=1f32`
+    pub fn new(cx: &mut Context, value: L, range: RangeInclusive<f32>) -> Handle<Self> {
+        Self::with_mapping(cx, value, range)
+    }
+
+    /// Create a new `Fader` with a non-linear taper, such as a
+    /// [`LogMapping`](crate::util::LogMapping)
+    pub fn with_mapping(
+        cx: &mut Context,
+        value: L,
+        mapping: impl ValueMapping + 'static,
+    ) -> Handle<Self> {
+        Self::build_fader(
+            cx,
+            value,
+            Rc::new(mapping),
+            Rc::new(Decimal::default()),
+            None,
+        )
+    }
+
+    /// Create a new `Fader` for a parameter, which provides the fader's
+    /// taper, readout and default. Choosing reset from the fader's context
+    /// menu, or double-clicking the fader, changes the value back to the
+    /// parameter's default.
+    pub fn with_param<P>(cx: &mut Context, value: L, param: P) -> Handle<Self>
+    where
+        P: 'static + NormalizedParam + Clone,
+    {
+        let default = param.default_plain();
+        Self::build_fader(
+            cx,
+            value,
+            Rc::new(param.clone()),
+            Rc::new(param),
+            Some(default),
+        )
+    }
+
+    fn build_fader(
+        cx: &mut Context,
+        value: L,
+        mapping: Rc<dyn ValueMapping>,
+        formatter: Rc<dyn ValueFormatter>,
+        default: Option<f32>,
+    ) -> Handle<Self> {
+        install_ticker(cx);
+        let position = mapping.normalize(value.get(cx));
+        let handle = Self {
+            value: value.clone(),
+            mapping,
+            formatter,
+            default,
+            glide: GLIDE_TIME,
+            position: Animator::new(position),
+            last_tick: Instant::now(),
+            cap_length: Cell::new(CAP_LENGTH),
+            sent: None,
+            offset: None,
+            moves: MoveCoalescer::new(),
+            presses: PressReader::new(),
+            hover: false,
+            styles: FaderStyles::new(cx),
+            on_changing: None,
+            on_context_menu: None,
+        }
+        .build(cx, |_| {})
+        .tab_stop();
+        handle.bind(value, |handle, value| {
+            let value = value.get(handle.cx);
+            let mut glides = false;
+            if let Some(view) = handle.cx.views.get_mut(&handle.entity) {
+                if let Some(fader) = view.downcast_mut::<Fader<L>>() {
+                    glides = fader.follow(value);
+                }
+            }
+            if glides {
+                handle.cx.request_tick(handle.entity);
+            }
+            handle.cx.style.needs_redraw = true;
+        })
+    }
+
+    /// Moves the cap to a new value of the lens: at once for the values the
+    /// fader changed to itself and while it's dragged, and gliding to the
+    /// others. Returns whether the cap started gliding.
+    fn follow(&mut self, value: f32) -> bool {
+        let target = self.mapping.normalize(value);
+        if self.sent.take() == Some(value) || self.offset.is_some() || self.glide <= 0f32 {
+            self.position.set(target);
+            false
+        } else if target != *self.position.target() {
+            self.position
+                .animate_to(target, self.glide, Easing::QuadInOut);
+            self.last_tick = Instant::now();
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Advances the glide of the cap by the time since the last tick, asking
+    /// for another tick until the cap arrives
+    fn advance_glide(&mut self, cx: &mut EventContext) {
+        let now = Instant::now();
+        let elapsed = now.duration_since(self.last_tick).as_secs_f32();
+        self.last_tick = now;
+        self.position.advance(elapsed);
+        if self.position.is_animating() {
+            cx.request_tick(cx.current());
+        }
+        cx.request_redraw();
+    }
+
+    /// Changes the value from the fader itself, moving the cap straight to it
+    fn change(&mut self, cx: &mut EventContext, value: f32) {
+        if let Some(callback) = &self.on_changing {
+            self.sent = Some(value);
+            self.position.set(self.mapping.normalize(value));
+            (callback)(cx, value);
+            cx.request_redraw();
+        }
+    }
+
+    /// Changes the value back to the default, if known
+    fn reset(&mut self, cx: &mut EventContext) {
+        if let Some(default) = self.default {
+            self.change(cx, default);
+            cx.single_edit(0, &[default]);
+        }
+    }
+
+    /// How far along the travel of the cap `cursor` is, from `0` with the
+    /// middle of the cap at the bottom or left end to `1` at the top or right
+    /// end
+    fn ratio_at(&self, cx: &EventContext, cursor: Vec2) -> f32 {
+        let rect = cx.cache.get_bounds(cx.current());
+        let ratio = rect.map_ui_point_unbounded(cursor, false);
+        let (along, length) = if rect.h > rect.w {
+            (1f32 - ratio.y, rect.h)
+        } else {
+            (ratio.x, rect.w)
+        };
+        let cap_length = self.cap_length.get();
+        let travel = length - cap_length;
+        if travel > 0f32 {
+            (along * length - cap_length / 2f32) / travel
+        } else {
+            0f32
+        }
+    }
+
+    /// Sets the value from the cursor while dragging
+    fn drag_to(&mut self, cx: &mut EventContext, cursor: Vec2) {
+        if let Some(offset) = self.offset {
+            // The mapping clamps to the ends of the range, which may be
+            // reversed
+            let value = self.mapping.denormalize(offset + self.ratio_at(cx, cursor));
+            self.change(cx, value);
+            cx.perform_edit(0, &[value]);
+            cx.show_value_tooltip(self.formatter.format(value));
+        }
+    }
+}
+
+impl<L> View for Fader<L>
+where
+    L: Lens<Target = f32>,
+{
+    fn element(&self) -> Option<&'static str> {
+        Some("fader")
+    }
+
+    fn event(&mut self, cx: &mut EventContext, event: &mut Event) {
+        // Ticks are handled even while disabled, so the cap still follows
+        // automation
+        event.map(|_: &FrameTick, meta| {
+            if self.position.is_animating() {
+                self.advance_glide(cx);
+            }
+            meta.consume();
+        });
+        if cx.is_disabled() {
+            return;
+        }
+        event.map(|ev: &WindowEvent, _| match *ev {
+            WindowEvent::MouseEnter => {
+                self.hover = true;
+                cx.show_tooltip(self.formatter.format(self.value.get(cx)));
+            }
+            WindowEvent::MouseLeave => {
+                self.hover = false;
+                if self.offset.is_none() {
+                    cx.hide_tooltip();
+                }
+            }
+            WindowEvent::MouseDown(_) => {
+                let press = match self.presses.read(cx, ev) {
+                    Some(press) => press,
+                    None => return,
+                };
+                let input = cx.input_map();
+                if input.triggers(InputAction::Reset, press) && self.default.is_some() {
+                    self.reset(cx);
+                } else if input.triggers(InputAction::OpenMenu, press)
+                    && (self.on_context_menu.is_some() || self.default.is_some())
+                {
+                    cx.open_context_menu(ContextMenuItem::parameter_items());
+                } else if press.button == MouseButton::Left {
+                    cx.capture();
+                    let cursor = Vec2::new(cx.mouse.cursorx, cx.mouse.cursory);
+                    let normalized = self.mapping.normalize(self.value.get(cx));
+                    self.offset = Some(normalized - self.ratio_at(cx, cursor));
+                    cx.begin_edit();
+                }
+            }
+            WindowEvent::MouseUp(MouseButton::Left) => {
+                if self.offset.is_some() {
+                    if let Some(cursor) = self.moves.take() {
+                        self.drag_to(cx, cursor);
+                    }
+                    self.offset = None;
+                    cx.release();
+                    if !self.hover {
+                        cx.hide_tooltip();
+                    }
+                    cx.end_edit();
+                }
+            }
+            WindowEvent::MouseMove(x, y) => {
+                if self.offset.is_some() {
+                    self.moves.push(cx, x, y);
+                }
+            }
+            // Once focused, the keys step the value like a drag would
+            WindowEvent::KeyDown(code, _) => {
+                if let Some(nudge) = KeyNudge::from_event(cx, code) {
+                    let normalized = self.mapping.normalize(self.value.get(cx));
+                    let value = self.mapping.denormalize(nudge.apply(normalized));
+                    self.change(cx, value);
+                    cx.single_edit(0, &[value]);
+                    cx.show_value_tooltip(self.formatter.format(value));
+                }
+            }
+            _ => (),
+        });
+        event.map(|_: &CoalescedMove, meta| {
+            if let Some(cursor) = self.moves.take() {
+                self.drag_to(cx, cursor);
+            }
+            meta.consume();
+        });
+        event.map(|action: &ContextMenuAction, _| {
+            if let ContextMenuAction::Reset = action {
+                self.reset(cx);
+            }
+            if let Some(callback) = &self.on_context_menu {
+                (callback)(cx, action.clone());
+            }
+        });
+    }
+
+    fn draw(&self, cx: &mut DrawContext, canvas: &mut Canvas) {
+        let bounds = cx.bounds();
+        let vertical = bounds.h > bounds.w;
+        let (length, thickness) = if vertical {
+            (bounds.h, bounds.w)
+        } else {
+            (bounds.w, bounds.h)
+        };
+        let track_width = self.styles.track_width(cx).unwrap_or(TRACK_WIDTH);
+        let cap_length = self.styles.cap_width(cx).unwrap_or(CAP_LENGTH);
+        self.cap_length.set(cap_length);
+        // The cap travels between the ends of the track, so it never hangs
+        // over the ends of the fader
+        let travel = (length - cap_length).max(0f32);
+        let cap_start = self.position.value().clamp(0f32, 1f32) * travel;
+
+        // A rectangle `start` pixels along the fader from the bottom or left,
+        // centered across it
+        let along = |start: f32, length: f32, width: f32| {
+            if vertical {
+                (
+                    bounds.left() + (bounds.w - width) / 2f32,
+                    bounds.bottom() - start - length,
+                    width,
+                    length,
+                )
+            } else {
+                (
+                    bounds.left() + start,
+                    bounds.top() + (bounds.h - width) / 2f32,
+                    length,
+                    width,
+                )
+            }
+        };
+
+        cx.draw_background(canvas);
+
+        // The track, filled up to the middle of the cap
+        let (x, y, w, h) = along(cap_length / 2f32, travel, track_width);
+        let mut track = Path::new();
+        track.rounded_rect(x, y, w, h, track_width / 2f32);
+        let track_color = self.styles.track_background_color(cx);
+        canvas.fill_path(&mut track, &Paint::color(track_color.into()));
+        let (x, y, w, h) = along(cap_length / 2f32, cap_start, track_width);
+        let mut fill = Path::new();
+        fill.rounded_rect(x, y, w, h, track_width / 2f32);
+        let fill_color = self.styles.track_border_color(cx);
+        canvas.fill_path(&mut fill, &Paint::color(fill_color.into()));
+
+        // The cap, with a line across its middle
+        let (x, y, w, h) = along(cap_start, cap_length, thickness);
+        let mut cap = Path::new();
+        cap.rounded_rect(x, y, w, h, CAP_RADIUS);
+        let cap_color = self.styles.cap_background_color(cx);
+        canvas.fill_path(&mut cap, &Paint::color(cap_color.into()));
+        let (x, y, w, h) = along(
+            cap_start + (cap_length - CAP_LINE_WIDTH) / 2f32,
+            CAP_LINE_WIDTH,
+            thickness,
+        );
+        let mut line = Path::new();
+        line.rect(x, y, w, h);
+        let line_color = self.styles.cap_border_color(cx);
+        canvas.fill_path(&mut line, &Paint::color(line_color.into()));
+
+        cx.draw_border(canvas);
+        cx.draw_outline(canvas);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{Recorder, TestContext};
+    use assert_approx_eq::assert_approx_eq;
+
+    #[derive(Lens)]
+    struct Level {
+        value: f32,
+    }
+
+    enum LevelEvent {
+        Set(f32),
+    }
+
+    impl Model for Level {
+        fn event(&mut self, _: &mut EventContext, event: &mut Event) {
+            event.map(|ev: &LevelEvent, _| match *ev {
+                LevelEvent::Set(value) => self.value = value,
+            });
+        }
+    }
+
+    #[test]
+    fn drags_vertical_faders() {
+        let mut test = TestContext::new(20f32, 200f32);
+        let changes = Recorder::new();
+        let record = changes.clone();
+        test.build(|cx| {
+            Level { value: 0f32 }.build(cx);
+            Fader::new(cx, Level::value, 0f32..=1f32)
+                .width(Pixels(20f32))
+                .height(Pixels(200f32))
+                .on_changing(move |cx, value| {
+                    record.push(value);
+                    cx.emit(LevelEvent::Set(value));
+                });
+        });
+        // Values rise from the bottom, and don't skip to where the drag
+        // started. The 24 pixel cap travels 176 pixels, so three quarters of
+        // the range is 132 pixels up.
+        test.drag((10f32, 190f32), (10f32, 58f32));
+        assert_approx_eq!(changes.last().unwrap(), 0.75f32, 1e-3f32);
+        // The next drag carries on from there
+        test.drag((10f32, 58f32), (10f32, 102f32));
+        assert_approx_eq!(changes.last().unwrap(), 0.5f32, 1e-3f32);
+        test.drag((10f32, 102f32), (10f32, -100f32));
+        assert_approx_eq!(changes.last().unwrap(), 1f32);
+    }
+}
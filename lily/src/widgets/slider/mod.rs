@@ -1,2 +1,4 @@
 mod continuous;
+mod fader;
 pub use continuous::{DragSlider, DragSliderHandle};
+pub use fader::{Fader, FaderHandle};
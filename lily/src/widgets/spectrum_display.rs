@@ -0,0 +1,191 @@
+use lily_derive::Handle;
+use vizia::cache::BoundingBox;
+use vizia::prelude::*;
+use vizia::vg;
+
+#[cfg(feature = "fft")]
+use crate::analysis::SpectrumAnalyzer;
+use crate::util::FrameExt;
+#[cfg(feature = "fft")]
+use std::ops::RangeInclusive;
+
+/// The width in pixels of the live trace when the `.spectrum` class sets no
+/// `border-width`
+const SPECTRUM_WIDTH: f32 = 1.5f32;
+/// The width in pixels of the snapshot traces when the `.snapshot` class sets
+/// no `border-width`
+const SNAPSHOT_WIDTH: f32 = 1f32;
+
+/// A spectrum frozen into a named snapshot, resampled onto the same points as
+/// the live spectrum of a [`SpectrumDisplay`]
+#[derive(Clone, Debug, PartialEq, Data)]
+pub struct SpectrumTrace {
+    /// The name the snapshot was frozen under
+    pub name: String,
+    /// The magnitudes normalized to `0..=1` at points spaced logarithmically
+    /// over the frequencies shown, lowest first
+    pub levels: Vec<f32>,
+}
+
+#[cfg(feature = "fft")]
+impl SpectrumTrace {
+    /// Gets the trace of the snapshot `analyzer` froze as `name`, resampled
+    /// like [`SpectrumAnalyzer::log_bins`] with the same arguments as the live
+    /// spectrum
+    pub fn from_snapshot(
+        analyzer: &SpectrumAnalyzer,
+        name: &str,
+        sample_rate: f32,
+        frequencies: RangeInclusive<f32>,
+        count: usize,
+    ) -> Option<Self> {
+        let levels = analyzer.snapshot_log_bins(name, sample_rate, frequencies, count)?;
+        Some(Self {
+            name: name.to_owned(),
+            levels,
+        })
+    }
+}
+
+/// Draws the live spectrum of a
+/// [`SpectrumAnalyzer`](crate::analysis::SpectrumAnalyzer) as a filled trace,
+/// with the snapshots frozen from it drawn behind as reference traces to
+/// compare against.
+///
+/// Only the snapshots named in the `shown` lens are drawn, in the order they
+/// are named, so snapshots are toggled by adding and removing their names.
+/// The live spectrum and every snapshot are spread evenly across the width,
+/// as [`SpectrumAnalyzer::log_bins`](crate::analysis::SpectrumAnalyzer::log_bins)
+/// and [`SpectrumTrace::from_snapshot`] resample them.
+///
+/// The live trace is filled in the `background-color` of the `.spectrum`
+/// class and outlined in its `border-color`. The snapshots are outlined in the
+/// `border-color` of the `.snapshot` class, each fainter than the one named
+/// after it. It takes no input, and lets the mouse through to the views under
+/// it.
+#[derive(Handle)]
+#[style_class("spectrum", "snapshot")]
+pub struct SpectrumDisplay<L, S, V>
+where
+    L: Lens<Target = Vec<f32>>,
+    S: Lens<Target = Vec<SpectrumTrace>>,
+    V: Lens<Target = Vec<String>>,
+{
+    /// A [`Lens`] of the live spectrum, normalized to `0..=1`
+    spectrum: L,
+    /// A [`Lens`] of the frozen snapshots
+    snapshots: S,
+    /// A [`Lens`] of the names of the snapshots to draw
+    shown: V,
+    styles: SpectrumDisplayStyles,
+}
+
+impl<L, S, V> SpectrumDisplay<L, S, V>
+where
+    L: Lens<Target = Vec<f32>>,
+    S: Lens<Target = Vec<SpectrumTrace>>,
+    V: Lens<Target = Vec<String>>,
+{
+    /// Create a new `SpectrumDisplay`
+    ///
+    /// # Parameters
+    ///
+    /// * `cx` - the current [`Context`]
+    /// * `spectrum` - a [`Lens`] of the live spectrum, normalized to `0..=1`
+    /// * `snapshots` - a [`Lens`] of the frozen snapshots, resampled like the
+    ///   live spectrum
+    /// * `shown` - a [`Lens`] of the names of the snapshots to draw
+    pub fn new(cx: &mut Context, spectrum: L, snapshots: S, shown: V) -> Handle<Self> {
+        Self {
+            spectrum: spectrum.clone(),
+            snapshots: snapshots.clone(),
+            shown: shown.clone(),
+            styles: SpectrumDisplayStyles::new(cx),
+        }
+        .build(cx, |_| {})
+        .hoverable(false)
+        .bind(spectrum, |handle, _| handle.cx.style.needs_redraw = true)
+        .bind(snapshots, |handle, _| handle.cx.style.needs_redraw = true)
+        .bind(shown, |handle, _| handle.cx.style.needs_redraw = true)
+    }
+}
+
+/// Traces `levels` across `rect`, from the bottom left
+fn trace(rect: BoundingBox, levels: &[f32]) -> vg::Path {
+    let mut path = vg::Path::new();
+    let last = levels.len().max(2) - 1;
+    for (i, level) in levels.iter().enumerate() {
+        let x = rect.left() + rect.w * i as f32 / last as f32;
+        let y = rect.bottom() - rect.h * level.clamp(0f32, 1f32);
+        if i == 0 {
+            path.move_to(x, y);
+        } else {
+            path.line_to(x, y);
+        }
+    }
+    path
+}
+
+impl<L, S, V> View for SpectrumDisplay<L, S, V>
+where
+    L: Lens<Target = Vec<f32>>,
+    S: Lens<Target = Vec<SpectrumTrace>>,
+    V: Lens<Target = Vec<String>>,
+{
+    fn element(&self) -> Option<&'static str> {
+        Some("spectrum-display")
+    }
+
+    fn draw(&self, cx: &mut DrawContext, canvas: &mut Canvas) {
+        let rect = cx.bounds();
+        cx.draw_background(canvas);
+
+        // The snapshots, behind the live trace
+        let shown = self.shown.view(cx.data().unwrap(), |shown| {
+            shown.cloned().unwrap_or_default()
+        });
+        let snapshots = self.snapshots.view(cx.data().unwrap(), |snapshots| {
+            snapshots.cloned().unwrap_or_default()
+        });
+        let snapshot_color: vg::Color = self.styles.snapshot_border_color(cx).into();
+        let snapshot_width = self
+            .styles
+            .snapshot_border_width(cx)
+            .unwrap_or(SNAPSHOT_WIDTH);
+        for (index, name) in shown.iter().enumerate() {
+            if let Some(snapshot) = snapshots.iter().find(|snapshot| &snapshot.name == name) {
+                let mut color = snapshot_color;
+                color.a *= (index + 1) as f32 / shown.len() as f32;
+                canvas.stroke_path(
+                    &mut trace(rect, &snapshot.levels),
+                    &vg::Paint::color(color).with_line_width(snapshot_width),
+                );
+            }
+        }
+
+        // The live spectrum, filled down to the bottom
+        let spectrum = self.spectrum.view(cx.data().unwrap(), |spectrum| {
+            spectrum.cloned().unwrap_or_default()
+        });
+        if !spectrum.is_empty() {
+            let mut line = trace(rect, &spectrum);
+            let mut fill = line.clone();
+            fill.line_to(rect.right(), rect.bottom());
+            fill.line_to(rect.left(), rect.bottom());
+            fill.close();
+            let fill_color = self.styles.spectrum_background_color(cx);
+            canvas.fill_path(&mut fill, &vg::Paint::color(fill_color.into()));
+            let line_color = self.styles.spectrum_border_color(cx);
+            let line_width = self
+                .styles
+                .spectrum_border_width(cx)
+                .unwrap_or(SPECTRUM_WIDTH);
+            canvas.stroke_path(
+                &mut line,
+                &vg::Paint::color(line_color.into()).with_line_width(line_width),
+            );
+        }
+
+        cx.draw_border(canvas);
+    }
+}
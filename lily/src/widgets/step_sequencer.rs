@@ -0,0 +1,325 @@
+use glam::Vec2;
+use lily_derive::Handle;
+use vizia::prelude::*;
+use vizia::vg;
+
+use crate::focus::FocusExt;
+use crate::gesture::GestureExt;
+use crate::tooltip::TooltipExt;
+use crate::util::{BeatGrid, CoalescedMove, CurvePoints, FrameExt, KeyNudge, MoveCoalescer};
+
+/// The gap in pixels between neighbouring steps
+const STEP_GAP: f32 = 2f32;
+/// How many seconds before each step the envelope of
+/// [`on_changing_envelope`](StepSequencerHandle::on_changing_envelope) jumps
+/// to it, short enough to sound like a step
+const ENVELOPE_GAP: f32 = 0.001f32;
+
+/// A lane of step values, such as the levels of a gated pattern, laid out on
+/// the steps of a [`BeatGrid`]. Each value is drawn as a bar rising from the
+/// bottom, and a value of `1` fills its step.
+///
+/// Pressing a step and dragging across its neighbours sets each step the
+/// cursor passes over, so a pattern can be drawn in one gesture. Once
+/// focused, the left and right keys pick a step and the other keys change it.
+///
+/// The beat grid can be shared with an [`Mseg`](crate::widgets::Mseg) through
+/// [`SnapGridExt::beat_grid`](crate::widgets::SnapGridExt::beat_grid), so the
+/// envelope snaps to the same steps. With
+/// [`on_changing_envelope`](StepSequencerHandle::on_changing_envelope), every
+/// edit also sends the steps as a stepped envelope on that grid, for the host
+/// to write into the MSEG's points and refine as a curve from there.
+///
+/// The bars are drawn in the `background-color` of the `.step` class, over
+/// the first step of each beat filled in the `background-color` of the
+/// `.beat` class.
+#[allow(clippy::type_complexity)]
+#[derive(Handle)]
+#[style_class("step", "beat")]
+pub struct StepSequencer<L, G>
+where
+    L: Lens<Target = Vec<f32>>,
+    G: Lens<Target = BeatGrid>,
+{
+    /// A [`Lens`] of the normalized step values, from the first step
+    steps: L,
+    /// A [`Lens`] of the grid the steps are laid out on
+    grid: G,
+    /// The step values as edited so far by the current gesture, ahead of the
+    /// lens, which only follows once the host handles the changes
+    edited: Vec<f32>,
+    /// The index of the last step set while dragging, used to fill in any
+    /// steps skipped over by fast cursor movement
+    last_index: Option<usize>,
+    /// The step edited from the keyboard, which is the last one pressed until
+    /// the left and right keys pick another
+    selected: usize,
+    /// The last cursor moved to while dragging, until it is handled
+    moves: MoveCoalescer,
+    styles: StepSequencerStyles,
+
+    #[callback(usize, f32)]
+    on_changing_step: Option<Box<dyn Fn(&mut EventContext, usize, f32)>>,
+    #[callback(CurvePoints)]
+    on_changing_envelope: Option<Box<dyn Fn(&mut EventContext, CurvePoints)>>,
+}
+
+impl<L, G> StepSequencer<L, G>
+where
+    L: Lens<Target = Vec<f32>>,
+    G: Lens<Target = BeatGrid>,
+{
+    /// Create a new `StepSequencer`
+    ///
+    /// # Parameters
+    ///
+    /// * `cx` - the current [`Context`]
+    /// * `steps` - a [`Lens`] of the normalized step values. One step is
+    ///   shown per value.
+    /// * `grid` - a [`Lens`] of the [`BeatGrid`] the steps are laid out on,
+    ///   which may be shared with an MSEG
+    pub fn new(cx: &mut Context, steps: L, grid: G) -> Handle<Self> {
+        Self {
+            steps: steps.clone(),
+            grid: grid.clone(),
+            edited: Vec::new(),
+            last_index: None,
+            selected: 0,
+            moves: MoveCoalescer::new(),
+            styles: StepSequencerStyles::new(cx),
+            on_changing_step: None,
+            on_changing_envelope: None,
+        }
+        .build(cx, |_| {})
+        .tab_stop()
+        .bind(steps, |handle, _| handle.cx.style.needs_redraw = true)
+        .bind(grid.map(|grid| grid.steps_per_beat), |handle, _| {
+            handle.cx.style.needs_redraw = true
+        })
+    }
+
+    /// Gets the step index and normalized value under the cursor
+    fn value_at(&self, cx: &EventContext, x: f32, y: f32) -> Option<(usize, f32)> {
+        let len = self.edited.len();
+        if len == 0 {
+            return None;
+        }
+        let rect = cx.cache.get_bounds(cx.current());
+        let index = (((x - rect.left()) / rect.w) * len as f32).floor();
+        let index = (index.max(0f32) as usize).min(len - 1);
+        let value = ((rect.bottom() - y) / rect.h).clamp(0f32, 1f32);
+        Some((index, value))
+    }
+
+    fn set_step(&mut self, cx: &mut EventContext, index: usize, value: f32) {
+        if let Some(step) = self.edited.get_mut(index) {
+            *step = value;
+        }
+        if let Some(callback) = &self.on_changing_step {
+            (callback)(cx, index, value);
+        }
+        cx.perform_edit(index, &[value]);
+        cx.show_value_tooltip(format!("{}: {:.2}", index + 1, value));
+    }
+
+    /// Sends the edited steps as a stepped envelope on the grid, if the host
+    /// regenerates one from them
+    fn send_envelope(&self, cx: &mut EventContext) {
+        if let Some(callback) = &self.on_changing_envelope {
+            let envelope = self
+                .grid
+                .get(cx)
+                .stepped_envelope(&self.edited, ENVELOPE_GAP);
+            (callback)(cx, envelope);
+        }
+    }
+
+    /// Sets the step under the cursor while dragging
+    fn drag_to(&mut self, cx: &mut EventContext, cursor: Vec2) {
+        if let Some(last_index) = self.last_index {
+            if let Some((index, value)) = self.value_at(cx, cursor.x, cursor.y) {
+                // Set every step between the last one and this one so that
+                // quick sweeps don't leave gaps
+                let (from, to) = if last_index < index {
+                    (last_index + 1, index)
+                } else {
+                    (index, last_index.saturating_sub(1).max(index))
+                };
+                for i in from..=to {
+                    self.set_step(cx, i, value);
+                }
+                self.last_index = Some(index);
+                self.send_envelope(cx);
+            }
+        }
+    }
+}
+
+impl<L, G> View for StepSequencer<L, G>
+where
+    L: Lens<Target = Vec<f32>>,
+    G: Lens<Target = BeatGrid>,
+{
+    fn element(&self) -> Option<&'static str> {
+        Some("step-sequencer")
+    }
+
+    fn event(&mut self, cx: &mut EventContext, event: &mut Event) {
+        if cx.is_disabled() {
+            return;
+        }
+        event.map(|ev: &WindowEvent, _| match *ev {
+            WindowEvent::MouseDown(MouseButton::Left) => {
+                cx.capture_active();
+                self.edited = self.steps.get(cx);
+                if let Some((index, value)) = self.value_at(cx, cx.mouse.cursorx, cx.mouse.cursory)
+                {
+                    self.last_index = Some(index);
+                    self.selected = index;
+                    cx.begin_edit();
+                    self.set_step(cx, index, value);
+                    self.send_envelope(cx);
+                }
+            }
+            WindowEvent::MouseMove(x, y) => {
+                if self.last_index.is_some() {
+                    self.moves.push(cx, x, y);
+                }
+            }
+            WindowEvent::MouseUp(MouseButton::Left) => {
+                // End the drag where the cursor was released
+                if let Some(cursor) = self.moves.take() {
+                    self.drag_to(cx, cursor);
+                }
+                cx.release_active();
+                if self.last_index.take().is_some() {
+                    cx.end_edit();
+                }
+                cx.hide_tooltip();
+            }
+            WindowEvent::KeyDown(code, _) => {
+                self.edited = self.steps.get(cx);
+                let len = self.edited.len();
+                if len == 0 {
+                    return;
+                }
+                let index = self.selected.min(len - 1);
+                let value = match KeyNudge::from_event(cx, code) {
+                    // left and right pick a step and show its value
+                    Some(KeyNudge::Step(step)) if step.x != 0f32 => {
+                        let index = if step.x < 0f32 {
+                            index.saturating_sub(1)
+                        } else {
+                            (index + 1).min(len - 1)
+                        };
+                        self.selected = index;
+                        let value = self.edited[index];
+                        cx.show_value_tooltip(format!("{}: {:.2}", index + 1, value));
+                        return;
+                    }
+                    Some(nudge) => nudge.apply(self.edited[index]),
+                    None => return,
+                };
+                cx.begin_edit();
+                self.set_step(cx, index, value);
+                cx.end_edit();
+                self.send_envelope(cx);
+            }
+            _ => (),
+        });
+        event.map(|_: &CoalescedMove, meta| {
+            if let Some(cursor) = self.moves.take() {
+                self.drag_to(cx, cursor);
+            }
+            meta.consume();
+        });
+    }
+
+    fn draw(&self, cx: &mut DrawContext, canvas: &mut Canvas) {
+        let rect = cx.bounds();
+        cx.draw_background(canvas);
+
+        let steps = self.steps.view(cx.data().unwrap(), |steps| {
+            steps.cloned().unwrap_or_default()
+        });
+        let steps_per_beat = self.grid.view(cx.data().unwrap(), |grid| {
+            grid.map_or(1, |grid| grid.steps_per_beat.max(1)) as usize
+        });
+        if !steps.is_empty() {
+            let width = rect.w / steps.len() as f32;
+            let mut beats = vg::Path::new();
+            let mut bars = vg::Path::new();
+            for (index, value) in steps.iter().enumerate() {
+                let x = rect.left() + width * index as f32;
+                if index % steps_per_beat == 0 {
+                    beats.rect(x, rect.top(), width, rect.h);
+                }
+                let height = rect.h * value.clamp(0f32, 1f32);
+                bars.rect(
+                    x + STEP_GAP / 2f32,
+                    rect.bottom() - height,
+                    (width - STEP_GAP).max(0f32),
+                    height,
+                );
+            }
+            let beat_color = self.styles.beat_background_color(cx);
+            canvas.fill_path(&mut beats, &vg::Paint::color(beat_color.into()));
+            let step_color = self.styles.step_background_color(cx);
+            canvas.fill_path(&mut bars, &vg::Paint::color(step_color.into()));
+        }
+
+        cx.draw_border(canvas);
+        cx.draw_outline(canvas);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{Recorder, TestContext};
+    use assert_approx_eq::assert_approx_eq;
+
+    #[derive(Lens)]
+    struct Pattern {
+        steps: Vec<f32>,
+        grid: BeatGrid,
+    }
+
+    impl Model for Pattern {}
+
+    #[test]
+    fn regenerates_the_envelope_from_steps() {
+        let mut test = TestContext::new(400f32, 100f32);
+        let changes = Recorder::new();
+        let record_changes = changes.clone();
+        let envelopes = Recorder::new();
+        let record_envelopes = envelopes.clone();
+        test.build(|cx| {
+            Pattern {
+                steps: vec![0f32; 4],
+                grid: BeatGrid::new(60f32, 4),
+            }
+            .build(cx);
+            StepSequencer::new(cx, Pattern::steps, Pattern::grid)
+                .width(Pixels(400f32))
+                .height(Pixels(100f32))
+                .on_changing_step(move |_, index, value| record_changes.push((index, value)))
+                .on_changing_envelope(move |_, points| record_envelopes.push(points));
+        });
+        // Sweeping across the first three steps sets each of them
+        test.drag((50f32, 25f32), (250f32, 25f32));
+        let mut indices: Vec<usize> = changes.values().iter().map(|(index, _)| *index).collect();
+        indices.dedup();
+        assert_eq!(indices, vec![0, 1, 2]);
+        // The envelope holds each step for a sixteenth note, a quarter of a
+        // second at 60 BPM, and the untouched step stays at 0
+        let envelope = envelopes.last().unwrap();
+        let values: Vec<f32> = envelope.iter().map(|point| point.y).collect();
+        assert_eq!(
+            values,
+            vec![0.75f32, 0.75f32, 0.75f32, 0.75f32, 0.75f32, 0.75f32, 0f32, 0f32]
+        );
+        assert_approx_eq!(envelope.iter().nth(2).unwrap().x, 0.25f32);
+        assert_approx_eq!(envelope.iter().last().unwrap().x, 1f32);
+    }
+}
@@ -0,0 +1,495 @@
+use glam::Vec2;
+use lily_derive::Handle;
+use vizia::cache::BoundingBox;
+use vizia::prelude::*;
+use vizia::vg;
+
+use crate::focus::FocusExt;
+use crate::gesture::GestureExt;
+use crate::input::{InputAction, InputMapExt, MouseBinding, PressReader};
+use crate::tooltip::TooltipExt;
+use crate::units::{format_db, format_seconds};
+use crate::util::{
+    Affordance, CoalescedMove, CursorAffordance, FrameExt, KeyNudge, MoveCoalescer, PathBatch,
+    PseudoClassExt, ScaleExt,
+};
+
+/// The default distance in logical pixels before a tap or pan handle is
+/// considered hovered
+const HOVER_RADIUS: f32 = 8f32;
+/// The height in pixels of the strip below the ruler holding the pan handles
+const PAN_STRIP: f32 = 16f32;
+/// How far in pixels a pan handle moves from its tap at full left or right
+const PAN_WIDTH: f32 = 24f32;
+
+/// The time in seconds between the ticks of a ruler `max` seconds long and
+/// `width` pixels wide: the snapping grid when its lines are far enough apart,
+/// and a tenth of the ruler otherwise. Returns `None` for a ruler with no
+/// length or an endless one, which has no ticks.
+fn tick_step(max: f32, snap: Option<f32>, width: f32) -> Option<f32> {
+    let step = snap
+        .filter(|grid| *grid > 0f32 && max / grid <= width / 4f32)
+        .unwrap_or(max / 10f32);
+    // also false for NaN, so drawing the ticks always ends
+    (max.is_finite() && max > 0f32 && step > 0f32).then_some(step)
+}
+
+/// A single delay tap
+#[derive(Clone, Copy, Debug, PartialEq, Data)]
+pub struct DelayTap {
+    /// The delay time in seconds
+    pub time: f32,
+    /// The normalized level, `0..=1`
+    pub level: f32,
+    /// The pan position, `-1..=1`
+    pub pan: f32,
+}
+
+impl DelayTap {
+    pub fn new(time: f32, level: f32) -> Self {
+        Self {
+            time,
+            level,
+            pan: 0f32,
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum TapHandle {
+    Level(usize),
+    Pan(usize),
+}
+
+/// Edits delay taps as markers on a time ruler, where x is the delay time and
+/// y is the level. Each tap also has a pan handle below the ruler.
+///
+/// Double-clicking empty space inserts a tap and right-clicking a tap removes
+/// it, unless the installed [`InputMap`](crate::input::InputMap) binds other
+/// presses.
+#[allow(clippy::type_complexity)]
+#[derive(Handle)]
+#[style_class("tap", "ruler")]
+pub struct TapEditor<T, S>
+where
+    T: Lens<Target = Vec<DelayTap>>,
+    S: Lens<Target = Option<f32>>,
+{
+    taps: T,
+    /// A [`Lens`] of the snapping grid in seconds, e.g. the length of a
+    /// sixteenth note when tempo-synced. `None` disables snapping.
+    snap: S,
+    /// The longest delay time shown on the ruler, in seconds
+    max: f32,
+    /// The handle currently hovered or being dragged
+    active_handle: Option<TapHandle>,
+    /// The handle edited from the keyboard, the last one pressed
+    selected_handle: Option<TapHandle>,
+    is_dragging: bool,
+    /// The last cursor moved to while dragging, until it is handled
+    moves: MoveCoalescer,
+    /// The presses of the mouse buttons
+    presses: PressReader,
+    /// The cursor over handles and while dragging them
+    cursor: CursorAffordance,
+    styles: TapEditorStyles,
+    /// The distance in logical pixels before a tap or pan handle is considered
+    /// hovered
+    #[property(res)]
+    hover_radius: f32,
+
+    #[callback(usize, f32, f32)]
+    on_changing_tap: Option<Box<dyn Fn(&mut EventContext, usize, f32, f32)>>,
+
+    #[callback(usize, f32)]
+    on_changing_pan: Option<Box<dyn Fn(&mut EventContext, usize, f32)>>,
+
+    #[callback(f32, f32)]
+    on_insert_tap: Option<Box<dyn Fn(&mut EventContext, f32, f32)>>,
+
+    #[callback(usize)]
+    on_remove_tap: Option<Box<dyn Fn(&mut EventContext, usize)>>,
+}
+
+impl<T, S> TapEditor<T, S>
+where
+    T: Lens<Target = Vec<DelayTap>>,
+    S: Lens<Target = Option<f32>>,
+{
+    /// Create a new `TapEditor`
+    ///
+    /// # Parameters
+    ///
+    /// * `cx` - the current [`Context`]
+    /// * `taps` - a [`Lens`] of the [`DelayTap`]s to edit
+    /// * `snap` - a [`Lens`] of the snapping grid in seconds, or `None` to
+    ///   move taps freely
+    /// * `max` - the longest delay time shown on the ruler, in seconds, which
+    ///   must be positive and finite
+    pub fn new(cx: &mut Context, taps: T, snap: S, max: f32) -> Handle<Self> {
+        debug_assert!(
+            max.is_finite() && max > 0f32,
+            "the ruler must be a finite length longer than 0 seconds"
+        );
+        Self {
+            taps,
+            snap,
+            max,
+            active_handle: None,
+            selected_handle: None,
+            is_dragging: false,
+            moves: MoveCoalescer::new(),
+            presses: PressReader::new(),
+            cursor: CursorAffordance::new(),
+            styles: TapEditorStyles::new(cx),
+            hover_radius: HOVER_RADIUS,
+            on_changing_tap: None,
+            on_changing_pan: None,
+            on_insert_tap: None,
+            on_remove_tap: None,
+        }
+        .build(cx, |_| {})
+        .tab_stop()
+    }
+
+    /// The area of the ruler, excluding the pan strip
+    fn ruler_bounds(bounds: BoundingBox) -> BoundingBox {
+        BoundingBox {
+            h: (bounds.h - PAN_STRIP).max(0f32),
+            ..bounds
+        }
+    }
+
+    fn tap_to_ui(&self, bounds: BoundingBox, tap: &DelayTap) -> (f32, f32) {
+        let ruler = Self::ruler_bounds(bounds);
+        (
+            ruler.left() + (tap.time / self.max) * ruler.w,
+            ruler.bottom() - tap.level * ruler.h,
+        )
+    }
+
+    /// Converts a cursor position to a delay time and level, snapping the time
+    /// to the grid if there is one
+    fn ui_to_tap(&self, bounds: BoundingBox, x: f32, y: f32, snap: Option<f32>) -> (f32, f32) {
+        let ruler = Self::ruler_bounds(bounds);
+        let mut time = ((x - ruler.left()) / ruler.w) * self.max;
+        if let Some(grid) = snap.filter(|grid| *grid > 0f32) {
+            time = (time / grid).round() * grid;
+        }
+        let level = (ruler.bottom() - y) / ruler.h;
+        (time.clamp(0f32, self.max), level.clamp(0f32, 1f32))
+    }
+
+    fn pan_handle_pos(&self, bounds: BoundingBox, tap: &DelayTap) -> (f32, f32) {
+        let (x, _) = self.tap_to_ui(bounds, tap);
+        (x + tap.pan * PAN_WIDTH, bounds.bottom() - PAN_STRIP / 2f32)
+    }
+
+    fn set_tap(&self, cx: &mut EventContext, index: usize, time: f32, level: f32, pan: f32) {
+        if let Some(callback) = &self.on_changing_tap {
+            (callback)(cx, index, time, level);
+        }
+        cx.perform_edit(index, &[time, level, pan]);
+        cx.show_value_tooltip(format!("{}, {}", format_seconds(time), format_db(level)));
+    }
+
+    fn set_pan(&self, cx: &mut EventContext, index: usize, tap: &DelayTap, pan: f32) {
+        if let Some(callback) = &self.on_changing_pan {
+            (callback)(cx, index, pan);
+        }
+        cx.perform_edit(index, &[tap.time, tap.level, pan]);
+        cx.show_value_tooltip(format!("pan {pan:.2}"));
+    }
+
+    /// Performs what a press of a mouse button is bound to: dragging the
+    /// hovered handle or removing its tap, or inserting a tap elsewhere
+    fn press(&mut self, cx: &mut EventContext, bounds: BoundingBox, press: MouseBinding) {
+        let input = cx.input_map();
+        match self.active_handle {
+            Some(TapHandle::Level(index) | TapHandle::Pan(index))
+                if input.triggers(InputAction::DeletePoint, press) =>
+            {
+                self.active_handle = None;
+                cx.release_active();
+                if self.is_dragging {
+                    self.is_dragging = false;
+                    cx.end_edit();
+                }
+                if let Some(callback) = &self.on_remove_tap {
+                    (callback)(cx, index);
+                }
+            }
+            Some(_) => {
+                if press.button == MouseButton::Left {
+                    self.selected_handle = self.active_handle;
+                    cx.capture_active();
+                    self.is_dragging = true;
+                    cx.begin_edit();
+                }
+            }
+            None => {
+                if input.triggers(InputAction::InsertPoint, press) {
+                    let (time, level) = self.ui_to_tap(
+                        bounds,
+                        cx.mouse.cursorx,
+                        cx.mouse.cursory,
+                        self.snap.get(cx),
+                    );
+                    if let Some(callback) = &self.on_insert_tap {
+                        (callback)(cx, time, level);
+                    }
+                }
+            }
+        }
+    }
+
+    /// Drags the active handle to the cursor
+    fn drag_to(&self, cx: &mut EventContext, bounds: BoundingBox, cursor: Vec2) {
+        let taps = self.taps.get(cx);
+        match self.active_handle {
+            Some(TapHandle::Level(index)) => {
+                let (time, level) = self.ui_to_tap(bounds, cursor.x, cursor.y, self.snap.get(cx));
+                let pan = taps.get(index).map_or(0f32, |tap| tap.pan);
+                self.set_tap(cx, index, time, level, pan);
+            }
+            Some(TapHandle::Pan(index)) => {
+                if let Some(tap) = taps.get(index) {
+                    let (tap_x, _) = self.tap_to_ui(bounds, tap);
+                    let pan = ((cursor.x - tap_x) / PAN_WIDTH).clamp(-1f32, 1f32);
+                    self.set_pan(cx, index, tap, pan);
+                }
+            }
+            None => (),
+        }
+    }
+
+    /// Moves a handle from the keyboard. Taps move in steps of the ruler's
+    /// size, or to the next grid line when snapping.
+    fn nudge_handle(
+        &self,
+        cx: &mut EventContext,
+        bounds: BoundingBox,
+        handle: TapHandle,
+        nudge: KeyNudge,
+    ) {
+        let taps = self.taps.get(cx);
+        match handle {
+            TapHandle::Level(index) => {
+                if let Some(tap) = taps.get(index) {
+                    let ruler = Self::ruler_bounds(bounds);
+                    let (x, y) = self.tap_to_ui(bounds, tap);
+                    let ui = nudge.apply_point(
+                        Vec2::new(x, y),
+                        Vec2::new(ruler.left(), ruler.bottom()),
+                        Vec2::new(ruler.right(), ruler.top()),
+                    );
+                    let (mut time, level) = self.ui_to_tap(bounds, ui.x, ui.y, None);
+                    let grid = self.snap.get(cx).filter(|grid| *grid > 0f32);
+                    if let (Some(grid), KeyNudge::Step(step)) = (grid, nudge) {
+                        if step.x != 0f32 {
+                            let line = (tap.time / grid).round() + step.x.signum();
+                            time = (line * grid).clamp(0f32, self.max);
+                        }
+                    }
+                    self.set_tap(cx, index, time, level, tap.pan);
+                }
+            }
+            TapHandle::Pan(index) => {
+                if let Some(tap) = taps.get(index) {
+                    let pan = nudge.apply_point(
+                        Vec2::new(tap.pan, 0f32),
+                        Vec2::new(-1f32, 0f32),
+                        Vec2::new(1f32, 0f32),
+                    );
+                    self.set_pan(cx, index, tap, pan.x);
+                }
+            }
+        }
+    }
+}
+
+impl<T, S> View for TapEditor<T, S>
+where
+    T: Lens<Target = Vec<DelayTap>>,
+    S: Lens<Target = Option<f32>>,
+{
+    fn element(&self) -> Option<&'static str> {
+        Some("tap-editor")
+    }
+
+    fn event(&mut self, cx: &mut EventContext, event: &mut Event) {
+        if cx.is_disabled() {
+            return;
+        }
+        let hover_radius = cx.to_physical(self.hover_radius);
+        let bounds = cx.cache.get_bounds(cx.current());
+        event.map(|ev: &WindowEvent, _| match *ev {
+            WindowEvent::MouseMove(x, y) => {
+                if self.is_dragging {
+                    self.moves.push(cx, x, y);
+                } else {
+                    // Find the closest tap head or pan handle to the cursor
+                    let taps = self.taps.get(cx);
+                    let cursor = (x, y);
+                    let distance =
+                        |(hx, hy): (f32, f32)| (hx - cursor.0).powi(2) + (hy - cursor.1).powi(2);
+                    self.active_handle = taps
+                        .iter()
+                        .enumerate()
+                        .flat_map(|(i, tap)| {
+                            [
+                                (TapHandle::Level(i), distance(self.tap_to_ui(bounds, tap))),
+                                (
+                                    TapHandle::Pan(i),
+                                    distance(self.pan_handle_pos(bounds, tap)),
+                                ),
+                            ]
+                        })
+                        .filter(|(_, d)| *d <= hover_radius.powi(2))
+                        .min_by(|a, b| a.1.total_cmp(&b.1))
+                        .map(|(handle, _)| handle);
+                }
+            }
+            WindowEvent::MouseDown(_) => {
+                if let Some(press) = self.presses.read(cx, ev) {
+                    self.press(cx, bounds, press);
+                }
+            }
+            WindowEvent::MouseUp(button) => {
+                if button == MouseButton::Left && self.is_dragging {
+                    // End the drag where the cursor was released
+                    if let Some(cursor) = self.moves.take() {
+                        self.drag_to(cx, bounds, cursor);
+                    }
+                    cx.release_active();
+                    self.is_dragging = false;
+                    cx.end_edit();
+                    cx.hide_tooltip();
+                }
+            }
+            // The keys move the last pressed handle, and delete removes its
+            // tap
+            WindowEvent::KeyDown(code, _) => {
+                let len = self.taps.get(cx).len();
+                let selected = self.selected_handle.filter(|handle| match handle {
+                    TapHandle::Level(index) | TapHandle::Pan(index) => *index < len,
+                });
+                if let (Some(handle), false) = (selected, self.is_dragging) {
+                    if let Code::Delete | Code::Backspace = code {
+                        self.selected_handle = None;
+                        if let TapHandle::Level(index) | TapHandle::Pan(index) = handle {
+                            if let Some(callback) = &self.on_remove_tap {
+                                (callback)(cx, index);
+                            }
+                        }
+                    } else if let Some(nudge) = KeyNudge::from_event(cx, code) {
+                        cx.begin_edit();
+                        self.nudge_handle(cx, bounds, handle, nudge);
+                        cx.end_edit();
+                    }
+                }
+            }
+            _ => (),
+        });
+        event.map(|_: &CoalescedMove, meta| {
+            if let Some(cursor) = self.moves.take() {
+                if self.is_dragging {
+                    self.drag_to(cx, bounds, cursor);
+                }
+            }
+            meta.consume();
+        });
+        let (hovered, dragging) = (self.active_handle.is_some(), self.is_dragging);
+        self.styles
+            .set_tap_pseudo_class(cx, PseudoClass::HOVER, hovered && !dragging);
+        self.styles
+            .set_tap_pseudo_class(cx, PseudoClass::ACTIVE, dragging);
+        self.cursor
+            .update(cx, event, Affordance::of_point(hovered, dragging));
+    }
+
+    fn draw(&self, cx: &mut DrawContext, canvas: &mut Canvas) {
+        let bounds = cx.bounds();
+        let ruler = Self::ruler_bounds(bounds);
+
+        cx.draw_background(canvas);
+
+        let ruler_color = self.styles.ruler_border_color(cx);
+        let tap_color = self.styles.tap_background_color(cx);
+        let active_color = self.styles.tap_border_color(cx);
+
+        // Ruler ticks, on the snapping grid when there is one and every tenth
+        // of the ruler otherwise
+        let snap = self
+            .snap
+            .view(cx.data().unwrap(), |snap| snap.copied().flatten());
+        let mut ticks = vg::Path::new();
+        if let Some(step) = tick_step(self.max, snap, ruler.w) {
+            let mut time = 0f32;
+            while time <= self.max {
+                let x = ruler.left() + (time / self.max) * ruler.w;
+                ticks.move_to(x, ruler.top());
+                ticks.line_to(x, ruler.bottom());
+                time += step;
+            }
+        }
+        ticks.move_to(ruler.left(), ruler.bottom());
+        ticks.line_to(ruler.right(), ruler.bottom());
+        canvas.stroke_path(&mut ticks, &vg::Paint::color(ruler_color.into()));
+
+        // Markers of the same color are drawn together
+        let (mut stems, mut pan_lines, mut heads) =
+            (PathBatch::new(), PathBatch::new(), PathBatch::new());
+        self.taps.view(cx.data().unwrap(), |taps| {
+            for (i, tap) in taps.unwrap().iter().enumerate() {
+                let (x, y) = self.tap_to_ui(bounds, tap);
+                let (pan_x, pan_y) = self.pan_handle_pos(bounds, tap);
+                let level_color = match self.active_handle {
+                    Some(TapHandle::Level(index)) if index == i => active_color,
+                    _ => tap_color,
+                };
+                let pan_color = match self.active_handle {
+                    Some(TapHandle::Pan(index)) if index == i => active_color,
+                    _ => tap_color,
+                };
+
+                // Marker stem and head
+                let stem = stems.path(level_color);
+                stem.move_to(x, ruler.bottom());
+                stem.line_to(x, y);
+                heads.path(level_color).circle(x, y, 4f32);
+
+                // Pan handle
+                let pan_line = pan_lines.path(pan_color);
+                pan_line.move_to(x, pan_y);
+                pan_line.line_to(pan_x, pan_y);
+                heads.path(pan_color).circle(pan_x, pan_y, 3f32);
+            }
+        });
+        stems.stroke(canvas, 2f32);
+        pan_lines.stroke(canvas, 1f32);
+        heads.fill(canvas);
+        cx.draw_border(canvas);
+        cx.draw_outline(canvas);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ticks_the_ruler() {
+        // A 1 second ruler 400 pixels wide fits grid lines every 20 ms, but
+        // not every millisecond
+        assert_eq!(tick_step(1f32, Some(0.02f32), 400f32), Some(0.02f32));
+        assert_eq!(tick_step(1f32, Some(0.001f32), 400f32), Some(0.1f32));
+        assert_eq!(tick_step(1f32, None, 400f32), Some(0.1f32));
+        // A ruler with no length has no ticks, rather than endless ones
+        assert_eq!(tick_step(0f32, None, 400f32), None);
+        assert_eq!(tick_step(0f32, Some(0.25f32), 400f32), None);
+        assert_eq!(tick_step(f32::NAN, None, 400f32), None);
+        assert_eq!(tick_step(f32::INFINITY, None, 400f32), None);
+        assert_eq!(tick_step(f32::INFINITY, Some(0.25f32), 400f32), None);
+    }
+}
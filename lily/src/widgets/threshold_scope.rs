@@ -0,0 +1,246 @@
+use lily_derive::Handle;
+use vizia::prelude::*;
+use vizia::vg;
+
+use crate::focus::FocusExt;
+use crate::gesture::GestureExt;
+use crate::tooltip::TooltipExt;
+use crate::util::{FrameExt, KeyNudge, PseudoClassExt, ScaleExt};
+
+/// The default distance in logical pixels before the threshold line is
+/// considered hovered
+const HOVER_RADIUS: f32 = 6f32;
+
+/// Scrolls a history of input levels and overlays a draggable threshold line,
+/// for gates, triggers and envelope followers.
+///
+/// [`feed::threshold_scope`](crate::feed::threshold_scope) builds one that
+/// drains its levels from the audio thread itself.
+#[allow(clippy::type_complexity)]
+#[derive(Handle)]
+#[style_class("level", "threshold", "hysteresis")]
+pub struct ThresholdScope<L, T>
+where
+    L: Lens<Target = Vec<f32>>,
+    T: Lens<Target = f32>,
+{
+    /// A [`Lens`] of the most recent levels, oldest first, normalized to
+    /// `0..=1`. The newest level is drawn at the right edge, so pushing new
+    /// values (and dropping old ones) scrolls the history to the left.
+    levels: L,
+    /// A [`Lens`] of the normalized threshold, `0..=1`
+    threshold: T,
+    /// The height of the hysteresis band drawn below the threshold, if any
+    hysteresis: Option<f32>,
+    /// Whether the cursor is over the threshold line
+    #[state]
+    hovering: bool,
+    /// Whether the threshold line is being dragged
+    #[state]
+    dragging: bool,
+    /// The offset of the cursor to the threshold line, set when clicking. This
+    /// ensures the threshold doesn't jump to the cursor when first dragging
+    offset: f32,
+    styles: ThresholdScopeStyles,
+    /// The distance in logical pixels before the threshold line is considered
+    /// hovered
+    #[property(res)]
+    hover_radius: f32,
+
+    #[callback(f32)]
+    on_changing_threshold: Option<Box<dyn Fn(&mut EventContext, f32)>>,
+
+    #[callback(ThresholdScopeState)]
+    on_state_change: Option<Box<dyn Fn(&mut EventContext, ThresholdScopeState)>>,
+}
+
+impl<L, T> ThresholdScope<L, T>
+where
+    L: Lens<Target = Vec<f32>>,
+    T: Lens<Target = f32>,
+{
+    /// Create a new `ThresholdScope`
+    ///
+    /// # Parameters
+    ///
+    /// * `cx` - the current [`Context`]
+    /// * `levels` - a [`Lens`] of the recent levels, oldest first, normalized
+    ///   to `0..=1`
+    /// * `threshold` - a [`Lens`] of the normalized threshold
+    /// * `hysteresis` - the normalized height of the hysteresis band below the
+    ///   threshold, or `None` to hide it
+    pub fn new(cx: &mut Context, levels: L, threshold: T, hysteresis: Option<f32>) -> Handle<Self> {
+        Self::build_scope(cx, levels, threshold, hysteresis, |_| {})
+    }
+
+    /// Builds the scope, running `content` inside it first, such as to add
+    /// the model holding its levels
+    pub(crate) fn build_scope(
+        cx: &mut Context,
+        levels: L,
+        threshold: T,
+        hysteresis: Option<f32>,
+        content: impl FnOnce(&mut Context),
+    ) -> Handle<Self> {
+        Self {
+            levels,
+            threshold,
+            hysteresis,
+            hovering: false,
+            dragging: false,
+            offset: 0f32,
+            styles: ThresholdScopeStyles::new(cx),
+            hover_radius: HOVER_RADIUS,
+            on_changing_threshold: None,
+            on_state_change: None,
+        }
+        .build(cx, content)
+        .tab_stop()
+    }
+}
+
+impl<L, T> View for ThresholdScope<L, T>
+where
+    L: Lens<Target = Vec<f32>>,
+    T: Lens<Target = f32>,
+{
+    fn element(&self) -> Option<&'static str> {
+        Some("threshold-scope")
+    }
+
+    fn event(&mut self, cx: &mut EventContext, event: &mut Event) {
+        if cx.is_disabled() {
+            return;
+        }
+        let hover_radius = cx.to_physical(self.hover_radius);
+        let state = self.state();
+        event.map(|ev: &WindowEvent, _| match *ev {
+            WindowEvent::MouseMove(_, y) => {
+                let rect = cx.cache.get_bounds(cx.current());
+                if self.dragging {
+                    let value = (rect.bottom() - y) / rect.h + self.offset;
+                    let value = value.clamp(0f32, 1f32);
+                    if let Some(callback) = &self.on_changing_threshold {
+                        (callback)(cx, value);
+                    }
+                    cx.perform_edit(0, &[value]);
+                    cx.show_value_tooltip(format!("{value:.2}"));
+                } else {
+                    let threshold = self.threshold.get(cx);
+                    let line_y = rect.bottom() - threshold * rect.h;
+                    let hovering = (line_y - y).abs() <= hover_radius;
+                    if hovering && !self.hovering {
+                        cx.show_tooltip(format!("{threshold:.2}"));
+                    } else if !hovering && self.hovering {
+                        cx.hide_tooltip();
+                    }
+                    self.hovering = hovering;
+                }
+            }
+            WindowEvent::MouseDown(button) => {
+                if button == MouseButton::Left && self.hovering {
+                    cx.capture_active();
+                    self.dragging = true;
+                    let rect = cx.cache.get_bounds(cx.current());
+                    let cursor_value = (rect.bottom() - cx.mouse.cursory) / rect.h;
+                    self.offset = self.threshold.get(cx) - cursor_value;
+                    cx.begin_edit();
+                }
+            }
+            WindowEvent::MouseUp(button) => {
+                if button == MouseButton::Left && self.dragging {
+                    cx.release_active();
+                    self.dragging = false;
+                    self.offset = 0f32;
+                    cx.end_edit();
+                    cx.hide_tooltip();
+                }
+            }
+            WindowEvent::MouseLeave => {
+                if !self.dragging {
+                    self.hovering = false;
+                    cx.hide_tooltip();
+                }
+            }
+            WindowEvent::KeyDown(code, _) => {
+                if let Some(nudge) = KeyNudge::from_event(cx, code) {
+                    let value = nudge.apply(self.threshold.get(cx));
+                    if let Some(callback) = &self.on_changing_threshold {
+                        (callback)(cx, value);
+                    }
+                    cx.single_edit(0, &[value]);
+                    cx.show_value_tooltip(format!("{value:.2}"));
+                }
+            }
+            _ => (),
+        });
+        self.styles.set_threshold_pseudo_class(
+            cx,
+            PseudoClass::HOVER,
+            self.hovering && !self.dragging,
+        );
+        self.styles
+            .set_threshold_pseudo_class(cx, PseudoClass::ACTIVE, self.dragging);
+        self.notify_state_change(cx, &state);
+    }
+
+    fn draw(&self, cx: &mut DrawContext, canvas: &mut Canvas) {
+        let rect = cx.bounds();
+
+        cx.draw_background(canvas);
+
+        let level_color = self.styles.level_background_color(cx);
+        let threshold_color = self.styles.threshold_border_color(cx);
+        let hysteresis_color = self.styles.hysteresis_background_color(cx);
+
+        // Level history, filled from the bottom up
+        self.levels.view(cx.data().unwrap(), |levels| {
+            let levels = levels.unwrap();
+            if levels.len() < 2 {
+                return;
+            }
+            let step = rect.w / (levels.len() - 1) as f32;
+            let mut path = vg::Path::new();
+            path.move_to(rect.left(), rect.bottom());
+            for (i, level) in levels.iter().enumerate() {
+                let level = level.clamp(0f32, 1f32);
+                path.line_to(
+                    rect.left() + step * i as f32,
+                    rect.bottom() - level * rect.h,
+                );
+            }
+            path.line_to(rect.right(), rect.bottom());
+            path.close();
+            canvas.fill_path(&mut path, &vg::Paint::color(level_color.into()));
+        });
+
+        let threshold = self
+            .threshold
+            .view(cx.data().unwrap(), |threshold| *threshold.unwrap());
+        let line_y = rect.bottom() - threshold * rect.h;
+
+        // Hysteresis band below the threshold
+        if let Some(hysteresis) = self.hysteresis {
+            let band_bottom = (line_y + hysteresis * rect.h).min(rect.bottom());
+            let mut path = vg::Path::new();
+            path.rect(rect.left(), line_y, rect.w, band_bottom - line_y);
+            canvas.fill_path(&mut path, &vg::Paint::color(hysteresis_color.into()));
+        }
+
+        // Threshold line
+        let line_width = if self.dragging || self.hovering {
+            3f32
+        } else {
+            1f32
+        };
+        let mut path = vg::Path::new();
+        path.move_to(rect.left(), line_y);
+        path.line_to(rect.right(), line_y);
+        canvas.stroke_path(
+            &mut path,
+            &vg::Paint::color(threshold_color.into()).with_line_width(line_width),
+        );
+        cx.draw_border(canvas);
+        cx.draw_outline(canvas);
+    }
+}
@@ -0,0 +1,99 @@
+use std::marker::PhantomData;
+
+use lily_derive::Handle;
+use vizia::prelude::*;
+
+use super::slider::{DragSlider, DragSliderHandle};
+use crate::locale::LocaleExt;
+use crate::util::PseudoClassExt;
+
+/// A microtuning table with one row per scale degree. Each row shows the
+/// degree's offset in cents as a draggable bar and as an editable number.
+#[allow(clippy::type_complexity)]
+#[derive(Handle)]
+pub struct TuningTable<L>
+where
+    L: Lens<Target = Vec<f32>>,
+{
+    offsets: PhantomData<L>,
+
+    #[event]
+    #[callback(usize, f32)]
+    on_changing_offset: Option<Box<dyn Fn(&mut EventContext, usize, f32)>>,
+}
+
+impl<L> TuningTable<L>
+where
+    L: Lens<Target = Vec<f32>>,
+{
+    /// Create a new `TuningTable`
+    ///
+    /// # Parameters
+    ///
+    /// * `cx` - the current [`Context`]
+    /// * `offsets` - a [`Lens`] of the offset in cents of each scale degree.
+    ///   One row is shown per degree.
+    /// * `max_offset` - the largest offset in cents reachable by dragging a
+    ///   bar, e.g. `100.0` for a semitone either way. Larger offsets can still
+    ///   be typed in.
+    pub fn new(cx: &mut Context, offsets: L, max_offset: f32) -> Handle<Self> {
+        Self {
+            offsets: PhantomData::default(),
+            on_changing_offset: None,
+        }
+        .build(cx, |cx| {
+            Binding::new(cx, offsets.clone().map(|o| o.len()), move |cx, len| {
+                let locale = cx.locale();
+                for index in 0..len.get(cx) {
+                    let cents = offsets.clone().index(index);
+                    HStack::new(cx, |cx| {
+                        Label::new(cx, &index.to_string()).class("degree");
+                        // The slider works in a normalized `-1..=1` range
+                        DragSlider::new(
+                            cx,
+                            cents
+                                .clone()
+                                .map(move |c| (c / max_offset).clamp(-1f32, 1f32)),
+                            -1f32..=1f32,
+                        )
+                        .on_changing(move |cx, value| {
+                            cx.emit(TuningTableInternalEvent::ChangingOffset(
+                                index,
+                                value * max_offset,
+                            ))
+                        })
+                        .class("bar");
+                        let (format_locale, parse_locale) = (locale.clone(), locale.clone());
+                        Textbox::new(
+                            cx,
+                            cents.map(move |c| format_locale.localize(&format!("{c:.2}"))),
+                        )
+                        .on_edit(move |cx, text| {
+                            if let Some(cents) = parse_locale.parse_number(&text) {
+                                cx.emit(TuningTableInternalEvent::ChangingOffset(index, cents));
+                            }
+                        })
+                        .class("cents");
+                    })
+                    .class("row");
+                }
+            });
+        })
+    }
+}
+
+impl<L> View for TuningTable<L>
+where
+    L: Lens<Target = Vec<f32>>,
+{
+    fn element(&self) -> Option<&'static str> {
+        Some("tuning-table")
+    }
+
+    fn event(&mut self, cx: &mut EventContext, event: &mut Event) {
+        if cx.is_disabled() {
+            return;
+        }
+        self.handle_internal_event(cx, event);
+    }
+}
@@ -0,0 +1,131 @@
+use std::marker::PhantomData;
+
+use vizia::prelude::*;
+
+use crate::locale::LocaleExt;
+use crate::units::note_name;
+
+/// The stage of a voice's amplitude envelope
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Data)]
+pub enum EnvelopeStage {
+    Idle,
+    Attack,
+    Decay,
+    Sustain,
+    Release,
+}
+
+impl EnvelopeStage {
+    /// The class given to a voice cell in this stage
+    fn class(&self) -> &'static str {
+        match self {
+            EnvelopeStage::Idle => "idle",
+            EnvelopeStage::Attack => "attack",
+            EnvelopeStage::Decay => "decay",
+            EnvelopeStage::Sustain => "sustain",
+            EnvelopeStage::Release => "release",
+        }
+    }
+}
+
+/// The state of a single synth voice
+#[derive(Clone, Copy, Debug, PartialEq, Data)]
+pub struct VoiceState {
+    /// The MIDI note being played, or `None` if the voice is free
+    pub note: Option<u8>,
+    /// How long the voice has been playing, in seconds
+    pub age: f32,
+    pub stage: EnvelopeStage,
+}
+
+impl Default for VoiceState {
+    fn default() -> Self {
+        Self {
+            note: None,
+            age: 0f32,
+            stage: EnvelopeStage::Idle,
+        }
+    }
+}
+
+/// Shows each voice of a synth as a cell with its note name and age, colored
+/// by envelope stage through the `idle`, `attack`, `decay`, `sustain` and
+/// `release` classes.
+///
+/// [`feed::voice_display`](crate::feed::voice_display) builds one that reads
+/// the voices from the audio thread itself.
+pub struct VoiceDisplay<L>
+where
+    L: Lens<Target = Vec<VoiceState>>,
+{
+    voices: PhantomData<L>,
+}
+
+impl<L> VoiceDisplay<L>
+where
+    L: Lens<Target = Vec<VoiceState>>,
+{
+    /// Create a new `VoiceDisplay`
+    ///
+    /// # Parameters
+    ///
+    /// * `cx` - the current [`Context`]
+    /// * `voices` - a [`Lens`] of the state of every voice. One cell is shown
+    ///   per voice.
+    pub fn new(cx: &mut Context, voices: L) -> Handle<Self> {
+        Self::build_display(cx, voices, |_| {})
+    }
+
+    /// Builds the display, running `content` inside it before the cells, such
+    /// as to add the model holding the voices
+    pub(crate) fn build_display(
+        cx: &mut Context,
+        voices: L,
+        content: impl FnOnce(&mut Context),
+    ) -> Handle<Self> {
+        Self {
+            voices: PhantomData::default(),
+        }
+        .build(cx, |cx| {
+            content(cx);
+            Binding::new(cx, voices.clone().map(|v| v.len()), move |cx, len| {
+                let locale = cx.locale();
+                for index in 0..len.get(cx) {
+                    let voice = voices.clone().index(index);
+                    let locale = locale.clone();
+                    let text = voice.clone().map(move |v| locale.localize(&voice_text(v)));
+                    let mut cell = Label::new(cx, text).class("voice");
+                    for stage in [
+                        EnvelopeStage::Idle,
+                        EnvelopeStage::Attack,
+                        EnvelopeStage::Decay,
+                        EnvelopeStage::Sustain,
+                        EnvelopeStage::Release,
+                    ] {
+                        cell = cell.toggle_class(
+                            stage.class(),
+                            voice.clone().map(move |v| v.stage == stage),
+                        );
+                    }
+                }
+            });
+        })
+        .layout_type(LayoutType::Row)
+    }
+}
+
+impl<L> View for VoiceDisplay<L>
+where
+    L: Lens<Target = Vec<VoiceState>>,
+{
+    fn element(&self) -> Option<&'static str> {
+        Some("voice-display")
+    }
+}
+
+fn voice_text(voice: &VoiceState) -> String {
+    match voice.note {
+        Some(note) => format!("{}\n{:.1}s", note_name(note), voice.age),
+        None => String::from("-"),
+    }
+}
@@ -0,0 +1,35 @@
+//! Waveform drawing shared by the widgets that preview audio
+
+use vizia::cache::BoundingBox;
+use vizia::prelude::*;
+use vizia::vg;
+
+/// Draws peaks normalized to `0..=1` across the width of `bounds`, mirrored
+/// around its horizontal center
+pub(crate) fn draw_peaks(canvas: &mut Canvas, bounds: BoundingBox, peaks: &[f32], color: Color) {
+    if peaks.len() < 2 {
+        return;
+    }
+    let center_y = bounds.top() + bounds.h / 2f32;
+    let half_height = bounds.h / 2f32;
+    let step = bounds.w / (peaks.len() - 1) as f32;
+
+    let mut path = vg::Path::new();
+    path.move_to(bounds.left(), center_y);
+    for (i, peak) in peaks.iter().enumerate() {
+        let peak = peak.clamp(0f32, 1f32);
+        path.line_to(
+            bounds.left() + step * i as f32,
+            center_y - peak * half_height,
+        );
+    }
+    for (i, peak) in peaks.iter().enumerate().rev() {
+        let peak = peak.clamp(0f32, 1f32);
+        path.line_to(
+            bounds.left() + step * i as f32,
+            center_y + peak * half_height,
+        );
+    }
+    path.close();
+    canvas.fill_path(&mut path, &vg::Paint::color(color.into()));
+}
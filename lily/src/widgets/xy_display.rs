@@ -0,0 +1,134 @@
+use std::collections::VecDeque;
+
+use glam::Vec2;
+use lily_derive::Handle;
+use vizia::prelude::*;
+use vizia::vg;
+
+use crate::util::{BoundingBoxExt, FrameExt};
+
+/// The radius in pixels of the point when the `.point` class sets no `width`
+const POINT_RADIUS: f32 = 3f32;
+/// The width in pixels of the trail when the `.trail` class sets no
+/// `border-width`
+const TRAIL_WIDTH: f32 = 1f32;
+
+/// Shows a point along a normalized XY axis `(-1,-1)..=(1,1)`, like an XY pad
+/// that takes no input. It's small enough for the slots showing where a
+/// modulation is, and lets the mouse through to the views under it.
+///
+/// With a [`trail`](XyDisplayHandle::trail), the last positions of the point
+/// are drawn behind it as a line in the `border-color` of the `.trail` class,
+/// fading out towards the oldest.
+#[derive(Handle)]
+#[style_class("point", "trail")]
+pub struct XyDisplay<P>
+where
+    P: Lens<Target = Vec2>,
+{
+    point: P,
+    /// How many of the last positions of the point are drawn as a trail, none
+    /// by default
+    #[property]
+    trail: usize,
+    /// The last positions of the point, oldest first
+    history: VecDeque<Vec2>,
+    styles: XyDisplayStyles,
+}
+
+impl<P> XyDisplay<P>
+where
+    P: Lens<Target = Vec2>,
+{
+    pub fn new(cx: &mut Context, point: P) -> Handle<Self> {
+        Self {
+            point: point.clone(),
+            trail: 0,
+            history: VecDeque::new(),
+            styles: XyDisplayStyles::new(cx),
+        }
+        .build(cx, |_| {})
+        .hoverable(false)
+        .bind(point, |handle, point| {
+            let position = point.get(handle.cx);
+            if let Some(view) = handle.cx.views.get_mut(&handle.entity) {
+                if let Some(display) = view.downcast_mut::<XyDisplay<P>>() {
+                    display.remember(position);
+                }
+            }
+            handle.cx.style.needs_redraw = true;
+        })
+    }
+
+    /// Adds `position` to the trail, dropping the positions beyond its length
+    fn remember(&mut self, position: Vec2) {
+        self.history.push_back(position);
+        while self.history.len() > self.trail.max(1) {
+            self.history.pop_front();
+        }
+    }
+}
+
+impl<P> View for XyDisplay<P>
+where
+    P: Lens<Target = Vec2>,
+{
+    fn element(&self) -> Option<&'static str> {
+        Some("xy-display")
+    }
+
+    fn draw(&self, cx: &mut DrawContext, canvas: &mut Canvas) {
+        let rect = cx.bounds();
+        let border = cx.border_color().copied().unwrap_or_default();
+
+        cx.draw_background(canvas);
+
+        // XY center lines
+        let (center_x, center_y) = rect.center();
+        let mut path = vg::Path::new();
+        path.move_to(center_x, rect.top());
+        path.line_to(center_x, rect.bottom());
+        path.move_to(rect.left(), center_y);
+        path.line_to(rect.right(), center_y);
+        canvas.stroke_path(&mut path, &vg::Paint::color(border.into()));
+
+        // The trail, each segment fainter than the one after it
+        let trail: Vec<Vec2> = self
+            .history
+            .iter()
+            .rev()
+            .take(self.trail)
+            .rev()
+            .map(|position| rect.map_data_point(*position, true))
+            .collect();
+        let trail_color: vg::Color = self.styles.trail_border_color(cx).into();
+        let trail_width = self.styles.trail_border_width(cx).unwrap_or(TRAIL_WIDTH);
+        for (index, segment) in trail.windows(2).enumerate() {
+            let mut color = trail_color;
+            color.a *= (index + 1) as f32 / trail.len() as f32;
+            let mut path = vg::Path::new();
+            path.move_to(segment[0].x, segment[0].y);
+            path.line_to(segment[1].x, segment[1].y);
+            canvas.stroke_path(
+                &mut path,
+                &vg::Paint::color(color).with_line_width(trail_width),
+            );
+        }
+
+        // The point, where the lens is now
+        let point = self.point.view(cx.data().unwrap(), |point| {
+            point.copied().unwrap_or_default()
+        });
+        let point = rect.map_data_point(point, true);
+        let point_radius = self
+            .styles
+            .point_width(cx)
+            .map_or(POINT_RADIUS, |width| width / 2f32);
+        let point_color = self.styles.point_background_color(cx);
+        let mut path = vg::Path::new();
+        path.circle(point.x, point.y, point_radius);
+        canvas.fill_path(&mut path, &vg::Paint::color(point_color.into()));
+
+        cx.draw_border(canvas);
+    }
+}
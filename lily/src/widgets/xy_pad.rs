@@ -1,32 +1,151 @@
-use std::collections::HashMap;
-
 use glam::Vec2;
 use lily_derive::Handle;
+use std::cmp::Ordering;
+use std::rc::Rc;
 use vizia::prelude::*;
 use vizia::vg;
 
-use crate::util::BoundingBoxExt;
+use crate::focus::FocusExt;
+use crate::gesture::GestureExt;
+use crate::input::PressReader;
+use crate::tooltip::TooltipExt;
+use crate::util::{
+    Affordance, BoundingBoxExt, CursorAffordance, CurvePoints, DragGesture, FrameExt, KeyNudge,
+    Modulation, ModulationExt, MotionCapture, PseudoClassExt, RedrawExt, ValueMapping,
+};
+
+/// The radius in pixels of the point when the `.point` class sets no `width`
+const POINT_RADIUS: f32 = 4f32;
+/// The radius in pixels of the ring around a hovered or dragged point when the
+/// `.hover-ring` class sets no `width`. Stylesheets animate the ring by
+/// transitioning the `width` and `border-color` of `.hover-ring:hover`.
+const HOVER_RING_RADIUS: f32 = 8f32;
+/// The width in pixels of the ring when the `.point` class sets no
+/// `border-width`
+const RING_WIDTH: f32 = 2f32;
+/// The width in pixels of the crosshair when the `.crosshair` class sets no
+/// `border-width`
+const CROSSHAIR_WIDTH: f32 = 1f32;
+/// The radius in pixels of the live modulated point when the `.modulation`
+/// class sets no `width`
+const LIVE_POINT_RADIUS: f32 = 3f32;
 
 /// Controls a single point along a normalized XY axis `(-1,-1)..=(1,1)`.
+///
+/// How quick a double click is depends on the installed
+/// [`InputMap`](crate::input::InputMap).
+///
+/// With `on_gesture` set, the pad captures performance gestures: each drag is
+/// recorded over time and handed to `on_gesture` when it's released, and the
+/// point then snaps back to where the drag started, ready for the next take.
+///
+/// With a [`grid`](XyPadHandle::grid), the pad picks between discrete
+/// positions, such as chords or presets to morph between: the point snaps to
+/// the center of the cell under the cursor and the arrow keys step from cell
+/// to cell. The grid is drawn in the `border-color` of the `.grid` class and
+/// the cell of the point is filled with its `background-color`.
+///
+/// Created [`with_mappings`](XyPad::with_mappings), the pad shows the values
+/// of both axes in a tooltip while the point is dragged or nudged.
+///
+/// Holding Ctrl while dragging keeps the point as far from the center as it
+/// was when the drag started, rotating it around the center, and holding
+/// Ctrl+Shift keeps its angle, moving it along the radius, so rotation and
+/// depth can be tweaked on their own. Holding Shift alone fine drags the point.
 #[derive(Handle)]
+#[style_class("point", "hover-ring", "crosshair", "modulation", "grid")]
 pub struct XyPad<P>
 where
     P: Lens<Target = Vec2>,
 {
     point: P,
-    offset: Vec2,
-    state: InternalState,
-    // Temporary workaround until we can get custom css stuff directly
-    classes: HashMap<&'static str, Entity>,
+    /// Whether the cursor is over the pad
+    hovered: bool,
+    /// The drag of the point, which remembers where the point started so
+    /// that a cancelled drag can put it back
+    drag: DragGesture<Vec2>,
+    /// The modulation drawn on top of the point
+    modulation: Modulation<Vec2>,
+    /// Quantizes the point to the centers of the cells of a grid this many
+    /// columns by rows, or leaves it free with `None`, the default
+    #[property]
+    grid: Option<(usize, usize)>,
+    /// The [`ValueMapping`]s from the `x` of the point, left to right, and
+    /// its `y`, bottom to top, to the values shown in the readout, if any
+    mappings: Option<(Rc<dyn ValueMapping>, Rc<dyn ValueMapping>)>,
+    /// The presses of the mouse buttons
+    presses: PressReader,
+    cursor: CursorAffordance,
+    /// Records the drag, while capturing a gesture
+    capture: MotionCapture,
+    styles: XyPadStyles,
     #[callback(Vec2)]
-    on_changing_point: Option<Box<dyn Fn(&mut Context, Vec2)>>,
+    on_changing_point: Option<Box<dyn Fn(&mut EventContext, Vec2)>>,
+    /// Called when the pad is double-clicked with the left button
+    #[callback]
+    on_double_click: Option<Box<dyn Fn(&mut EventContext)>>,
+    /// Called when a drag is released with the drag captured as two
+    /// envelopes over the seconds since it started, one of the `x` of the
+    /// point from left to right and one of the `y` from bottom to top, both
+    /// in `0..=1`
+    #[callback(CurvePoints, CurvePoints)]
+    on_gesture: Option<Box<dyn Fn(&mut EventContext, CurvePoints, CurvePoints)>>,
 }
 
+/// How a drag of the pad is held back by the modifier keys
 #[derive(PartialEq, Eq, Clone, Copy)]
-enum InternalState {
-    NoOp,
-    Hovering,
-    Dragging,
+enum DragConstraint {
+    Free,
+    /// Keeps the distance from the center, with Ctrl
+    Radius,
+    /// Keeps the angle around the center, with Ctrl+Shift
+    Angle,
+}
+
+impl DragConstraint {
+    fn from_modifiers(modifiers: Modifiers) -> Self {
+        if !modifiers.contains(Modifiers::CTRL) {
+            DragConstraint::Free
+        } else if modifiers.contains(Modifiers::SHIFT) {
+            DragConstraint::Angle
+        } else {
+            DragConstraint::Radius
+        }
+    }
+
+    /// Holds `point` to the radius or the angle of `start`, where the drag
+    /// started, keeping it on the pad
+    fn apply(self, point: Vec2, start: Vec2) -> Vec2 {
+        match self {
+            DragConstraint::Radius => match point.try_normalize() {
+                Some(direction) => {
+                    let radius = start.length();
+                    let point = direction * radius;
+                    // A radius longer than 1 only fits the corners, so past
+                    // an edge the point slides along it instead, still on
+                    // the circle
+                    let across = (radius * radius - 1f32).max(0f32).sqrt();
+                    if point.x.abs() > 1f32 {
+                        Vec2::new(point.x.signum(), point.y.signum() * across)
+                    } else if point.y.abs() > 1f32 {
+                        Vec2::new(point.x.signum() * across, point.y.signum())
+                    } else {
+                        point
+                    }
+                }
+                None => start,
+            },
+            DragConstraint::Angle => match start.try_normalize() {
+                Some(direction) => {
+                    let point = direction * point.dot(direction).max(0f32);
+                    // Shortened rather than clamped, so the angle holds
+                    point / point.abs().max_element().max(1f32)
+                }
+                None => point,
+            },
+            DragConstraint::Free => point,
+        }
+    }
 }
 
 impl<P> XyPad<P>
@@ -34,21 +153,148 @@ where
     P: Lens<Target = Vec2>,
 {
     pub fn new(cx: &mut Context, point: P) -> Handle<Self> {
-        let mut classes = HashMap::<&'static str, Entity>::default();
-        let mut insert_color = |name| {
-            let e = Element::new(cx).class(name).display(Display::None).entity;
-            classes.insert(name, e);
-        };
-        insert_color("point");
-        insert_color("crosshair");
+        Self::build_pad(cx, point, None)
+    }
+
+    /// Create a new `XyPad` whose readout shows the position of the point
+    /// through a [`ValueMapping`] per axis, such as a cutoff frequency across
+    /// and a resonance up. The point is still stored normalized.
+    pub fn with_mappings(
+        cx: &mut Context,
+        point: P,
+        x_mapping: impl ValueMapping + 'static,
+        y_mapping: impl ValueMapping + 'static,
+    ) -> Handle<Self> {
+        let mappings: (Rc<dyn ValueMapping>, Rc<dyn ValueMapping>) =
+            (Rc::new(x_mapping), Rc::new(y_mapping));
+        Self::build_pad(cx, point, Some(mappings))
+    }
+
+    fn build_pad(
+        cx: &mut Context,
+        point: P,
+        mappings: Option<(Rc<dyn ValueMapping>, Rc<dyn ValueMapping>)>,
+    ) -> Handle<Self> {
         Self {
             point,
             on_changing_point: None,
-            state: InternalState::NoOp,
-            classes,
-            offset: Vec2::ZERO,
+            on_double_click: None,
+            on_gesture: None,
+            presses: PressReader::new(),
+            cursor: CursorAffordance::new(),
+            capture: MotionCapture::new(),
+            hovered: false,
+            drag: DragGesture::new(),
+            modulation: Modulation::None,
+            grid: None,
+            mappings,
+            styles: XyPadStyles::new(cx),
         }
         .build(cx, |_| {})
+        .tab_stop()
+    }
+
+    /// Puts the point back at `start`, where the drag started, after a
+    /// gesture was captured
+    fn snap_back(&self, cx: &mut EventContext, start: Vec2) {
+        if let Some(callback) = &self.on_changing_point {
+            (callback)(cx, start);
+        }
+        cx.request_redraw();
+        cx.single_edit(0, &[start.x, start.y]);
+    }
+
+    /// Shows the values of `point` in the readout, if the pad has mappings
+    fn show_readout(&self, cx: &mut EventContext, point: Vec2) {
+        if let Some((x_mapping, y_mapping)) = &self.mappings {
+            let normalized = gesture_values(point);
+            cx.show_value_tooltip(format!(
+                "{:.2}, {:.2}",
+                x_mapping.denormalize(normalized.x),
+                y_mapping.denormalize(normalized.y)
+            ));
+        }
+    }
+
+    /// The grid the point is quantized to, unless it has no cells
+    fn cells(&self) -> Option<(usize, usize)> {
+        self.grid
+            .filter(|(columns, rows)| *columns > 0 && *rows > 0)
+    }
+
+    /// Snaps `point` to the center of its cell of the grid, if there is one
+    fn quantize(&self, point: Vec2) -> Vec2 {
+        match self.cells() {
+            Some(grid) => cell_center(grid_cell(point, grid), grid),
+            None => point,
+        }
+    }
+
+    /// Moves `point` as `nudge` asks, a whole cell at a time on a grid
+    fn nudge(&self, point: Vec2, nudge: KeyNudge) -> Vec2 {
+        // like the UI, the point's y grows downwards
+        let nudged = nudge.apply_point(point, Vec2::new(-1f32, 1f32), Vec2::new(1f32, -1f32));
+        match (self.cells(), nudge) {
+            (Some((columns, rows)), KeyNudge::Step(step)) => {
+                let (column, row) = grid_cell(point, (columns, rows));
+                let cell = (
+                    step_cell(column, columns, step.x),
+                    step_cell(row, rows, -step.y),
+                );
+                cell_center(cell, (columns, rows))
+            }
+            _ => self.quantize(nudged),
+        }
+    }
+}
+
+/// The column and row of the cell of a `columns` by `rows` grid over the pad
+/// that `point` is in
+fn grid_cell(point: Vec2, (columns, rows): (usize, usize)) -> (usize, usize) {
+    let index =
+        |value: f32, count: usize| (((value + 1f32) / 2f32 * count as f32) as usize).min(count - 1);
+    (index(point.x, columns), index(point.y, rows))
+}
+
+/// The center of the cell in `column` and `row` of a `columns` by `rows` grid
+/// over the pad
+fn cell_center((column, row): (usize, usize), (columns, rows): (usize, usize)) -> Vec2 {
+    let center = |index: usize, count: usize| (index as f32 + 0.5f32) / count as f32 * 2f32 - 1f32;
+    Vec2::new(center(column, columns), center(row, rows))
+}
+
+/// The index of the next cell of `count` in the direction of `step`
+fn step_cell(index: usize, count: usize, step: f32) -> usize {
+    match step.partial_cmp(&0f32) {
+        Some(Ordering::Greater) => (index + 1).min(count - 1),
+        Some(Ordering::Less) => index.saturating_sub(1),
+        _ => index,
+    }
+}
+
+/// The position of `point` on the pad as values in `0..=1` growing towards the
+/// right and the top, as captured into envelopes and mapped for the readout
+fn gesture_values(point: Vec2) -> Vec2 {
+    Vec2::new((point.x + 1f32) / 2f32, (1f32 - point.y) / 2f32)
+}
+
+impl<P> ModulationExt<Vec2> for Handle<'_, XyPad<P>>
+where
+    P: Lens<Target = Vec2>,
+{
+    fn modulation<M>(self, modulation: M) -> Self
+    where
+        M: Lens<Target = Modulation<Vec2>>,
+    {
+        modulation.set_or_bind(self.cx, self.entity, |cx, entity, modulation| {
+            if let Some(view) = cx.views.get_mut(&entity) {
+                if let Some(pad) = view.downcast_mut::<XyPad<P>>() {
+                    pad.modulation = modulation;
+                }
+            }
+            cx.style.needs_redraw = true;
+        });
+        self
     }
 }
 
@@ -61,75 +307,118 @@ where
     }
 
     fn event(&mut self, cx: &mut EventContext, event: &mut Event) {
-        // If clicking and hovered, set the state to dragging
+        if cx.is_disabled() {
+            return;
+        }
+        let before = (self.hovered, self.drag.is_dragging());
         event.map(|ev: &WindowEvent, _| match *ev {
-            WindowEvent::MouseEnter => {
-                if self.state != InternalState::Dragging {
-                    self.state = InternalState::Hovering;
-                }
-            }
-            WindowEvent::MouseLeave => {
-                if self.state != InternalState::Dragging {
-                    self.state = InternalState::NoOp;
-                }
-            }
+            WindowEvent::MouseEnter => self.hovered = true,
+            WindowEvent::MouseLeave => self.hovered = false,
             WindowEvent::MouseMove(x, y) => {
-                if let InternalState::Dragging = self.state {
-                    let mouse_pos = Vec2::new(x, y);
-                    let mouse_pos_scaled = cx
-                        .cache
-                        .get_bounds(cx.current())
-                        .map_ui_point_unbounded(mouse_pos, true);
-                    let final_value = (mouse_pos_scaled + self.offset)
-                        .clamp(Vec2::splat(-1f32), Vec2::splat(1f32));
+                // Shift fine drags, unless it's held with Ctrl to keep the
+                // angle of the point
+                let fine = cx.modifiers == Modifiers::SHIFT;
+                if let Some(drag) = self.drag.update_to(Vec2::new(x, y), fine) {
+                    let start = self.drag.start().copied().unwrap_or_default();
+                    let bounds = cx.cache.get_bounds(cx.current());
+                    let moved = bounds.map_ui_point_unbounded(drag.position, true)
+                        - bounds.map_ui_point_unbounded(drag.position - drag.total, true);
+                    let point = (start + moved).clamp(Vec2::splat(-1f32), Vec2::splat(1f32));
+                    let constraint = DragConstraint::from_modifiers(cx.modifiers);
+                    let final_value = self.quantize(constraint.apply(point, start));
                     if let Some(callback) = &self.on_changing_point {
                         (callback)(cx, final_value);
                     }
+                    cx.request_redraw();
+                    cx.perform_edit(0, &[final_value.x, final_value.y]);
+                    self.show_readout(cx, final_value);
+                    self.capture.record(gesture_values(final_value));
                 }
             }
             WindowEvent::MouseDown(button) => {
-                if button == MouseButton::Left {
-                    cx.capture();
-                    if self.state == InternalState::Hovering {
-                        self.state = InternalState::Dragging;
-                        // Set the offset
-                        let rect = cx.cache.get_bounds(cx.current);
-                        let cursor_pos_scaled = rect.map_ui_point_unbounded(
-                            (cx.mouse.cursorx, cx.mouse.cursory).into(),
-                            true,
-                        );
-                        self.offset = self.point.get(cx) - cursor_pos_scaled;
+                let press = self.presses.read(cx, ev);
+                if press.is_some_and(|press| press.double_click) && button == MouseButton::Left {
+                    if let Some(callback) = &self.on_double_click {
+                        (callback)(cx);
+                    }
+                }
+                if button == MouseButton::Left && self.hovered && !self.drag.is_dragging() {
+                    let start = self.point.get(cx);
+                    self.drag.begin(cx, start);
+                    cx.begin_edit();
+                    if self.on_gesture.is_some() {
+                        self.capture.start(gesture_values(start));
                     }
                 }
             }
-            WindowEvent::MouseUp(button) => {
-                let cursor_pos: Vec2 = (cx.mouse.cursorx, cx.mouse.cursory).into();
-                let rect = cx.cache.get_bounds(cx.current);
-
-                if button == MouseButton::Left {
-                    cx.release();
-                    self.offset = Vec2::ZERO;
-                    self.state = if rect.contains_point(cursor_pos) {
-                        InternalState::Hovering
-                    } else {
-                        InternalState::NoOp
+            WindowEvent::MouseUp(MouseButton::Left) => {
+                let start = self.drag.start().copied();
+                if self.drag.end(cx) {
+                    cx.end_edit();
+                    cx.hide_tooltip();
+                }
+                if let (Some((x, y)), Some(callback), Some(start)) =
+                    (self.capture.finish(), &self.on_gesture, start)
+                {
+                    (callback)(cx, x, y);
+                    self.snap_back(cx, start);
+                }
+                let cursor: Vec2 = (cx.mouse.cursorx, cx.mouse.cursory).into();
+                self.hovered = cx.cache.get_bounds(cx.current()).contains_point(cursor);
+            }
+            // Escape cancels the drag, putting the point back where it was
+            WindowEvent::KeyDown(Code::Escape, _) if self.drag.is_dragging() => {
+                if let Some(start) = self.drag.cancel(cx) {
+                    if let Some(callback) = &self.on_changing_point {
+                        (callback)(cx, start);
+                    }
+                    cx.request_redraw();
+                    cx.perform_edit(0, &[start.x, start.y]);
+                    cx.end_edit();
+                    cx.hide_tooltip();
+                    self.capture.cancel();
+                }
+                let cursor: Vec2 = (cx.mouse.cursorx, cx.mouse.cursory).into();
+                self.hovered = cx.cache.get_bounds(cx.current()).contains_point(cursor);
+            }
+            WindowEvent::KeyDown(code, _) => {
+                if let Some(nudge) = KeyNudge::from_event(cx, code) {
+                    let point = self.nudge(self.point.get(cx), nudge);
+                    if let Some(callback) = &self.on_changing_point {
+                        (callback)(cx, point);
                     }
+                    cx.request_redraw();
+                    cx.single_edit(0, &[point.x, point.y]);
+                    self.show_readout(cx, point);
                 }
             }
             _ => (),
         });
+        let dragging = self.drag.is_dragging();
+        self.styles
+            .set_point_pseudo_class(cx, PseudoClass::HOVER, self.hovered && !dragging);
+        self.styles
+            .set_point_pseudo_class(cx, PseudoClass::ACTIVE, dragging);
+        // The ring grows and fades in through the transitions of the
+        // `.hover-ring` class
+        self.styles
+            .set_hover_ring_pseudo_class(cx, PseudoClass::HOVER, self.hovered || dragging);
+        let affordance = if dragging {
+            Affordance::Grabbing
+        } else {
+            Affordance::Pick
+        };
+        self.cursor.update(cx, event, affordance);
+        // The point itself is redrawn when it moves, so only a change of
+        // state needs a redraw here
+        cx.redraw_if_changed(&before, &(self.hovered, dragging));
     }
 
     fn draw(&self, cx: &mut DrawContext, canvas: &mut Canvas) {
         let rect = cx.bounds();
-        let bg = cx.background_color().copied().unwrap_or_default();
         let border = cx.border_color().copied().unwrap_or_default();
 
-        // Draw background shapes
-        // Background
-        let mut path = Path::new();
-        path.rect(rect.x, rect.y, rect.w, rect.h);
-        canvas.fill_path(&mut path, &Paint::color(bg.into()));
+        cx.draw_background(canvas);
 
         // XY center lines
         let (center_top_x, center_top_y) = rect.center_top();
@@ -137,7 +426,7 @@ where
         let (center_left_x, center_left_y) = rect.center_left();
         let (center_right_x, center_right_y) = rect.center_right();
 
-        let mut path = Path::new();
+        let mut path = vg::Path::new();
         path.move_to(center_top_x, center_top_y);
         path.line_to(center_bottom_x, center_bottom_y);
         path.move_to(center_left_x, center_left_y);
@@ -148,54 +437,247 @@ where
         for scale in [1.0, 0.66, 0.33] {
             path.circle(center_x, center_y, (rect.w / 2f32) * scale);
         }
-        canvas.stroke_path(&mut path, Paint::color(border.into()));
+        canvas.stroke_path(&mut path, &vg::Paint::color(border.into()));
 
         // Data point
         self.point.view(cx.data().unwrap(), |point| {
             let point = *point.unwrap();
-            let point_entity = *self.classes.get("point").unwrap();
             let ui_point = rect.map_data_point(point, true);
-            let point_border = cx.border_color(point_entity).cloned().unwrap_or_default();
-            let point_color = cx
-                .background_color(point_entity)
-                .cloned()
-                .unwrap_or_default();
+            let point_color = self.styles.point_background_color(cx);
+
+            // The grid of positions, with the cell of the point filled in
+            if let Some((columns, rows)) = self.cells() {
+                let (cell_w, cell_h) = (rect.w / columns as f32, rect.h / rows as f32);
+                let (column, row) = grid_cell(point, (columns, rows));
+                let mut path = vg::Path::new();
+                path.rect(
+                    rect.left() + column as f32 * cell_w,
+                    rect.top() + row as f32 * cell_h,
+                    cell_w,
+                    cell_h,
+                );
+                let cell_color = self.styles.grid_background_color(cx);
+                canvas.fill_path(&mut path, &vg::Paint::color(cell_color.into()));
+                let mut path = vg::Path::new();
+                for column in 1..columns {
+                    let x = rect.left() + column as f32 * cell_w;
+                    path.move_to(x, rect.top());
+                    path.line_to(x, rect.bottom());
+                }
+                for row in 1..rows {
+                    let y = rect.top() + row as f32 * cell_h;
+                    path.move_to(rect.left(), y);
+                    path.line_to(rect.right(), y);
+                }
+                let grid_color = self.styles.grid_border_color(cx);
+                canvas.stroke_path(&mut path, &vg::Paint::color(grid_color.into()));
+            }
 
             // Draw crosshairs when dragging
-            let crosshair_entity = *self.classes.get("crosshair").unwrap();
-            let crosshair_color = cx
-                .border_color(crosshair_entity)
-                .cloned()
-                .unwrap_or_default();
+            let crosshair_color = self.styles.crosshair_border_color(cx);
+            let crosshair_width = self
+                .styles
+                .crosshair_border_width(cx)
+                .unwrap_or(CROSSHAIR_WIDTH);
             if self.state == InternalState::Dragging {
-                let mut path = Path::new();
+                let mut path = vg::Path::new();
                 path.move_to(ui_point.x, rect.top());
                 path.line_to(ui_point.x, rect.bottom());
                 path.move_to(rect.left(), ui_point.y);
                 path.line_to(rect.right(), ui_point.y);
-                canvas.stroke_path(&mut path, Paint::color(crosshair_color.into()));
+                canvas.stroke_path(
+                    &mut path,
+                    &vg::Paint::color(crosshair_color.into()).with_line_width(crosshair_width),
+                );
             }
 
-            // Point fill
-            let mut path = Path::new();
-            path.circle(ui_point.x, ui_point.y, 4f32);
-            canvas.fill_path(&mut path, Paint::color(point_color.into()));
-
-            // Point outline
-            let mut path = Path::new();
-            match self.state {
-                InternalState::Dragging | InternalState::Hovering => {
-                    path.circle(ui_point.x, ui_point.y, 8f32)
-                }
-                _ => (),
+            // The area swept by the modulation, and a line to the live point
+            // of offsets
+            if let Some((min, max)) = self.modulation.span(point) {
+                let (a, b) = (
+                    rect.map_data_point(min, true),
+                    rect.map_data_point(max, true),
+                );
+                let mut path = vg::Path::new();
+                path.rect(
+                    a.x.min(b.x),
+                    a.y.min(b.y),
+                    (b.x - a.x).abs(),
+                    (b.y - a.y).abs(),
+                );
+                let band_color = self.styles.modulation_background_color(cx);
+                canvas.fill_path(&mut path, &vg::Paint::color(band_color.into()));
+            }
+            if let Some(live) = self.modulation.live(point) {
+                let live = rect.map_data_point(live, true);
+                let live_color = self.styles.modulation_border_color(cx);
+                let live_radius = self
+                    .styles
+                    .modulation_width(cx)
+                    .map_or(LIVE_POINT_RADIUS, |width| width / 2f32);
+                let mut path = vg::Path::new();
+                path.move_to(ui_point.x, ui_point.y);
+                path.line_to(live.x, live.y);
+                canvas.stroke_path(&mut path, &vg::Paint::color(live_color.into()));
+                let mut path = vg::Path::new();
+                path.circle(live.x, live.y, live_radius);
+                canvas.fill_path(&mut path, &vg::Paint::color(live_color.into()));
             }
 
-            // Get custom CSS info from a display none element
+            // Point fill
+            let mut path = vg::Path::new();
+            let point_radius = self
+                .styles
+                .point_width(cx)
+                .map_or(POINT_RADIUS, |width| width / 2f32);
+            path.circle(ui_point.x, ui_point.y, point_radius);
+            canvas.fill_path(&mut path, &vg::Paint::color(point_color.into()));
 
+            // Point outline, always drawn so it can shrink and fade out after
+            // the point is left
+            let ring_radius = self
+                .styles
+                .hover_ring_width(cx)
+                .map_or(HOVER_RING_RADIUS, |width| width / 2f32);
+            let ring_width = self.styles.point_border_width(cx).unwrap_or(RING_WIDTH);
+            let ring_color = self.styles.hover_ring_border_color(cx);
+            let mut path = vg::Path::new();
+            path.circle(ui_point.x, ui_point.y, ring_radius);
             canvas.stroke_path(
                 &mut path,
-                Paint::color(point_border.into()).with_line_width(2f32),
+                &vg::Paint::color(ring_color.into()).with_line_width(ring_width),
             );
         });
+        cx.draw_border(canvas);
+        cx.draw_outline(canvas);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{Recorder, TestContext};
+    use assert_approx_eq::assert_approx_eq;
+
+    #[derive(Lens)]
+    struct Pad {
+        point: Vec2,
+    }
+
+    impl Model for Pad {}
+
+    #[test]
+    fn captures_dragged_gestures() {
+        // The center of the pad is at (100, 100)
+        let mut test = TestContext::new(200f32, 200f32);
+        let (changes, gestures) = (Recorder::new(), Recorder::new());
+        let (record_change, record_gesture) = (changes.clone(), gestures.clone());
+        test.build(|cx| {
+            Pad { point: Vec2::ZERO }.build(cx);
+            XyPad::new(cx, Pad::point)
+                .width(Pixels(200f32))
+                .height(Pixels(200f32))
+                .on_changing_point(move |_, point| record_change.push(point))
+                .on_gesture(move |_, x, y| record_gesture.push((x, y)));
+        });
+
+        // Dragged up and to the right, to three quarters of the way along
+        // both axes
+        test.drag((100f32, 100f32), (150f32, 50f32));
+        assert_eq!(gestures.len(), 1);
+        let (x, y) = gestures.last().unwrap();
+        assert_eq!(x.first().unwrap().x, 0f32);
+        assert_eq!(y.first().unwrap().x, 0f32);
+        assert_approx_eq!(x.last().unwrap().y, 0.75f32, 1e-3f32);
+        assert_approx_eq!(y.last().unwrap().y, 0.75f32, 1e-3f32);
+        // and the point snaps back to where the drag started
+        assert_eq!(changes.last(), Some(Vec2::ZERO));
+    }
+
+    #[test]
+    fn cancels_drags_with_escape() {
+        let mut test = TestContext::new(200f32, 200f32);
+        let changes = Recorder::new();
+        let record = changes.clone();
+        test.build(|cx| {
+            Pad { point: Vec2::ZERO }.build(cx);
+            XyPad::new(cx, Pad::point)
+                .width(Pixels(200f32))
+                .height(Pixels(200f32))
+                .on_changing_point(move |_, point| record.push(point));
+        });
+
+        test.move_mouse(100f32, 100f32);
+        test.press(MouseButton::Left);
+        test.move_mouse(150f32, 50f32);
+        assert_eq!(changes.last(), Some(Vec2::new(0.5f32, -0.5f32)));
+        test.press_key(Code::Escape);
+        // The point goes back to where it started, and stays there
+        assert_eq!(changes.last(), Some(Vec2::ZERO));
+        test.move_mouse(120f32, 50f32);
+        test.release(MouseButton::Left);
+        assert_eq!(changes.last(), Some(Vec2::ZERO));
+    }
+
+    #[test]
+    fn drags_onto_the_nearest_cell_of_the_grid() {
+        let mut test = TestContext::new(200f32, 200f32);
+        let changes = Recorder::new();
+        let record = changes.clone();
+        test.build(|cx| {
+            Pad { point: Vec2::ZERO }.build(cx);
+            XyPad::new(cx, Pad::point)
+                .grid(Some((4, 4)))
+                .width(Pixels(200f32))
+                .height(Pixels(200f32))
+                .on_changing_point(move |_, point| record.push(point));
+        });
+
+        // Dragged to (0.3, -0.4), in the third column and the second row of
+        // cells a quarter of the pad wide and high, which is centered on
+        // (0.25, -0.25)
+        test.drag((100f32, 100f32), (130f32, 60f32));
+        let point = changes.last().unwrap();
+        assert_approx_eq!(point.x, 0.25f32, 1e-3f32);
+        assert_approx_eq!(point.y, -0.25f32, 1e-3f32);
+        // and it never left the centers of the cells on the way
+        let centered = |point: &Vec2| *point == cell_center(grid_cell(*point, (4, 4)), (4, 4));
+        assert!(changes.values().iter().all(centered));
+    }
+
+    #[test]
+    fn keeps_the_radius_of_points_dragged_with_ctrl() {
+        // The point starts at (0.9, 0.6), at (190, 160)
+        let start = Vec2::new(0.9f32, 0.6f32);
+        let mut test = TestContext::new(200f32, 200f32);
+        let changes = Recorder::new();
+        let record = changes.clone();
+        test.build(|cx| {
+            Pad { point: start }.build(cx);
+            XyPad::new(cx, Pad::point)
+                .width(Pixels(200f32))
+                .height(Pixels(200f32))
+                .on_changing_point(move |_, point| record.push(point));
+        });
+
+        // Rotated up towards the right edge, where its radius only fits
+        // further up, so it slides along the edge instead of being cut short
+        test.set_modifiers(Modifiers::CTRL);
+        test.drag((190f32, 160f32), (199f32, 90f32));
+        let point = changes.last().unwrap();
+        assert_eq!(point.x, 1f32);
+        assert!(point.y < 0f32);
+        assert_approx_eq!(point.length(), start.length(), 1e-3f32);
+
+        // Within the pad, it follows the cursor around the circle
+        test.drag((190f32, 160f32), (150f32, 40f32));
+        let point = changes.last().unwrap();
+        let expected = Vec2::new(0.5f32, -0.6f32).normalize() * start.length();
+        assert_approx_eq!(point.x, expected.x, 1e-3f32);
+        assert_approx_eq!(point.y, expected.y, 1e-3f32);
+        assert!(changes
+            .values()
+            .iter()
+            .all(|point| (point.length() - start.length()).abs() < 1e-3f32));
     }
 }
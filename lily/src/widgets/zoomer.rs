@@ -9,6 +9,8 @@ use vizia::vg::{Paint, Path};
 //     View, WindowEvent, ZStack,
 // };
 
+use crate::util::{Affordance, CursorAffordance, PseudoClassExt};
+
 const HANDLE_SIZE: f32 = 16.0;
 const SMALLEST_RANGE: f32 = 0.1;
 
@@ -36,15 +38,24 @@ pub enum ZoomerEvent {
     FinishSet,
 }
 
-struct ZoomerControl;
+struct ZoomerControl {
+    cursor: CursorAffordance,
+}
 
 impl ZoomerControl {
     pub fn new(cx: &mut Context) -> Handle<Self> {
-        Self {}.build(cx, |_| {})
+        Self {
+            cursor: CursorAffordance::new(),
+        }
+        .build(cx, |_| {})
     }
 }
 
 impl View for ZoomerControl {
+    fn event(&mut self, cx: &mut EventContext, event: &mut Event) {
+        self.cursor.update(cx, event, Affordance::ResizeHorizontal);
+    }
+
     fn draw(&self, cx: &mut DrawContext, canvas: &mut Canvas) {
         let bounds = cx.bounds();
         let background_color = cx.background_color().cloned().unwrap_or_default();
@@ -187,6 +198,9 @@ where
     }
 
     fn event(&mut self, cx: &mut EventContext, event: &mut Event) {
+        if cx.is_disabled() {
+            return;
+        }
         event.map(|ev: &ZoomerEvent, _| {
             self.status = *ev;
         });
@@ -222,13 +236,13 @@ where
             }
             WindowEvent::MouseDown(button) => {
                 if button == MouseButton::Left {
-                    cx.capture();
+                    cx.capture_active();
                 }
             }
             WindowEvent::MouseUp(button) => {
                 if button == MouseButton::Left {
                     cx.emit(ZoomerEvent::FinishSet);
-                    cx.release();
+                    cx.release_active();
                 }
             }
             _ => (),